@@ -152,6 +152,20 @@ pub fn build_ast(code: &str) -> ASTFlatten {
     full_ast.unwrap()
 }
 
+// Record the position of the parse tree node an AST was built from, unless a
+// more precise location was already set while building it
+// (python: sub_ast.line = tree.start.line; sub_ast.column = tree.start.column + 1)
+fn with_location(ast: Option<AST>, (line, column): (isize, isize)) -> Option<AST> {
+    if let Some(base) = ast.as_ref().and_then(|ast| ast.ast_base_ref()) {
+        let mut base = base.borrow_mut();
+        if base.line == 0 {
+            base.line = line as i32;
+            base.column = column as i32 + 1;
+        }
+    }
+    ast
+}
+
 struct BuildASTVisitor {
     pub emitter: Emitter,
     pub code: String,
@@ -407,7 +421,7 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
             })
             .collect();
 
-        Some(
+        let ast = Some(
             ContractDefinition::new(
                 idf.map(RcCell::new),
                 state_variable_declarations
@@ -422,7 +436,8 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
                 vec![],
             )
             .into_ast(),
-        )
+        );
+        with_location(ast, (ctx.start().line, ctx.start().column))
     }
 
     // fn  handle_fdef(self, ctx){
@@ -549,7 +564,7 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
                         .try_as_block()
                 })
         });
-        Some(
+        let ast = Some(
             ConstructorOrFunctionDefinition::new(
                 idf.map(RcCell::new),
                 parameters,
@@ -558,7 +573,8 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
                 body.map(RcCell::new),
             )
             .into_ast(),
-        )
+        );
+        with_location(ast, (ctx.start().line, ctx.start().column))
     }
 
     fn visit_constructorDefinition(
@@ -627,7 +643,7 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
                         .map(RcCell::new)
                 })
         });
-        Some(
+        let ast = Some(
             ConstructorOrFunctionDefinition::new(
                 idf,
                 parameters,
@@ -636,7 +652,8 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
                 body,
             )
             .into_ast(),
-        )
+        );
+        with_location(ast, (ctx.start().line, ctx.start().column))
     }
 
     fn visit_enumDefinition(&mut self, ctx: &EnumDefinitionContext<'input>) -> Self::Return {
@@ -1821,7 +1838,7 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
             })
             .collect();
         // println!("==visit_stateVariableDeclaration=====keywords================={:?}", keywords);
-        Some(
+        let ast = Some(
             StateVariableDeclaration::new(
                 annotated_type.map(RcCell::new),
                 keywords,
@@ -1829,7 +1846,8 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
                 expr.map(RcCell::new).map(Into::<ASTFlatten>::into),
             )
             .into_ast(),
-        )
+        );
+        with_location(ast, (ctx.start().line, ctx.start().column))
     }
 
     fn visit_returnParameters(&mut self, ctx: &ReturnParametersContext<'input>) -> Self::Return {
@@ -1883,7 +1901,7 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
         let keywords: Vec<_> = ctx.keywords.iter().map(|kw| kw.to_string()).collect();
         // //println!("{:?},{:?},{:?}",keywords,annotated_type,idf);
 
-        Some(
+        let ast = Some(
             Parameter::new(
                 keywords,
                 annotated_type.map(RcCell::new),
@@ -1891,7 +1909,8 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
                 None,
             )
             .into_ast(),
-        )
+        );
+        with_location(ast, (ctx.start().line, ctx.start().column))
     }
     fn visit_variableDeclaration(
         &mut self,
@@ -2064,42 +2083,43 @@ impl<'input> SolidityVisitorCompat<'input> for BuildASTVisitor {
     }
 
     fn visit_statement(&mut self, ctx: &StatementContext<'input>) -> Self::Return {
+        let start = (ctx.start().line, ctx.start().column);
         if let Some(statement) = ctx.ifStatement() {
             statement.accept(self);
 
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.whileStatement() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.forStatement() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.block() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.doWhileStatement() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.continueStatement() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.breakStatement() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.returnStatement() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         if let Some(statement) = ctx.simpleStatement() {
             statement.accept(self);
-            return self.temp_result().clone();
+            return with_location(self.temp_result().clone(), start);
         }
         None
     }
@@ -15,17 +15,15 @@ use type_check::type_checker::type_check as t;
 // use type_check::type_exceptions::TypeMismatchException, TypeException, RequireException, ReclassifyException;
 use crate::build_ast::build_ast;
 use zkay_ast::analysis::{
-    alias_analysis::alias_analysis as a,
-    call_graph::call_graph_analysis,
+    alias_analysis::alias_analysis as a, call_graph::call_graph_analysis,
     circuit_compatibility_checker::check_circuit_compliance,
-    hybrid_function_detector::detect_hybrid_functions,
-    loop_checker::check_loops,
-    return_checker::check_return as r,
-    side_effects::{check_for_undefined_behavior_due_to_eval_order, compute_modified_sets},
+    hybrid_function_detector::detect_hybrid_functions, return_checker::check_return as r,
+    side_effects::compute_modified_sets,
 };
 use zkay_ast::ast::{
     source_unit::SourceUnit, ASTBaseProperty, ASTFlatten, IdentifierBaseProperty, AST,
 }; //, AstException;
+use zkay_ast::lints::check_lints;
 use zkay_ast::pointers::{parent_setter::set_parents, symbol_table::link_identifiers as link};
 use zkay_utils::progress_printer::print_step;
 // use crate::pointers::pointer_exceptions::UnknownIdentifierException;
@@ -36,7 +34,7 @@ use zkay_ast::global_defs::{
 };
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct ASTFlags(u32);
+pub struct ASTFlags(u32);
 bitflags! {
     impl ASTFlags: u32 {
         const PARENTS           = 0b00000001;
@@ -45,18 +43,20 @@ bitflags! {
         const ALIAS_ANALYSIS      = 0b00100000;
         const TYPE_CHECK        = 0b00001000;
         const SOLC_CHECK        = 0b00010000;
+        const LINTS             = 0b01000000;
         const FLAG_ALL    = Self::PARENTS.bits()
                            | Self::LINK_IDENTIFIERS.bits()
                            | Self::CHECK_RETURN.bits()
                            | Self::ALIAS_ANALYSIS.bits()
                            | Self::TYPE_CHECK.bits()
-                           | Self::SOLC_CHECK.bits();
+                           | Self::SOLC_CHECK.bits()
+                           | Self::LINTS.bits();
     }
 }
 #[warn(dead_code)]
 impl ASTFlags {
     pub fn new(flag: Option<u32>) -> Self {
-        Self(flag.unwrap_or(0x3f))
+        Self(flag.unwrap_or(Self::FLAG_ALL.bits()))
     }
     // pub fn clear(&mut self) -> &mut ASTFlags {
     //     self
@@ -76,6 +76,9 @@ impl ASTFlags {
     pub fn type_check(&self) -> bool {
         *self & Self::TYPE_CHECK == Self::TYPE_CHECK
     }
+    pub fn lints(&self) -> bool {
+        *self & Self::LINTS == Self::LINTS
+    }
 
     // pub fn solc_check(&self) -> bool {
     //     *self & Self::SOLC_CHECK == Self::SOLC_CHECK
@@ -95,12 +98,13 @@ fn get_parsed_ast_and_fake_code(code: &str, solc_check: bool) -> (ASTFlatten, St
     //     raise ZkaySyntaxError(f"\n\nSYNTAX ERROR: {e}")
     });
 
-    let fake_code = solidity::fake_solidity_generator::fake_solidity_code(code);
+    let (fake_code, source_map) =
+        solidity::fake_solidity_generator::fake_solidity_code_with_source_map(code);
     if solc_check {
         // Solc type checking
         with_context_block!(var _ps=print_step("Type checking with solc")=>{
         // try:
-        check_for_zkay_solc_errors(code, &fake_code);
+        check_for_zkay_solc_errors(code, &fake_code, &source_map);
         // except SolcException as e:
         //     raise ZkayCompilerError(f"{e}")
         });
@@ -108,12 +112,12 @@ fn get_parsed_ast_and_fake_code(code: &str, solc_check: bool) -> (ASTFlatten, St
     (_ast, fake_code)
 }
 
-//parents:bool, link_identifiers:bool, check_return:bool, alias_analysis:bool, type_check:bool, solc_check:bool
+//parents:bool, link_identifiers:bool, check_return:bool, alias_analysis:bool, type_check:bool, solc_check:bool, lints:bool
 pub fn get_processed_ast(
     code: &str,
     flag: Option<u32>,
     global_vars: RcCell<GlobalVars>,
-) -> ASTFlatten {
+) -> eyre::Result<ASTFlatten> {
     let flag = ASTFlags::new(flag);
     // println!(
     //     "====flag=================={:?}======={:?}",
@@ -125,27 +129,31 @@ pub fn get_processed_ast(
 
     // Zkay preprocessing and type checking
     process_ast(
+        code,
         &ast.clone().into(),
         flag.parents(),
         flag.link_identifiers(),
         flag.check_return(),
         flag.alias_analysis(),
         flag.type_check(),
+        flag.lints(),
         global_vars,
-    );
+    )?;
 
-    ast
+    Ok(ast)
 }
 
 fn process_ast(
+    code: &str,
     ast: &ASTFlatten,
     parents: bool,
     link_identifiers: bool,
     check_return: bool,
     alias_analysis: bool,
     type_check: bool,
+    lints: bool,
     global_vars: RcCell<GlobalVars>,
-) {
+) -> eyre::Result<()> {
     with_context_block!(var _ps=print_step("Preprocessing AST")=>{
         if parents {
             set_parents(ast);
@@ -167,7 +175,6 @@ fn process_ast(
         // println!("{:?}", global_vars.borrow().vars().len());
         call_graph_analysis(ast);
         compute_modified_sets(ast);
-        check_for_undefined_behavior_due_to_eval_order(ast);
         // except AstException as e:
         //     raise AnalysisException(f"\n\nANALYSIS ERROR: {e}")
         // println!("======{type_check}=========process======before====");
@@ -182,20 +189,27 @@ fn process_ast(
         check_circuit_compliance(ast);
         //  println!("======check_circuit_compliance================*******222222**********===========");
         detect_hybrid_functions(ast);
-        check_loops(ast);
         //  println!("======check_circuit_compliance================*******3333**********===========");
         // except (TypeMismatchException, TypeException, RequireException, ReclassifyException) as e:
         //     raise TypeCheckException(f"\n\nCOMPILER ERROR: {e}")
         });
     }
+
+    // includes the eval_order and private_loop soundness checks, see zkay_ast::lints
+    if lints {
+        with_context_block!(var _ps=print_step("Running lints")=>{
+        check_lints(code, ast, type_check)?;
+        });
+    }
+    Ok(())
 }
 
 pub fn get_verification_contract_names(
     code_or_ast: (Option<String>, Option<ASTFlatten>),
     global_vars: RcCell<GlobalVars>,
-) -> Vec<String> {
+) -> eyre::Result<Vec<String>> {
     let ast = if let (Some(code), None) = code_or_ast {
-        Some(get_processed_ast(&code, None, global_vars))
+        Some(get_processed_ast(&code, None, global_vars)?)
     } else {
         code_or_ast.1.clone()
     };
@@ -224,5 +238,5 @@ pub fn get_verification_contract_names(
                 .collect::<Vec<_>>(),
         );
     }
-    vc_names
+    Ok(vc_names)
 }
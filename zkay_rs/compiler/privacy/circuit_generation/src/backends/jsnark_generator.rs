@@ -1452,7 +1452,7 @@ impl CircuitGenerator for JsnarkGenerator {
 
         //Invoke jsnark compilation if either the jsnark-wrapper or the current circuit was modified (based on hash comparison)
         if oldhash != digest
-            || output_dir
+            || !output_dir
                 .join("circuit.arith")
                 .try_exists()
                 .map_or(false, |v| v)
@@ -11,6 +11,7 @@
 // from abc import ABCMeta, abstractmethod
 // from multiprocessing import Pool, Value
 // from typing import List, Tuple
+use crate::cost_model::check_circuit_budget;
 use circuit_helper::circuit_helper::CircuitHelper;
 use circuit_helper_config::circuit_helper_config::CircuitHelperConfig;
 use proving_scheme::backends::{gm17::ProvingSchemeGm17, groth16::ProvingSchemeGroth16};
@@ -53,7 +54,16 @@ pub trait CircuitGenerator {
     fn _get_prover_key_hash(&self, circuit: &RcCell<CircuitHelper>) -> Vec<u8>;
     fn _get_primary_inputs(&self, circuit: &RcCell<CircuitHelper>) -> Vec<String>;
     fn base(&self) -> &CircuitGeneratorBase;
-    fn generate_circuits(&self, import_keys: bool) {
+    fn generate_circuits(&self, import_keys: bool) -> eyre::Result<()> {
+        if !import_keys {
+            // Refuse to spend hours on key generation for circuits which are too large anyway
+            let budget = CFG.lock().unwrap().max_circuit_constraints();
+            check_circuit_budget(
+                &self.base().circuits_to_prove,
+                &self.base().circuits,
+                budget,
+            )?;
+        }
         let _c_count = self.base().circuits_to_prove.len();
         zk_print!("Compiling {_c_count} circuits...");
 
@@ -195,6 +205,7 @@ pub trait CircuitGenerator {
                 // }
             }
         });
+        Ok(())
     }
     fn _generate_keys_par(&self, circuit: &RcCell<CircuitHelper>) {
         self._generate_keys(circuit);
@@ -0,0 +1,429 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(nonstandard_style)]
+#![allow(unused_imports)]
+#![allow(unused_mut)]
+#![allow(unused_braces)]
+
+// """
+// Static cost model for abstract proof circuits.
+
+// Walks the circuit statements of a CircuitHelper and estimates the number of constraints of the
+// resulting jsnark circuit as well as the gas required to verify a proof on-chain.
+// All numbers are rough estimates which are meant for comparing functions against each other,
+// not exact constraint counts.
+// """
+use circuit_helper::circuit_helper::CircuitHelper;
+use circuit_helper_config::circuit_helper_config::CircuitHelperConfig;
+use rccell::RcCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use zkay_ast::ast::{
+    is_instance, ASTChildren, ASTFlatten, ASTInstanceOf, ASTType, ArrayBaseProperty,
+    ConstructorOrFunctionDefinition, Expression, FunctionCallExprBaseProperty, IntoAST, AST,
+};
+use zkay_ast::circuit_constraints::CircuitStatement;
+use zkay_config::{config::CFG, config_user::UserConfig};
+
+// Approximate number of constraints of a single encryption or decryption check for the given crypto backend,
+// None if there is no estimate for the backend
+pub fn encryption_constraints(crypto_name: &str) -> Option<u64> {
+    match crypto_name {
+        "dummy" | "dummy-hom" => Some(1),
+        "ecdh-chaskey" => Some(7_000),
+        "ecdh-aes" => Some(35_000),
+        "elgamal" => Some(30_000),
+        "rsa-pkcs1.5" => Some(95_000),
+        "rsa-oaep" => Some(160_000),
+        "paillier" => Some(220_000),
+        _ => None,
+    }
+}
+
+// Approximate number of constraints of one sha256 compression (used for public input hashing)
+pub const SHA256_COMPRESSION_CONSTRAINTS: u64 = 27_000;
+
+// Number of sha256 compressions needed to hash `pub_arg_size` uint256 values
+pub fn sha256_compressions(pub_arg_size: i32) -> u64 {
+    // 8 bytes length + 1 byte padding
+    (pub_arg_size.max(0) as u64 * 32 + 9 + 63) / 64
+}
+
+// """
+// Estimated gas for verifying a proof on-chain.
+
+// :param proving_scheme: name of the proving scheme (groth16 or gm17)
+// :param pub_arg_size: number of public circuit inputs and outputs (in #uints)
+// :param uses_hash: whether the public inputs are hashed into a single public input
+// """
+pub fn verification_gas(proving_scheme: &str, pub_arg_size: i32, uses_hash: bool) -> u64 {
+    // ecPairing precompile (EIP-1108)
+    const PAIRING_BASE: u64 = 45_000;
+    const PAIRING_PER_PAIR: u64 = 34_000;
+    // ecMul + ecAdd per public input when accumulating the verification key
+    const PER_INPUT: u64 = 6_150;
+    // calldata and memory handling per uint256 argument
+    const PER_WORD: u64 = 600;
+
+    let pub_arg_size = pub_arg_size.max(0) as u64;
+    let pairing = match proving_scheme {
+        // one pairing check with 5 pairings and two checks in total, plus the BN256G2 library calls
+        "gm17" => 2 * PAIRING_BASE + 5 * PAIRING_PER_PAIR + 60_000,
+        // one pairing check with 4 pairings
+        _ => PAIRING_BASE + 4 * PAIRING_PER_PAIR,
+    };
+    let proof_words = CFG.lock().unwrap().proof_len() as u64;
+    let (n_inputs, hash_gas) = if uses_hash {
+        // sha256 precompile: 60 + 12 per word
+        (1, 60 + 12 * pub_arg_size)
+    } else {
+        (pub_arg_size, 0)
+    };
+    pairing + n_inputs * PER_INPUT + (proof_words + pub_arg_size) * PER_WORD + hash_gas
+}
+
+// Estimated costs of the proof circuit of a single function
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitCost {
+    pub function: String,
+    pub verification_contract: String,
+    // public circuit inputs/outputs including those of called functions (in #uints)
+    pub in_size: i32,
+    pub out_size: i32,
+    pub uses_hash: bool,
+    // number of encryption/decryption checks per crypto backend
+    pub encryptions: BTreeMap<String, u64>,
+    // number of private comparisons per operand bitwidth
+    pub comparisons: BTreeMap<i32, u64>,
+    // number of private arithmetic/bitwise operations per operand bitwidth
+    pub arithmetic: BTreeMap<i32, u64>,
+    // number of checked equalities (in #uints)
+    pub equalities: u64,
+    // number of sha256 compressions for public input hashing
+    pub hashes: u64,
+    pub constraints: u64,
+    pub verification_gas: u64,
+    // parts of the circuit which are not included in the constraint estimate
+    pub warnings: Vec<String>,
+}
+
+impl CircuitCost {
+    // Constraint estimate for each contributing category, largest first
+    // (encryptions of backends without an estimate are reported in warnings instead)
+    pub fn contributors(&self) -> Vec<(String, u64)> {
+        let mut res: Vec<_> = self
+            .encryptions
+            .iter()
+            .filter_map(|(backend, n)| {
+                Some((
+                    format!("{n}x {backend} encryption"),
+                    n * encryption_constraints(backend)?,
+                ))
+            })
+            .chain(self.comparisons.iter().map(|(bits, n)| {
+                (
+                    format!("{n}x {bits}-bit comparison"),
+                    n * comparison_constraints(*bits),
+                )
+            }))
+            .chain(self.arithmetic.iter().map(|(bits, n)| {
+                (
+                    format!("{n}x {bits}-bit arithmetic"),
+                    n * arithmetic_constraints(*bits),
+                )
+            }))
+            .chain([
+                (format!("{}x equality", self.equalities), self.equalities),
+                (
+                    format!("{}x sha256 compression", self.hashes),
+                    self.hashes * SHA256_COMPRESSION_CONSTRAINTS,
+                ),
+            ])
+            .filter(|(_, c)| *c > 0)
+            .collect();
+        res.sort_by(|a, b| b.1.cmp(&a.1));
+        res
+    }
+}
+
+fn comparison_constraints(bits: i32) -> u64 {
+    // bit decomposition of the difference
+    bits.max(1) as u64 + 2
+}
+
+fn arithmetic_constraints(bits: i32) -> u64 {
+    // truncation of the result to the operand bitwidth
+    bits.max(1) as u64
+}
+
+// """
+// Estimate the costs of all circuits.
+
+// :param circuits: all circuit helpers of the transformed AST, keyed by function (used to resolve CircCalls)
+// :return: one entry per circuit which requires verification, in function order
+// """
+pub fn estimate_circuit_costs(
+    circuits: &BTreeMap<RcCell<ConstructorOrFunctionDefinition>, RcCell<CircuitHelper>>,
+) -> Vec<CircuitCost> {
+    circuits
+        .values()
+        .filter(|c| c.borrow().requires_verification())
+        .map(|c| estimate_circuit_cost(c, circuits))
+        .collect()
+}
+
+pub fn estimate_circuit_cost(
+    circuit: &RcCell<CircuitHelper>,
+    circuits: &BTreeMap<RcCell<ConstructorOrFunctionDefinition>, RcCell<CircuitHelper>>,
+) -> CircuitCost {
+    let c = circuit.borrow();
+    let in_size = c.in_size_trans();
+    let out_size = c.out_size_trans();
+    let (uses_hash, proving_scheme) = {
+        let cfg = CFG.lock().unwrap();
+        (
+            cfg.should_use_hash(in_size + out_size),
+            cfg.proving_scheme(),
+        )
+    };
+    let mut cost = CircuitCost {
+        function: c.fct.borrow().name(),
+        verification_contract: c
+            .verifier_contract_type
+            .borrow()
+            .as_ref()
+            .map(|_| c.get_verification_contract_name())
+            .unwrap_or_default(),
+        in_size,
+        out_size,
+        uses_hash,
+        ..Default::default()
+    };
+    count_statements(&c.phi(), circuits, &mut cost, 0);
+    for (backend, n) in &cost.encryptions {
+        if encryption_constraints(backend).is_none() {
+            cost.warnings.push(format!(
+                "no constraint estimate for crypto backend {backend}, its {n} encryption(s) are not counted"
+            ));
+        }
+    }
+    if uses_hash {
+        cost.hashes = sha256_compressions(in_size + out_size);
+    }
+    cost.constraints = cost.contributors().iter().map(|(_, c)| c).sum();
+    cost.verification_gas = verification_gas(&proving_scheme, in_size + out_size, uses_hash);
+    cost
+}
+
+// """
+// Check the estimated size of the given circuits against a constraint budget (max_circuit_constraints).
+
+// :param budget: maximum number of constraints per circuit, the check is disabled if budget <= 0
+// :return: one message per circuit which exceeds the budget, naming its largest contributors
+// """
+pub fn circuit_budget_violations(
+    circuits_to_check: &[RcCell<CircuitHelper>],
+    circuits: &BTreeMap<RcCell<ConstructorOrFunctionDefinition>, RcCell<CircuitHelper>>,
+    budget: i32,
+) -> Vec<String> {
+    if budget <= 0 {
+        return vec![];
+    }
+    circuits_to_check
+        .iter()
+        .map(|c| estimate_circuit_cost(c, circuits))
+        .filter(|cost| cost.constraints > budget as u64)
+        .map(|cost| {
+            let contributors: Vec<_> = cost
+                .contributors()
+                .into_iter()
+                .take(3)
+                .map(|(what, n)| format!("{what} (~{n})"))
+                .collect();
+            format!(
+                "Circuit of function {} needs ~{} constraints, which exceeds max_circuit_constraints ({budget}); largest contributors: {}",
+                cost.function,
+                cost.constraints,
+                contributors.join(", ")
+            )
+        })
+        .collect()
+}
+
+// Like circuit_budget_violations, but fails with all violations
+pub fn check_circuit_budget(
+    circuits_to_check: &[RcCell<CircuitHelper>],
+    circuits: &BTreeMap<RcCell<ConstructorOrFunctionDefinition>, RcCell<CircuitHelper>>,
+    budget: i32,
+) -> eyre::Result<()> {
+    let violations = circuit_budget_violations(circuits_to_check, circuits, budget);
+    eyre::ensure!(violations.is_empty(), "{}", violations.join("\n"));
+    Ok(())
+}
+
+// Private function calls are inlined into the caller's circuit, recursion is not allowed
+const MAX_CALL_DEPTH: usize = 32;
+
+fn count_statements(
+    stmts: &[RcCell<CircuitStatement>],
+    circuits: &BTreeMap<RcCell<ConstructorOrFunctionDefinition>, RcCell<CircuitHelper>>,
+    cost: &mut CircuitCost,
+    depth: usize,
+) {
+    for stmt in stmts {
+        match &*stmt.borrow() {
+            CircuitStatement::CircIndentBlock(block) => {
+                count_statements(&block.statements, circuits, cost, depth)
+            }
+            CircuitStatement::CircCall(call) => {
+                let name = call.fct.name();
+                if depth >= MAX_CALL_DEPTH {
+                    cost.warnings.push(format!(
+                        "call to {name} exceeds the maximum call depth of {MAX_CALL_DEPTH}, its constraints are not counted"
+                    ));
+                    continue;
+                }
+                match circuits.iter().find(|(f, _)| *f.borrow() == call.fct) {
+                    Some((_, target)) => {
+                        let phi = target.borrow().phi();
+                        count_statements(&phi, circuits, cost, depth + 1);
+                    }
+                    None => cost.warnings.push(format!(
+                        "no circuit found for called function {name}, its constraints are not counted"
+                    )),
+                }
+            }
+            CircuitStatement::CircVarDecl(decl) => count_expression(&decl.expr, cost),
+            CircuitStatement::CircEqConstraint(eq) => {
+                cost.equalities += eq
+                    .tgt
+                    .t
+                    .to_ast()
+                    .try_as_type_name()
+                    .map_or(1, |t| t.size_in_uints().max(1) as u64);
+            }
+            CircuitStatement::CircEncConstraint(enc) => {
+                *cost
+                    .encryptions
+                    .entry(crypto_name(&enc.cipher.t))
+                    .or_default() += 1;
+            }
+            CircuitStatement::CircSymmEncConstraint(enc) => {
+                *cost
+                    .encryptions
+                    .entry(crypto_name(&enc.iv_cipher.t))
+                    .or_default() += 1;
+            }
+            CircuitStatement::CircComment(_) | CircuitStatement::CircGuardModification(_) => {}
+        }
+    }
+}
+
+fn crypto_name(t: &ASTFlatten) -> String {
+    t.to_ast()
+        .try_as_type_name()
+        .and_then(|t| {
+            t.try_as_array_ref()
+                .and_then(|a| a.crypto_params().as_ref().map(|p| p.crypto_name.clone()))
+        })
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+// Bitwidth of the annotated type of `expr`, 256 if unknown
+fn bitwidth_of(expr: &ASTFlatten) -> i32 {
+    expr.ast_base_ref()
+        .and_then(|base| base.borrow().annotated_type.clone())
+        .and_then(|at| at.borrow().type_name.clone())
+        .and_then(|t| t.to_ast().try_as_type_name().map(|t| t.elem_bitwidth()))
+        .unwrap_or(256)
+}
+
+fn count_expression(expr: &ASTFlatten, cost: &mut CircuitCost) {
+    if matches!(
+        expr.to_ast(),
+        AST::Expression(Expression::FunctionCallExpr(_))
+    ) {
+        let (func, args) = {
+            let e = expr.try_as_expression_ref().unwrap().borrow();
+            let fce = e.try_as_function_call_expr_ref().unwrap();
+            (fce.func().clone(), fce.args().clone())
+        };
+        if is_instance(&func, ASTType::BuiltinFunction) {
+            let (is_comp, is_arith) = {
+                let f = func.try_as_expression_ref().unwrap().borrow();
+                let bf = f.try_as_builtin_function_ref().unwrap();
+                (
+                    bf.is_comp(),
+                    bf.is_arithmetic() || bf.is_bitop() || bf.is_shiftop(),
+                )
+            };
+            let bits = args.first().map_or(256, bitwidth_of);
+            if is_comp {
+                *cost.comparisons.entry(bits).or_default() += 1;
+            } else if is_arith {
+                *cost.arithmetic.entry(bits).or_default() += 1;
+            }
+        }
+    }
+    for child in expr.children() {
+        count_expression(&child, cost);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_backend_is_not_estimated() {
+        assert_eq!(encryption_constraints("elgamal"), Some(30_000));
+        assert_eq!(encryption_constraints("unknown"), None);
+
+        let cost = CircuitCost {
+            encryptions: BTreeMap::from([
+                (String::from("elgamal"), 2),
+                (String::from("unknown"), 3),
+            ]),
+            comparisons: BTreeMap::from([(32, 1)]),
+            arithmetic: BTreeMap::from([(8, 4)]),
+            equalities: 5,
+            ..Default::default()
+        };
+        assert_eq!(
+            cost.contributors(),
+            vec![
+                (String::from("2x elgamal encryption"), 60_000),
+                (String::from("1x 32-bit comparison"), 34),
+                (String::from("4x 8-bit arithmetic"), 32),
+                (String::from("5x equality"), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn public_input_hashing() {
+        assert_eq!(sha256_compressions(0), 1);
+        assert_eq!(sha256_compressions(1), 1);
+        assert_eq!(sha256_compressions(2), 2);
+        assert_eq!(sha256_compressions(10), 6);
+    }
+
+    #[test]
+    fn verification_gas_estimate() {
+        let proof_words = CFG.lock().unwrap().proof_len() as u64;
+        assert_eq!(
+            verification_gas("groth16", 3, false),
+            181_000 + 3 * 6_150 + (proof_words + 3) * 600
+        );
+        assert_eq!(
+            verification_gas("groth16", 3, true),
+            181_000 + 6_150 + (proof_words + 3) * 600 + 60 + 36
+        );
+        assert_eq!(
+            verification_gas("gm17", 0, false),
+            320_000 + proof_words * 600
+        );
+        assert!(verification_gas("groth16", 20, true) < verification_gas("groth16", 20, false));
+    }
+}
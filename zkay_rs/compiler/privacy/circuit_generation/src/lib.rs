@@ -7,6 +7,7 @@
 // * :py:mod:`.circuit_helper`:     Helper class to construct high-level abstract proof circuits
 // * :py:mod:`.circuit_constraints` Defines the different types of abstract circuit statements
 // * :py:mod:`.circuit_generator`   Compiles abstract proof circuits generated by circuit_helper into concrete proof circuits and generates verification contracts
+// * :py:mod:`.cost_model`          Estimates constraint counts and verification gas of abstract proof circuits
 
 // ===========
 // Subpackages
@@ -16,5 +17,6 @@
 pub mod backends;
 // pub mod circuit_constraints;
 pub mod circuit_generator;
+pub mod cost_model;
 // pub mod circuit_helper;
 // pub mod name_factory;
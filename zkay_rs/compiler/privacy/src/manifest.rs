@@ -27,11 +27,21 @@ impl Manifest {
     // @staticmethod
     // """Returned parsed manifest json file located in project dir::"""
     pub fn load(project_dir: &str) -> Value {
-        let f = File::open(Path::new(project_dir).join("manifest::json"));
+        Self::try_load(project_dir).unwrap()
+    }
+
+    // Like load, but reports a missing or malformed manifest instead of panicking
+    pub fn try_load(project_dir: &str) -> std::io::Result<Value> {
         let mut s = String::new();
-        f.unwrap().read_to_string(&mut s).unwrap();
-        let j: Value = serde_json::from_str(&s).unwrap();
-        j
+        File::open(Path::new(project_dir).join("manifest.json"))?.read_to_string(&mut s)?;
+        Ok(serde_json::from_str(&s)?)
+    }
+
+    // Whether the configuration already uses the solc version and compiler settings of manifest
+    pub fn is_current_config(manifest: &Value) -> bool {
+        let cfg = CFG.lock().unwrap();
+        manifest[Manifest::solc_version].as_str() == Some(cfg.solc_version().as_str())
+            && manifest[Manifest::zkay_options] == cfg.export_compiler_settings()
     }
 
     // @staticmethod
@@ -61,7 +71,7 @@ impl Manifest {
 
     // @staticmethod
     // @contextmanager
-    pub fn with_manifest_config(manifest: &str) -> WithManifestConfig {
+    pub fn with_manifest_config(manifest: Value) -> WithManifestConfig {
         // try
         // yield
         // finally
@@ -69,15 +79,23 @@ impl Manifest {
     }
 }
 
+// Leaves the configuration untouched if it already matches the manifest (e.g. it was imported once by a
+// service), so concurrent users of the same project never observe a swapped configuration
 pub struct WithManifestConfig {
     old_solc: Option<String>,
     old_settings: Value,
 }
 impl WithManifestConfig {
-    pub fn new(manifest: &str) -> Self {
+    pub fn new(manifest: Value) -> Self {
+        if Manifest::is_current_config(&manifest) {
+            return Self {
+                old_solc: None,
+                old_settings: Value::Null,
+            };
+        }
         let old_solc = Some(CFG.lock().unwrap().solc_version());
         let old_settings = CFG.lock().unwrap().export_compiler_settings();
-        Manifest::import_manifest_config(Value::String(manifest.to_string()));
+        Manifest::import_manifest_config(manifest);
         Self {
             old_solc,
             old_settings,
@@ -86,9 +104,10 @@ impl WithManifestConfig {
 }
 impl Drop for WithManifestConfig {
     fn drop(&mut self) {
-        CFG.lock()
-            .unwrap()
-            .override_solc(self.old_solc.clone().unwrap());
+        let Some(old_solc) = self.old_solc.take() else {
+            return;
+        };
+        CFG.lock().unwrap().override_solc(old_solc);
         CFG.lock()
             .unwrap()
             .import_compiler_settings(self.old_settings.clone());
@@ -1221,6 +1221,16 @@ impl ZkayTransformer {
             .collect();
         f.borrow_mut().modifiers = modifiers;
         f.borrow_mut().requires_verification_when_external = false;
+        // the external wrapper stems from the same zkay function (used to map solc errors back)
+        {
+            let (line, column) = {
+                let base = f.borrow().ast_base_ref();
+                let base = base.borrow();
+                (base.line, base.column)
+            };
+            new_f.ast_base_ref().borrow_mut().line = line;
+            new_f.ast_base_ref().borrow_mut().column = column;
+        }
         let mut new_f = RcCell::new(new_f);
         // Create new circuit for external function
         let circuit = Self::create_circuit_helper(
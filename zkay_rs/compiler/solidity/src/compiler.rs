@@ -16,6 +16,7 @@
 // from solcx import compile_standard
 // from solcx.exceptions import SolcError
 
+use crate::source_map::SourceMap;
 use serde_json::{Map, Result, Value};
 use std::io::Read;
 use std::path::PathBuf;
@@ -156,7 +157,7 @@ fn solc_compile(_input: &str, solp: &str, input_json_file_path: &str) -> String
     //     .spawn()
     //     .expect("failed echo command");
     let arg = format!("{solp},.");
-    let mut solc = Command::new("/Users/lisheng/mygit/arron/zkay-rs/solc-macos")
+    let mut solc = Command::new(crate::solc_manager::solc_binary())
         .args(["--standard-json", "--allow-paths", &arg])
         .stdin(input)
         .output()
@@ -198,6 +199,17 @@ pub fn get_error_order_key(error: &Value) -> i64 {
 // :param display_code: code to use when displaying the compiler errors
 // :raise SolcException: raised if solc reports a compiler error
 pub fn check_compilation(filename: &str, show_errors: bool, display_code: &str) {
+    check_compilation_with_source_map(filename, show_errors, display_code, None)
+}
+
+// Same as check_compilation, but solc source locations are translated to display_code with source_map
+// (display_code being the code from which the file was generated)
+pub fn check_compilation_with_source_map(
+    filename: &str,
+    show_errors: bool,
+    display_code: &str,
+    source_map: Option<&SourceMap>,
+) {
     let p = PathBuf::from(filename);
     let sol_name = p.file_name().unwrap();
     let mut f = File::open(filename).unwrap();
@@ -250,13 +262,21 @@ pub fn check_compilation(filename: &str, show_errors: bool, display_code: &str)
                             .as_i64()
                             .unwrap() as i32;
                         if file == sol_name {
-                            let (line, column) = _get_line_col(&code, start);
+                            let (line, column) = match source_map {
+                                Some(source_map) => {
+                                    source_map.original_line_col(&display_code, start as usize)
+                                }
+                                None => {
+                                    let (line, column) = _get_line_col(&code, start);
+                                    (line, column + 1)
+                                }
+                            };
                             had_error |= is_error;
                             format!(
                                 "{:?}\n",
                                 get_code_error_msg(
                                     line,
-                                    column + 1,
+                                    column,
                                     display_code.split("\n").map(String::from).collect(),
                                     None,
                                     None,
@@ -317,7 +337,12 @@ pub fn check_compilation(filename: &str, show_errors: bool, display_code: &str)
 // Fake solidity code = zkay code with privacy features removed in a source-location preserving way (whitespace padding)
 // :param zkay_code: Original zkay code
 // :param fake_solidity_code: Corresponding "fake solidity code"
-pub fn check_for_zkay_solc_errors(zkay_code: &str, fake_solidity_code: &str) {
+// :param source_map: Source map from the fake solidity code to the zkay code
+pub fn check_for_zkay_solc_errors(
+    zkay_code: &str,
+    fake_solidity_code: &str,
+    source_map: &SourceMap,
+) {
     use std::env::temp_dir;
     use std::fs::File;
     use std::io::Result;
@@ -337,7 +362,7 @@ pub fn check_for_zkay_solc_errors(zkay_code: &str, fake_solidity_code: &str) {
     // with tempfile.NamedTemporaryFile('w', suffix='.sol') as f
     //     f.write(fake_solidity_code)
     //     f.flush()
-    check_compilation(dir.to_str().unwrap(), true, zkay_code);
+    check_compilation_with_source_map(dir.to_str().unwrap(), true, zkay_code, Some(source_map));
 }
 
 // def compile_solidity_code(code: str, working_directory: Optional[str] = None, optimizer_runs=cfg.opt_solc_optimizer_runs) -> Dict:
@@ -2,6 +2,7 @@
 // // so that code can be passed to solc for type checking.
 
 // import re
+use crate::source_map::SourceMap;
 use fancy_regex::{Captures, Regex as Regexf};
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -63,9 +64,16 @@ static ref ATYPE_PATTERN : Regex =  Regex::new(&format!(r"(?P<keep>{NONID_START}
 // :return whitespace string with same length as instr and with the same line breaks
 // """
 pub fn create_surrogate_string(instr: &str) -> String {
+    // one space per byte, solc reports source locations as byte offsets
     instr
         .chars()
-        .map(|e| if e == '\n' { '\n' } else { ' ' })
+        .map(|e| {
+            if e == '\n' {
+                String::from("\n")
+            } else {
+                " ".repeat(e.len_utf8())
+            }
+        })
         .collect()
 }
 
@@ -319,18 +327,33 @@ pub fn replace_with_surrogatef(
 // while preserving original formatting
 // """
 pub fn fake_solidity_code(code: &str) -> String {
+    fake_solidity_code_with_source_map(code).0
+}
+
+// """
+// Same as fake_solidity_code, but additionally returns a source map from the fake solidity code back to code.
+// All privacy features are replaced by whitespace of the same length, so only the pragma, the injected
+// me declarations and the license header move source locations.
+// """
+pub fn fake_solidity_code_with_source_map(code: &str) -> (String, SourceMap) {
+    let mut source_map = SourceMap::identity(code.len());
+
     // Strip string literals and comments
     let mut code = replace_with_surrogatef(code, &STRING_OR_COMMENT_PATTERN, "");
 
     // Replace zkay pragma with solidity pragma
-    code = replace_with_surrogate(
-        &code,
-        &PRAGMA_PATTERN,
-        &format!(
-            " solidity {};",
-            CFG.lock().unwrap().zkay_solc_version_compatibility()
-        ),
+    let pragma = format!(
+        " solidity {};",
+        CFG.lock().unwrap().zkay_solc_version_compatibility()
     );
+    let mut shift = 0isize;
+    for caps in PRAGMA_PATTERN.captures_iter(&code) {
+        let start = caps.name("repl").unwrap().start() as isize + shift;
+        let end = caps.get(0).unwrap().end() as isize + shift;
+        source_map.replace(start as usize..end as usize, pragma.len());
+        shift += pragma.len() as isize - (end - start);
+    }
+    code = replace_with_surrogate(&code, &PRAGMA_PATTERN, &pragma);
 
     // Strip final
     code = replace_with_surrogatef(&code, &FINAL_PATTERN, "");
@@ -338,10 +361,8 @@ pub fn fake_solidity_code(code: &str) -> String {
     // Strip ownership annotations
     code = replace_with_surrogate(&code, &ATYPE_PATTERN, "");
 
-    // println!("===={}====before====={code}",MAP_PATTERN.as_str());
     // Strip map key tags
     code = replace_with_surrogatef(&code, &MAP_PATTERN, "");
-    // println!("======after======={code}");
 
     // Strip addhom / unhom expressions
     code = replace_with_surrogatef(&code, &ADDHOM_UNHOM_PATTERN, "");
@@ -351,7 +372,187 @@ pub fn fake_solidity_code(code: &str) -> String {
 
     // Inject me address declaration (should be okay for type checking, maybe not for program analysis)
     // An alternative would be to replace me by msg.sender, but this would affect code length (error locations)
+    for (i, m) in CONTRACT_START_PATTERN.find_iter(&code).enumerate() {
+        let loc = find_matching_parenthesis(&code, m.unwrap().end() as i32) as usize;
+        source_map.insert(loc + i * ME_DECL.len(), ME_DECL.len());
+    }
     code = inject_me_decls(&code);
 
-    format!("//SPDX-License-Identifier: UNLICENSED\n{code}")
+    let header = "//SPDX-License-Identifier: UNLICENSED\n";
+    source_map.insert(0, header.len());
+    (format!("{header}{code}"), source_map)
+}
+
+// """
+// Return a standalone solidity version of code which keeps all comments and the original layout.
+// In contrast to fake_solidity_code, zkay specific language features are not blanked out but turned into
+// inline comments (e.g. uint@me -> uint/*@me*/), so that the result can be fed to solidity linters,
+// storage layout tools and coverage instrumentation.
+// """
+pub fn public_solidity_code(code: &str) -> String {
+    let mut fake = replace_with_surrogatef(code, &STRING_OR_COMMENT_PATTERN, "");
+    let unchanged = fake.clone();
+    fake = replace_with_surrogatef(&fake, &FINAL_PATTERN, "");
+    fake = replace_with_surrogate(&fake, &ATYPE_PATTERN, "");
+    fake = replace_with_surrogatef(&fake, &MAP_PATTERN, "");
+    fake = replace_with_surrogatef(&fake, &ADDHOM_UNHOM_PATTERN, "");
+    fake = strip_reveals(&fake);
+    assert_eq!(
+        fake.len(),
+        code.len(),
+        "surrogate replacement must preserve source locations"
+    );
+
+    // Characters which were blanked out by the replacements above and are not part of a comment or string
+    let protected = comment_or_string_mask(code);
+    let (src, orig, stripped) = (code.as_bytes(), unchanged.as_bytes(), fake.as_bytes());
+    let removed: Vec<_> = (0..src.len())
+        .map(|i| {
+            !protected[i] && !orig[i].is_ascii_whitespace() && stripped[i].is_ascii_whitespace()
+        })
+        .collect();
+
+    // Group removed characters into runs, a run may span blanks within a line (e.g. "@ me" or ", all")
+    let mut runs = vec![];
+    let mut i = 0;
+    while i < src.len() {
+        if !removed[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut end = i + 1;
+        let mut j = end;
+        while j < src.len() && (removed[j] || src[j] == b' ' || src[j] == b'\t') {
+            if removed[j] {
+                end = j + 1;
+            }
+            j += 1;
+        }
+        runs.push((start, end));
+        i = end;
+    }
+
+    let me_decl_locs: Vec<_> = CONTRACT_START_PATTERN
+        .find_iter(&fake)
+        .map(|m| find_matching_parenthesis(&fake, m.unwrap().end() as i32) as usize)
+        .collect();
+
+    let mut out = String::with_capacity(code.len() + 4 * runs.len());
+    let mut runs = runs.into_iter().peekable();
+    let mut pos = 0;
+    for (idx, _) in code.char_indices() {
+        if idx < pos {
+            continue;
+        }
+        if me_decl_locs.contains(&idx) {
+            out.push_str(ME_DECL);
+        }
+        match runs.peek() {
+            Some(&(start, end)) if start == idx => {
+                out.push_str(&format!("/*{}*/", &code[start..end]));
+                runs.next();
+                pos = end;
+            }
+            _ => {
+                let c = code[idx..].chars().next().unwrap();
+                out.push(c);
+                pos = idx + c.len_utf8();
+            }
+        }
+    }
+
+    // pragma zkay -> pragma solidity, the zkay version requirement is kept as a comment
+    let solc_version = CFG.lock().unwrap().zkay_solc_version_compatibility();
+    let out = PRAGMA_PATTERN.replace(&out, |caps: &regex::Captures| {
+        format!(
+            "{}solidity {solc_version}; /* {} */",
+            &caps["keep"], &caps["repl"]
+        )
+    });
+    if out.contains("SPDX-License-Identifier") {
+        out.into_owned()
+    } else {
+        format!("//SPDX-License-Identifier: UNLICENSED\n{out}")
+    }
+}
+
+// Mark all bytes of code which belong to a comment or a string literal
+pub(crate) fn comment_or_string_mask(code: &str) -> Vec<bool> {
+    let src = code.as_bytes();
+    let mut mask = vec![false; src.len()];
+    let mut i = 0;
+    while i < src.len() {
+        let start = i;
+        match src[i] {
+            b'/' if src.get(i + 1) == Some(&b'/') => {
+                while i < src.len() && src[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if src.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < src.len() && !(src[i] == b'*' && src.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(src.len());
+            }
+            q @ (b'"' | b'\'') => {
+                i += 1;
+                while i < src.len() && src[i] != q && src[i] != b'\n' {
+                    i += if src[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(src.len());
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        mask[start..i].iter_mut().for_each(|m| *m = true);
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_map::line_col_offset;
+
+    #[test]
+    fn annotations_become_comments() {
+        let code = "pragma zkay ^0.3.0;\ncontract A {\n    // owner@me\n    final address owner;\n    mapping(address!x => uint@x) bal;\n    function f(uint@me v) public {\n        bal[me] = reveal(v, all);\n    }\n}\n";
+        let sol = public_solidity_code(code);
+        assert!(sol.starts_with("//SPDX-License-Identifier: UNLICENSED\n"));
+        assert!(sol.contains("pragma solidity "));
+        assert!(sol.contains("/* zkay ^0.3.0 */"));
+        assert!(sol.contains("// owner@me\n"));
+        assert!(sol.contains("/*final*/ address owner;"));
+        assert!(sol.contains("mapping(address/*!x*/ => uint/*@x*/) bal;"));
+        assert!(sol.contains("function f(uint/*@me*/ v)"));
+        assert!(sol.contains("bal[me] = /*reveal*/(v/*, all*/);"));
+        assert!(sol.contains(&format!("{ME_DECL}}}")));
+    }
+
+    #[test]
+    fn non_ascii_comments_and_strings() {
+        let code = "pragma zkay ^0.3.0;\ncontract A {\n    // gr\u{fc}\u{df}e \u{20ac}\n    uint@me x; /* \u{e9}t\u{e9} */ uint@all y;\n    function f(uint@me v) public {\n        require(reveal(v, all) > 0, \"\u{fc}ber \u{20ac}\");\n    }\n}\n";
+        assert_eq!(fake_solidity_code(code).len(), code.len());
+        let sol = public_solidity_code(code);
+        assert!(sol.contains("// gr\u{fc}\u{df}e \u{20ac}\n"));
+        assert!(sol.contains("uint/*@me*/ x; /* \u{e9}t\u{e9} */ uint/*@all*/ y;"));
+        assert!(sol.contains("require(/*reveal*/(v/*, all*/) > 0, \"\u{fc}ber \u{20ac}\");"));
+    }
+
+    #[test]
+    fn source_map_points_to_zkay_code() {
+        let code = "pragma zkay ^0.3.0;\n// gr\u{fc}\u{df}e\ncontract A {\n    uint@me x;\n}\ncontract B {\n    function f() public {}\n}\n";
+        let (fake, source_map) = fake_solidity_code_with_source_map(code);
+        assert_eq!(source_map.generated_len(), fake.len());
+        for needle in ["contract A", "uint", "x;", "contract B", "function f"] {
+            let (line, column) = source_map.original_line_col(code, fake.find(needle).unwrap());
+            let expected = code.find(needle).unwrap();
+            assert_eq!(line_col_offset(code, line, column), expected, "{needle}");
+        }
+    }
 }
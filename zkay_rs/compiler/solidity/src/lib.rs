@@ -6,6 +6,10 @@
 // ==========
 // * :py:mod:`.compiler`: Type-check or compile solidity code (uses standard_json interface internally).
 // * :py:mod:`.fake_solidity_generator`: Strip privacy features from zkay in a source-code location preserving way, so that type-checking/analysis can be performed with tools designed for solidity code.
+// * :py:mod:`.source_map`: Map locations in generated solidity code back to the zkay source.
+// * :py:mod:`.solc_manager`: Offline cache of solc binaries and selection of the solc version to use.
 // """
 pub mod compiler;
 pub mod fake_solidity_generator;
+pub mod solc_manager;
+pub mod source_map;
@@ -0,0 +1,211 @@
+// """
+// Offline management of solc binaries.
+
+// Every solc version is kept in its own directory of the cache ``<data_dir>/solc/<version>/solc``.
+// Binaries are never downloaded, instead they are imported from local files or directories
+// (e.g. a directory copied onto an air-gapped machine), see ``zkay update-solc``.
+// """
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use zkay_config::{config::CFG, config_user::UserConfig, config_version::Versions};
+
+// Name of a cached binary inside its version directory
+const SOLC_BINARY_NAME: &str = if cfg!(windows) { "solc.exe" } else { "solc" };
+
+lazy_static! {
+    static ref SOLC_VERSION_REGEX: Regex =
+        Regex::new(r"Version:\s*v?(\d+\.\d+\.\d+)").expect("solc version regex");
+}
+
+// """Directory containing one subdirectory per installed solc version."""
+pub fn solc_cache_dir() -> PathBuf {
+    PathBuf::from(CFG.lock().unwrap().data_dir()).join("solc")
+}
+
+fn strip_v(version: &str) -> &str {
+    version.strip_prefix('v').unwrap_or(version)
+}
+
+// """Path of the cached binary for version, if it is installed."""
+pub fn installed_solc_path(version: &str) -> Option<PathBuf> {
+    installed_solc_path_in(&solc_cache_dir(), version)
+}
+
+fn installed_solc_path_in(cache_dir: &Path, version: &str) -> Option<PathBuf> {
+    let path = cache_dir.join(strip_v(version)).join(SOLC_BINARY_NAME);
+    path.is_file().then_some(path)
+}
+
+// """All installed solc versions in ascending order."""
+pub fn installed_versions() -> Vec<String> {
+    installed_versions_in(&solc_cache_dir())
+}
+
+fn installed_versions_in(cache_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return vec![];
+    };
+    let mut versions: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join(SOLC_BINARY_NAME).is_file())
+        .filter_map(|e| e.file_name().to_str().map(String::from))
+        .filter(|v| semver_rs::Version::new(v).parse().is_ok())
+        .collect();
+    versions.sort_by(|a, b| semver_rs::compare(a, b, None).unwrap_or(Ordering::Equal));
+    versions
+}
+
+// """The latest installed version satisfying the npm-style semver range (e.g. ^0.6.0)."""
+pub fn resolve_version(range: &str) -> Option<String> {
+    resolve_version_in(&solc_cache_dir(), range)
+}
+
+fn resolve_version_in(cache_dir: &Path, range: &str) -> Option<String> {
+    installed_versions_in(cache_dir)
+        .into_iter()
+        .rev()
+        .find(|v| semver_rs::satisfies(v, range, None).unwrap_or(false))
+}
+
+// """Version reported by `<binary> --version`, None if binary is not a working solc executable."""
+pub fn solc_binary_version(binary: &Path) -> Option<String> {
+    let output = Command::new(binary).arg("--version").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    SOLC_VERSION_REGEX
+        .captures(&stdout)
+        .map(|c| c[1].to_string())
+}
+
+// """
+// Copy a solc binary into the cache.
+
+// :param binary: path to a solc executable
+// :return: the installed version
+// """
+pub fn install_solc_binary(binary: &Path) -> Result<String, String> {
+    let version = solc_binary_version(binary)
+        .ok_or_else(|| format!("{} is not a solc executable", binary.display()))?;
+    let dir = solc_cache_dir().join(&version);
+    let target = dir.join(SOLC_BINARY_NAME);
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::copy(binary, &target))
+        .map_err(|e| format!("Could not install {}: {e}", binary.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Could not install {}: {e}", binary.display()))?;
+    }
+    Ok(version)
+}
+
+// """
+// Install every solc executable found directly inside dir (other files are skipped).
+
+// :return: the installed versions
+// """
+pub fn import_from_dir(dir: &Path) -> Result<Vec<String>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Could not read {}: {e}", dir.display()))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+    let mut versions = vec![];
+    for path in paths {
+        if solc_binary_version(&path).is_some() {
+            versions.push(install_solc_binary(&path)?);
+        }
+    }
+    Ok(versions)
+}
+
+// Version of the solc found on PATH, if any
+fn path_solc_version() -> Option<String> {
+    solc_binary_version(Path::new(SOLC_BINARY_NAME))
+}
+
+// """
+// Select the solc version used for compilation.
+
+// :param version: 'latest' (latest installed version compatible with this zkay version) or a concrete version
+// :return: the selected version
+// """
+pub fn set_solc_version(version: &str) -> Result<String, String> {
+    let range = CFG.lock().unwrap().zkay_solc_version_compatibility();
+    let version = strip_v(version);
+    let selected = if version == "latest" {
+        resolve_version(&range)
+            .or_else(|| {
+                path_solc_version()
+                    .filter(|v| semver_rs::satisfies(v, &range, None).unwrap_or(false))
+            })
+            .ok_or_else(|| {
+                format!(
+                    "No compatible solc version is installed.\n\
+                     Please use \"zkay update-solc\" to install a solc version satisfying {range}."
+                )
+            })?
+    } else {
+        if !semver_rs::satisfies(version, &range, None)
+            .map_err(|e| format!("Invalid version string {version}\n{e:?}"))?
+        {
+            return Err(format!(
+                "Zkay only supports solc versions satisfying {range}"
+            ));
+        }
+        if installed_solc_path(version).is_none() && path_solc_version().as_deref() != Some(version)
+        {
+            return Err(format!(
+                "solc version {version} is not installed.\n\
+                 Please use \"zkay update-solc\" to install it."
+            ));
+        }
+        version.to_string()
+    };
+    CFG.lock().unwrap().set_solc_version(selected.clone());
+    Ok(selected)
+}
+
+// """The solc executable of the configured version (falls back to the solc on PATH)."""
+pub fn solc_binary() -> PathBuf {
+    let version = CFG.lock().unwrap().solc_version();
+    installed_solc_path(&version).unwrap_or_else(|| PathBuf::from(SOLC_BINARY_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_installed_versions() {
+        let cache_dir = std::env::temp_dir().join(format!("zkay-solc-{}", uuid::Uuid::new_v4()));
+        for version in ["0.6.9", "0.5.17", "0.6.12"] {
+            let dir = cache_dir.join(version);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(SOLC_BINARY_NAME), "").unwrap();
+        }
+        // not a version directory
+        fs::create_dir_all(cache_dir.join("tmp")).unwrap();
+
+        assert_eq!(
+            installed_versions_in(&cache_dir),
+            ["0.5.17", "0.6.9", "0.6.12"]
+        );
+        assert_eq!(
+            resolve_version_in(&cache_dir, "^0.6.0").as_deref(),
+            Some("0.6.12")
+        );
+        assert_eq!(resolve_version_in(&cache_dir, "^0.7.0"), None);
+        assert!(installed_solc_path_in(&cache_dir, "v0.6.9").is_some());
+        assert!(installed_solc_path_in(&cache_dir, "0.6.10").is_none());
+
+        let _ = fs::remove_dir_all(cache_dir);
+    }
+}
@@ -0,0 +1,279 @@
+// """
+// Mapping from byte offsets in generated solidity code back to the zkay source it was derived from.
+
+// The generated code is covered by consecutive segments. An exact segment maps byte by byte
+// (e.g. code which was only padded with whitespace surrogates in the fake solidity code),
+// any other segment maps every byte to the start of its original range (e.g. an injected declaration
+// or a transformed function which was generated from a zkay function).
+// """
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Segment {
+    generated: Range<usize>,
+    original: Range<usize>,
+    // byte by byte correspondence (generated and original range have the same length)
+    exact: bool,
+}
+
+impl Segment {
+    // Restrict the segment to the generated range gen (which must overlap it)
+    fn clip(&self, gen: Range<usize>) -> Self {
+        let start = gen.start.max(self.generated.start);
+        let end = gen.end.min(self.generated.end);
+        if !self.exact {
+            return Self {
+                generated: start..end,
+                original: self.original.clone(),
+                exact: false,
+            };
+        }
+        let offset = self.original.start as isize - self.generated.start as isize;
+        Self {
+            generated: start..end,
+            original: (start as isize + offset) as usize..(end as isize + offset) as usize,
+            exact: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    // sorted by generated offset, without gaps
+    segments: Vec<Segment>,
+}
+
+impl SourceMap {
+    // """Map for generated code which is identical to the original code of length len."""
+    pub fn identity(len: usize) -> Self {
+        Self {
+            segments: vec![Segment {
+                generated: 0..len,
+                original: 0..len,
+                exact: true,
+            }],
+        }
+    }
+
+    // """
+    // Map for generated code of length len where only the given regions are known to stem from the
+    // original code, as (generated range, original range) pairs. Regions may be nested (e.g. functions
+    // within a contract), code which is not part of any region is attributed to the start of the original code.
+    // """
+    pub fn from_regions(len: usize, mut regions: Vec<(Range<usize>, Range<usize>)>) -> Self {
+        let mut map = Self {
+            segments: vec![Segment {
+                generated: 0..len,
+                original: 0..0,
+                exact: false,
+            }],
+        };
+        // outer regions first, so that inner regions take precedence
+        regions.sort_by_key(|(generated, _)| (generated.start, usize::MAX - generated.end));
+        for (generated, original) in regions {
+            let generated = generated.start.min(len)..generated.end.min(len);
+            if generated.is_empty() {
+                continue;
+            }
+            let mut segments = vec![];
+            for s in &map.segments {
+                if s.generated.start < generated.start {
+                    segments.push(s.clip(s.generated.start..generated.start.min(s.generated.end)));
+                }
+            }
+            segments.push(Segment {
+                generated: generated.clone(),
+                original: original.clone(),
+                exact: false,
+            });
+            for s in &map.segments {
+                if s.generated.end > generated.end {
+                    segments.push(s.clip(generated.end.max(s.generated.start)..s.generated.end));
+                }
+            }
+            map.segments = segments;
+        }
+        map
+    }
+
+    // """Length of the generated code."""
+    pub fn generated_len(&self) -> usize {
+        self.segments.last().map_or(0, |s| s.generated.end)
+    }
+
+    // """Record that the generated bytes in range were replaced by new_len bytes."""
+    pub fn replace(&mut self, range: Range<usize>, new_len: usize) {
+        if range.len() == new_len {
+            return;
+        }
+        let original = self.original_offset(range.start)..self.original_end(range.end);
+        let delta = new_len as isize - range.len() as isize;
+        let mut segments = vec![];
+        for s in &self.segments {
+            if s.generated.start < range.start {
+                segments.push(s.clip(s.generated.start..range.start));
+            }
+        }
+        if new_len > 0 {
+            segments.push(Segment {
+                generated: range.start..range.start + new_len,
+                original,
+                exact: false,
+            });
+        }
+        for s in &self.segments {
+            if s.generated.end > range.end {
+                let mut s = s.clip(range.end..s.generated.end);
+                s.generated = (s.generated.start as isize + delta) as usize
+                    ..(s.generated.end as isize + delta) as usize;
+                segments.push(s);
+            }
+        }
+        self.segments = segments
+            .into_iter()
+            .filter(|s| !s.generated.is_empty())
+            .collect();
+    }
+
+    // """Record that len bytes were inserted at generated offset at."""
+    pub fn insert(&mut self, at: usize, len: usize) {
+        self.replace(at..at, len);
+    }
+
+    // """Offset in the original code which the generated byte at offset gen stems from."""
+    pub fn original_offset(&self, gen: usize) -> usize {
+        match self.segments.iter().find(|s| s.generated.contains(&gen)) {
+            Some(s) if s.exact => s.original.start + (gen - s.generated.start),
+            Some(s) => s.original.start,
+            None => self.segments.last().map_or(0, |s| s.original.end),
+        }
+    }
+
+    // End offset in the original code of a generated range ending at gen
+    fn original_end(&self, gen: usize) -> usize {
+        if gen == 0 {
+            return self.original_offset(0);
+        }
+        let s = self
+            .segments
+            .iter()
+            .find(|s| s.generated.contains(&(gen - 1)));
+        match s {
+            Some(s) if s.exact => s.original.start + (gen - s.generated.start),
+            Some(s) => s.original.end,
+            None => self.original_offset(gen),
+        }
+    }
+
+    // """Line and column (both 1-based) in original_code of the generated byte at offset gen."""
+    pub fn original_line_col(&self, original_code: &str, gen: usize) -> (i32, i32) {
+        let offset = self.original_offset(gen).min(original_code.len());
+        let before = &original_code.as_bytes()[..offset];
+        let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        (line as i32, (offset - line_start) as i32 + 1)
+    }
+}
+
+// """Byte offset of the 1-based line and column in code."""
+pub fn line_col_offset(code: &str, line: i32, column: i32) -> usize {
+    let line_start: usize = code
+        .split_inclusive('\n')
+        .take((line.max(1) - 1) as usize)
+        .map(str::len)
+        .sum();
+    (line_start + (column.max(1) - 1) as usize).min(code.len())
+}
+
+// """
+// Source map for the public solidity code (of length sol_len) generated from zkay_code by the contract transformation.
+
+// provenance lists the generated byte ranges of the transformed contracts and functions together with the
+// line and column of the zkay element they were generated from (see to_solidity_with_provenance),
+// any other code is attributed to the innermost enclosing element.
+// """
+pub fn transformed_source_map(
+    zkay_code: &str,
+    sol_len: usize,
+    provenance: &[(Range<usize>, i32, i32)],
+) -> SourceMap {
+    let regions = provenance
+        .iter()
+        .map(|(generated, line, column)| {
+            let original = line_col_offset(zkay_code, *line, *column);
+            (generated.clone(), original..original)
+        })
+        .collect();
+    SourceMap::from_regions(sol_len, regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacements_keep_surrounding_offsets() {
+        let original = "pragma zkay ^0.3.0;\ncontract A {\n}\n";
+        let mut map = SourceMap::identity(original.len());
+        // pragma zkay ^0.3.0; -> pragma solidity ^0.6.0;
+        map.replace(7..18, 15);
+        // inject a declaration before the closing brace of the contract
+        let brace = original.rfind('}').unwrap() + 4;
+        map.insert(brace, 10);
+        // license header
+        map.insert(0, 5);
+
+        assert_eq!(map.generated_len(), original.len() + 4 + 10 + 5);
+        assert_eq!(map.original_line_col(original, 5 + 9), (1, 8));
+        assert_eq!(map.original_line_col(original, 5 + 24), (2, 1));
+        assert_eq!(map.original_line_col(original, 5 + brace + 3), (3, 1));
+        assert_eq!(map.original_line_col(original, 5 + brace + 10), (3, 1));
+        assert_eq!(map.original_line_col(original, 2), (1, 1));
+    }
+
+    #[test]
+    fn regions_cover_generated_code() {
+        let original = "contract A {\n    function f() public {}\n}\n";
+        let f = line_col_offset(original, 2, 5);
+        assert_eq!(&original[f..f + 8], "function");
+        let contract = original.len() - 2;
+        let map = SourceMap::from_regions(100, vec![(40..60, f..f + 22), (10..80, 0..contract)]);
+        assert_eq!(map.original_line_col(original, 5), (1, 1));
+        assert_eq!(map.original_line_col(original, 45), (2, 5));
+        assert_eq!(map.original_line_col(original, 70), (1, 1));
+        assert_eq!(map.original_line_col(original, 99), (1, 1));
+    }
+
+    #[test]
+    fn transformed_code_maps_to_provenance() {
+        let zkay = "pragma zkay ^0.3.0;\ncontract A {\n    // function g\n    function f(uint@me x) public {\n        x = x + 1;\n    }\n}\n";
+        let sol = "pragma solidity ^0.6.0;\ncontract A {\n    uint zk__data;\n    function f(uint[] memory zk__in) public {\n        _zk__f(zk__in);\n    }\n    function _zk__f(uint[] memory zk__in) internal {\n        zk__in[0] = 1;\n    }\n    function helper() internal {}\n}\n";
+        let range = |needle: &str, end: &str| {
+            let start = sol.find(needle).unwrap();
+            start..start + sol[start..].find(end).unwrap() + end.len()
+        };
+        // both the external wrapper and the internal function stem from f, the helper was generated
+        let provenance = [
+            (range("contract A", "}\n}"), 2, 1),
+            (range("function f", "}"), 4, 5),
+            (range("function _zk__f", "}"), 4, 5),
+        ];
+        let map = transformed_source_map(zkay, sol.len(), &provenance);
+        let f = zkay.find("function f").unwrap();
+        for needle in ["_zk__f(zk__in);", "zk__in[0] = 1"] {
+            let (line, column) = map.original_line_col(zkay, sol.find(needle).unwrap());
+            assert_eq!(line_col_offset(zkay, line, column), f, "{needle}");
+        }
+        for needle in ["uint zk__data", "function helper"] {
+            let (line, column) = map.original_line_col(zkay, sol.find(needle).unwrap());
+            assert_eq!(
+                line_col_offset(zkay, line, column),
+                zkay.find("contract A").unwrap(),
+                "{needle}"
+            );
+        }
+    }
+}
@@ -20,6 +20,7 @@ use crate::config_version::{Versions, VersionsBase};
 // use circuit_generation::circuit_helper::CircuitHelper;
 use crate::config_user::{UserConfig, UserConfigBase};
 use crate::lc_vec_s;
+use crate::project::PROJECT_FILE;
 use app_dirs2::*;
 use lazy_static::lazy_static;
 use serde_json::{json, Map, Result, Value};
@@ -79,6 +80,108 @@ pub fn indent(s: String) -> String {
         .join("\n")
 }
 
+// All user configuration options, in the order of UserConfig
+pub const USER_CONFIG_OPTIONS: &[&str] = &[
+    "proving_scheme",
+    "snark_backend",
+    "main_crypto_backend",
+    "addhom_crypto_backend",
+    "blockchain_backend",
+    "blockchain_node_uri",
+    "blockchain_pki_address",
+    "blockchain_crypto_lib_addresses",
+    "blockchain_default_account",
+    "blockchain_max_transaction_retries",
+    "indentation",
+    "libsnark_check_verify_locally_during_proof_generation",
+    "opt_solc_optimizer_runs",
+    "opt_hash_threshold",
+    "max_circuit_constraints",
+    "opt_eval_constexpr_in_circuit",
+    "opt_cache_circuit_inputs",
+    "opt_cache_circuit_outputs",
+    "data_dir",
+    "log_dir",
+    "use_circuit_cache_during_testing_with_encryption",
+    "verbosity",
+    "disable_verification",
+    "lint_levels",
+];
+
+// Table of zkay.toml which holds project settings rather than configuration options
+pub const PROJECT_SECTION: &str = "project";
+
+// Environment variable overriding the option `name`, e.g. ZKAY_PROVING_SCHEME
+pub fn env_var_name(name: &str) -> String {
+    format!("ZKAY_{}", name.to_ascii_uppercase())
+}
+
+// An invalid configuration file or option
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigError(pub String);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Closest zkay.toml in `dir` or one of its parents. The search does not leave the
+// enclosing repository (it stops at the first directory containing .git).
+pub fn find_project_config_file(dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    for d in dir.ancestors() {
+        let project_file = d.join(PROJECT_FILE);
+        if project_file.is_file() {
+            return Some(project_file);
+        }
+        if d.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+// Set option `name` to `value` in the project configuration file `filename`, which is created if missing
+// (used to remember the addresses of deployed library contracts for all later deployments)
+pub fn store_project_option(
+    filename: &std::path::Path,
+    name: &str,
+    value: &str,
+) -> std::io::Result<()> {
+    assert!(
+        USER_CONFIG_OPTIONS.contains(&name),
+        "Unknown config option \"{name}\""
+    );
+    let content = if filename.is_file() {
+        std::fs::read_to_string(filename)?
+    } else {
+        String::new()
+    };
+    let invalid = |e: String| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{e} (in file \"{}\")", filename.display()),
+        )
+    };
+    let content = if filename.extension().map_or(false, |ext| ext == "toml") {
+        let mut table: toml::Table =
+            toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        table.insert(name.to_owned(), toml::Value::String(value.to_owned()));
+        toml::to_string_pretty(&table).map_err(|e| invalid(e.to_string()))?
+    } else {
+        let mut options: Map<String, Value> = if content.trim().is_empty() {
+            Map::new()
+        } else {
+            serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?
+        };
+        options.insert(name.to_owned(), Value::String(value.to_owned()));
+        serde_json::to_string_pretty(&options).unwrap()
+    };
+    std::fs::write(filename, content)
+}
+
 pub trait ConstructorOrFunctionDefinitionAttr {
     fn get_requires_verification_when_external(&self) -> bool;
     fn get_name(&self) -> String;
@@ -91,7 +194,8 @@ pub struct Config {
     _is_unit_test: bool,
     _concrete_solc_version: Option<String>,
     vals: HashMap<String, String>,
-    attrs: HashMap<String, String>,
+    // option name -> where its value was configured
+    sources: HashMap<String, String>,
 }
 impl Default for Config {
     fn default() -> Self {
@@ -121,63 +225,367 @@ impl Config {
             _is_unit_test: false,
             _concrete_solc_version: None,
             vals: HashMap::new(),
-            attrs: HashMap::new(),
+            sources: HashMap::new(),
         }
     }
 
-    pub fn _load_cfg_file_if_exists(&mut self, filename: String) {
-        if std::path::Path::new(&filename).exists() {
-            // with open(filename) as conf:
-            //     try:
-            let _v: Value =
-                serde_json::from_str(&std::fs::read_to_string(&filename).unwrap()).unwrap();
-            // self.override_defaults(v);
-            // except ValueError as e:
-            //     raise ValueError(f"{e} (in file "{filename}")")
+    // Parse a json or toml (by extension) configuration file and apply its values
+    pub fn _load_cfg_file_if_exists(
+        &mut self,
+        filename: String,
+        source: &str,
+    ) -> std::result::Result<(), ConfigError> {
+        let path = std::path::Path::new(&filename);
+        if filename.is_empty() || !path.is_file() {
+            return Ok(());
         }
+        let invalid = |e: String| ConfigError(format!("{e} (in file \"{filename}\")"));
+        // with open(filename) as conf:
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError(format!("Could not read config file {filename}: {e}")))?;
+        let v: Value = if path.extension().map_or(false, |ext| ext == "toml") {
+            let v: toml::Value = toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+            serde_json::to_value(v).map_err(|e| invalid(e.to_string()))?
+        } else {
+            serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?
+        };
+        let Some(mut overrides) = v.as_object().cloned() else {
+            return Err(ConfigError(format!(
+                "Config file \"{filename}\" must contain a table of options"
+            )));
+        };
+        // project settings of zkay.toml are not user configuration options
+        overrides.remove(PROJECT_SECTION);
+        self.override_defaults(&overrides, &format!("{source} {filename}"))
+    }
+
+    // config.json in the app directory of the given type (global or per user), if there is one
+    fn app_config_file(&self, t: AppDataType) -> std::result::Result<String, ConfigError> {
+        let dir = get_app_dir(t, self.appdirs(), "")
+            .map_err(|e| ConfigError(format!("Could not locate the {t:?} directory: {e}")))?;
+        let file = dir.join("config.json");
+        file.to_str().map(str::to_owned).ok_or_else(|| {
+            ConfigError(format!(
+                "Config file path {} is not valid unicode",
+                file.display()
+            ))
+        })
     }
 
-    pub fn load_configuration_from_disk(&mut self, local_cfg_file: String) {
+    pub fn load_configuration_from_disk(
+        &mut self,
+        local_cfg_file: String,
+    ) -> std::result::Result<(), ConfigError> {
         // Load global configuration file
-        let global_config_dir = get_app_dir(AppDataType::SharedConfig, &self.appdirs(), "");
-        let global_cfg_file =
-            std::path::PathBuf::from(global_config_dir.expect("").to_str().expect(""))
-                .join("config.json");
-        self._load_cfg_file_if_exists(global_cfg_file.to_str().expect("").to_string());
+        let global_cfg_file = self.app_config_file(AppDataType::SharedConfig)?;
+        self._load_cfg_file_if_exists(global_cfg_file, "global")?;
 
         // Load user configuration file
-        let user_config_dir = get_app_dir(AppDataType::UserConfig, self.appdirs(), "");
-        let user_cfg_file =
-            std::path::PathBuf::from(&user_config_dir.expect("").to_str().expect(""))
-                .join("config.json");
-        self._load_cfg_file_if_exists(user_cfg_file.to_str().expect("").to_string());
+        let user_cfg_file = self.app_config_file(AppDataType::UserConfig)?;
+        self._load_cfg_file_if_exists(user_cfg_file, "user")?;
+
+        // Load project configuration file (closest zkay.toml of the working directory)
+        if let Some(project_cfg_file) = std::env::current_dir()
+            .ok()
+            .and_then(|dir| find_project_config_file(&dir))
+        {
+            self._load_cfg_file_if_exists(
+                project_cfg_file.to_string_lossy().into_owned(),
+                "project",
+            )?;
+        }
 
         // Load local configuration file
-        self._load_cfg_file_if_exists(local_cfg_file);
+        self._load_cfg_file_if_exists(local_cfg_file, "local")?;
+
+        // Environment variables take precedence over all configuration files
+        self.load_env_overrides()
+    }
+
+    // Apply ZKAY_<OPTION> environment variables, e.g. ZKAY_PROVING_SCHEME=gm17
+    // (nothing is changed if one of them is invalid)
+    pub fn load_env_overrides(&mut self) -> std::result::Result<(), ConfigError> {
+        let mut overrides = Vec::new();
+        for &name in USER_CONFIG_OPTIONS {
+            let var = env_var_name(name);
+            if let Ok(raw) = std::env::var(&var) {
+                let invalid = |e: ConfigError| ConfigError(format!("{e} (in env {var})"));
+                let val = self.parse_option_str(name, &raw).map_err(invalid)?;
+                self.check_attr(name, &val).map_err(invalid)?;
+                overrides.push((name, val, var));
+            }
+        }
+        for (name, val, var) in overrides {
+            self.set_attr(name, &val);
+            self.sources.insert(name.to_owned(), format!("env {var}"));
+        }
+        Ok(())
+    }
+
+    // Convert the string representation of an option (as given in an environment variable) to the option's type
+    pub fn parse_option_str(
+        &self,
+        arg: &str,
+        raw: &str,
+    ) -> std::result::Result<Value, ConfigError> {
+        let invalid = |expected: &str| {
+            ConfigError(format!(
+                "Invalid config value {raw} for {arg}, expected {expected}"
+            ))
+        };
+        Ok(match self.get_attr(arg) {
+            Some(Value::Bool(_)) => match raw.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Value::Bool(true),
+                "0" | "false" | "no" | "off" => Value::Bool(false),
+                _ => return Err(invalid("a boolean")),
+            },
+            Some(Value::Number(_)) => {
+                json!(raw.parse::<i32>().map_err(|_| invalid("an integer"))?)
+            }
+            // comma separated list of lint=level pairs
+            Some(Value::Object(_)) => Value::Object(
+                raw.split(',')
+                    .filter(|kv| !kv.trim().is_empty())
+                    .map(|kv| -> std::result::Result<_, ConfigError> {
+                        let (k, v) = kv
+                            .split_once('=')
+                            .ok_or_else(|| invalid("name=value pairs"))?;
+                        Ok((k.trim().to_owned(), json!(v.trim())))
+                    })
+                    .collect::<std::result::Result<_, ConfigError>>()?,
+            ),
+            Some(Value::Null) if raw.is_empty() => Value::Null,
+            _ => json!(raw),
+        })
     }
 
     pub fn has_attr(&self, arg: &String) -> bool {
-        self.attrs.get(arg).is_some()
+        USER_CONFIG_OPTIONS.contains(&arg.as_str())
+    }
+
+    // Current value of the user configuration option `arg` as json
+    pub fn get_attr(&self, arg: &str) -> Option<Value> {
+        Some(match arg {
+            "proving_scheme" => json!(self.proving_scheme()),
+            "snark_backend" => json!(self.snark_backend()),
+            "main_crypto_backend" => json!(self.main_crypto_backend()),
+            "addhom_crypto_backend" => json!(self.addhom_crypto_backend()),
+            "blockchain_backend" => json!(self.blockchain_backend()),
+            "blockchain_node_uri" => json!(self.blockchain_node_uri()),
+            "blockchain_pki_address" => json!(self.blockchain_pki_address()),
+            "blockchain_crypto_lib_addresses" => json!(self.blockchain_crypto_lib_addresses()),
+            "blockchain_default_account" => json!(self.blockchain_default_account()),
+            "blockchain_max_transaction_retries" => {
+                json!(self.blockchain_max_transaction_retries())
+            }
+            "indentation" => json!(self.indentation()),
+            "libsnark_check_verify_locally_during_proof_generation" => {
+                json!(self.libsnark_check_verify_locally_during_proof_generation())
+            }
+            "opt_solc_optimizer_runs" => json!(self.opt_solc_optimizer_runs()),
+            "opt_hash_threshold" => json!(self.opt_hash_threshold()),
+            "max_circuit_constraints" => json!(self.max_circuit_constraints()),
+            "opt_eval_constexpr_in_circuit" => json!(self.opt_eval_constexpr_in_circuit()),
+            "opt_cache_circuit_inputs" => json!(self.opt_cache_circuit_inputs()),
+            "opt_cache_circuit_outputs" => json!(self.opt_cache_circuit_outputs()),
+            "data_dir" => json!(self.data_dir()),
+            "log_dir" => json!(self.log_dir()),
+            "use_circuit_cache_during_testing_with_encryption" => {
+                json!(self.use_circuit_cache_during_testing_with_encryption())
+            }
+            "verbosity" => json!(self.verbosity()),
+            "disable_verification" => json!(self.disable_verification()),
+            "lint_levels" => json!(self.lint_levels()),
+            _ => return None,
+        })
     }
-    pub fn get_attr(&self, arg: &String) -> String {
-        self.attrs.get(arg).unwrap_or(&String::new()).clone()
+
+    // Check that `val` is a valid value of the user configuration option `arg` (what the setters assert)
+    pub fn check_attr(&self, arg: &str, val: &Value) -> std::result::Result<(), ConfigError> {
+        let invalid = |expected: &str| {
+            ConfigError(format!(
+                "Invalid config value {val} for {arg}, expected {expected}"
+            ))
+        };
+        let as_str = |val: &Value| {
+            val.as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| invalid("a string"))
+        };
+        let one_of = |val: &Value, legal_vals: &[String]| -> std::result::Result<(), ConfigError> {
+            let val = as_str(val)?;
+            if legal_vals.contains(&val) {
+                Ok(())
+            } else {
+                Err(ConfigError(format!(
+                    "Invalid config value {val} for {arg}, must be one of {legal_vals:?}"
+                )))
+            }
+        };
+        let as_i32 = |val: &Value| {
+            val.as_i64()
+                .and_then(|v| i32::try_from(v).ok())
+                .ok_or_else(|| invalid("an integer"))
+        };
+        let base = self.user_config_base_ref();
+        let crypto_backends = |hom: &str| -> Vec<String> {
+            base._crypto_backend_values[hom]
+                .iter()
+                .flatten()
+                .cloned()
+                .collect()
+        };
+        match arg {
+            "proving_scheme" => one_of(val, &base._proving_scheme_values),
+            "snark_backend" => one_of(val, &base._snark_backend_values),
+            "main_crypto_backend" => one_of(val, &crypto_backends("NON_HOMOMORPHIC")),
+            "addhom_crypto_backend" => one_of(val, &crypto_backends("ADDITIVE")),
+            "blockchain_backend" => one_of(val, &base._blockchain_backend_values),
+            "blockchain_node_uri" if val.is_null() => Ok(()),
+            "blockchain_node_uri"
+            | "blockchain_pki_address"
+            | "blockchain_crypto_lib_addresses"
+            | "indentation"
+            | "data_dir"
+            | "log_dir" => as_str(val).map(|_| ()),
+            // account index or address
+            "blockchain_default_account" if val.is_number() => Ok(()),
+            "blockchain_default_account" => as_str(val).map(|_| ()),
+            "blockchain_max_transaction_retries" | "max_circuit_constraints" => {
+                if as_i32(val)? < 0 {
+                    return Err(ConfigError(format!("{arg} must not be negative")));
+                }
+                Ok(())
+            }
+            "opt_solc_optimizer_runs" | "opt_hash_threshold" | "verbosity" => {
+                as_i32(val).map(|_| ())
+            }
+            "libsnark_check_verify_locally_during_proof_generation"
+            | "opt_eval_constexpr_in_circuit"
+            | "opt_cache_circuit_inputs"
+            | "opt_cache_circuit_outputs"
+            | "use_circuit_cache_during_testing_with_encryption"
+            | "disable_verification" => val
+                .as_bool()
+                .map(|_| ())
+                .ok_or_else(|| invalid("a boolean")),
+            "lint_levels" => val
+                .as_object()
+                .ok_or_else(|| invalid("a table of lint levels"))?
+                .values()
+                .try_for_each(|level| one_of(level, &base._lint_level_values)),
+            _ => Err(ConfigError(format!(
+                "Unknown config option {arg}, must be one of {}",
+                USER_CONFIG_OPTIONS.join(", ")
+            ))),
+        }
     }
+
+    // Set the user configuration option `arg`, values are validated by the corresponding setters
     pub fn set_attr(&mut self, arg: &str, val: &Value) {
-        self.attrs
-            .insert(arg.to_owned(), val.as_str().unwrap().to_owned());
+        let as_str = |val: &Value| -> String {
+            match val {
+                Value::String(s) => s.clone(),
+                _ => panic!("Invalid config value {val} for {arg}, expected a string"),
+            }
+        };
+        let as_bool = |val: &Value| -> bool {
+            val.as_bool().unwrap_or_else(|| {
+                panic!("Invalid config value {val} for {arg}, expected a boolean")
+            })
+        };
+        let as_i32 = |val: &Value| -> i32 {
+            val.as_i64()
+                .and_then(|v| i32::try_from(v).ok())
+                .unwrap_or_else(|| {
+                    panic!("Invalid config value {val} for {arg}, expected an integer")
+                })
+        };
+        match arg {
+            "proving_scheme" => self.set_proving_scheme(as_str(val)),
+            "snark_backend" => self.set_snark_backend(as_str(val)),
+            "main_crypto_backend" => self.set_main_crypto_backend(as_str(val)),
+            "addhom_crypto_backend" => self.set_addhom_crypto_backend(as_str(val)),
+            "blockchain_backend" => self.set_blockchain_backend(as_str(val)),
+            "blockchain_node_uri" => {
+                self.set_blockchain_node_uri((!val.is_null()).then(|| as_str(val)))
+            }
+            "blockchain_pki_address" => self.set_blockchain_pki_address(as_str(val)),
+            "blockchain_crypto_lib_addresses" => {
+                self.set_blockchain_crypto_lib_addresses(as_str(val))
+            }
+            // account index or address
+            "blockchain_default_account" => self.set_blockchain_default_account(match val {
+                Value::Number(n) => n.to_string(),
+                _ => as_str(val),
+            }),
+            "blockchain_max_transaction_retries" => {
+                self.set_blockchain_max_transaction_retries(as_i32(val))
+            }
+            "indentation" => self.set_indentation(as_str(val)),
+            "libsnark_check_verify_locally_during_proof_generation" => {
+                self.set_libsnark_check_verify_locally_during_proof_generation(as_bool(val))
+            }
+            "opt_solc_optimizer_runs" => self.set_opt_solc_optimizer_runs(as_i32(val)),
+            "opt_hash_threshold" => self.set_opt_hash_threshold(as_i32(val)),
+            "max_circuit_constraints" => self.set_max_circuit_constraints(as_i32(val)),
+            "opt_eval_constexpr_in_circuit" => self.set_opt_eval_constexpr_in_circuit(as_bool(val)),
+            "opt_cache_circuit_inputs" => self.set_opt_cache_circuit_inputs(as_bool(val)),
+            "opt_cache_circuit_outputs" => self.set_opt_cache_circuit_outputs(as_bool(val)),
+            "data_dir" => self.set_data_dir(as_str(val)),
+            "log_dir" => self.set_log_dir(as_str(val)),
+            "use_circuit_cache_during_testing_with_encryption" => {
+                self.set_use_circuit_cache_during_testing_with_encryption(as_bool(val))
+            }
+            "verbosity" => self.set_verbosity(as_i32(val)),
+            "disable_verification" => self.set_disable_verification(as_bool(val)),
+            "lint_levels" => {
+                let levels = val.as_object().unwrap_or_else(|| {
+                    panic!("Invalid config value {val} for {arg}, expected a table of lint levels")
+                });
+                for (lint, level) in levels {
+                    self.set_lint_level(lint.clone(), as_str(level));
+                }
+            }
+            _ => panic!("Tried to set non-existing config value {arg}"),
+        }
     }
-    pub fn override_defaults(&mut self, overrides: &Map<String, Value>) {
+
+    // """
+    // Override the values of multiple options.
+
+    // :param overrides: map from option name to new value
+    // :param source: where the values come from (reported by ``zkay config show``)
+    // :raise ConfigError: if overrides contains an unknown option or an invalid value, nothing is changed in that case
+    // """
+    pub fn override_defaults(
+        &mut self,
+        overrides: &Map<String, Value>,
+        source: &str,
+    ) -> std::result::Result<(), ConfigError> {
+        if let Some(arg) = overrides.keys().find(|arg| !self.has_attr(arg)) {
+            return Err(ConfigError(format!(
+                "Unknown config option {arg} ({source}), must be one of {}",
+                USER_CONFIG_OPTIONS.join(", ")
+            )));
+        }
+        for (arg, val) in overrides {
+            self.check_attr(arg, val)
+                .map_err(|e| ConfigError(format!("{e} ({source})")))?;
+        }
         for (arg, val) in overrides {
-            if !self.has_attr(arg) {
-                // raise ValueError(f"Tried to override non-existing config value {arg}")
-                return;
-            }
-            // try:
             self.set_attr(arg, val);
-            // except ValueError as e:
-            //     raise ValueError(f"{e} (for entry "{arg}")")
-            // }
+            self.sources.insert(arg.clone(), source.to_owned());
         }
+        Ok(())
+    }
+
+    // Where the effective value of option `arg` was configured ("default" if it was never overridden)
+    pub fn config_source(&self, arg: &str) -> String {
+        self.sources
+            .get(arg)
+            .cloned()
+            .unwrap_or_else(|| String::from("default"))
     }
 
     pub fn export_compiler_settings(&self) -> Value {
@@ -208,6 +616,7 @@ impl Config {
                 return;
             }
             self.set_attr(k, v);
+            self.sources.insert(k.clone(), String::from("manifest"));
         }
     }
 
@@ -378,3 +787,132 @@ impl Drop for WithLibraryCompilationEnvironment {
             .set_opt_solc_optimizer_runs(self.old_opt_runs);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_defaults_records_source() {
+        let mut cfg = Config::new();
+        let overrides = json!({"proving_scheme": "gm17", "opt_hash_threshold": 3, "lint_levels": {"private_require": "deny"}});
+        cfg.override_defaults(overrides.as_object().unwrap(), "project zkay.toml")
+            .unwrap();
+        assert_eq!(cfg.proving_scheme(), "gm17");
+        assert_eq!(cfg.opt_hash_threshold(), 3);
+        assert_eq!(cfg.lint_levels()["private_require"], "deny");
+        assert_eq!(cfg.config_source("proving_scheme"), "project zkay.toml");
+        assert_eq!(cfg.config_source("verbosity"), "default");
+    }
+
+    #[test]
+    fn override_defaults_validates_values() {
+        let mut cfg = Config::new();
+        let overrides = json!({"opt_hash_threshold": 3, "proving_scheme": "plonk"});
+        let err = cfg
+            .override_defaults(overrides.as_object().unwrap(), "test")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Invalid config value plonk for proving_scheme"));
+        // nothing is applied, not even the valid values
+        assert_ne!(cfg.opt_hash_threshold(), 3);
+        assert_eq!(cfg.config_source("opt_hash_threshold"), "default");
+
+        let overrides = json!({"verbosity": "high"});
+        assert!(cfg
+            .override_defaults(overrides.as_object().unwrap(), "test")
+            .is_err());
+        let overrides = json!({"max_circuit_constraints": -1});
+        assert!(cfg
+            .override_defaults(overrides.as_object().unwrap(), "test")
+            .is_err());
+    }
+
+    #[test]
+    fn override_defaults_rejects_unknown_options() {
+        let mut cfg = Config::new();
+        let overrides = json!({"proving_scheme": "gm17", "proving_sceme": "gm17"});
+        let err = cfg
+            .override_defaults(overrides.as_object().unwrap(), "project zkay.toml")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unknown config option proving_sceme (project zkay.toml)"));
+        // nothing is applied from an invalid file
+        assert_eq!(cfg.config_source("proving_scheme"), "default");
+    }
+
+    #[test]
+    fn invalid_config_file_is_an_error() {
+        let file = std::env::temp_dir().join(format!("zkay-invalid-{}.json", std::process::id()));
+        std::fs::write(&file, "[1, 2]").unwrap();
+        let mut cfg = Config::new();
+        let err = cfg
+            ._load_cfg_file_if_exists(file.to_str().unwrap().to_owned(), "local")
+            .unwrap_err();
+        std::fs::remove_file(&file).unwrap();
+        assert!(err.to_string().contains("must contain a table of options"));
+    }
+
+    #[test]
+    fn project_config_discovery() {
+        let root = std::env::temp_dir().join(format!("zkay-discovery-{}", std::process::id()));
+        let nested = root.join("repo").join("contracts").join("sub");
+        std::fs::create_dir_all(&nested).unwrap();
+        // config.json files are not project configuration
+        std::fs::write(root.join("repo").join("config.json"), "{}").unwrap();
+        // zkay.toml outside of the repository is not picked up
+        std::fs::write(root.join(PROJECT_FILE), "").unwrap();
+        std::fs::create_dir(root.join("repo").join(".git")).unwrap();
+        assert_eq!(find_project_config_file(&nested), None);
+
+        let project_file = root.join("repo").join("contracts").join(PROJECT_FILE);
+        std::fs::write(&project_file, "").unwrap();
+        assert_eq!(find_project_config_file(&nested), Some(project_file));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn parse_option_str_uses_option_type() {
+        let cfg = Config::new();
+        assert_eq!(
+            cfg.parse_option_str("opt_cache_circuit_inputs", "off")
+                .unwrap(),
+            json!(false)
+        );
+        assert_eq!(cfg.parse_option_str("verbosity", "2").unwrap(), json!(2));
+        assert_eq!(
+            cfg.parse_option_str("proving_scheme", "gm17").unwrap(),
+            json!("gm17")
+        );
+        assert_eq!(
+            cfg.parse_option_str("lint_levels", "rehom_constant=warn")
+                .unwrap(),
+            json!({"rehom_constant": "warn"})
+        );
+        assert!(cfg.parse_option_str("verbosity", "high").is_err());
+        assert!(cfg
+            .parse_option_str("lint_levels", "rehom_constant")
+            .is_err());
+        assert_eq!(env_var_name("proving_scheme"), "ZKAY_PROVING_SCHEME");
+    }
+
+    #[test]
+    fn store_project_option_round_trip() {
+        let file = std::env::temp_dir().join(format!("zkay-store-{}.toml", std::process::id()));
+        std::fs::write(
+            &file,
+            "proving_scheme = \"gm17\"\n\n[project]\nname = \"p\"\n",
+        )
+        .unwrap();
+        store_project_option(&file, "blockchain_pki_address", "0x01").unwrap();
+
+        let mut cfg = Config::new();
+        cfg._load_cfg_file_if_exists(file.to_str().unwrap().to_owned(), "project")
+            .unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(cfg.blockchain_pki_address(), "0x01");
+        assert_eq!(cfg.proving_scheme(), "gm17");
+    }
+}
@@ -92,12 +92,14 @@ pub struct UserConfigBase {
     pub _blockchain_pki_address: String,
     pub _blockchain_crypto_lib_addresses: String,
     pub _blockchain_default_account: Option<String>,
+    pub _blockchain_max_transaction_retries: i32,
 
     pub _indentation: String,
     pub _libsnark_check_verify_locally_during_proof_generation: bool,
 
     pub _opt_solc_optimizer_runs: i32,
     pub _opt_hash_threshold: i32,
+    pub _max_circuit_constraints: i32,
     pub _opt_eval_constexpr_in_circuit: bool,
     pub _opt_cache_circuit_inputs: bool,
     pub _opt_cache_circuit_outputs: bool,
@@ -108,6 +110,9 @@ pub struct UserConfigBase {
     pub _verbosity: i32,
 
     pub _disable_verification: bool,
+
+    pub _lint_levels: BTreeMap<String, String>,
+    pub _lint_level_values: Vec<String>,
 }
 impl Default for UserConfigBase {
     fn default() -> Self {
@@ -178,6 +183,7 @@ impl UserConfigBase {
             _blockchain_backend_values: lc_vec_s![
                 "w3-eth-tester",
                 "w3-ganache",
+                "w3-evm",
                 "w3-ipc",
                 "w3-websocket",
                 "w3-http",
@@ -187,10 +193,12 @@ impl UserConfigBase {
             _blockchain_pki_address: String::new(),
             _blockchain_crypto_lib_addresses: String::new(),
             _blockchain_default_account: Some(String::from("0")),
+            _blockchain_max_transaction_retries: 3,
             _indentation: " ".repeat(4),
             _libsnark_check_verify_locally_during_proof_generation: false,
             _opt_solc_optimizer_runs: 50,
             _opt_hash_threshold: 1,
+            _max_circuit_constraints: 0,
             _opt_eval_constexpr_in_circuit: true,
             _opt_cache_circuit_inputs: true,
             _opt_cache_circuit_outputs: true,
@@ -207,6 +215,8 @@ impl UserConfigBase {
             _use_circuit_cache_during_testing_with_encryption: true,
             _verbosity: 1,
             _disable_verification: false,
+            _lint_levels: BTreeMap::new(),
+            _lint_level_values: lc_vec_s!["allow", "warn", "deny"],
             _appdirs,
         }
     }
@@ -322,9 +332,10 @@ pub trait UserConfig {
 
     fn blockchain_backend(&self) -> String {
         // Backend to use when interacting with the blockchain.
-        // Running unit tests is only supported with w3-eth-tester and w3-ganache at the moment (because they need pre-funded dummy accounts).
+        // Running unit tests is only supported with w3-eth-tester, w3-ganache and w3-evm at the moment (because they need pre-funded dummy accounts).
+        // w3-evm runs an embedded EVM in-process and needs neither a network nor an external node.
         // See https://web3py.readthedocs.io/en/stable/providers.html for more information.
-        // Available Options: [w3-eth-tester, w3-ganache, w3-ipc, w3-websocket, w3-http, w3-custom]
+        // Available Options: [w3-eth-tester, w3-ganache, w3-evm, w3-ipc, w3-websocket, w3-http, w3-custom]
 
         self.user_config_base_ref()._blockchain_backend.clone()
     }
@@ -343,6 +354,7 @@ pub trait UserConfig {
 
         // Backend specific location of the ethereum node
         // w3-eth-tester : unused
+        // w3-evm        : unused
         // w3-ganache    : url
         // w3-ipc        : path to ipc socket file
         // w3-websocket  : web socket uri
@@ -412,6 +424,25 @@ pub trait UserConfig {
         self.user_config_base_mut()._blockchain_default_account = Some(val);
     }
 
+    fn blockchain_max_transaction_retries(&self) -> i32 {
+        // How often a transaction is re-simulated, re-proven and resubmitted when the private state it read
+        // was modified by another transaction before it was included (0 = fail on the first conflict).
+
+        self.user_config_base_ref()
+            ._blockchain_max_transaction_retries
+    }
+
+    // @blockchain_max_transaction_retries.setter
+    fn set_blockchain_max_transaction_retries(&mut self, val: i32) {
+        //_type_check(val, i32)
+        assert!(
+            val >= 0,
+            "blockchain_max_transaction_retries must not be negative"
+        );
+        self.user_config_base_mut()
+            ._blockchain_max_transaction_retries = val;
+    }
+
     fn indentation(&self) -> String {
         // Specifies the identation which should be used for the generated code output.
         self.user_config_base_ref()._indentation.clone()
@@ -466,6 +497,23 @@ pub trait UserConfig {
         self.user_config_base_mut()._opt_hash_threshold = val;
     }
 
+    fn max_circuit_constraints(&self) -> i32 {
+        // Upper bound for the estimated number of constraints of a single proof circuit (0 = no limit).
+
+        // Circuit generation is aborted before key generation if any circuit exceeds this budget,
+        // since huge circuits (e.g. because of inlined private function calls) may take hours to compile
+        // and easily exhaust the memory available to the jvm.
+
+        self.user_config_base_ref()._max_circuit_constraints
+    }
+
+    // @max_circuit_constraints.setter
+    fn set_max_circuit_constraints(&mut self, val: i32) {
+        //_type_check(val, i32)
+        assert!(val >= 0, "max_circuit_constraints must not be negative");
+        self.user_config_base_mut()._max_circuit_constraints = val;
+    }
+
     fn opt_eval_constexpr_in_circuit(&self) -> bool {
         // If true, literal expressions are folded and the result is baked into the circuit as a constant
         // (as opposed to being evaluated outside the circuit and the result being moved in as an additional circuit input)
@@ -585,4 +633,17 @@ pub trait UserConfig {
         //_type_check(val, bool)
         self.user_config_base_mut()._disable_verification = val;
     }
+
+    fn lint_levels(&self) -> BTreeMap<String, String> {
+        // Level overrides for individual lints, e.g. {"rehom_constant": "allow", "implicit_exposure": "deny"}.
+        // Lints which are not listed use their default level.
+        // Available Options: [allow, warn, deny]
+
+        self.user_config_base_ref()._lint_levels.clone()
+    }
+
+    fn set_lint_level(&mut self, lint: String, level: String) {
+        _check_is_one_of(&level, &self.user_config_base_ref()._lint_level_values);
+        self.user_config_base_mut()._lint_levels.insert(lint, level);
+    }
 }
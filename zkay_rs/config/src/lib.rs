@@ -9,6 +9,7 @@
 //                      (both user-configuration as well as zkay-internal configuration)
 // * :py:mod:`.config_version`: Contains pinned version numbers (e.g. solc) used by zkay
 // * :py:mod:`.config_user`: Defines options which the user can configure.
+// * :py:mod:`.project`: Layout of multi-contract projects created by `zkay init`.
 // * :py:mod:`.zkay_frontend`: Programmatic access to zkay compilation, transaction and packaging facilities.
 
 // ===========
@@ -27,3 +28,4 @@ pub mod config;
 pub mod config_user;
 pub mod config_version;
 pub mod meta;
+pub mod project;
@@ -0,0 +1,158 @@
+// Layout of a multi-contract zkay project, stored in the [project] table of zkay.toml
+// (the other entries of zkay.toml are ordinary configuration options, see config::load_configuration_from_disk)
+
+use crate::config::{find_project_config_file, ConfigError, PROJECT_SECTION};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Name of the project configuration file created by `zkay init`
+pub const PROJECT_FILE: &str = "zkay.toml";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectLayout {
+    pub name: String,
+    // Directories relative to the project root
+    pub contracts: String,
+    pub scenarios: String,
+    pub out: String,
+    // solc version used to compile the contracts of this project (default: zkay's pinned version)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solc_version: Option<String>,
+}
+
+impl Default for ProjectLayout {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            contracts: String::from("contracts"),
+            scenarios: String::from("scenarios"),
+            out: String::from("out"),
+            solc_version: None,
+        }
+    }
+}
+
+impl ProjectLayout {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    // Parse the [project] table of the project file, None if the file does not define a project
+    pub fn load(project_file: &Path) -> Result<Option<Self>, ConfigError> {
+        let invalid =
+            |e: String| ConfigError(format!("{e} (in file \"{}\")", project_file.display()));
+        let content = std::fs::read_to_string(project_file)
+            .map_err(|e| invalid(format!("Could not read project file: {e}")))?;
+        let mut table: toml::Table =
+            toml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        table
+            .remove(PROJECT_SECTION)
+            .map(|project| {
+                project
+                    .try_into()
+                    .map_err(|e| invalid(format!("Invalid [{PROJECT_SECTION}] table: {e}")))
+            })
+            .transpose()
+    }
+
+    // Project root of dir: the directory of the closest zkay.toml (see find_project_config_file),
+    // None if there is no such file or it does not define a project
+    pub fn find(dir: &Path) -> Result<Option<(PathBuf, Self)>, ConfigError> {
+        let Some(project_file) = find_project_config_file(dir) else {
+            return Ok(None);
+        };
+        let root = project_file.parent().unwrap_or(dir).to_path_buf();
+        Ok(Self::load(&project_file)?.map(|layout| (root, layout)))
+    }
+
+    // The [project] table as it appears in zkay.toml
+    pub fn to_toml(&self) -> String {
+        let mut table = toml::Table::new();
+        table.insert(
+            PROJECT_SECTION.to_owned(),
+            toml::Value::try_from(self).unwrap(),
+        );
+        toml::to_string(&table).unwrap()
+    }
+
+    // All zkay contracts of the project, sorted by file name
+    pub fn contract_files(&self, root: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut files: Vec<_> = std::fs::read_dir(root.join(&self.contracts))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && p.extension().map_or(false, |ext| ext == "zkay"))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    // Each contract is compiled into its own directory (with its own manifest.json) below `out`
+    pub fn contract_output_dir(&self, root: &Path, contract_file: &Path) -> PathBuf {
+        root.join(&self.out)
+            .join(contract_file.file_stem().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_round_trip() {
+        let layout = ProjectLayout {
+            solc_version: Some(String::from("0.6.12")),
+            ..ProjectLayout::new("demo")
+        };
+        let file = std::env::temp_dir().join(format!("zkay-project-{}.toml", std::process::id()));
+        std::fs::write(
+            &file,
+            format!("proving_scheme = \"groth16\"\n\n{}", layout.to_toml()),
+        )
+        .unwrap();
+        let loaded = ProjectLayout::load(&file);
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(loaded.unwrap(), Some(layout));
+    }
+
+    #[test]
+    fn invalid_project_file_is_an_error() {
+        let root =
+            std::env::temp_dir().join(format!("zkay-project-invalid-{}", std::process::id()));
+        let nested = root.join("contracts");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(".git"), "").unwrap();
+
+        std::fs::write(root.join(PROJECT_FILE), "[project\n").unwrap();
+        assert!(ProjectLayout::load(&root.join(PROJECT_FILE)).is_err());
+        assert!(ProjectLayout::find(&nested).is_err());
+
+        std::fs::write(root.join(PROJECT_FILE), "[project]\nout = 1\n").unwrap();
+        assert!(ProjectLayout::find(&nested).is_err());
+
+        std::fs::write(root.join(PROJECT_FILE), "proving_scheme = \"groth16\"\n").unwrap();
+        assert_eq!(ProjectLayout::find(&nested).unwrap(), None);
+
+        std::fs::write(
+            root.join(PROJECT_FILE),
+            ProjectLayout::new("demo").to_toml(),
+        )
+        .unwrap();
+        let (found_root, layout) = ProjectLayout::find(&nested).unwrap().unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(found_root, root);
+        assert_eq!(layout.name, "demo");
+    }
+
+    #[test]
+    fn contract_output_dir_per_contract() {
+        let layout = ProjectLayout::default();
+        assert_eq!(
+            layout.contract_output_dir(Path::new("/p"), Path::new("/p/contracts/Bank.zkay")),
+            PathBuf::from("/p/out/Bank")
+        );
+    }
+}
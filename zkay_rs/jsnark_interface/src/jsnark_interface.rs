@@ -33,6 +33,11 @@ pub fn pop_first_two_path_components(path: &str) -> std::path::PathBuf {
 }
 //path to jsnark interface jar
 pub const CIRCUIT_BUILDER_JAR: &str = "JsnarkCircuitBuilder.jar";
+//SHA-512 of the circuit builder jar this version of zkay was built and tested with
+pub const CIRCUIT_BUILDER_JAR_SHA512: &str = "cffca874d1b5a0a58bddc63b0d30ce84d60d19a3a13ed260eec5809c5432b33d46f86ad557510c10b528342775e14885e1fcf44d1b37541674e34f8c3dd91998";
+//JVM heap settings for running jsnark, large circuits need a lot of memory
+pub const JAVA_MIN_HEAP: &str = "-Xms4096m";
+pub const JAVA_MAX_HEAP: &str = "-Xmx16384m";
 lazy_static! {
     pub static ref JARS_DIR: String = file_abs_workspace!()
         .parent()
@@ -89,8 +94,8 @@ pub fn compile_and_run_with_circuit_builder(
     return run_command(
         [
             "java",
-            "-Xms4096m",
-            "-Xmx16384m",
+            JAVA_MIN_HEAP,
+            JAVA_MAX_HEAP,
             "-cp",
             &format!(
                 "{}:{working_dir}",
@@ -127,8 +132,8 @@ pub fn prepare_proof(
     run_commands(
         [
             "java",
-            "-Xms4096m",
-            "-Xmx16384m",
+            JAVA_MIN_HEAP,
+            JAVA_MAX_HEAP,
             "-cp",
             &format!("{CIRCUIT_BUILDER_JAR}:{circuit_dir}"),
             &CFG.lock().unwrap().jsnark_circuit_classname(),
@@ -0,0 +1,243 @@
+use super::OutputFormat;
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use rccell::RcCell;
+use serde::Serialize;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::Once;
+
+use ast_builder::process_ast::{get_processed_ast, ASTFlags};
+use type_check::type_checker::take_type_error_location;
+use zkay_ast::global_defs::{global_defs, global_vars};
+use zkay_ast::lints::{run_lints, LintLevel};
+use zkay_config::{config::CFG, config_user::UserConfig};
+use zkay_utils::helpers::read_file;
+
+/// CLI arguments for `zkay check`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Parse, link, analyze and type check a zkay contract without generating any code.", long_about = None)]
+pub struct CheckArgs {
+    /// The zkay source file
+    #[arg(value_hint = ValueHint::FilePath, value_name = "ZKAY_FILE")]
+    pub input: PathBuf,
+
+    /// Output format of the diagnostics
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// A single problem found by `zkay check`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CheckDiagnostic {
+    pub file: String,
+    pub severity: String,
+    /// Name of the lint which produced the diagnostic, if any
+    pub lint: Option<String>,
+    pub line: Option<i32>,
+    pub column: Option<i32>,
+    pub message: String,
+}
+
+impl CheckArgs {
+    pub fn run(self) -> Result<()> {
+        if !self.input.is_file() {
+            eyre::bail!("input file {:?} does not exist", self.input);
+        }
+        let file = self.input.to_string_lossy().to_string();
+        let code = read_file(&file);
+        let diagnostics = check_code(&file, &code);
+
+        match self.format {
+            OutputFormat::Json => sh_println!("{}", serde_json::to_string_pretty(&diagnostics)?)?,
+            OutputFormat::Text => {
+                for d in &diagnostics {
+                    sh_println!("{}", format_diagnostic(d))?;
+                }
+            }
+        }
+        let errors = diagnostics.iter().filter(|d| d.severity == "error").count();
+        if errors > 0 {
+            eyre::bail!("{file}: {errors} error(s) found");
+        }
+        if self.format == OutputFormat::Text {
+            sh_println!("{file}: ok ({} warning(s))", diagnostics.len())?;
+        }
+        Ok(())
+    }
+}
+
+// Run all front-end passes on `code` (the compiler reports errors by panicking)
+pub(crate) fn check_code(file: &str, code: &str) -> Vec<CheckDiagnostic> {
+    // diagnostics are reported here instead of being printed by the individual passes
+    let verbosity = CFG.lock().unwrap().verbosity();
+    CFG.lock().unwrap().set_verbosity(0);
+    let result = catch_compiler_error(|| {
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        // lints are run below so that lint errors are reported as diagnostics
+        let flags = ASTFlags::FLAG_ALL.difference(ASTFlags::LINTS).bits();
+        get_processed_ast(code, Some(flags), global_vars)
+            .and_then(|ast| run_lints(code, &ast, true))
+    });
+    CFG.lock().unwrap().set_verbosity(verbosity);
+
+    match result.and_then(|lints| lints.map_err(|e| format!("{e:#}"))) {
+        Ok(lints) => lints
+            .into_iter()
+            .map(|d| CheckDiagnostic {
+                file: file.to_owned(),
+                severity: if d.level == LintLevel::Deny {
+                    String::from("error")
+                } else {
+                    String::from("warning")
+                },
+                lint: Some(d.lint),
+                line: Some(d.line),
+                column: Some(d.column),
+                message: d.message,
+            })
+            .collect(),
+        Err(message) => vec![compiler_error_diagnostic(file, message)],
+    }
+}
+
+// Diagnostic for an error caught by catch_compiler_error, located at the node whose type check failed
+pub(crate) fn compiler_error_diagnostic(file: &str, message: String) -> CheckDiagnostic {
+    let location = take_type_error_location();
+    CheckDiagnostic {
+        file: file.to_owned(),
+        severity: String::from("error"),
+        lint: None,
+        line: location.map(|(line, _)| line),
+        column: location.map(|(_, column)| column),
+        message,
+    }
+}
+
+thread_local! {
+    static SILENCE_PANICS: Cell<bool> = const { Cell::new(false) };
+}
+
+// Install (once) a panic hook which stays quiet for panics caught by catch_compiler_error
+// and forwards all other panics to the previously installed hook. Swapping the hook per
+// call would race with concurrent checks and with panics on other threads.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !SILENCE_PANICS.with(Cell::get) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+// Run f and return the message of the panic by which the compiler reports an error, if any
+pub(crate) fn catch_compiler_error<T>(f: impl FnOnce() -> T) -> std::result::Result<T, String> {
+    install_panic_hook();
+    let silenced = SILENCE_PANICS.with(|s| s.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    SILENCE_PANICS.with(|s| s.set(silenced));
+    // a pass which panicked while holding the configuration lock must not take down
+    // every later check (e.g. in watch mode)
+    if CFG.is_poisoned() {
+        CFG.clear_poison();
+    }
+    result.map_err(|payload| {
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_else(|| String::from("unknown error"))
+            .trim()
+            .to_owned()
+    })
+}
+
+pub(crate) fn format_diagnostic(d: &CheckDiagnostic) -> String {
+    let location = match (d.line, d.column) {
+        (Some(line), Some(column)) => format!("{}:{line}:{column}", d.file),
+        _ => d.file.clone(),
+    };
+    match &d.lint {
+        Some(lint) => format!("{location}: {}[{lint}]: {}", d.severity, d.message),
+        None => format!("{location}: {}: {}", d.severity, d.message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_check_args() {
+        let args: CheckArgs = CheckArgs::parse_from(["foundry-cli", "contract.zkay"]);
+        assert_eq!(args.format, OutputFormat::Text);
+    }
+
+    #[test]
+    fn format_lint_diagnostic() {
+        let d = CheckDiagnostic {
+            file: String::from("c.zkay"),
+            severity: String::from("warning"),
+            lint: Some(String::from("private_require")),
+            line: Some(3),
+            column: Some(5),
+            message: String::from("msg"),
+        };
+        assert_eq!(
+            format_diagnostic(&d),
+            "c.zkay:3:5: warning[private_require]: msg"
+        );
+    }
+
+    #[test]
+    fn type_error_is_located() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract TypeError {
+    uint x;
+
+    function f() public {
+        x = true;
+    }
+}
+"#;
+        let diagnostics = check_code("c.zkay", code);
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].severity, "error");
+        assert!(diagnostics[0].message.contains("TypeMismatch"));
+        assert_eq!(diagnostics[0].line, Some(7));
+    }
+
+    #[test]
+    fn soundness_check_is_reported_as_diagnostic() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract EvalOrder {
+    uint x;
+
+    function inc() public returns (uint) {
+        x = x + 1;
+        return x;
+    }
+
+    function f() public returns (uint) {
+        return inc() + x;
+    }
+}
+"#;
+        let diagnostics = check_code("c.zkay", code);
+        let eval_order: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.lint.as_deref() == Some("eval_order"))
+            .collect();
+        assert!(!eval_order.is_empty(), "{diagnostics:?}");
+        assert!(eval_order
+            .iter()
+            .all(|d| d.severity == "error" && d.line == Some(12)));
+    }
+}
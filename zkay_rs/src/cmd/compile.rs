@@ -26,19 +26,33 @@ use foundry_config::{
     Config,
 };
 use serde::Serialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::check::{catch_compiler_error, compiler_error_diagnostic, format_diagnostic};
 
 use my_logging::log_context::log_context;
-use zkay_config::{config::library_compilation_environment, with_context_block};
+use solidity::solc_manager::set_solc_version;
+use zkay_config::{
+    config::{find_project_config_file, library_compilation_environment, CFG},
+    config_version::Versions,
+    project::ProjectLayout,
+    with_context_block,
+};
 use zkay_utils::progress_printer::{fail_print, success_print};
 
+// How often `zkay compile --watch` polls the watched files for modifications
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
 // foundry_config::merge_impl_figment_convert!(CompileArgs, args);
 const SOLC_VERSION_HELP: &'static str = "zkay defaults to the latest installed\n \
           solidity version supported by the current zkay version.\n\n \
           If you need to use a particular minor release (e.g. because \n \
           the latest release is broken or you need determinism for testing)\n \
           you can specify a particular solc version (e.g. v0.5.12) via this argument.\n \
-          Note: An internet connection is required if the selected version is not installed";
+          Note: The selected version must be installed, see zkay update-solc\n \
+          An explicit version takes precedence over the solc_version of the project";
 
 /// CLI arguments for `forge build`.
 ///
@@ -65,22 +79,30 @@ const SOLC_VERSION_HELP: &'static str = "zkay defaults to the latest installed\n
 #[command(next_help_heading = "Build options", about = "Compile a zkay contract.", long_about = None)] // override doc
 pub struct CompileArgs {
     /// Build source files from specified paths.
-    #[arg(short,long,value_hint = ValueHint::DirPath, value_name = "OUTPUT_DIRECTORY",default_value=".",help="The directory to output the compiled contract to. Default: Current directory")]
+    #[arg(short,long,value_hint = ValueHint::DirPath, value_name = "OUTPUT_DIRECTORY",help="The directory to output the compiled contract to. Default: Current directory, or out/<contract> for each contract of a project")]
     #[serde(skip)]
-    pub output: PathBuf,
+    pub output: Option<PathBuf>,
 
-    #[arg(long,value_hint = ValueHint::FilePath, value_name = "ZKAY_FILE",help="The zkay source file")]
+    #[arg(long,value_hint = ValueHint::FilePath, value_name = "ZKAY_FILE",help="The zkay source file. Default: all contracts of the project in the working directory (see zkay init)")]
     #[serde(skip)]
-    pub input: PathBuf,
+    pub input: Option<PathBuf>,
 
     /// Print compiled contract names.
     #[arg(long, help = "enable logging")]
     #[serde(skip)]
     pub log: bool,
 
-    #[arg(long,value_name="CFG_VAL",default_value="0.6.12",help=SOLC_VERSION_HELP)]
+    #[arg(long, value_name = "CFG_VAL", help = SOLC_VERSION_HELP)]
     #[serde(skip)]
-    pub solc_version: String,
+    pub solc_version: Option<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "Watch the contracts and recompile them whenever they change"
+    )]
+    #[serde(skip)]
+    pub watch: bool,
     // /// Print compiled contract sizes.
     // /// Constructor argument length is not included in the calculation of initcode size.
     // #[arg(long)]
@@ -144,7 +166,14 @@ impl CompileArgs {
         //     sh_println!("{}", serde_json::to_string_pretty(&output.output())?)?;
         // }
         // println!("========compile======================{:?}", 1);
-        let input_path = self.input.clone();
+        let solc_version = self.solc_version.as_deref().unwrap_or("latest");
+        set_solc_version(solc_version).map_err(|e| eyre::eyre!(e))?;
+        if self.watch {
+            return self.watch_and_compile();
+        }
+        let Some(input_path) = self.input.clone() else {
+            return self.compile_project();
+        };
         // if let Ok(Some(input_path)) = self.input{
         //     if let Err(_) | Ok(false) = Path::new(input_path).try_exists() {
         //         with_context_block!(var _fp=fail_print()=>{
@@ -156,44 +185,115 @@ impl CompileArgs {
         // } else {
         //     PathBuf::new()
         // };
-        // create output directory
-        let output = self.output;
-        // println!("============================={:?}", output);
-        use path_absolutize::*;
-        let output_dir = Path::new(&output).absolutize().expect("absolute path fail");
-        if let Err(_) | Ok(false) = output_dir.try_exists() {
-            let _ = std::fs::create_dir_all(output_dir.clone());
-        } else if !output_dir.is_dir() {
-            with_context_block!(var _fp=fail_print()=>{
-                    println!("Error: \'{output_dir:?}\' is not a directory");});
-            std::process::exit(2);
-        }
+        let output = self.output.unwrap_or_else(|| PathBuf::from("."));
+        compile_contract_file(&input_path, &output)
+    }
 
-        // // Enable logging
-        // if let Some(true) = self.log {
-        //     // log_file = my_logging.get_log_file(filename='compile', include_timestamp=False, label=None)
-        //     // my_logging.prepare_logger(log_file)
-        // }
-        // // only type-check
-        println!("Compiling file {:?}:", input_path);
-
-        // // compile
-        let input_basename = input_path.file_name().unwrap().to_str().unwrap();
-        with_context_block!(var _lc=log_context(input_basename)=>{
-        if let Err(e) = crate::zkay_frontend::compile_zkay_file(
-            &input_path.to_str().expect(""),
-            output_dir.to_str().expect(""),
-            false,
-        ) {
-            //ZkayCompilerError
-            with_context_block!(var _fp=fail_print()=>{
-            println!("===compile_zkay_file===fail==={e}");});
-            std::process::exit(3);
+    // Compile every contract of the project in the working directory, each into its own output directory
+    fn compile_project(&self) -> Result<()> {
+        let (layout, targets) = self.project_targets()?;
+        for (contract, output_dir) in &targets {
+            // compilation adjusts the configuration to the contract (e.g. unused crypto backends),
+            // so every contract starts from the project settings
+            let settings = CFG.lock().unwrap().export_compiler_settings();
+            let result = compile_contract_file(contract, output_dir);
+            CFG.lock().unwrap().import_compiler_settings(settings);
+            result?;
         }
-        });
+        sh_println!(
+            "Compiled {} contract(s) of project {}",
+            targets.len(),
+            layout.name
+        )?;
         Ok(())
     }
 
+    // The contracts of the project in the working directory together with their output directories
+    fn project_targets(&self) -> Result<(ProjectLayout, Vec<(PathBuf, PathBuf)>)> {
+        let cwd = std::env::current_dir()?;
+        let Some((root, layout)) = ProjectLayout::find(&cwd)? else {
+            eyre::bail!("no --input given and no zkay project found (zkay.toml with a [project] table), see `zkay init`");
+        };
+        // the project's solc version only applies if none was selected on the command line
+        if let (None, Some(solc_version)) = (&self.solc_version, &layout.solc_version) {
+            set_solc_version(solc_version).map_err(|e| eyre::eyre!(e))?;
+        }
+        let contracts = layout.contract_files(&root)?;
+        if contracts.is_empty() {
+            eyre::bail!(
+                "no .zkay files found in {}",
+                root.join(&layout.contracts).display()
+            );
+        }
+        let targets = contracts
+            .into_iter()
+            .map(|contract| {
+                let output_dir = match &self.output {
+                    Some(output) => output.join(contract.file_stem().unwrap()),
+                    None => layout.contract_output_dir(&root, &contract),
+                };
+                (contract, output_dir)
+            })
+            .collect();
+        Ok((layout, targets))
+    }
+
+    // Rerun the pipeline whenever a watched contract (or the project configuration) changes.
+    // Every contract is recompiled into the same output directory, so circuits whose content hash
+    // did not change are neither recompiled nor re-keyed (see JsnarkGenerator::_generate_zkcircuit).
+    fn watch_and_compile(&self) -> Result<()> {
+        let mut last_modified = HashMap::new();
+        let mut changed = |path: &Path| {
+            let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            last_modified.insert(path.to_path_buf(), modified) != Some(modified)
+        };
+        let mut project_error = None;
+        sh_println!("Watching for changes, press Ctrl+C to stop")?;
+        loop {
+            let cwd = std::env::current_dir()?;
+            let config_changed =
+                find_project_config_file(&cwd).map_or(false, |f| changed(f.as_path()));
+            if config_changed {
+                // keep watching with the previous configuration until the file is fixed
+                let reloaded = CFG
+                    .lock()
+                    .unwrap()
+                    .load_configuration_from_disk(String::new());
+                if let Err(e) = reloaded {
+                    sh_println!("{e}")?;
+                }
+            }
+            let targets = match &self.input {
+                Some(input) => vec![(
+                    input.clone(),
+                    self.output.clone().unwrap_or_else(|| PathBuf::from(".")),
+                )],
+                None => match self.project_targets() {
+                    Ok((_, targets)) => targets,
+                    Err(e) => {
+                        // e.g. the last contract was removed, report it once and keep watching
+                        let e = e.to_string();
+                        if project_error.as_ref() != Some(&e) {
+                            sh_println!("{e}")?;
+                            project_error = Some(e);
+                        }
+                        std::thread::sleep(WATCH_INTERVAL);
+                        continue;
+                    }
+                },
+            };
+            project_error = None;
+            // the file list of a project is determined anew, so new contracts are picked up as well
+            for (contract, output_dir) in targets {
+                let contract_changed = changed(contract.as_path());
+                if contract_changed || config_changed {
+                    watch_compile(&contract, &output_dir)?;
+                }
+            }
+            std::thread::sleep(WATCH_INTERVAL);
+        }
+    }
+
     // /// Returns the `Project` for the current workspace
     // ///
     // /// This loads the `foundry_config::Config` for the current workspace (see
@@ -221,6 +321,58 @@ impl CompileArgs {
     // }
 }
 
+// Compile a single zkay file into output
+fn compile_contract_file(input_path: &Path, output: &Path) -> Result<()> {
+    // println!("============================={:?}", output);
+    use path_absolutize::*;
+    let output_dir = Path::new(&output).absolutize()?;
+    if let Err(_) | Ok(false) = output_dir.try_exists() {
+        std::fs::create_dir_all(output_dir.clone())?;
+    } else if !output_dir.is_dir() {
+        eyre::bail!("'{}' is not a directory", output_dir.display());
+    }
+
+    // // Enable logging
+    // if let Some(true) = self.log {
+    //     // log_file = my_logging.get_log_file(filename='compile', include_timestamp=False, label=None)
+    //     // my_logging.prepare_logger(log_file)
+    // }
+    // // only type-check
+    println!("Compiling file {:?}:", input_path);
+
+    // // compile
+    let input_basename = input_path.file_name().unwrap().to_str().unwrap();
+    let result;
+    with_context_block!(var _lc=log_context(input_basename)=>{
+    result = crate::zkay_frontend::compile_zkay_file(
+        &input_path.to_str().expect(""),
+        output_dir.to_str().expect(""),
+        false,
+    );
+    });
+    //ZkayCompilerError
+    result.map_err(|e| eyre::eyre!("{}", e.to_string().trim()))
+}
+
+// Compile a contract in watch mode: problems are reported as concise diagnostics instead of ending the process.
+// The front end (parsing, type checking, lints) runs as part of the compilation, so it runs once per change.
+fn watch_compile(input_path: &Path, output_dir: &Path) -> Result<()> {
+    let file = input_path.to_string_lossy().to_string();
+    let start = Instant::now();
+    let settings = CFG.lock().unwrap().export_compiler_settings();
+    let result = catch_compiler_error(|| compile_contract_file(input_path, output_dir))
+        .and_then(|compiled| compiled.map_err(|e| e.to_string()));
+    CFG.lock().unwrap().import_compiler_settings(settings);
+    match result {
+        Ok(()) => sh_println!("{file}: compiled in {:.1}s", start.elapsed().as_secs_f64())?,
+        Err(message) => sh_println!(
+            "{}",
+            format_diagnostic(&compiler_error_diagnostic(&file, message))
+        )?,
+    }
+    Ok(())
+}
+
 // // Make this args a `figment::Provider` so that it can be merged into the `Config`
 // impl Provider for CompileArgs {
 //     fn metadata(&self) -> Metadata {
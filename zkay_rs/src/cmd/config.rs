@@ -0,0 +1,90 @@
+use super::OutputFormat;
+use clap::{Parser, Subcommand};
+use eyre::Result;
+use foundry_common::sh_println;
+use serde_json::{json, Value};
+
+use zkay_config::config::{env_var_name, CFG, USER_CONFIG_OPTIONS};
+
+/// CLI arguments for `zkay config`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Inspect the zkay configuration.", long_about = None)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub cmd: ConfigSubcommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum ConfigSubcommand {
+    /// Print the effective value of every option and where it was configured.
+    ///
+    /// Later sources take precedence: default, global config, user config,
+    /// project config (closest zkay.toml), ZKAY_* environment variables.
+    Show {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+impl ConfigArgs {
+    pub fn run(self) -> Result<()> {
+        match self.cmd {
+            ConfigSubcommand::Show { format } => {
+                let cfg = CFG.lock().unwrap();
+                let entries: Vec<(&str, Value, String)> = USER_CONFIG_OPTIONS
+                    .iter()
+                    .map(|&name| {
+                        (
+                            name,
+                            cfg.get_attr(name).unwrap_or(Value::Null),
+                            cfg.config_source(name),
+                        )
+                    })
+                    .collect();
+                match format {
+                    OutputFormat::Json => {
+                        let out: serde_json::Map<String, Value> = entries
+                            .into_iter()
+                            .map(|(name, value, source)| {
+                                (name.to_owned(), json!({"value": value, "source": source}))
+                            })
+                            .collect();
+                        sh_println!("{}", serde_json::to_string_pretty(&out)?)?
+                    }
+                    OutputFormat::Text => {
+                        let width = USER_CONFIG_OPTIONS
+                            .iter()
+                            .map(|n| n.len())
+                            .max()
+                            .unwrap_or(0);
+                        for (name, value, source) in entries {
+                            sh_println!("{name:<width$} = {value}  # {source}")?;
+                        }
+                        sh_println!(
+                            "\nEvery option can be overridden via an environment variable, e.g. {}",
+                            env_var_name("proving_scheme")
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_show() {
+        let args: ConfigArgs = ConfigArgs::parse_from(["foundry-cli", "show", "--format", "json"]);
+        assert!(matches!(
+            args.cmd,
+            ConfigSubcommand::Show {
+                format: OutputFormat::Json
+            }
+        ));
+    }
+}
@@ -105,11 +105,13 @@ pub struct CreateArgs {
     )]
     constructor_args_path: Option<PathBuf>,
 
-    /// The constructor arguments.
+    /// Addresses of the deployed pki contracts, one per crypto backend
+    /// (default: blockchain_pki_address from the configuration, otherwise deploy them).
     #[arg(long, allow_hyphen_values = true)]
     blockchain_pki_addresses: Vec<String>,
 
-    /// The constructor arguments.
+    /// Addresses of the deployed crypto libraries
+    /// (default: blockchain_crypto_lib_addresses from the configuration).
     #[arg(long, allow_hyphen_values = true)]
     blockchain_crypto_lib_addresses: Vec<String>,
 
@@ -455,7 +457,7 @@ impl CreateArgs {
                 None,
             ),
             global_vars,
-        );
+        )?;
 
         // Deploy verification contracts if not already done
         let external_contract_addresses = self
@@ -635,27 +637,28 @@ impl CreateArgs {
         // }
         // with_context_block!(var _lce=library_compilation_environment()=>{
         // let tmpdir = std::env::temp_dir();
-        if !self.blockchain_pki_addresses.is_empty() {
-            return Ok(CFG
-                .lock()
-                .unwrap()
-                .all_crypto_params()
-                .into_iter()
-                .zip(
-                    self.blockchain_pki_addresses
-                        .iter()
-                        .map(|v| Address::from_str(&v).unwrap()),
-                )
-                .collect());
-        }
-
+        // Addresses given on the command line take precedence over the ones stored in the
+        // configuration by `zkay deploy-pki` / `zkay deploy-crypto-libs`, only missing pki contracts are deployed
+        let (main_crypto_backend, configured_pki_address, lib_names, configured_lib_addresses) = {
+            let cfg = CFG.lock().unwrap();
+            (
+                cfg.main_crypto_backend(),
+                cfg.blockchain_pki_address(),
+                cfg.external_crypto_lib_names(),
+                cfg.blockchain_crypto_lib_addresses(),
+            )
+        };
+        let parse_address = |option: &str, address: &str| {
+            Address::from_str(address.trim())
+                .wrap_err_with(|| format!("invalid address {address} in {option}"))
+        };
         let mut contract_name2address = BTreeMap::new();
         let all_crypto_params = CFG.lock().unwrap().all_crypto_params();
-        for crypto_params in all_crypto_params {
+        for (i, crypto_params) in all_crypto_params.into_iter().enumerate() {
             let contract_name = CFG
                 .lock()
                 .unwrap()
-                .get_pki_contract_name(&CryptoParams::new(crypto_params).identifier_name());
+                .get_pki_contract_name(&CryptoParams::new(crypto_params.clone()).identifier_name());
             // let pki_sol = save_to_file(
             //     Some(tmpdir.clone()),
             //     &format!("{contract_name}.sol"),
@@ -670,11 +673,40 @@ impl CreateArgs {
             //     false,
             //     None,
             // );
-            let filename = project_dir.join(&format!("{contract_name}.sol"));
-            let (abi, bin, id) = self.compile_contract(&filename, project, &contract_name)?;
-            let v = self._deploy_contract(abi, bin, id).await?;
+            let address = if let Some(address) = self.blockchain_pki_addresses.get(i) {
+                parse_address("--blockchain-pki-addresses", address)?
+            } else if crypto_params == main_crypto_backend && !configured_pki_address.is_empty() {
+                parse_address("blockchain_pki_address", &configured_pki_address)?
+            } else {
+                let filename = project_dir.join(&format!("{contract_name}.sol"));
+                let (abi, bin, id) = self.compile_contract(&filename, project, &contract_name)?;
+                self._deploy_contract(abi, bin, id).await?
+            };
             // with_context_block!(var _tm= time_measure("transaction_full",false,false)=>{
-            contract_name2address.insert(contract_name.clone(), v);
+            contract_name2address.insert(contract_name, address);
+        }
+
+        let lib_addresses: Vec<_> = if !self.blockchain_crypto_lib_addresses.is_empty() {
+            self.blockchain_crypto_lib_addresses
+                .iter()
+                .map(|address| parse_address("--blockchain-crypto-lib-addresses", address))
+                .collect::<Result<_>>()?
+        } else if !configured_lib_addresses.is_empty() {
+            configured_lib_addresses
+                .split(',')
+                .map(|address| parse_address("blockchain_crypto_lib_addresses", address))
+                .collect::<Result<_>>()?
+        } else {
+            vec![]
+        };
+        if !lib_addresses.is_empty() {
+            eyre::ensure!(
+                lib_addresses.len() == lib_names.len(),
+                "Must specify all crypto library addresses\nExpected {} was {}",
+                lib_names.len(),
+                lib_addresses.len()
+            );
+            contract_name2address.extend(lib_names.into_iter().zip(lib_addresses));
         }
         Ok(contract_name2address)
         // let verify_sol = save_to_file(
@@ -935,68 +967,17 @@ impl CreateArgs {
         let bin = bin.into_bytes().unwrap_or_else(|| {
             panic!("no bytecode found in bin object for {}", self.contract.name)
         });
-        let provider = Arc::new(provider);
-        let factory = ContractFactory::new(abi.clone(), bin.clone(), provider.clone(), timeout);
-
-        let is_args_empty = args.is_empty();
-        let mut deployer =
-            factory.deploy_tokens(args.clone()).context("failed to deploy contract").map_err(|e| {
-                if is_args_empty {
-                    e.wrap_err("no arguments provided for contract constructor; consider --constructor-args or --constructor-args-path")
-                } else {
-                    e
-                }
-            })?;
-        let is_legacy = self.tx.legacy || Chain::from(chain).is_legacy();
-
-        deployer.tx.set_from(deployer_address);
-        deployer.tx.set_chain_id(chain);
-        // `to` field must be set explicitly, cannot be None.
-        if deployer.tx.to.is_none() {
-            deployer.tx.set_create();
-        }
-        deployer.tx.set_nonce(if let Some(nonce) = self.tx.nonce {
-            Ok(nonce.to())
-        } else {
-            provider.get_transaction_count(deployer_address).await
-        }?);
-
-        // set tx value if specified
-        if let Some(value) = self.tx.value {
-            deployer.tx.set_value(value);
-        }
-
-        deployer
-            .tx
-            .set_gas_limit(if let Some(gas_limit) = self.tx.gas_limit {
-                Ok(gas_limit.to())
-            } else {
-                provider.estimate_gas(&deployer.tx).await
-            }?);
-
-        if is_legacy {
-            let gas_price = if let Some(gas_price) = self.tx.gas_price {
-                gas_price.to()
-            } else {
-                provider.get_gas_price().await?
-            };
-            deployer.tx.set_gas_price(gas_price);
-        } else {
-            let estimate = provider.estimate_eip1559_fees(None).await.wrap_err("Failed to estimate EIP1559 fees. This chain might not support EIP1559, try adding --legacy to your command.")?;
-            let priority_fee = if let Some(priority_fee) = self.tx.priority_gas_price {
-                priority_fee.to()
-            } else {
-                estimate.max_priority_fee_per_gas
-            };
-            let max_fee = if let Some(max_fee) = self.tx.gas_price {
-                max_fee.to()
-            } else {
-                estimate.max_fee_per_gas
-            };
-
-            deployer.tx.set_max_fee_per_gas(max_fee);
-            deployer.tx.set_max_priority_fee_per_gas(priority_fee);
-        }
+        let deployer = prepare_deployment(
+            &self.tx,
+            abi.clone(),
+            bin,
+            args.clone(),
+            Arc::new(provider),
+            chain,
+            deployer_address,
+            timeout,
+        )
+        .await?;
 
         // Before we actually deploy the contract we try check if the verify settings are valid
         let mut constructor_args = None;
@@ -1097,6 +1078,83 @@ impl CreateArgs {
     }
 }
 
+/// Builds the transaction deploying `bin` with the constructor arguments `args` from
+/// `deployer_address`: nonce, value, gas limit and fees are taken from `tx` or requested from the node.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn prepare_deployment<P: Provider<T, AnyNetwork>, T: Transport + Clone>(
+    tx: &TransactionOpts,
+    abi: JsonAbi,
+    bin: Bytes,
+    args: Vec<DynSolValue>,
+    provider: Arc<P>,
+    chain: u64,
+    deployer_address: Address,
+    timeout: u64,
+) -> Result<Deployer<Arc<P>, P, T>> {
+    let factory = ContractFactory::new(abi, bin, provider.clone(), timeout);
+
+    let is_args_empty = args.is_empty();
+    let mut deployer =
+        factory.deploy_tokens(args).context("failed to deploy contract").map_err(|e| {
+            if is_args_empty {
+                e.wrap_err("no arguments provided for contract constructor; consider --constructor-args or --constructor-args-path")
+            } else {
+                e
+            }
+        })?;
+    let is_legacy = tx.legacy || Chain::from(chain).is_legacy();
+
+    deployer.tx.set_from(deployer_address);
+    deployer.tx.set_chain_id(chain);
+    // `to` field must be set explicitly, cannot be None.
+    if deployer.tx.to.is_none() {
+        deployer.tx.set_create();
+    }
+    deployer.tx.set_nonce(if let Some(nonce) = tx.nonce {
+        Ok(nonce.to())
+    } else {
+        provider.get_transaction_count(deployer_address).await
+    }?);
+
+    // set tx value if specified
+    if let Some(value) = tx.value {
+        deployer.tx.set_value(value);
+    }
+
+    deployer
+        .tx
+        .set_gas_limit(if let Some(gas_limit) = tx.gas_limit {
+            Ok(gas_limit.to())
+        } else {
+            provider.estimate_gas(&deployer.tx).await
+        }?);
+
+    if is_legacy {
+        let gas_price = if let Some(gas_price) = tx.gas_price {
+            gas_price.to()
+        } else {
+            provider.get_gas_price().await?
+        };
+        deployer.tx.set_gas_price(gas_price);
+    } else {
+        let estimate = provider.estimate_eip1559_fees(None).await.wrap_err("Failed to estimate EIP1559 fees. This chain might not support EIP1559, try adding --legacy to your command.")?;
+        let priority_fee = if let Some(priority_fee) = tx.priority_gas_price {
+            priority_fee.to()
+        } else {
+            estimate.max_priority_fee_per_gas
+        };
+        let max_fee = if let Some(max_fee) = tx.gas_price {
+            max_fee.to()
+        } else {
+            estimate.max_fee_per_gas
+        };
+
+        deployer.tx.set_max_fee_per_gas(max_fee);
+        deployer.tx.set_max_priority_fee_per_gas(priority_fee);
+    }
+    Ok(deployer)
+}
+
 impl figment::Provider for CreateArgs {
     fn metadata(&self) -> Metadata {
         Metadata::named("Create Args Provider")
@@ -0,0 +1,276 @@
+use alloy_json_abi::JsonAbi;
+use alloy_network::{AnyNetwork, EthereumWallet};
+use alloy_primitives::{Address, Bytes};
+use alloy_provider::{Provider, ProviderBuilder};
+use alloy_signer::Signer;
+use alloy_transport::Transport;
+use clap::{Parser, ValueHint};
+use eyre::{Context, Result};
+use foundry_cli::{
+    opts::{CoreBuildArgs, EthereumOpts, TransactionOpts},
+    utils::{self, remove_contract, LoadConfig},
+};
+use foundry_common::{compile, sh_println};
+use foundry_config::{
+    figment::{
+        self,
+        value::{Dict, Map},
+        Metadata, Profile,
+    },
+    merge_impl_figment_convert, Config,
+};
+use std::{path::PathBuf, sync::Arc};
+
+use super::create::prepare_deployment;
+use privacy::library_contracts;
+use zkay_config::{
+    config::{
+        find_project_config_file, library_compilation_environment, store_project_option, CFG,
+    },
+    config_user::UserConfig,
+    project::PROJECT_FILE,
+    with_context_block,
+};
+use zkay_transaction_crypto_params::params::CryptoParams;
+use zkay_utils::helpers::save_to_file;
+
+/// CLI arguments for `zkay deploy-pki`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Deploy the global pki contract compatible with a particular crypto backend.", long_about = None)]
+pub struct DeployPkiArgs {
+    /// Crypto backend of the pki contract (default: main_crypto_backend from the configuration)
+    #[arg(value_parser = ["dummy", "dummy-hom", "rsa-pkcs1.5", "rsa-oaep", "ecdh-aes", "ecdh-chaskey", "paillier", "elgamal"])]
+    pub crypto_backend: Option<String>,
+
+    #[command(flatten)]
+    pub deploy: LibraryDeployOpts,
+}
+
+/// CLI arguments for `zkay deploy-crypto-libs`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Deploy the crypto libraries required by the configured proving scheme.", long_about = None)]
+pub struct DeployCryptoLibsArgs {
+    #[command(flatten)]
+    pub deploy: LibraryDeployOpts,
+}
+
+/// Account, node and transaction options shared by the library deployment commands.
+#[derive(Clone, Debug, Parser)]
+pub struct LibraryDeployOpts {
+    /// Project configuration file to store the deployed addresses in
+    /// (default: the closest zkay.toml, otherwise ./zkay.toml)
+    #[arg(long, value_hint = ValueHint::FilePath, value_name = "FILE")]
+    pub config_file: Option<PathBuf>,
+
+    /// Only print the deployed addresses, do not store them in the project configuration
+    #[arg(long, conflicts_with = "config_file")]
+    pub no_save: bool,
+
+    /// Send via `eth_sendTransaction` using the `--from` argument or `$ETH_FROM` as sender
+    #[arg(long, requires = "from")]
+    pub unlocked: bool,
+
+    /// Timeout to use for broadcasting transactions.
+    #[arg(long, env = "ETH_TIMEOUT")]
+    pub timeout: Option<u64>,
+
+    #[command(flatten)]
+    opts: CoreBuildArgs,
+
+    #[command(flatten)]
+    tx: TransactionOpts,
+
+    #[command(flatten)]
+    eth: EthereumOpts,
+}
+merge_impl_figment_convert!(LibraryDeployOpts, opts, eth);
+
+impl DeployPkiArgs {
+    pub async fn run(self) -> Result<()> {
+        let crypto_backend = self
+            .crypto_backend
+            .unwrap_or_else(|| CFG.lock().unwrap().main_crypto_backend());
+        let crypto_params = CryptoParams::new(crypto_backend);
+        let address;
+        with_context_block!(var _lce=library_compilation_environment()=>{
+            let pki_contract_name = CFG
+                .lock()
+                .unwrap()
+                .get_pki_contract_name(&crypto_params.identifier_name());
+            let file = save_to_file(
+                Some(std::env::temp_dir()),
+                &format!("{pki_contract_name}.sol"),
+                &library_contracts::get_pki_contract(&crypto_params),
+            );
+            address = self
+                .deploy
+                .deploy_solidity_contract(&PathBuf::from(file), &pki_contract_name)
+                .await?;
+        });
+        sh_println!("Deployed pki contract at: {address}")?;
+        self.deploy
+            .store_address("blockchain_pki_address", &address.to_checksum(None))
+    }
+}
+
+impl DeployCryptoLibsArgs {
+    pub async fn run(self) -> Result<()> {
+        let lib_names = CFG.lock().unwrap().external_crypto_lib_names();
+        if lib_names.is_empty() {
+            sh_println!("Current proving scheme does not require library deployment")?;
+            return Ok(());
+        }
+        let mut addresses = vec![];
+        with_context_block!(var _lce=library_compilation_environment()=>{
+            let file = save_to_file(
+                Some(std::env::temp_dir()),
+                "verify_libs.sol",
+                &library_contracts::get_verify_libs_code(),
+            );
+            for lib in &lib_names {
+                let address = self
+                    .deploy
+                    .deploy_solidity_contract(&PathBuf::from(&file), lib)
+                    .await?;
+                sh_println!("Deployed crypto library {lib} at: {address}")?;
+                addresses.push(address.to_checksum(None));
+            }
+        });
+        // same order as external_crypto_lib_names, which is what the config option expects
+        self.deploy
+            .store_address("blockchain_crypto_lib_addresses", &addresses.join(","))
+    }
+}
+
+impl LibraryDeployOpts {
+    /// Compiles `contract_name` in `sol_filename` and deploys it from the selected account.
+    async fn deploy_solidity_contract(
+        &self,
+        sol_filename: &PathBuf,
+        contract_name: &str,
+    ) -> Result<Address> {
+        let config = self.try_load_config_emit_warnings()?;
+        let project = config.project()?;
+        let output = compile::compile_target(sol_filename, &project, false)?;
+        let (abi, bin, _) = remove_contract(output, sol_filename, contract_name)?;
+        let Some(bin) = bin.object.into_bytes() else {
+            eyre::bail!("{contract_name} requires linking and cannot be deployed as a library");
+        };
+
+        let provider = utils::get_provider(&config)?;
+        let chain_id = provider.get_chain_id().await?;
+        if self.unlocked {
+            // Deploy with unlocked account
+            let sender = self.eth.wallet.from.expect("required");
+            self.send_deployment(
+                abi,
+                bin,
+                provider,
+                chain_id,
+                sender,
+                config.transaction_timeout,
+            )
+            .await
+        } else {
+            // Deploy with signer
+            let signer = self.eth.wallet.signer().await?;
+            let deployer = signer.address();
+            let provider = ProviderBuilder::<_, _, AnyNetwork>::default()
+                .wallet(EthereumWallet::new(signer))
+                .on_provider(provider);
+            self.send_deployment(
+                abi,
+                bin,
+                provider,
+                chain_id,
+                deployer,
+                config.transaction_timeout,
+            )
+            .await
+        }
+    }
+
+    async fn send_deployment<P: Provider<T, AnyNetwork>, T: Transport + Clone>(
+        &self,
+        abi: JsonAbi,
+        bin: Bytes,
+        provider: P,
+        chain: u64,
+        deployer_address: Address,
+        timeout: u64,
+    ) -> Result<Address> {
+        let deployer = prepare_deployment(
+            &self.tx,
+            abi,
+            bin,
+            vec![],
+            Arc::new(provider),
+            chain,
+            deployer_address,
+            timeout,
+        )
+        .await?;
+        let (address, receipt) = deployer.send_with_receipt().await?;
+        sh_println!("Transaction hash: {:?}", receipt.transaction_hash)?;
+        Ok(address)
+    }
+
+    /// Stores a deployed address in the project configuration, where all later deployments pick it up.
+    fn store_address(&self, option: &str, value: &str) -> Result<()> {
+        if self.no_save {
+            return Ok(());
+        }
+        let file = match &self.config_file {
+            Some(file) => file.clone(),
+            None => {
+                let cwd = std::env::current_dir()?;
+                find_project_config_file(&cwd).unwrap_or_else(|| cwd.join(PROJECT_FILE))
+            }
+        };
+        store_project_option(&file, option, value)
+            .wrap_err_with(|| format!("could not update {}", file.display()))?;
+        sh_println!("Stored {option} in {}", file.display())?;
+        Ok(())
+    }
+}
+
+impl figment::Provider for LibraryDeployOpts {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Library Deploy Args Provider")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        let mut dict = Dict::default();
+        if let Some(timeout) = self.timeout {
+            dict.insert("transaction_timeout".to_string(), timeout.into());
+        }
+        Ok(Map::from([(Config::selected_profile(), dict)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_deploy_pki_args() {
+        let args: DeployPkiArgs =
+            DeployPkiArgs::parse_from(["foundry-cli", "elgamal", "--no-save", "--legacy"]);
+        assert_eq!(args.crypto_backend.as_deref(), Some("elgamal"));
+        assert!(args.deploy.no_save);
+    }
+
+    #[test]
+    fn parse_deploy_crypto_libs_args() {
+        let args: DeployCryptoLibsArgs = DeployCryptoLibsArgs::parse_from([
+            "foundry-cli",
+            "--config-file",
+            "zkay.toml",
+            "--unlocked",
+            "--from",
+            "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
+        ]);
+        assert_eq!(args.deploy.config_file, Some(PathBuf::from("zkay.toml")));
+        assert!(args.deploy.unlocked);
+    }
+}
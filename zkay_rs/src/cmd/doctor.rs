@@ -0,0 +1,428 @@
+use super::OutputFormat;
+use clap::Parser;
+use eyre::Result;
+use foundry_common::sh_println;
+use serde::Serialize;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use jsnark_interface::jsnark_interface::{
+    CIRCUIT_BUILDER_JAR, CIRCUIT_BUILDER_JAR_SHA512, JARS_DIR, JAVA_MAX_HEAP,
+};
+use rs_sha512::{HasherContext, Sha512State};
+use solidity::solc_manager::{installed_solc_path, installed_versions, solc_binary_version};
+use zkay_config::{config::CFG, config_user::UserConfig, config_version::Versions};
+
+// Docker image in which the libsnark interface is run (see jsnark_interface::libsnark_interface)
+const LIBSNARK_DOCKER_IMAGE: &str = "ethsrilab/zkay:version-1.5";
+
+/// CLI arguments for `zkay doctor`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Check that all external tools required by zkay are installed and usable.", long_about = None)]
+pub struct DoctorArgs {
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Result of checking a single dependency.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    /// Version, path or other details of what was found
+    pub detail: String,
+    /// How to fix the problem, if any
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_owned(),
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn problem(
+        name: &str,
+        status: CheckStatus,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.to_owned(),
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+impl DoctorArgs {
+    pub fn run(self) -> Result<()> {
+        let (data_dir, log_dir) = {
+            let cfg = CFG.lock().unwrap();
+            (cfg.data_dir(), cfg.log_dir())
+        };
+        let checks = vec![
+            check_java_tool("java", &["-version"]),
+            check_java_tool("javac", &["-version"]),
+            check_circuit_builder_jar(),
+            check_libsnark(),
+            check_solc(),
+            check_writable_dir("data_dir", Path::new(&data_dir)),
+            check_writable_dir("log_dir", Path::new(&log_dir)),
+            check_memory(),
+        ];
+
+        match self.format {
+            OutputFormat::Json => sh_println!("{}", serde_json::to_string_pretty(&checks)?)?,
+            OutputFormat::Text => {
+                for check in &checks {
+                    sh_println!("{}", format_check(check))?;
+                }
+            }
+        }
+        let errors = checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Error)
+            .count();
+        if errors > 0 {
+            eyre::bail!("{errors} check(s) failed");
+        }
+        Ok(())
+    }
+}
+
+fn format_check(check: &DoctorCheck) -> String {
+    let status = match check.status {
+        CheckStatus::Ok => "ok",
+        CheckStatus::Warning => "warning",
+        CheckStatus::Error => "error",
+    };
+    let mut out = format!("[{status:^7}] {:<18} {}", check.name, check.detail);
+    if let Some(fix) = &check.fix {
+        out += &format!("\n{:28}fix: {fix}", "");
+    }
+    out
+}
+
+// First non-empty line printed by the command (on stdout or stderr), None if it cannot be run
+fn command_output(program: impl AsRef<std::ffi::OsStr>, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).to_string()
+        + &String::from_utf8_lossy(&output.stderr);
+    Some(
+        text.lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .unwrap_or_default()
+            .to_owned(),
+    )
+}
+
+fn check_java_tool(tool: &str, version_args: &[&str]) -> DoctorCheck {
+    match command_output(tool, version_args) {
+        Some(version) => DoctorCheck::ok(tool, version),
+        None => DoctorCheck::problem(
+            tool,
+            CheckStatus::Error,
+            "not found on PATH",
+            "install a JDK (version 11 or newer) and make sure java and javac are on PATH",
+        ),
+    }
+}
+
+fn check_circuit_builder_jar() -> DoctorCheck {
+    let jar = Path::new(JARS_DIR.as_str()).join(CIRCUIT_BUILDER_JAR);
+    check_jar(&jar, CIRCUIT_BUILDER_JAR_SHA512)
+}
+
+// Check that jar is the circuit builder with the pinned SHA-512 hash expected_sha512
+fn check_jar(jar: &Path, expected_sha512: &str) -> DoctorCheck {
+    let name = "circuit builder";
+    let content = match fs::read(jar) {
+        Ok(content) => content,
+        Err(e) => {
+            return DoctorCheck::problem(
+                name,
+                CheckStatus::Error,
+                format!("cannot read {}: {e}", jar.display()),
+                format!(
+                    "build the jsnark circuit builder and copy {CIRCUIT_BUILDER_JAR} to {}",
+                    JARS_DIR.as_str()
+                ),
+            )
+        }
+    };
+    if !content.starts_with(b"PK\x03\x04") {
+        return DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            format!("{} is not a jar file", jar.display()),
+            format!("rebuild {CIRCUIT_BUILDER_JAR}, the file is corrupted"),
+        );
+    }
+    let hash = sha512_hex(&content);
+    if hash != expected_sha512 {
+        return DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            format!(
+                "{} has hash {}..., expected {}...",
+                jar.display(),
+                &hash[..16],
+                &expected_sha512[..16]
+            ),
+            format!("replace {CIRCUIT_BUILDER_JAR} by the version shipped with this zkay release, a different circuit builder produces incompatible circuits"),
+        );
+    }
+    DoctorCheck::ok(name, format!("{} (hash {}...)", jar.display(), &hash[..16]))
+}
+
+fn sha512_hex(content: &[u8]) -> String {
+    let mut digest = Sha512State::default().build_hasher();
+    digest.write(content);
+    format!("{:02x}", HasherContext::finish(&mut digest))
+}
+
+// Key and proof generation run the libsnark interface inside docker with $ZKAY_PATH mounted to /tmp
+fn check_libsnark() -> DoctorCheck {
+    let name = "libsnark";
+    if command_output("docker", &["--version"]).is_none() {
+        return DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            "docker not found on PATH",
+            "install docker, key and proof generation run the libsnark interface in a container",
+        );
+    }
+    let Ok(zkay_path) = std::env::var("ZKAY_PATH") else {
+        return DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            "ZKAY_PATH is not set",
+            "set ZKAY_PATH to the directory containing the zkay checkout with the built run_snark binary",
+        );
+    };
+    let runner = Path::new(&zkay_path)
+        .join("zkay")
+        .join("jsnark_interface")
+        .join("run_snark");
+    if !runner.is_file() {
+        return DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            format!("{} not found", runner.display()),
+            "build the libsnark interface (run_snark) in the zkay checkout referenced by ZKAY_PATH",
+        );
+    }
+    // run_snark is built from the checkout, its command line interface has to match this zkay version
+    let version_file = Path::new(&zkay_path).join("zkay").join("VERSION");
+    let expected = CFG.lock().unwrap().zkay_version().trim().to_owned();
+    match fs::read_to_string(&version_file) {
+        Ok(version) if version.trim() == expected => {}
+        Ok(version) => {
+            return DoctorCheck::problem(
+                name,
+                CheckStatus::Error,
+                format!(
+                    "{} is built from zkay {}, expected {expected}",
+                    runner.display(),
+                    version.trim()
+                ),
+                format!("check out zkay {expected} in ZKAY_PATH and rebuild run_snark"),
+            )
+        }
+        Err(e) => {
+            return DoctorCheck::problem(
+                name,
+                CheckStatus::Error,
+                format!(
+                    "cannot read the interface version {}: {e}",
+                    version_file.display()
+                ),
+                format!(
+                    "set ZKAY_PATH to a zkay {expected} checkout with the built run_snark binary"
+                ),
+            )
+        }
+    }
+    let image_present = Command::new("docker")
+        .args(["image", "inspect", LIBSNARK_DOCKER_IMAGE])
+        .output()
+        .map_or(false, |o| o.status.success());
+    if !image_present {
+        return DoctorCheck::problem(
+            name,
+            CheckStatus::Warning,
+            format!(
+                "{} (image {LIBSNARK_DOCKER_IMAGE} not pulled)",
+                runner.display()
+            ),
+            format!(
+                "run `docker pull {LIBSNARK_DOCKER_IMAGE}` (otherwise it is pulled on first use)"
+            ),
+        );
+    }
+    DoctorCheck::ok(
+        name,
+        format!(
+            "{} (zkay {expected}) in {LIBSNARK_DOCKER_IMAGE}",
+            runner.display()
+        ),
+    )
+}
+
+fn check_solc() -> DoctorCheck {
+    let name = "solc";
+    let (version, range) = {
+        let cfg = CFG.lock().unwrap();
+        (cfg.solc_version(), cfg.zkay_solc_version_compatibility())
+    };
+    let binary = installed_solc_path(&version).unwrap_or_else(|| PathBuf::from("solc"));
+    match solc_binary_version(&binary) {
+        Some(found) if found == version.trim_start_matches('v') => {
+            DoctorCheck::ok(name, format!("{found} ({})", binary.display()))
+        }
+        Some(found) => DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            format!("configured {version}, but {} is {found}", binary.display()),
+            format!("install solc {version} with `zkay update-solc --binary <solc>`"),
+        ),
+        None => DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            format!(
+                "solc {version} not installed (installed: {})",
+                installed_versions().join(", ")
+            ),
+            format!("install a solc binary satisfying {range} with `zkay update-solc --binary <solc>` or `--from-dir <dir>`"),
+        ),
+    }
+}
+
+fn check_writable_dir(name: &str, dir: &Path) -> DoctorCheck {
+    let probe = dir.join(".zkay-doctor");
+    match fs::create_dir_all(dir).and_then(|_| fs::write(&probe, b"")) {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            DoctorCheck::ok(name, dir.display().to_string())
+        }
+        Err(e) => DoctorCheck::problem(
+            name,
+            CheckStatus::Error,
+            format!("{} is not writable: {e}", dir.display()),
+            format!("fix the permissions or set {name} to a writable directory in the zkay configuration"),
+        ),
+    }
+}
+
+fn check_memory() -> DoctorCheck {
+    let name = "memory";
+    let required = max_heap_bytes(JAVA_MAX_HEAP);
+    let Some(total) = total_memory_bytes() else {
+        return DoctorCheck::problem(
+            name,
+            CheckStatus::Warning,
+            "could not determine the total memory",
+            format!("jsnark runs with {JAVA_MAX_HEAP}, make sure that much memory is available"),
+        );
+    };
+    let detail = format!(
+        "{} MiB total, jsnark runs with {JAVA_MAX_HEAP}",
+        total >> 20
+    );
+    match required {
+        Some(required) if total < required => DoctorCheck::problem(
+            name,
+            CheckStatus::Warning,
+            detail,
+            "large circuits may fail with OutOfMemoryError, add memory or swap or split the contract into smaller functions",
+        ),
+        _ => DoctorCheck::ok(name, detail),
+    }
+}
+
+// Size in bytes of a JVM heap setting such as -Xmx16384m
+fn max_heap_bytes(setting: &str) -> Option<u64> {
+    let value = setting.strip_prefix("-Xmx").filter(|v| !v.is_empty())?;
+    let (digits, unit) = value.split_at(value.len() - 1);
+    let shift = match unit {
+        "k" | "K" => 10,
+        "m" | "M" => 20,
+        "g" | "G" => 30,
+        _ => return value.parse().ok(),
+    };
+    digits.parse::<u64>().ok().map(|n| n << shift)
+}
+
+fn total_memory_bytes() -> Option<u64> {
+    if let Ok(meminfo) = fs::read_to_string("/proc/meminfo") {
+        let line = meminfo.lines().find(|l| l.starts_with("MemTotal:"))?;
+        let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        return Some(kb << 10);
+    }
+    command_output("sysctl", &["-n", "hw.memsize"])?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_doctor_args() {
+        let args: DoctorArgs = DoctorArgs::parse_from(["foundry-cli", "--format", "json"]);
+        assert_eq!(args.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn heap_setting_size() {
+        assert_eq!(max_heap_bytes("-Xmx16384m"), Some(16384 << 20));
+        assert_eq!(max_heap_bytes("-Xmx2g"), Some(2 << 30));
+        assert_eq!(max_heap_bytes("-Xms4096m"), None);
+    }
+
+    #[test]
+    fn circuit_builder_hash_is_pinned() {
+        let dir = std::env::temp_dir().join(format!("zkay-doctor-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let jar = dir.join(CIRCUIT_BUILDER_JAR);
+        let content = b"PK\x03\x04circuit builder";
+        fs::write(&jar, content).unwrap();
+        let expected = sha512_hex(content);
+        assert_eq!(expected.len(), 128);
+        assert_eq!(check_jar(&jar, &expected).status, CheckStatus::Ok);
+
+        // a different jar must not pass, even though it is a valid jar file
+        fs::write(&jar, b"PK\x03\x04tampered").unwrap();
+        let check = check_jar(&jar, &expected);
+        assert_eq!(check.status, CheckStatus::Error);
+        assert!(check.fix.is_some());
+
+        fs::write(&jar, b"not a jar").unwrap();
+        assert_eq!(check_jar(&jar, &expected).status, CheckStatus::Error);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sha512_of_empty_input() {
+        assert!(sha512_hex(b"").starts_with("cf83e1357eefb8bd"));
+    }
+}
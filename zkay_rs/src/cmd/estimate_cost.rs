@@ -0,0 +1,101 @@
+use super::OutputFormat;
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use rccell::RcCell;
+use std::path::PathBuf;
+
+use ast_builder::process_ast::get_processed_ast;
+use circuit_generation::cost_model::{estimate_circuit_costs, CircuitCost};
+use transformation::zkay_contract_transformer::transform_ast;
+use zkay_ast::ast::DeepClone;
+use zkay_ast::global_defs::{global_defs, global_vars};
+use zkay_config::{config::CFG, config_user::UserConfig};
+use zkay_utils::helpers::read_file;
+
+/// CLI arguments for `zkay estimate-cost`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Estimate circuit size and verification gas of every function which requires a proof.", long_about = None)]
+pub struct EstimateCostArgs {
+    /// The zkay source file
+    #[arg(value_hint = ValueHint::FilePath, value_name = "ZKAY_FILE")]
+    pub input: PathBuf,
+
+    /// NIZK proving scheme to estimate the verification costs for (default: from the configuration)
+    #[arg(long, value_parser = ["groth16", "gm17"])]
+    pub proving_scheme: Option<String>,
+
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+impl EstimateCostArgs {
+    pub fn run(self) -> Result<()> {
+        if !self.input.is_file() {
+            eyre::bail!("input file {:?} does not exist", self.input);
+        }
+        if let Some(proving_scheme) = self.proving_scheme {
+            CFG.lock().unwrap().set_proving_scheme(proving_scheme);
+        }
+        let code = read_file(self.input.to_str().unwrap());
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let ast = get_processed_ast(&code, None, global_vars.clone())?;
+        let (_, circuits) = transform_ast(Some(ast.clone_inner()), global_vars);
+        let costs = estimate_circuit_costs(&circuits);
+
+        match self.format {
+            OutputFormat::Json => sh_println!("{}", serde_json::to_string_pretty(&costs)?)?,
+            OutputFormat::Text => sh_println!("{}", format_costs(&costs))?,
+        }
+        Ok(())
+    }
+}
+
+fn format_costs(costs: &[CircuitCost]) -> String {
+    if costs.is_empty() {
+        return String::from("No function requires a proof.");
+    }
+    costs
+        .iter()
+        .map(|c| {
+            let mut lines = vec![format!(
+                "{} ({})\n    constraints:      ~{}\n    verification gas: ~{}\n    public args:      {} in, {} out{}",
+                c.function,
+                c.verification_contract,
+                c.constraints,
+                c.verification_gas,
+                c.in_size,
+                c.out_size,
+                if c.uses_hash { " (hashed)" } else { "" }
+            )];
+            lines.extend(
+                c.contributors()
+                    .into_iter()
+                    .map(|(what, n)| format!("    {what:<32} ~{n}")),
+            );
+            lines.extend(c.warnings.iter().map(|w| format!("    warning: {w}")));
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_estimate_cost_args() {
+        let args: EstimateCostArgs = EstimateCostArgs::parse_from([
+            "foundry-cli",
+            "contract.zkay",
+            "--proving-scheme",
+            "gm17",
+            "--format",
+            "json",
+        ]);
+        assert_eq!(args.proving_scheme.as_deref(), Some("gm17"));
+        assert_eq!(args.format, OutputFormat::Json);
+    }
+}
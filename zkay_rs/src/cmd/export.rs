@@ -0,0 +1,44 @@
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use std::path::PathBuf;
+
+use crate::zkay_frontend::package_zkay_contract;
+
+/// CLI arguments for `zkay export`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Package a compiled zkay contract with its keys into a single archive.", long_about = None)]
+pub struct ExportArgs {
+    /// Compilation output directory of the contract
+    #[arg(value_hint = ValueHint::DirPath, value_name = "CONTRACT_DIR", default_value = ".")]
+    pub input: PathBuf,
+
+    /// The package file (.zkp is appended if missing)
+    #[arg(short, long, value_hint = ValueHint::FilePath, value_name = "FILE", default_value = "contract.zkp")]
+    pub output: PathBuf,
+}
+
+impl ExportArgs {
+    pub fn run(self) -> Result<()> {
+        if !self.input.is_dir() {
+            eyre::bail!("{:?} is not a directory", self.input);
+        }
+        let filename =
+            package_zkay_contract(self.input.to_str().unwrap(), self.output.to_str().unwrap())
+                .map_err(|e| eyre::eyre!("{e:#}"))?;
+        sh_println!("Exported package {filename}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_export_args() {
+        let args: ExportArgs = ExportArgs::parse_from(["foundry-cli", "out", "-o", "audited.zkp"]);
+        assert_eq!(args.input, PathBuf::from("out"));
+        assert_eq!(args.output, PathBuf::from("audited.zkp"));
+    }
+}
@@ -0,0 +1,74 @@
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use rccell::RcCell;
+use std::path::PathBuf;
+
+use ast_builder::process_ast::get_processed_ast;
+use zkay_ast::analysis::contract_graph::contract_graph;
+use zkay_ast::global_defs::{global_defs, global_vars};
+use zkay_utils::helpers::{read_file, save_to_file};
+
+/// Output format of `zkay graph`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT (call graph followed by privacy flow graph)
+    #[default]
+    Dot,
+    /// Machine readable JSON
+    Json,
+}
+
+/// CLI arguments for `zkay graph`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Export the function call graph and the privacy flows between owners.", long_about = None)]
+pub struct GraphArgs {
+    /// The zkay source file
+    #[arg(value_hint = ValueHint::FilePath, value_name = "ZKAY_FILE")]
+    pub input: PathBuf,
+
+    /// Output format of the graph
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    pub format: GraphFormat,
+
+    /// Write the graph to this file instead of stdout
+    #[arg(short, long, value_hint = ValueHint::FilePath, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+impl GraphArgs {
+    pub fn run(self) -> Result<()> {
+        if !self.input.is_file() {
+            eyre::bail!("input file {:?} does not exist", self.input);
+        }
+        let code = read_file(self.input.to_str().unwrap());
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let ast = get_processed_ast(&code, None, global_vars)?;
+        let graph = contract_graph(&ast);
+
+        let out = match self.format {
+            GraphFormat::Json => serde_json::to_string_pretty(&graph)?,
+            GraphFormat::Dot => format!("{}\n{}", graph.call_graph_dot(), graph.privacy_flow_dot()),
+        };
+        match self.output {
+            Some(path) => {
+                save_to_file(None, path.to_str().unwrap(), &out);
+            }
+            None => sh_println!("{out}")?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_graph_args() {
+        let args: GraphArgs =
+            GraphArgs::parse_from(["foundry-cli", "contract.zkay", "-o", "graph.dot"]);
+        assert_eq!(args.format, GraphFormat::Dot);
+        assert_eq!(args.output, Some(PathBuf::from("graph.dot")));
+    }
+}
@@ -0,0 +1,45 @@
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use std::path::PathBuf;
+
+use crate::zkay_frontend::extract_zkay_package;
+
+/// CLI arguments for `zkay import`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Unpack a zkay package, verify its hashes and compile it with the packaged keys.", long_about = None)]
+pub struct ImportArgs {
+    /// The package created by `zkay export`
+    #[arg(value_hint = ValueHint::FilePath, value_name = "ZKP_FILE")]
+    pub input: PathBuf,
+
+    /// Directory to unpack the contract to, must not exist yet
+    #[arg(short, long, value_hint = ValueHint::DirPath, value_name = "OUTPUT_DIRECTORY")]
+    pub output: Option<PathBuf>,
+}
+
+impl ImportArgs {
+    pub fn run(self) -> Result<()> {
+        if !self.input.is_file() {
+            eyre::bail!("package {:?} does not exist", self.input);
+        }
+        // default: directory named after the package next to it
+        let output = self.output.unwrap_or_else(|| self.input.with_extension(""));
+        extract_zkay_package(self.input.to_str().unwrap(), output.to_str().unwrap())
+            .map_err(|e| eyre::eyre!("{e:#}"))?;
+        sh_println!("Imported package into {}", output.display())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_import_args() {
+        let args: ImportArgs = ImportArgs::parse_from(["foundry-cli", "audited.zkp"]);
+        assert_eq!(args.input, PathBuf::from("audited.zkp"));
+        assert_eq!(args.output, None);
+    }
+}
@@ -0,0 +1,104 @@
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use path_absolutize::Absolutize;
+use std::path::PathBuf;
+
+use zkay_config::{
+    config::CFG,
+    config_user::UserConfig,
+    config_version::Versions,
+    project::{ProjectLayout, PROJECT_FILE},
+};
+
+// Contract placed into contracts/ of a new project
+const EXAMPLE_CONTRACT: (&str, &str) = (
+    "IncrementIncome.zkay",
+    include_str!("../../examples/code/IncrementIncome.zkay"),
+);
+
+/// CLI arguments for `zkay init`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Create a new zkay project with contracts/, scenarios/, out/ and zkay.toml.", long_about = None)]
+pub struct InitArgs {
+    /// The project root directory
+    #[arg(value_hint = ValueHint::DirPath, value_name = "DIR", default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Name of the project (default: name of the project directory)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Do not add an example contract
+    #[arg(long)]
+    pub empty: bool,
+}
+
+impl InitArgs {
+    pub fn run(self) -> Result<()> {
+        let project_file = self.dir.join(PROJECT_FILE);
+        if project_file.exists() {
+            eyre::bail!("{} already exists", project_file.display());
+        }
+        let name = match self.name {
+            Some(name) => name,
+            None => self
+                .dir
+                .absolutize()?
+                .file_name()
+                .map_or(String::from("zkay-project"), |n| {
+                    n.to_string_lossy().to_string()
+                }),
+        };
+        let layout = ProjectLayout {
+            solc_version: Some(CFG.lock().unwrap().solc_version()),
+            ..ProjectLayout::new(&name)
+        };
+
+        for dir in [&layout.contracts, &layout.scenarios, &layout.out] {
+            std::fs::create_dir_all(self.dir.join(dir))?;
+        }
+        let contracts_dir = self.dir.join(&layout.contracts);
+        if !self.empty && std::fs::read_dir(&contracts_dir)?.next().is_none() {
+            std::fs::write(contracts_dir.join(EXAMPLE_CONTRACT.0), EXAMPLE_CONTRACT.1)?;
+        }
+        std::fs::write(&project_file, project_config(&layout))?;
+
+        sh_println!(
+            "Initialized zkay project {name} in {}\nRun `zkay compile` in the project to compile all contracts in {}/ into {}/",
+            self.dir.display(),
+            layout.contracts,
+            layout.out
+        )?;
+        Ok(())
+    }
+}
+
+// zkay.toml of a new project: the backends currently in effect and the project layout
+fn project_config(layout: &ProjectLayout) -> String {
+    let cfg = CFG.lock().unwrap();
+    format!(
+        "# zkay configuration of this project, run `zkay config show` to list all options\n\
+         proving_scheme = {:?}\n\
+         main_crypto_backend = {:?}\n\
+         addhom_crypto_backend = {:?}\n\n\
+         {}",
+        cfg.proving_scheme(),
+        cfg.main_crypto_backend(),
+        cfg.addhom_crypto_backend(),
+        layout.to_toml()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_init_args() {
+        let args: InitArgs = InitArgs::parse_from(["foundry-cli", "my-project", "--empty"]);
+        assert_eq!(args.dir, PathBuf::from("my-project"));
+        assert!(args.empty);
+        assert_eq!(args.name, None);
+    }
+}
@@ -0,0 +1,86 @@
+use super::OutputFormat;
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use rccell::RcCell;
+use std::path::PathBuf;
+
+use ast_builder::process_ast::get_processed_ast;
+use zkay_ast::analysis::information_leakage::{information_leakage_analysis, LeakRecord};
+use zkay_ast::global_defs::{global_defs, global_vars};
+use zkay_utils::helpers::read_file;
+
+/// CLI arguments for `zkay leaks`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Report every place where private data is declassified or exposed to another owner.", long_about = None)]
+pub struct LeaksArgs {
+    /// The zkay source file
+    #[arg(value_hint = ValueHint::FilePath, value_name = "ZKAY_FILE")]
+    pub input: PathBuf,
+
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+impl LeaksArgs {
+    pub fn run(self) -> Result<()> {
+        if !self.input.is_file() {
+            eyre::bail!("input file {:?} does not exist", self.input);
+        }
+        let code = read_file(self.input.to_str().unwrap());
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let ast = get_processed_ast(&code, None, global_vars)?;
+        let leaks = information_leakage_analysis(&ast);
+
+        match self.format {
+            OutputFormat::Json => sh_println!("{}", serde_json::to_string_pretty(&leaks)?)?,
+            OutputFormat::Text => sh_println!("{}", format_leaks(&leaks))?,
+        }
+        Ok(())
+    }
+}
+
+fn format_leaks(leaks: &[LeakRecord]) -> String {
+    if leaks.is_empty() {
+        return String::from("No declassifications found.");
+    }
+    leaks
+        .iter()
+        .map(|l| {
+            let mut guard = if l.guard.is_empty() {
+                String::from("<always>")
+            } else {
+                l.guard.join(" && ")
+            };
+            if !l.guard_aliases.is_empty() {
+                guard += &format!(" (so {})", l.guard_aliases.join(", "));
+            }
+            format!(
+                "{}.{} ({}:{}) [{}]\n    expr:  {}\n    label: {} -> {}\n    guard: {}",
+                l.contract,
+                l.function,
+                l.line,
+                l.column,
+                l.kind,
+                l.expr,
+                l.old_label,
+                l.new_label,
+                guard
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_leaks_format() {
+        let args: LeaksArgs =
+            LeaksArgs::parse_from(["foundry-cli", "contract.zkay", "--format", "json"]);
+        assert_eq!(args.format, OutputFormat::Json);
+    }
+}
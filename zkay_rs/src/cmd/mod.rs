@@ -1,6 +1,29 @@
 pub mod call;
+pub mod check;
 pub mod compile;
+pub mod config;
 pub mod create;
+pub mod deploy_libs;
+pub mod doctor;
 pub mod estimate;
+pub mod estimate_cost;
+pub mod export;
+pub mod graph;
+pub mod import;
+pub mod init;
+pub mod leaks;
 pub mod rpc;
 pub mod send;
+pub mod solify;
+pub mod survey;
+pub mod update_solc;
+
+/// Output format of the analysis subcommands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// Machine readable JSON
+    Json,
+}
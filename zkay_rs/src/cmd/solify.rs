@@ -0,0 +1,50 @@
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use std::path::PathBuf;
+
+use solidity::fake_solidity_generator::public_solidity_code;
+use zkay_utils::helpers::{read_file, save_to_file};
+
+/// CLI arguments for `zkay solify`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Emit the public Solidity view of a zkay contract, with privacy annotations turned into comments.", long_about = None)]
+pub struct SolifyArgs {
+    /// The zkay source file
+    #[arg(value_hint = ValueHint::FilePath, value_name = "ZKAY_FILE")]
+    pub input: PathBuf,
+
+    /// Write the Solidity code to this file instead of stdout
+    #[arg(short, long, value_hint = ValueHint::FilePath, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+impl SolifyArgs {
+    pub fn run(self) -> Result<()> {
+        if !self.input.is_file() {
+            eyre::bail!("input file {:?} does not exist", self.input);
+        }
+        let code = read_file(self.input.to_str().unwrap());
+        let out = public_solidity_code(&code);
+        match self.output {
+            Some(path) => {
+                save_to_file(None, path.to_str().unwrap(), &out);
+            }
+            None => sh_println!("{out}")?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_solify_args() {
+        let args: SolifyArgs =
+            SolifyArgs::parse_from(["foundry-cli", "contract.zkay", "--output", "contract.sol"]);
+        assert_eq!(args.input, PathBuf::from("contract.zkay"));
+        assert_eq!(args.output, Some(PathBuf::from("contract.sol")));
+    }
+}
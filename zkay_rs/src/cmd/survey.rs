@@ -0,0 +1,32 @@
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_cli::opts::EthereumOpts;
+use std::path::PathBuf;
+
+/// CLI arguments for `zkay survey`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Run the Survey example against its recorded deployment on the network.", long_about = None)]
+pub struct SurveyArgs {
+    /// Directory of the compiled Survey contract
+    #[arg(
+        value_hint = ValueHint::DirPath,
+        value_name = "PROJECT_DIR",
+        default_value = "survey_compiled"
+    )]
+    pub project_dir: PathBuf,
+
+    #[command(flatten)]
+    pub eth: EthereumOpts,
+}
+
+impl SurveyArgs {
+    pub fn run(self) -> Result<()> {
+        let rpc = self.eth.rpc.clone();
+        crate::contract::main0(
+            &self.project_dir.to_string_lossy(),
+            Some(self.eth),
+            Some(rpc),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use clap::{Parser, ValueHint};
+use eyre::Result;
+use foundry_common::sh_println;
+use std::path::PathBuf;
+
+use solidity::solc_manager::{
+    import_from_dir, install_solc_binary, installed_versions, resolve_version, solc_cache_dir,
+};
+use zkay_config::{config::CFG, config_version::Versions};
+
+/// CLI arguments for `zkay update-solc`.
+#[derive(Clone, Debug, Parser)]
+#[command(about = "Install solc binaries into the local solc cache and list the installed versions.", long_about = None)]
+pub struct UpdateSolcArgs {
+    /// Install every solc executable in this directory (e.g. on machines without internet access)
+    #[arg(long, value_hint = ValueHint::DirPath, value_name = "DIR")]
+    pub from_dir: Vec<PathBuf>,
+
+    /// Install this solc executable
+    #[arg(long, value_hint = ValueHint::FilePath, value_name = "FILE")]
+    pub binary: Vec<PathBuf>,
+}
+
+impl UpdateSolcArgs {
+    pub fn run(self) -> Result<()> {
+        for dir in &self.from_dir {
+            let versions = import_from_dir(dir).map_err(|e| eyre::eyre!(e))?;
+            if versions.is_empty() {
+                sh_println!("No solc executables found in {}", dir.display())?;
+            }
+            for version in versions {
+                sh_println!("Installed solc {version} from {}", dir.display())?;
+            }
+        }
+        for binary in &self.binary {
+            let version = install_solc_binary(binary).map_err(|e| eyre::eyre!(e))?;
+            sh_println!("Installed solc {version} from {}", binary.display())?;
+        }
+
+        let installed = installed_versions();
+        if installed.is_empty() {
+            sh_println!(
+                "No solc versions installed in {}",
+                solc_cache_dir().display()
+            )?;
+        } else {
+            sh_println!("Installed solc versions ({}):", solc_cache_dir().display())?;
+            for version in &installed {
+                sh_println!("  {version}")?;
+            }
+        }
+        let range = CFG.lock().unwrap().zkay_solc_version_compatibility();
+        match resolve_version(&range) {
+            Some(version) => sh_println!("Latest version compatible with {range}: {version}")?,
+            None => sh_println!(
+                "No installed version satisfies {range}, the solc on PATH is used if compatible"
+            )?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_update_solc_args() {
+        let args: UpdateSolcArgs = UpdateSolcArgs::parse_from([
+            "foundry-cli",
+            "--from-dir",
+            "bins",
+            "--binary",
+            "solc-0.6.12",
+        ]);
+        assert_eq!(args.from_dir, vec![PathBuf::from("bins")]);
+        assert_eq!(args.binary, vec![PathBuf::from("solc-0.6.12")]);
+    }
+}
@@ -18,7 +18,7 @@
 // from typing import Dict, List, Tuple, Optional, Union, Any
 use foundry_cli::opts::{EthereumOpts,RpcOpts};
 use foundry_config::Config;
- use alloy_primitives::Address;
+ use alloy_primitives::{Address, U256};
 use foundry_compilers::Project;
 use my_logging;
 use proving_scheme::backends::groth16::ProvingSchemeGroth16;
@@ -43,12 +43,12 @@ use zkay_transaction::blockchain::web3rs::Web3Blockchain;
 use zkay_transaction::int_casts::*;
 use zkay_transaction::interface::{
     ZkayBlockchainInterface, ZkayCryptoInterface, ZkayHomomorphicCryptoInterface,
-    ZkayKeystoreInterface, ZkayProverInterface,
+    ZkayKeystoreInterface, ZkayProverInterface, ZkayRuntimeError,
 };
 use zkay_transaction::keystore::simple::SimpleKeystore;
 use zkay_transaction::offchain::{
-    new_contract_simulator, ContractSimulator, ContractSimulatorConfig, ContractSimulatorRef,
-    BN128_SCALAR_FIELDS,
+    new_contract_simulator, require, ContractSimulator, ContractSimulatorConfig,
+    ContractSimulatorRef, DryRunReport, BN128_SCALAR_FIELDS,
 };
 use zkay_transaction::prover::jsnark::JsnarkProver;
 use zkay_transaction::solidity_math::*;
@@ -58,7 +58,7 @@ use zkay_transaction::types::{
 use zkay_utils::timer::time_measure;
 // me = None
 // use ark_ff::{BigInteger, BigInteger256, Field, MontFp, PrimeField};
-struct Survey<
+pub(crate) struct Survey<
     C: ZkayCryptoInterface<P, B, K> + ZkayHomomorphicCryptoInterface<P, B, K> + Clone,
     P: ZkayProverInterface + Clone,
     B: ZkayBlockchainInterface<P> + Web3Blockchain + Clone,
@@ -69,7 +69,7 @@ struct Survey<
 
 // class Survey(ContractSimulator){
 #[derive(PartialEq, Clone)]
-enum Choice {
+pub(crate) enum Choice {
     none = 0,
     a = 1,
     b = 2,
@@ -159,7 +159,7 @@ impl<
 
     //@staticmethod
     fn connect<PS: ProvingScheme>(
-        address: &Address,
+        address: Option<&Address>,
         user: &str,
         project_dir: &str,
         mut contract_simulator: RcCell<ContractSimulator<C, P, B, K>>,
@@ -170,7 +170,7 @@ impl<
         ) -> anyhow::Result<()>,
         get_verification_contract_names: fn(code_or_ast: String) -> Vec<String>,
         project:&Project,
-    ) -> Survey<C, P, B, K> {
+    ) -> Result<Survey<C, P, B, K>, ZkayRuntimeError> {
         // = os.path.dirname(os.path.realpath(__file__))
         let mut c = Survey::new(project_dir, user, contract_simulator.clone());
         c.api().borrow().connect::<PS>(
@@ -178,9 +178,9 @@ impl<
             compile_zkay_file,
             get_verification_contract_names,
             project,
-        );
-        contract_simulator.borrow().initialize_keys_for(user);
-        c
+        )?;
+        contract_simulator.borrow().initialize_keys_for(user)?;
+        Ok(c)
     }
 
     //@staticmethod
@@ -190,15 +190,15 @@ impl<
         project_dir: &str,
         mut contract_simulator: RcCell<ContractSimulator<C, P, B, K>>,
         project:&Project,
-    ) -> Survey<C, P, B, K> {
+    ) -> Result<Survey<C, P, B, K>, ZkayRuntimeError> {
         //= os.path.dirname(os.path.realpath(__file__))
-        contract_simulator.borrow().initialize_keys_for(user);
+        contract_simulator.borrow().initialize_keys_for(user)?;
         let mut c = Survey::new(project_dir, user, contract_simulator);
-        c.constructor(_min_votes,project);
-        c
+        c.constructor(_min_votes,project)?;
+        Ok(c)
     }
-    fn constructor(&self, _min_votes: i32,project:&Project,) {
-        with_context_block!(var _fc= self._function_ctx(-1,0,"constructor")=> {
+    fn constructor(&self, _min_votes: i32,project:&Project,) -> Result<(), ZkayRuntimeError> {
+        with_context_block!(var _fc= self._function_ctx(-1,0,"constructor")?=> {
             let (zk__is_ext,_fc)=_fc;
              with_context_block!(var _tm=time_measure("transaction_full", !zk__is_ext,false)=>{
                 let (msg, block, _tx) = self.api().borrow().get_special_variables();
@@ -206,24 +206,25 @@ impl<
                 let actual_params = vec![_min_votes.to_string()];
                 // BEGIN Simulate body
                  with_context_block!(var _sc= self._scope()=>{
-                    assert!(_min_votes > 0,"require(_min_votes > 0) failed");
-                    self.state().borrow_mut()[&["organizer"]] = DataType::String(msg.borrow().as_ref().unwrap().sender.clone());
-                    self.state().borrow_mut()[&["min_votes"]] =  DataType::Int(_min_votes as u128);
+                    require(_min_votes > 0,"require(_min_votes > 0) failed")?;
+                    self.state().borrow().__setitem__(&["organizer"], DataType::String(msg.borrow().as_ref().unwrap().sender.clone()));
+                    self.state().borrow().__setitem__(&["min_votes"], DataType::from(_min_votes as u128));
                 });
                 // END Simulate body
 
                 if zk__is_ext{
                     // Deploy contract
-                    self.api().borrow().deploy(actual_params, vec![false],None,project);
+                    self.api().borrow().deploy(actual_params, vec![false],None,project)?;
                 }
 
             });
         });
+        Ok(())
     }
     // constructor._can_be_external = True
 
-    fn get_result_for(&self, option: &DataType) -> DataType {
-        with_context_block!(var _fc=self._function_ctx(-1,0,"get_result_for")=>{
+    pub(crate) fn get_result_for(&self, option: &DataType) -> Result<DataType, ZkayRuntimeError> {
+        with_context_block!(var _fc=self._function_ctx(-1,0,"get_result_for")?=>{
         let (zk__is_ext,_fc)=_fc;
                      with_context_block!(var _tm=time_measure("transaction_full", !zk__is_ext,false)=>{
                          let (_msg, block, _tx) = self.api().borrow().get_special_variables();
@@ -233,31 +234,31 @@ impl<
 
                         // BEGIN Simulate body
                          with_context_block!(var _sc0=self._scope()=>{
-                            assert!(self.is_result_published(),"require(is_result_published()) failed");
-                            self.locals().borrow_mut().decl("res", DataType::Int(0));
-                            if option != &DataType::Int(Choice::none as u128){
+                            require(self.is_result_published()?,"require(is_result_published()) failed")?;
+                            self.locals().borrow_mut().decl("res", DataType::Int(U256::ZERO));
+                            if option != &DataType::from(Choice::none as u128){
                                  with_context_block!(var _sc=self._scope()=>{
-                                    self.locals().borrow_mut()["res"] = DataType::Int(*self.state().borrow()[&["packed_results"]].try_as_int_ref().unwrap() >> (64 * (*option.try_as_int_ref().unwrap() - 1)));
+                                    self.locals().borrow_mut()["res"] = DataType::Int(*self.state().borrow().__getitem__(&["packed_results"])?.try_as_int_ref().unwrap() >> (64 * (option.try_as_int_ref().unwrap().to::<usize>() - 1)));
                                 });
                             }
                             if ! zk__is_ext{
-                                return self.locals().borrow()["res"].clone()
+                                return Ok(self.locals().borrow()["res"].clone())
                             }
                         });
                         // END Simulate body
 
                         if zk__is_ext{
                             // Call pure/view function and return value
-                            return self.api().borrow().call("get_result_for(Survey.Choice option) public view returns (uint64)", actual_params, vec![(false, "None".to_string(), convert_type)]);
+                            return self.api().borrow().call("get_result_for", actual_params, vec![(false, "None".to_string(), convert_type)]);
                         }
                     });
                 });
-        DataType::String(String::new())
+        Ok(DataType::String(String::new()))
     }
     // get_result_for._can_be_external = True
 
-    fn get_winning_choice(&self) -> DataType {
-        with_context_block!(var _fc=self._function_ctx(-1,0,"get_winning_choice") => {
+    pub(crate) fn get_winning_choice(&self) -> Result<DataType, ZkayRuntimeError> {
+        with_context_block!(var _fc=self._function_ctx(-1,0,"get_winning_choice")? => {
         let (zk__is_ext,_fc)=_fc;
                      with_context_block!(var _tm=time_measure("transaction_full", !zk__is_ext,false)=>{
                         let (_msg, block,_tx) = self.api().borrow().get_special_variables();
@@ -267,13 +268,13 @@ impl<
 
                         // BEGIN Simulate body
                          with_context_block!(var _sc=self._scope()=>{
-                            self.locals().borrow_mut().decl("c", DataType::Int(Choice::none as u128));
-                            self.locals().borrow_mut().decl("votes", DataType::Int(0));
-                            self.locals().borrow_mut().decl("i",DataType::Int( Choice::a as u128));
-                            while self.locals().borrow()["i"] <=DataType::Int(Choice::c as u128){
+                            self.locals().borrow_mut().decl("c", DataType::from(Choice::none as u128));
+                            self.locals().borrow_mut().decl("votes", DataType::Int(U256::ZERO));
+                            self.locals().borrow_mut().decl("i",DataType::from(Choice::a as u128));
+                            while self.locals().borrow()["i"] <=DataType::from(Choice::c as u128){
                                 // try{
                                      with_context_block!(var _sc1=self._scope()=>{
-                                        self.locals().borrow_mut().decl("res", self.get_result_for(&self.locals().borrow()["i"]));
+                                        self.locals().borrow_mut().decl("res", self.get_result_for(&self.locals().borrow()["i"])?);
                                         if self.locals().borrow()["res"] > self.locals().borrow()["votes"]{
                                              with_context_block!(var _sc2=self._scope()=>{
                                                 self.locals().borrow_mut()["c"] = self.locals().borrow()["i"].clone();
@@ -282,26 +283,26 @@ impl<
                                         }
                                     });
                                 // finally{
-                                    self.locals().borrow_mut()["i"] =DataType::Int(*self.locals().borrow()["i"].try_as_int_ref().unwrap()+ 1);
+                                    self.locals().borrow_mut()["i"] =DataType::Int(*self.locals().borrow()["i"].try_as_int_ref().unwrap() + U256::from(1));
                             }
                             if ! zk__is_ext{
-                                return self.locals().borrow()["c"].clone()
+                                return Ok(self.locals().borrow()["c"].clone())
                             }
                         });
                         // END Simulate body
 
                         if zk__is_ext{
                             // Call pure/view function and return value
-                            return self.api().borrow().call("get_winning_choice() public view returns (Survey.Choice)", actual_params, vec![(false, "None".to_string(), convert_type)])
+                            return self.api().borrow().call("get_winning_choice", actual_params, vec![(false, "None".to_string(), convert_type)])
                         }
                     });
                 });
-        DataType::String(String::new())
+        Ok(DataType::String(String::new()))
     }
     // get_winning_choice._can_be_external = True
 
-    fn min_votes_reached(&self) -> bool {
-        with_context_block!(var _fc=self._function_ctx(-1,0,"min_votes_reached") => {
+    pub(crate) fn min_votes_reached(&self) -> Result<bool, ZkayRuntimeError> {
+        with_context_block!(var _fc=self._function_ctx(-1,0,"min_votes_reached")? => {
         let (zk__is_ext,_fc)=_fc;
                      with_context_block!(var _tm=time_measure("transaction_full", !zk__is_ext,false)=>{
                         let (_msg, block,_tx) = self.api().borrow().get_special_variables();
@@ -312,24 +313,24 @@ impl<
                         // BEGIN Simulate body
                          with_context_block!(var _sc=self._scope()=>{
                             if ! zk__is_ext{
-                                return self.state().borrow()[&["vote_count"]] >= self.state().borrow()[&["min_votes"]]
+                                return Ok(self.state().borrow().__getitem__(&["vote_count"])? >= self.state().borrow().__getitem__(&["min_votes"])?)
                             }
                         });
                         // END Simulate body
 
                         if zk__is_ext{
                             // Call pure/view function and return value
-                            return self.api().borrow().call("min_votes_reached() public view returns (bool)", actual_params,vec![(false, "None".to_string(), convert_type)]).try_as_bool().unwrap()
+                            return Ok(self.api().borrow().call("min_votes_reached", actual_params,vec![(false, "None".to_string(), convert_type)])?.try_as_bool().unwrap())
                         }
                     });
                 });
-        false
+        Ok(false)
     }
 
     // min_votes_reached._can_be_external = True
 
-    fn is_result_published(&self) -> bool {
-        with_context_block!(var _fc=self._function_ctx(-1,0,"is_result_published")=>{
+    pub(crate) fn is_result_published(&self) -> Result<bool, ZkayRuntimeError> {
+        with_context_block!(var _fc=self._function_ctx(-1,0,"is_result_published")?=>{
         let (zk__is_ext,_fc)=_fc;
                      with_context_block!(var _tm=time_measure("transaction_full", ! zk__is_ext,false)=>{
                         let (_msg, block,_tx) = self.api().borrow().get_special_variables();
@@ -340,23 +341,24 @@ impl<
                         // BEGIN Simulate body
                          with_context_block!(var _sc=self._scope()=>{
                             if ! zk__is_ext{
-                                return self.state().borrow()[&["packed_results"]] != DataType::Int(0)
+                                return Ok(self.state().borrow().__getitem__(&["packed_results"])? != DataType::Int(U256::ZERO))
                             }
                         });
                         // END Simulate body
 
                         if zk__is_ext{
                             // Call pure/view function and return value
-                            return *self.api().borrow().call("is_result_published() public view returns (bool)", actual_params, vec![(false, "None".to_string(), convert_type)]).try_as_bool_ref().unwrap()
+                            return Ok(*self.api().borrow().call("is_result_published", actual_params, vec![(false, "None".to_string(), convert_type)])?.try_as_bool_ref().unwrap())
                         }
                     });
                 });
-        false
+        Ok(false)
     }
     // is_result_published._can_be_external = True
 
-    fn vote(&self, votum: u8) {
-        with_context_block!(var _fc=self._function_ctx(7,0,"vote") =>{
+    pub(crate) fn vote(&self, votum: u8) -> Result<Option<DryRunReport>, ZkayRuntimeError> {
+        self._with_transaction_retries("vote", || {
+        with_context_block!(var _fc=self._function_ctx(7,0,"vote")? =>{
                 let (zk__is_ext,_fc)=_fc;
                              with_context_block!(var _tm=time_measure("transaction_full", !zk__is_ext,false)=>{
                                 assert!( zk__is_ext);
@@ -365,23 +367,23 @@ impl<
 
                                 let mut zk__priv =
                                     BTreeMap::from([("glob_sk_Ecdh_Chaskey__me", PublicKeyValue::data_type("ecdh-chaskey")),
-        ("votum", DataType::Int(0))]);
+        ("votum", DataType::Int(U256::ZERO))]);
 
 
                                 // Encrypt parameters
-                                zk__priv.insert("votum",DataType::Int(votum as u128));
-                                let mut d=self.api().borrow().enc(*zk__priv["votum"].try_as_int_ref().unwrap() as i32,None, "ecdh-chaskey").0.contents.clone();
+                                zk__priv.insert("votum", ApiWrapper::<P, B, K>::range_checked(DataType::from(votum as u128), 8, false)?);
+                                let mut d=self.api().borrow().enc(*zk__priv["votum"].try_as_int_ref().unwrap(),None, "ecdh-chaskey")?.0.contents.clone();
                                 d.pop();
                                 d.push(self.api().borrow().get_my_pk("ecdh-chaskey")[0].to_string());
                                 let votum=  DataType::CipherValue(Value::<String,CipherValue>::new(d,None,Some("ecdh-chaskey".to_owned())));
                                 let mut actual_params = vec![votum.clone()];
-                                let zk__out = vec![DataType::Int(0);25];
+                                let zk__out = vec![DataType::Int(U256::ZERO);25];
                                 actual_params.push(DataType::List(zk__out.clone()));
-                                let mut zk__in = vec![DataType::Int(0);28];
+                                let mut zk__in = vec![DataType::Int(U256::ZERO);28];
                                 // BEGIN Simulate body
                                  with_context_block!(var _sc=self._scope()=>{
                                     zk__priv.insert("glob_sk_Ecdh_Chaskey__me",DataType::PrivateKeyValue(self.api().borrow().get_my_sk("ecdh-chaskey")));
-                                    assert!(zk__out.len() == 25,"require(zk__out.length == 25) failed");
+                                    require(zk__out.len() == 25,"require(zk__out.length == 25) failed")?;
 
                                     // Request static public keys
                                     // {
@@ -389,7 +391,7 @@ impl<
                                     let mut _tmp_key_Elgamal  = PublicKeyValue::data_type("elgamal");
                                      _tmp_key_Ecdh_Chaskey = DataType::PublicKeyValue(self.api().borrow().get_keystore("ecdh-chaskey").borrow().getPk(&msg.borrow().as_ref().unwrap().sender));
                                     zk__in[0] = DataType::String(_tmp_key_Ecdh_Chaskey.try_as_public_key_value_ref().unwrap()[0].clone());
-                                    let organizer=self.state().borrow()[&["organizer"]].try_as_string_ref().unwrap().clone();
+                                    let organizer=self.state().borrow().__getitem__(&["organizer"])?.try_as_string_ref().unwrap().clone();
                                     _tmp_key_Elgamal =  DataType::PublicKeyValue(self.api().borrow().get_keystore("elgamal").borrow().getPk(&organizer));
                                     zk__in[1..3].clone_from_slice(&_tmp_key_Elgamal.try_as_public_key_value_ref().unwrap()[..2].iter().map(|s|DataType::String(s.clone())).collect::<Vec<_>>()) ;
                                     // }
@@ -403,21 +405,22 @@ impl<
                                     // }
 
                                     // Call internal function
-                                    self.api().borrow().call_fct(2, ||{self._zk__vote(self.locals().borrow()["votum"].clone(), zk__in.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 5, zk__out.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 0);} );
+                                    self.api().borrow().call_fct(2, ||{self._zk__vote(self.locals().borrow()["votum"].clone(), zk__in.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 5, zk__out.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 0)} )?;
                                 });
                                 // END Simulate body
 
                                 // Serialize circuit outputs and/or secret circuit inputs
-                                self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![0, 256]);
+                                self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![0, 256])?;
 
                                 //Generate proof
-                                let proof = self.api().borrow().gen_proof("vote", zk__in.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), zk__out.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect());
+                                let proof = self.api().borrow().gen_proof("vote", zk__in.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), zk__out.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect())?;
                                 actual_params.push(DataType::List(proof.into_iter().map(|s|DataType::String(s)).collect()));
                                 // let actual_params:Vec<_>=actual_params.into_iter().flatten().collect();
                                 // Invoke public transaction
-                                return self.api().borrow().transact("vote(uint[3] calldata votum, uint[] calldata zk__out, uint[8] calldata zk__proof) external", actual_params, vec![true, false, false],None)
+                                return self.api().borrow().transact("vote", actual_params, vec![true, false, false],None)
                             });
                         });
+        })
     }
     // vote._can_be_external = True
 
@@ -428,10 +431,10 @@ impl<
         zk__in_start_idx: i32,
         mut zk__out: Vec<String>,
         zk__out_start_idx: i32,
-    ) {
+    ) -> Result<(), ZkayRuntimeError> {
         let (zk__in_start_idx, zk__out_start_idx) =
             (zk__in_start_idx as usize, zk__out_start_idx as usize);
-        with_context_block!(var _fc=self._function_ctx(5,0,"?") =>{
+        with_context_block!(var _fc=self._function_ctx(5,0,"?")? =>{
         let (zk__is_ext,_fc)=_fc;
                     assert! (!zk__is_ext);
 
@@ -439,80 +442,80 @@ impl<
                     let _now = block.borrow().as_ref().unwrap().timestamp;
 
                     let mut zk__priv =
-                        BTreeMap::from([("secret0_plain_votum", DataType::Int(0)), ("secret1_plain", DataType::Int(0)),
+                        BTreeMap::from([("secret0_plain_votum", DataType::Int(U256::ZERO)), ("secret1_plain", DataType::Int(U256::ZERO)),
                         ("zk__out1_cipher_R", RandomnessValue::data_type("elgamal")), ("zk__out3_cipher_R", RandomnessValue::data_type("elgamal")),
                         ("zk__out5_cipher_R", RandomnessValue::data_type("elgamal"))]);
                     let  mut zk__data;// = BTreeMap::new();
 
                     // BEGIN Simulate body
                      with_context_block!(var _sc=self._scope()=>{
-                        assert!((zk__out_start_idx + 25) <= zk__out.len() ,"require(zk__out_start_idx + 25 <= zk__out.length) failed");
-                        assert!((zk__in_start_idx + 23) <= zk__in.len(),"require(zk__in_start_idx + 23 <= zk__in.length) failed");
+                        require((zk__out_start_idx + 25) <= zk__out.len() ,"require(zk__out_start_idx + 25 <= zk__out.length) failed")?;
+                        require((zk__in_start_idx + 23) <= zk__in.len(),"require(zk__in_start_idx + 23 <= zk__in.length) failed")?;
                         zk__data = BTreeMap::from([
                             ("zk__out0_plain", DataType::Bool(false)), ("zk__out1_cipher", CipherValue::data_type("elgamal")),
                             ("zk__out2_cipher", CipherValue::data_type("elgamal")), ("zk__out3_cipher", CipherValue::data_type("elgamal")),
                             ("zk__out4_cipher", CipherValue::data_type("elgamal")), ("zk__out5_cipher",CipherValue::data_type("elgamal")),
                              ("zk__out6_cipher", CipherValue::data_type("elgamal")), ("zk__in0_cipher_votum", CipherValue::data_type("ecdh-chaskey")),
-                            ("zk__in1_key_sender", PublicKeyValue::data_type("ecdh-chaskey")), ("zk__in2_plain", DataType::Int(0)),
+                            ("zk__in1_key_sender", PublicKeyValue::data_type("ecdh-chaskey")), ("zk__in2_plain", DataType::Int(U256::ZERO)),
                             ("zk__in3_cipher", CipherValue::data_type("ecdh-chaskey")), ("zk__in4_key_sender", PublicKeyValue::data_type("ecdh-chaskey")),
-                            ("zk__in5_plain", DataType::Int(0)), ("zk__in6_cipher_a_count", CipherValue::data_type("elgamal")),
-                            ("zk__in7_plain", DataType::Int(0)), ("zk__in8_cipher_b_count", CipherValue::data_type("elgamal")),
-                            ("zk__in9_plain", DataType::Int(0)), ("zk__in10_cipher_c_count", CipherValue::data_type("elgamal")),( "zk__in11_plain",DataType::Int(0)),
+                            ("zk__in5_plain", DataType::Int(U256::ZERO)), ("zk__in6_cipher_a_count", CipherValue::data_type("elgamal")),
+                            ("zk__in7_plain", DataType::Int(U256::ZERO)), ("zk__in8_cipher_b_count", CipherValue::data_type("elgamal")),
+                            ("zk__in9_plain", DataType::Int(U256::ZERO)), ("zk__in10_cipher_c_count", CipherValue::data_type("elgamal")),( "zk__in11_plain",DataType::Int(U256::ZERO)),
                         ]);
 
                         // require(reveal(votum != reveal(Choice::None.to_string(), me) && current_votes[me] == reveal(Choice::None.to_string(), me), all));
                         // {
                         zk__data.insert("zk__in0_cipher_votum",votum.clone());
-                        zk__priv.insert("secret0_plain_votum", self.api().borrow().dec(zk__data["zk__in0_cipher_votum"].clone(), convert_type, "ecdh-chaskey").0);
+                        zk__priv.insert("secret0_plain_votum", self.api().borrow().dec(zk__data["zk__in0_cipher_votum"].clone(), convert_type, "ecdh-chaskey")?.0);
                         zk__data.insert("zk__in1_key_sender", DataType::PublicKeyValue(Value::<String,PublicKeyValue>::new(vec![zk__data["zk__in0_cipher_votum"].try_as_cipher_value_ref().unwrap()[2].clone()],None, Some("ecdh-chaskey".to_owned()))));
-                        zk__data.insert("zk__in2_plain", DataType::Int(Choice::none as u128));
-                        zk__data.insert("zk__in3_cipher", self.state().borrow()[&["current_votes", &msg.borrow().as_ref().unwrap().sender]].clone());
-                        zk__priv.insert("secret1_plain",self.api().borrow().dec(zk__data["zk__in3_cipher"].clone(), convert_type, "ecdh-chaskey").0);
+                        zk__data.insert("zk__in2_plain", DataType::from(Choice::none as u128));
+                        zk__data.insert("zk__in3_cipher", self.state().borrow().__getitem__(&["current_votes", &msg.borrow().as_ref().unwrap().sender])?);
+                        zk__priv.insert("secret1_plain",self.api().borrow().dec(zk__data["zk__in3_cipher"].clone(), convert_type, "ecdh-chaskey")?.0);
                         zk__data.insert("zk__in4_key_sender",DataType::PublicKeyValue(Value::<String,PublicKeyValue>::new(vec![zk__data["zk__in3_cipher"].try_as_cipher_value_ref().unwrap()[2].clone()],None, Some("ecdh-chaskey".to_owned()))));
-                        zk__data.insert("zk__in5_plain", DataType::Int(Choice::none as u128));
+                        zk__data.insert("zk__in5_plain", DataType::from(Choice::none as u128));
                         zk__data.insert("zk__out0_plain",DataType::Bool(zk__priv["secret0_plain_votum"] != zk__data["zk__in2_plain"]&&zk__priv["secret1_plain"] == zk__data["zk__in5_plain"]));
 
-                        assert!(*zk__data["zk__out0_plain"].try_as_bool_ref().unwrap(),"require(reveal(votum != Choice::none && current_votes[me] == Choice::None.to_string(), all)) failed");
+                        require(*zk__data["zk__out0_plain"].try_as_bool_ref().unwrap(),"require(reveal(votum != Choice::none && current_votes[me] == Choice::None.to_string(), all)) failed")?;
                         // }
 
-                        assert!(!self.is_result_published(),"require(!is_result_published()) failed");
-                        self.state().borrow_mut()[&["current_votes", &msg.borrow().as_ref().unwrap().sender]] = votum;
-                        self.state().borrow_mut()[&["vote_count"]] = DataType::Int(*self.state().borrow()[&["vote_count"]].try_as_int_ref().unwrap()+1);
+                        require(!self.is_result_published()?,"require(!is_result_published()) failed")?;
+                        self.state().borrow().__setitem__(&["current_votes", &msg.borrow().as_ref().unwrap().sender], votum);
+                        self.state().borrow().__setitem__(&["vote_count"], DataType::Int(*self.state().borrow().__getitem__(&["vote_count"])?.try_as_int_ref().unwrap() + U256::from(1)));
                         // a_count = a_count + reveal<+>(votum == reveal(Choice::a.to_string(), me) ? reveal(1, me) : reveal(0, me), organizer);
                         // {
-                        zk__data.insert("zk__in6_cipher_a_count",self.state().borrow()[&["a_count"]].clone());
-                        zk__data.insert("zk__in7_plain",DataType::Int(Choice::a as u128));
-                        let (zk__out1_cipher, zk__out1_cipher_r) = self.api().borrow().enc( if zk__priv["secret0_plain_votum"] == zk__data["zk__in7_plain"]{1} else{ 0}, Some(self.state().borrow()[&["organizer"]].try_as_string_ref().unwrap().clone()), "elgamal");
+                        zk__data.insert("zk__in6_cipher_a_count",self.state().borrow().__getitem__(&["a_count"])?);
+                        zk__data.insert("zk__in7_plain",DataType::from(Choice::a as u128));
+                        let (zk__out1_cipher, zk__out1_cipher_r) = self.api().borrow().enc(U256::from(zk__priv["secret0_plain_votum"] == zk__data["zk__in7_plain"]), Some(self.state().borrow().__getitem__(&["organizer"])?.try_as_string_ref().unwrap().clone()), "elgamal")?;
                         zk__data.insert("zk__out1_cipher",DataType::CipherValue(zk__out1_cipher));
                         zk__priv.insert("zk__out1_cipher_R",DataType::RandomnessValue(zk__out1_cipher_r.unwrap()));
 
-                        zk__data.insert("zk__out2_cipher", DataType::CipherValue(self.api().borrow().do_homomorphic_op("+", "elgamal", self.state().borrow()[&["organizer"]].try_as_string_ref().unwrap().clone(), vec![zk__data["zk__in6_cipher_a_count"].clone(), zk__data["zk__out1_cipher"].clone()])));
+                        zk__data.insert("zk__out2_cipher", DataType::CipherValue(self.api().borrow().do_homomorphic_op("+", "elgamal", self.state().borrow().__getitem__(&["organizer"])?.try_as_string_ref().unwrap().clone(), vec![zk__data["zk__in6_cipher_a_count"].clone(), zk__data["zk__out1_cipher"].clone()])?));
 
-                        self.state().borrow_mut()[&["a_count"]] = zk__data["zk__out2_cipher"].clone();
+                        self.state().borrow().__setitem__(&["a_count"], zk__data["zk__out2_cipher"].clone());
                         // }
 
                         // b_count = b_count + reveal<+>(votum == reveal(Choice::b, me) ? reveal(1, me) : reveal(0, me), organizer);
                         // {
-                        zk__data.insert("zk__in8_cipher_b_count",self.state().borrow()[&["b_count"]].clone());
-                        zk__data.insert("zk__in9_plain",DataType::Int(Choice::b as u128));
-                        let (zk__out3_cipher,zk__out3_cipher_r) = self.api().borrow().enc( if zk__priv["secret0_plain_votum"] == zk__data["zk__in9_plain"]{1} else{ 0}, Some(self.state().borrow()[&["organizer"]].try_as_string_ref().unwrap().clone()), "elgamal");
+                        zk__data.insert("zk__in8_cipher_b_count",self.state().borrow().__getitem__(&["b_count"])?);
+                        zk__data.insert("zk__in9_plain",DataType::from(Choice::b as u128));
+                        let (zk__out3_cipher,zk__out3_cipher_r) = self.api().borrow().enc(U256::from(zk__priv["secret0_plain_votum"] == zk__data["zk__in9_plain"]), Some(self.state().borrow().__getitem__(&["organizer"])?.try_as_string_ref().unwrap().clone()), "elgamal")?;
                         zk__data.insert("zk__out3_cipher",DataType::CipherValue(zk__out3_cipher));
                         zk__priv.insert("zk__out3_cipher_R",DataType::RandomnessValue(zk__out3_cipher_r.unwrap()));
-                        zk__data.insert("zk__out4_cipher",DataType::CipherValue(self.api().borrow().do_homomorphic_op("+", "elgamal", self.state().borrow()[&["organizer"]].try_as_string_ref().unwrap().clone(), vec![zk__data["zk__in8_cipher_b_count"].clone(), zk__data["zk__out3_cipher"].clone()])));
+                        zk__data.insert("zk__out4_cipher",DataType::CipherValue(self.api().borrow().do_homomorphic_op("+", "elgamal", self.state().borrow().__getitem__(&["organizer"])?.try_as_string_ref().unwrap().clone(), vec![zk__data["zk__in8_cipher_b_count"].clone(), zk__data["zk__out3_cipher"].clone()])?));
 
-                        self.state().borrow_mut()[&["b_count"]] = zk__data["zk__out4_cipher"].clone();
+                        self.state().borrow().__setitem__(&["b_count"], zk__data["zk__out4_cipher"].clone());
                         // }
 
                         // c_count = c_count + reveal<+>(votum == reveal(Choice::c, me) ? reveal(1, me) : reveal(0, me), organizer);
                         // {
-                        zk__data.insert("zk__in10_cipher_c_count",self.state().borrow()[&["c_count"]].clone());
-                        zk__data.insert("zk__in11_plain",DataType::Int(Choice::c as u128));
-                        let (zk__out5_cipher,zk__out5_cipher_r) = self.api().borrow().enc(if zk__priv["secret0_plain_votum"] == zk__data["zk__in11_plain"]{1 } else {0}, self.state().borrow()[&["organizer"]].try_as_string_ref().cloned(), "elgamal");
+                        zk__data.insert("zk__in10_cipher_c_count",self.state().borrow().__getitem__(&["c_count"])?);
+                        zk__data.insert("zk__in11_plain",DataType::from(Choice::c as u128));
+                        let (zk__out5_cipher,zk__out5_cipher_r) = self.api().borrow().enc(U256::from(zk__priv["secret0_plain_votum"] == zk__data["zk__in11_plain"]), self.state().borrow().__getitem__(&["organizer"])?.try_as_string_ref().cloned(), "elgamal")?;
                         zk__data.insert("zk__out5_cipher",DataType::CipherValue(zk__out5_cipher));
                         zk__priv.insert("zk__out5_cipher_R",DataType::RandomnessValue(zk__out5_cipher_r.unwrap()));
-                        zk__data.insert("zk__out6_cipher",DataType::CipherValue(self.api().borrow().do_homomorphic_op("+", "elgamal", self.state().borrow()[&["organizer"]].try_as_string_ref().unwrap().clone(), vec![zk__data["zk__in10_cipher_c_count"].clone(), zk__data["zk__out5_cipher"].clone()])));
+                        zk__data.insert("zk__out6_cipher",DataType::CipherValue(self.api().borrow().do_homomorphic_op("+", "elgamal", self.state().borrow().__getitem__(&["organizer"])?.try_as_string_ref().unwrap().clone(), vec![zk__data["zk__in10_cipher_c_count"].clone(), zk__data["zk__out5_cipher"].clone()])?));
 
-                        self.state().borrow_mut()[&["c_count"]] = zk__data["zk__out6_cipher"].clone();
+                        self.state().borrow().__setitem__(&["c_count"], zk__data["zk__out6_cipher"].clone());
                         // }
 
                         // Serialize input values
@@ -530,18 +533,29 @@ impl<
                         zk__in[zk__in_start_idx + 18..zk__in_start_idx + 22].clone_from_slice(&zk__data["zk__in10_cipher_c_count"].try_as_cipher_value_ref().unwrap()[..4]);
                         zk__in[zk__in_start_idx + 22] = zk__data["zk__in11_plain"].try_as_string_ref().unwrap().clone();
                         // }
+
+                        // State values among the inputs, a proof for them is stale once another transaction changes them
+                        // {
+                        let sender = msg.borrow().as_ref().unwrap().sender.clone();
+                        self.api().borrow().state_proof_input(zk__in_start_idx + 4, "current_votes", &[&sender], 3);
+                        self.api().borrow().state_proof_input(zk__in_start_idx + 8, "a_count", &[], 4);
+                        self.api().borrow().state_proof_input(zk__in_start_idx + 13, "b_count", &[], 4);
+                        self.api().borrow().state_proof_input(zk__in_start_idx + 18, "c_count", &[], 4);
+                        // }
                     });
                     // END Simulate body
 
                     // Serialize circuit outputs and/or secret circuit inputs
-                    zk__out[zk__out_start_idx..zk__out_start_idx + 25].clone_from_slice(&self.api().borrow().serialize_circuit_outputs(zk__data.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![1, 0, 0, 0, 0, 0, 0])) ;
-                    self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![256, 256, 0, 0, 0])
+                    zk__out[zk__out_start_idx..zk__out_start_idx + 25].clone_from_slice(&self.api().borrow().serialize_circuit_outputs(zk__data.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![1, 0, 0, 0, 0, 0, 0])?) ;
+                    self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![256, 256, 0, 0, 0])?;
                 });
+        Ok(())
     }
     // _zk__vote._can_be_external =
 
-    fn publish_results(&self) {
-        with_context_block!(var _fc=self._function_ctx(6,0,"publish_results") =>{
+    pub(crate) fn publish_results(&self) -> Result<Option<DryRunReport>, ZkayRuntimeError> {
+        self._with_transaction_retries("publish_results", || {
+        with_context_block!(var _fc=self._function_ctx(6,0,"publish_results")? =>{
         let (zk__is_ext,_fc)=_fc;
                      with_context_block!(var _tm=time_measure("transaction_full", !zk__is_ext,false)=>{
                         assert! (zk__is_ext);
@@ -549,12 +563,12 @@ impl<
                         let _now = block.borrow().as_ref().unwrap().timestamp;
 
                         let mut actual_params = vec![];
-                        let mut zk__out = vec![DataType::Int(0);1];
+                        let mut zk__out = vec![DataType::Int(U256::ZERO);1];
                         actual_params.push(DataType::List(zk__out.clone()));
-                         let mut zk__in= vec![DataType::Int(0);14];
+                         let mut zk__in= vec![DataType::Int(U256::ZERO);14];
                         // BEGIN Simulate body
                          with_context_block!(var _sc=self._scope()=>{
-                            assert!(zk__out.len() == 1,"require(zk__out.length == 1) failed");
+                            require(zk__out.len() == 1,"require(zk__out.length == 1) failed")?;
 
                             // Request static public keys
                             // {
@@ -565,18 +579,19 @@ impl<
                             // }
 
                             // Call internal function
-                            self.api().borrow().call_fct(0, ||{self._zk__publish_results(zk__in.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 2, zk__out.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 0);} );
+                            self.api().borrow().call_fct(0, ||{self._zk__publish_results(zk__in.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 2, zk__out.iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), 0)} )?;
                         });
                         // END Simulate body
 
                         //Generate proof
-                        let proof = self.api().borrow().gen_proof("publish_results", zk__in.into_iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), zk__out.into_iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect());
+                        let proof = self.api().borrow().gen_proof("publish_results", zk__in.into_iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect(), zk__out.into_iter().map(|s|s.try_as_string_ref().unwrap().clone()).collect())?;
                         actual_params.push(DataType::List(proof.into_iter().map(|s|DataType::String(s)).collect()));
 
                         // Invoke public transaction
-                        return self.api().borrow().transact("publish_results(uint[] calldata zk__out, uint[8] calldata zk__proof) external", actual_params, vec![false, false],None)
+                        return self.api().borrow().transact("publish_results", actual_params, vec![false, false],None)
                     });
                 });
+        })
     }
     // publish_results._can_be_external = True
 
@@ -586,10 +601,10 @@ impl<
         zk__in_start_idx: i32,
         mut zk__out: Vec<String>,
         zk__out_start_idx: i32,
-    ) {
+    ) -> Result<(), ZkayRuntimeError> {
         let (zk__in_start_idx, zk__out_start_idx) =
             (zk__in_start_idx as usize, zk__out_start_idx as usize);
-        with_context_block!(var _fc=self._function_ctx(6,0,"?") =>{
+        with_context_block!(var _fc=self._function_ctx(6,0,"?")? =>{
         let (zk__is_ext,_fc)=_fc;
                     assert! (!zk__is_ext);
 
@@ -597,46 +612,45 @@ impl<
                    let _now = block.borrow().as_ref().unwrap().timestamp;
 
                     let mut zk__priv =
-                       BTreeMap::from( [("secret0_plain_c_count",DataType::Int(0)), ("zk__in0_cipher_c_count_R", RandomnessValue::data_type("elgamal")),
-                        ("secret2_plain_b_count",DataType::Int(0)), ("zk__in1_cipher_b_count_R", RandomnessValue::data_type("elgamal")),
-                        ("secret4_plain_a_count",DataType::Int(0)), ("zk__in2_cipher_a_count_R", RandomnessValue::data_type("elgamal")),
+                       BTreeMap::from( [("secret0_plain_c_count",DataType::Int(U256::ZERO)), ("zk__in0_cipher_c_count_R", RandomnessValue::data_type("elgamal")),
+                        ("secret2_plain_b_count",DataType::Int(U256::ZERO)), ("zk__in1_cipher_b_count_R", RandomnessValue::data_type("elgamal")),
+                        ("secret4_plain_a_count",DataType::Int(U256::ZERO)), ("zk__in2_cipher_a_count_R", RandomnessValue::data_type("elgamal")),
                     ]);
            let mut zk__data =
-                            BTreeMap::from([("zk__out0_plain",DataType::Int(0)), ("zk__in0_cipher_c_count", CipherValue::data_type("elgamal")),
+                            BTreeMap::from([("zk__out0_plain",DataType::Int(U256::ZERO)), ("zk__in0_cipher_c_count", CipherValue::data_type("elgamal")),
                             ("zk__in1_cipher_b_count",CipherValue::data_type("elgamal")), ("zk__in2_cipher_a_count", CipherValue::data_type("elgamal"))]);
 
                     // BEGIN Simulate body
                      with_context_block!(var _sc=self._scope()=>{
-                        assert!((zk__out_start_idx + 1) <= zk__out.len(),"require(zk__out_start_idx + 1 <= zk__out.length) failed");
-                        assert!((zk__in_start_idx + 12) <= zk__in.len(),"require(zk__in_start_idx + 12 <= zk__in.length) failed");
+                        require((zk__out_start_idx + 1) <= zk__out.len(),"require(zk__out_start_idx + 1 <= zk__out.length) failed")?;
+                        require((zk__in_start_idx + 12) <= zk__in.len(),"require(zk__in_start_idx + 12 <= zk__in.length) failed")?;
 
 
-                         assert!(DataType::String(msg.borrow().as_ref().unwrap().sender.clone() )== self.state().borrow()[&["organizer"]],"require(me == organizer) failed");
-                         assert!(self.min_votes_reached(),"require(min_votes_reached()) failed");
-                         assert!(!self.is_result_published(),"require(!is_result_published()) failed");
+                         require(DataType::String(msg.borrow().as_ref().unwrap().sender.clone() )== self.state().borrow().__getitem__(&["organizer"])?,"require(me == organizer) failed")?;
+                         require(self.min_votes_reached()?,"require(min_votes_reached()) failed")?;
+                         require(!self.is_result_published()?,"require(!is_result_published()) failed")?;
                         // packed_results = reveal(((unhom(c_count)) << 128) | ((unhom(b_count)) << 64) | (unhom(a_count)), all);
                         // {
-                        zk__data.insert("zk__in0_cipher_c_count",self.state().borrow()[&["c_count"]].clone());
-                        let (secret0_plain_c_count, zk__in0_cipher_c_count_r) = self.api().borrow().dec(zk__data["zk__in0_cipher_c_count"].clone(), convert_type, "elgamal");
+                        zk__data.insert("zk__in0_cipher_c_count",self.state().borrow().__getitem__(&["c_count"])?);
+                        let (secret0_plain_c_count, zk__in0_cipher_c_count_r) = self.api().borrow().dec(zk__data["zk__in0_cipher_c_count"].clone(), convert_type, "elgamal")?;
                         zk__priv.insert("secret0_plain_c_count",secret0_plain_c_count);
                         zk__priv.insert("zk__in0_cipher_c_count_R",DataType::RandomnessValue(zk__in0_cipher_c_count_r.unwrap()));
 
-                        zk__data.insert("zk__in1_cipher_b_count", self.state().borrow()[&["b_count"]].clone());
+                        zk__data.insert("zk__in1_cipher_b_count", self.state().borrow().__getitem__(&["b_count"])?);
 
-                        let (secret2_plain_b_count, zk__in1_cipher_b_count_r) = self.api().borrow().dec(zk__data["zk__in1_cipher_b_count"].clone(), convert_type, "elgamal");
+                        let (secret2_plain_b_count, zk__in1_cipher_b_count_r) = self.api().borrow().dec(zk__data["zk__in1_cipher_b_count"].clone(), convert_type, "elgamal")?;
                         zk__priv.insert("secret2_plain_b_count",secret2_plain_b_count);
                         zk__priv.insert("zk__in1_cipher_b_count_R",DataType::RandomnessValue(zk__in1_cipher_b_count_r.unwrap()));
 
-                        zk__data.insert("zk__in2_cipher_a_count", self.state().borrow()[&["a_count"]].clone());
+                        zk__data.insert("zk__in2_cipher_a_count", self.state().borrow().__getitem__(&["a_count"])?);
 
-                        let (secret4_plain_a_count, zk__in2_cipher_a_count_r) = self.api().borrow().dec(zk__data["zk__in2_cipher_a_count"].clone(), convert_type, "elgamal");
+                        let (secret4_plain_a_count, zk__in2_cipher_a_count_r) = self.api().borrow().dec(zk__data["zk__in2_cipher_a_count"].clone(), convert_type, "elgamal")?;
                         zk__priv.insert("secret4_plain_a_count",secret4_plain_a_count);
                         zk__priv.insert("zk__in2_cipher_a_count_R",DataType::RandomnessValue(zk__in2_cipher_a_count_r.unwrap()));
-                           use alloy_primitives::{Address, Bytes, U256};
-                        let zk__out0_plain:U256=U256::from(*zk__priv["secret0_plain_c_count"].try_as_int_ref().unwrap()) << 128 | U256::from(*zk__priv["secret2_plain_b_count"].try_as_int_ref().unwrap()) << 64 | U256::from(*zk__priv["secret4_plain_a_count"].try_as_int_ref().unwrap());
+                        let zk__out0_plain:U256=*zk__priv["secret0_plain_c_count"].try_as_int_ref().unwrap() << 128 | *zk__priv["secret2_plain_b_count"].try_as_int_ref().unwrap() << 64 | *zk__priv["secret4_plain_a_count"].try_as_int_ref().unwrap();
                         zk__data.insert("zk__out0_plain",DataType::String( zk__out0_plain.to_string()));
 
-                        self.state().borrow_mut()[&["packed_results"]] = zk__data["zk__out0_plain"].clone();
+                        self.state().borrow().__setitem__(&["packed_results"], zk__data["zk__out0_plain"].clone());
                         // }
 
                         // Serialize input values
@@ -645,18 +659,26 @@ impl<
                         zk__in[zk__in_start_idx + 4..zk__in_start_idx + 8].clone_from_slice(&zk__data["zk__in1_cipher_b_count"].try_as_cipher_value_ref().unwrap()[..4]);
                         zk__in[zk__in_start_idx + 8..zk__in_start_idx + 12].clone_from_slice(&zk__data["zk__in2_cipher_a_count"].try_as_cipher_value_ref().unwrap()[..4]);
                         // }
+
+                        // State values among the inputs, a proof for them is stale once another transaction changes them
+                        // {
+                        self.api().borrow().state_proof_input(zk__in_start_idx, "c_count", &[], 4);
+                        self.api().borrow().state_proof_input(zk__in_start_idx + 4, "b_count", &[], 4);
+                        self.api().borrow().state_proof_input(zk__in_start_idx + 8, "a_count", &[], 4);
+                        // }
                      });
                     // END Simulate body
 
                     // Serialize circuit outputs and/or secret circuit inputs
-                    zk__out[zk__out_start_idx] = self.api().borrow().serialize_circuit_outputs(zk__data.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![192]).concat();
-                    self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)| (k.to_owned(),v)).collect(), vec![32, 0, 32, 0, 32, 0]);
+                    zk__out[zk__out_start_idx] = self.api().borrow().serialize_circuit_outputs(zk__data.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![192])?.concat();
+                    self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)| (k.to_owned(),v)).collect(), vec![32, 0, 32, 0, 32, 0])?;
                     });
+        Ok(())
     }
     // _zk__publish_results._can_be_external = false
 
-    fn check_if_agree_with_majority(&self) -> DataType {
-        with_context_block!(var _fc=self._function_ctx(2,0,"check_if_agree_with_majority") =>{
+    pub(crate) fn check_if_agree_with_majority(&self) -> Result<DataType, ZkayRuntimeError> {
+        with_context_block!(var _fc=self._function_ctx(2,0,"check_if_agree_with_majority")? =>{
         let (zk__is_ext,_fc)=_fc;
                      with_context_block!(var _tm=time_measure("transaction_full", !zk__is_ext,false)=>{
                         assert! (zk__is_ext);
@@ -668,13 +690,13 @@ impl<
 
 
                         let mut actual_params = vec![];
-                        let mut zk__out = vec![DataType::Int(0);2];
+                        let mut zk__out = vec![DataType::Int(U256::ZERO);2];
                         actual_params.push(DataType::List(zk__out.clone()));
 
                         // BEGIN Simulate body
                          with_context_block!(var _sc=self._scope()=>{
                             zk__priv.insert("glob_sk_Ecdh_Chaskey__me",DataType::PrivateKeyValue(self.api().borrow().get_my_sk("ecdh-chaskey")));
-                            assert!(zk__out.len() == 2,"require(zk__out.length == 2) failed");
+                            require(zk__out.len() == 2,"require(zk__out.length == 2) failed")?;
                             let mut zk__in = vec![0.to_string();5];
 
                             // Request static public keys
@@ -690,15 +712,15 @@ impl<
 
                             // Call internal function
                             // zk__ret_0 =
-                             self.api().borrow().call_fct(1, ||{self._zk__check_if_agree_with_majority( zk__in.clone(), 1, zk__out.clone().into_iter().map(|s|s.try_as_string().unwrap()).collect(), 0);});
+                             self.api().borrow().call_fct(1, ||{self._zk__check_if_agree_with_majority( zk__in.clone(), 1, zk__out.clone().into_iter().map(|s|s.try_as_string().unwrap()).collect(), 0)})?;
                          });
                         // END Simulate body
 
                         // Serialize circuit outputs and/or secret circuit inputs
-                        self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![0]);
+                        self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![0])?;
 
                         // Call pure/view function and return value
-                        return self.api().borrow().call("check_if_agree_with_majority(uint[] calldata zk__out) external view returns (uint[3] memory)", actual_params, vec![(true, "ecdh-chaskey".to_owned(), convert_type)])
+                        return self.api().borrow().call("check_if_agree_with_majority", actual_params, vec![(true, "ecdh-chaskey".to_owned(), convert_type)])
 
                     });
                 });
@@ -711,8 +733,8 @@ impl<
         zk__in_start_idx: usize,
         mut zk__out: Vec<String>,
         zk__out_start_idx: usize,
-    ) -> String {
-        with_context_block!(var _fc=self._function_ctx(1,0,"?") =>{
+    ) -> Result<String, ZkayRuntimeError> {
+        with_context_block!(var _fc=self._function_ctx(1,0,"?")? =>{
         let (zk__is_ext,_fc)=_fc;
                     assert! (!zk__is_ext);
 
@@ -720,31 +742,31 @@ impl<
                    let _now = block.borrow().as_ref().unwrap().timestamp;
 
                     let mut zk__priv =
-                         BTreeMap::from([("secret0_plain", DataType::Int(0))]);
+                         BTreeMap::from([("secret0_plain", DataType::Int(U256::ZERO))]);
 
         // Declare return variables
                         let  zk__ret_0;//= CipherValue::data_type("ecdh-chaskey");
           let mut zk__data =
                            BTreeMap::from ([("zk__out0_cipher", CipherValue::data_type("ecdh-chaskey")),
-                        ("zk__in0_plain_c", DataType::Int(0)), ("zk__in1_cipher", CipherValue::data_type("ecdh-chaskey")),
+                        ("zk__in0_plain_c", DataType::Int(U256::ZERO)), ("zk__in1_cipher", CipherValue::data_type("ecdh-chaskey")),
                     ("zk__in2_key_sender", PublicKeyValue::data_type("ecdh-chaskey"))]);
 
 
                     // BEGIN Simulate body
                      with_context_block!(var _sc=self._scope()=>{
-                        assert!((zk__out_start_idx + 2) <= zk__out.len(),"require(zk__out_start_idx + 2 <= zk__out.length) failed");
-                        assert!((zk__in_start_idx + 4) <= zk__in.len(),"require(zk__in_start_idx + 4 <= zk__in.length) failed");
+                        require((zk__out_start_idx + 2) <= zk__out.len(),"require(zk__out_start_idx + 2 <= zk__out.length) failed")?;
+                        require((zk__in_start_idx + 4) <= zk__in.len(),"require(zk__in_start_idx + 4 <= zk__in.length) failed")?;
 
 
-                        self.locals().borrow_mut().decl("c", self.get_winning_choice() );
+                        self.locals().borrow_mut().decl("c", self.get_winning_choice()? );
                         // return (reveal(c, me) == current_votes[me]);
                         // {
                         zk__data.insert("zk__in0_plain_c",self.locals().borrow()["c"].clone());
-                        zk__data.insert("zk__in1_cipher",self.state().borrow()[&["current_votes", &msg.borrow().as_ref().unwrap().sender]].clone());
-                        zk__priv.insert("secret0_plain",self.api().borrow().dec(zk__data["zk__in1_cipher"].clone(), convert_type, "ecdh-chaskey").0);
+                        zk__data.insert("zk__in1_cipher",self.state().borrow().__getitem__(&["current_votes", &msg.borrow().as_ref().unwrap().sender])?);
+                        zk__priv.insert("secret0_plain",self.api().borrow().dec(zk__data["zk__in1_cipher"].clone(), convert_type, "ecdh-chaskey")?.0);
                         zk__data.insert("zk__in2_key_sender",DataType::PublicKeyValue(Value::<String,PublicKeyValue>::new(vec![zk__data["zk__in1_cipher"].try_as_cipher_value_ref().unwrap()[2].clone()],None, Some("ecdh-chaskey".to_owned()))));
                         //msg.borrow().as_ref().unwrap().sender
-                        let mut s=self.api().borrow().enc(if zk__data["zk__in0_plain_c"] == zk__priv["secret0_plain"]{1}else{0},Some(msg.borrow().as_ref().unwrap().sender.clone()) , "ecdh-chaskey").0;
+                        let mut s=self.api().borrow().enc(U256::from(zk__data["zk__in0_plain_c"] == zk__priv["secret0_plain"]),Some(msg.borrow().as_ref().unwrap().sender.clone()) , "ecdh-chaskey")?.0;
                         s.contents.pop();
                         s.contents.push(self.api().borrow().get_my_pk("ecdh-chaskey")[0].clone());
                         zk__data.insert("zk__out0_cipher",DataType::CipherValue(s));
@@ -762,16 +784,16 @@ impl<
                     // END Simulate body
 
                     // Serialize circuit outputs and/or secret circuit inputs
-                    zk__out[zk__out_start_idx..zk__out_start_idx + 2].clone_from_slice(&self.api().borrow().serialize_circuit_outputs(zk__data.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![0]));
-                    self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![256]);
+                    zk__out[zk__out_start_idx..zk__out_start_idx + 2].clone_from_slice(&self.api().borrow().serialize_circuit_outputs(zk__data.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![0])?);
+                    self.api().borrow().serialize_private_inputs(zk__priv.into_iter().map(|(k,v)|(k.to_owned(),v)).collect(), vec![256])?;
 
-                    return zk__ret_0.try_as_string().unwrap()
+                    return Ok(zk__ret_0.try_as_string().unwrap())
                         });
     }
     // _zk__check_if_agree_with_majority._can_be_external = false
 }
 
-fn deploy<
+pub(crate) fn deploy<
     C: ZkayCryptoInterface<P, B, K> + ZkayHomomorphicCryptoInterface<P, B, K> + Clone,
     P: ZkayProverInterface + Clone,
     B: ZkayBlockchainInterface<P> + Web3Blockchain + Clone,
@@ -781,7 +803,7 @@ fn deploy<
     user: &str,
     mut cs: RcCell<ContractSimulator<C, P, B, K>>,
     project:&Project,
-) -> Survey<C, P, B, K> {
+) -> Result<Survey<C, P, B, K>, ZkayRuntimeError> {
     let user = if user.is_empty() {
         "me".to_owned()
     } else {
@@ -790,14 +812,14 @@ fn deploy<
     Survey::deploy(_min_votes, &user, "", cs,project)
 }
 
-fn connect<
+pub(crate) fn connect<
     PS: ProvingScheme,
     C: ZkayCryptoInterface<P, B, K> + ZkayHomomorphicCryptoInterface<P, B, K> + Clone,
     P: ZkayProverInterface + Clone,
     B: ZkayBlockchainInterface<P> + Web3Blockchain + Clone,
     K: ZkayKeystoreInterface<P, B> + Clone,
 >(
-    address: &Address,
+    address: Option<&Address>,
     user: &str,
     mut cs: RcCell<ContractSimulator<C, P, B, K>>,
     compile_zkay_file: fn(
@@ -807,7 +829,7 @@ fn connect<
     ) -> anyhow::Result<()>,
     get_verification_contract_names: fn(code_or_ast: String) -> Vec<String>,
     project:&Project,
-) -> Survey<C, P, B, K> {
+) -> Result<Survey<C, P, B, K>, ZkayRuntimeError> {
     let user = if user.is_empty() {
         "me".to_owned()
     } else {
@@ -840,16 +862,21 @@ fn help(val: Option<String>) {
     // }
 }
 fn convert_type(v: String) -> DataType {
-    DataType::String(v)
+    match v.as_str() {
+        "true" | "false" => DataType::Bool(v == "true"),
+        _ => DataType::parse_uint(v),
+    }
 }
 use crate::zkay_frontend::compile_zkay_file;
 use ast_builder::process_ast::{get_processed_ast, get_verification_contract_names};
 use zkay_ast::global_defs::{
     array_length_member, global_defs, global_vars, GlobalDefs, GlobalVars,
 };
-pub fn main0(    eth: Option<EthereumOpts>,
+pub fn main0(
+    project_dir: &str,
+    eth: Option<EthereumOpts>,
     rpc: Option<RpcOpts>,
-) {
+) -> Result<(), ZkayRuntimeError> {
     // contract_simulator.use_config_from_manifest(file!());
     // os.path.dirname(os.path.realpath(__file__);
     // let me = contract_simulator.default_address();
@@ -862,17 +889,22 @@ pub fn main0(    eth: Option<EthereumOpts>,
     // let contract_simulator=ContractSimulator::new(".","","",runtime.clone());
     // RcCell::new(_crypto_classes::<P, B, K>(&crypto_backend, keystore)),
     
-       let project= rpc.as_ref().and_then(|rpc|{let config = Config::from(rpc);
-        config.project().ok()}).unwrap();
+    let project = rpc
+        .as_ref()
+        .and_then(|rpc| Config::from(rpc).project().ok())
+        .ok_or_else(|| {
+            ZkayRuntimeError::BlockchainError("No foundry project for the rpc endpoint".to_owned())
+        })?;
     let _contract_simulator = new_contract_simulator(
-        "/Users/lisheng/mygit/arron/zkay-rs/survey_compiled/",
+        project_dir,
         "",
         "",
         eth,
         rpc,
-    );
+    )?;
+    // # Address of the last deployment of Survey on this network
     let survey = connect::<ProvingSchemeGroth16, _, _, _, _>(
-        &Address::default(),
+        None,
         "",
         RcCell::new(_contract_simulator),
         compile_zkay_file,
@@ -881,9 +913,10 @@ pub fn main0(    eth: Option<EthereumOpts>,
             get_verification_contract_names((Some(s), None), global_vars)
         },
         &project,
-    );
-    let min_votes = survey.min_votes_reached();
+    )?;
+    let min_votes = survey.min_votes_reached()?;
     println!("{min_votes:?}");
-    let is_result_published = survey.is_result_published();
+    let is_result_published = survey.is_result_published()?;
     println!("{is_result_published}");
+    Ok(())
 }
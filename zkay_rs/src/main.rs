@@ -35,7 +35,11 @@ use foundry_config::Config;
 use itertools::Itertools;
 use my_logging::log_context::log_context;
 use std::time::Instant;
-use zkay_config::{config::library_compilation_environment, with_context_block};
+use zkay_config::{
+    config::{library_compilation_environment, CFG},
+    config_user::UserConfig,
+    with_context_block,
+};
 use zkay_utils::progress_printer::{fail_print, success_print};
 
 use std::path::{Path, PathBuf};
@@ -70,21 +74,35 @@ use zkay::{Zkay, ZkaySubcommand};
 // static ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 fn main() {
-    crate::contract::main0(None,None)
-
-    // if let Err(err) = run() {
-    //     // let _ = foundry_common::Shell::get().error(&err);
-    //     println!("=========={err:?}");
-    //     std::process::exit(1);
-    // }
+    if let Err(err) = run() {
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
 }
 
 fn run() -> Result<()> {
     let args = Zkay::parse();
+    // global, user and project configuration files, then ZKAY_* environment variables
+    CFG.lock().unwrap().load_configuration_from_disk(String::new())?;
+    zkay_ast::lints::validate_lint_levels(&CFG.lock().unwrap().lint_levels())?;
 
     match args.cmd {
+        ZkaySubcommand::Init(cmd) => cmd.run(),
         ZkaySubcommand::Compile(cmd) => cmd.run().map(drop),
+        ZkaySubcommand::Check(cmd) => cmd.run(),
+        ZkaySubcommand::Leaks(cmd) => cmd.run(),
+        ZkaySubcommand::EstimateCost(cmd) => cmd.run(),
+        ZkaySubcommand::Graph(cmd) => cmd.run(),
+        ZkaySubcommand::Solify(cmd) => cmd.run(),
+        ZkaySubcommand::Export(cmd) => cmd.run(),
+        ZkaySubcommand::Import(cmd) => cmd.run(),
+        ZkaySubcommand::UpdateSolc(cmd) => cmd.run(),
+        ZkaySubcommand::Doctor(cmd) => cmd.run(),
+        ZkaySubcommand::Config(cmd) => cmd.run(),
+        ZkaySubcommand::Survey(cmd) => cmd.run(),
         ZkaySubcommand::Create(cmd) => utils::block_on(cmd.run()),
+        ZkaySubcommand::DeployPki(cmd) => utils::block_on(cmd.run()),
+        ZkaySubcommand::DeployCryptoLibs(cmd) => utils::block_on(cmd.run()),
         ZkaySubcommand::SendTx(cmd) => utils::block_on(cmd.run()),
         ZkaySubcommand::Call(cmd) => utils::block_on(cmd.run()),
         ZkaySubcommand::Balance {
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use ast_builder::process_ast::get_processed_ast;
+    use circuit_generation::cost_model::{
+        check_circuit_budget, encryption_constraints, estimate_circuit_costs, verification_gas,
+    };
+    use circuit_helper::circuit_helper::CircuitHelper;
+    use rccell::RcCell;
+    use std::collections::BTreeMap;
+    use transformation::zkay_contract_transformer::transform_ast;
+    use zkay_ast::ast::{ConstructorOrFunctionDefinition, DeepClone};
+    use zkay_ast::global_defs::{global_defs, global_vars};
+    use zkay_config::{config::CFG, config_user::UserConfig};
+
+    const CODE: &str = r#"pragma zkay ^0.3.0;
+
+contract Cost {
+    final address owner;
+    uint32@owner total;
+
+    constructor() public {
+        owner = me;
+    }
+
+    function add(uint32@me v) public {
+        require(owner == me);
+        total = v < 10 ? total + v : total;
+    }
+}
+"#;
+
+    fn circuits(
+        code: &str,
+    ) -> BTreeMap<RcCell<ConstructorOrFunctionDefinition>, RcCell<CircuitHelper>> {
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let ast = get_processed_ast(code, None, global_vars.clone()).unwrap();
+        transform_ast(Some(ast.clone_inner()), global_vars).1
+    }
+
+    #[test]
+    pub fn test_cost_of_private_computation() {
+        let costs = estimate_circuit_costs(&circuits(CODE));
+        assert_eq!(costs.len(), 1);
+        let cost = &costs[0];
+        assert_eq!(cost.function, "add");
+        assert!(cost.warnings.is_empty(), "{:?}", cost.warnings);
+
+        // one comparison and one addition, both on 32-bit operands
+        assert_eq!(cost.comparisons, BTreeMap::from([(32, 1)]));
+        assert_eq!(cost.arithmetic, BTreeMap::from([(32, 1)]));
+        // v and the old value of total are decrypted, the new value of total is encrypted
+        let backend = CFG.lock().unwrap().main_crypto_backend();
+        assert_eq!(cost.encryptions.keys().collect::<Vec<_>>(), vec![&backend]);
+        assert!(cost.encryptions[&backend] >= 2);
+        assert!(encryption_constraints(&backend).is_some());
+
+        assert_eq!(
+            cost.constraints,
+            cost.contributors().iter().map(|(_, n)| n).sum::<u64>()
+        );
+        let proving_scheme = CFG.lock().unwrap().proving_scheme();
+        assert_eq!(
+            cost.verification_gas,
+            verification_gas(
+                &proving_scheme,
+                cost.in_size + cost.out_size,
+                cost.uses_hash
+            )
+        );
+    }
+
+    #[test]
+    pub fn test_circuit_budget() {
+        let circuits = circuits(CODE);
+        let to_prove: Vec<_> = circuits
+            .values()
+            .filter(|c| c.borrow().requires_verification())
+            .cloned()
+            .collect();
+        let constraints = estimate_circuit_costs(&circuits)[0].constraints;
+
+        assert!(check_circuit_budget(&to_prove, &circuits, 0).is_ok());
+        assert!(check_circuit_budget(&to_prove, &circuits, constraints as i32).is_ok());
+        let err = check_circuit_budget(&to_prove, &circuits, 1000)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Circuit of function add"), "{err}");
+        assert!(err.contains("max_circuit_constraints (1000)"), "{err}");
+        assert!(err.contains("encryption"), "{err}");
+    }
+}
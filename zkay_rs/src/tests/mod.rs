@@ -1 +1,3 @@
+pub mod compiler;
+pub mod transaction;
 pub mod zkay_ast;
@@ -185,3 +185,260 @@
 //     def test_offchain_simulation_elgamal(self):
 //         with _mock_config('dummy', 'elgamal', False):
 //             self.run_scenario()
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{connect, deploy, Choice, Survey};
+    use crate::zkay_frontend::compile_zkay_file;
+    use alloy_primitives::Address;
+    use ast_builder::process_ast::get_verification_contract_names;
+    use foundry_config::Config;
+    use proving_scheme::backends::groth16::ProvingSchemeGroth16;
+    use rccell::RcCell;
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use zkay_ast::global_defs::{global_defs, global_vars};
+    use zkay_config::{config::CFG, config_user::UserConfig};
+    use zkay_transaction::blockchain::evm::EVM_CHAIN_ID;
+    use zkay_transaction::deployments::Deployments;
+    use zkay_transaction::interface::ZkayRuntimeError;
+    use zkay_transaction::offchain::{
+        new_contract_simulator, BlockchainClassType, ContractSimulatorConfig, CryptoClassType,
+        DryRun, KeystoreType,
+    };
+    use zkay_transaction::prover::jsnark::JsnarkProver;
+    use zkay_transaction::types::DataType;
+
+    // Backends of the scenario, the previous ones are restored when dropped (also if the scenario fails)
+    struct MockConfig {
+        old: (String, String, String),
+    }
+
+    impl MockConfig {
+        fn new(crypto: &str, crypto_addhom: &str, blockchain: &str) -> Self {
+            let mut cfg = CFG.lock().unwrap();
+            let old = (
+                cfg.main_crypto_backend(),
+                cfg.addhom_crypto_backend(),
+                cfg.blockchain_backend(),
+            );
+            cfg.set_main_crypto_backend(crypto.to_owned());
+            cfg.set_addhom_crypto_backend(crypto_addhom.to_owned());
+            cfg.set_blockchain_backend(blockchain.to_owned());
+            Self { old }
+        }
+    }
+
+    impl Drop for MockConfig {
+        fn drop(&mut self) {
+            let mut cfg = CFG.lock().unwrap_or_else(|e| e.into_inner());
+            let (crypto, crypto_addhom, blockchain) = self.old.clone();
+            cfg.set_main_crypto_backend(crypto);
+            cfg.set_addhom_crypto_backend(crypto_addhom);
+            cfg.set_blockchain_backend(blockchain);
+        }
+    }
+
+    fn output_dir(name: &str) -> PathBuf {
+        let log_dir = CFG.lock().unwrap().log_dir();
+        let d = PathBuf::from(log_dir)
+            .join("transaction_tests")
+            .join("output")
+            .join(name);
+        if d.is_dir() {
+            std::fs::remove_dir_all(&d).unwrap();
+        }
+        std::fs::create_dir_all(&d).unwrap();
+        d
+    }
+
+    fn verification_contract_names(code: String) -> eyre::Result<Vec<String>> {
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        get_verification_contract_names((Some(code), None), global_vars)
+    }
+
+    fn choice(c: Choice) -> DataType {
+        DataType::from(c as u128)
+    }
+
+    type SurveyType = Survey<CryptoClassType, JsnarkProver, BlockchainClassType, KeystoreType>;
+
+    // Compile survey.zkay into d, deploy it as the first of four dummy accounts and connect the
+    // other three (all of them share the chain of the simulator), returns the voters' addresses
+    fn deploy_survey(d: &Path) -> (SurveyType, Vec<SurveyType>, Vec<String>) {
+        let d_str = d.to_str().unwrap();
+
+        // Compile contract
+        let code = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../survey.zkay");
+        compile_zkay_file(code.to_str().unwrap(), d_str, false).unwrap();
+        let project = Config::load_with_root(d).sanitized().project().unwrap();
+
+        // Create dummy users
+        let sim = new_contract_simulator(d_str, "Survey", "", None, None).unwrap();
+        let accounts = sim.create_dummy_accounts(4).unwrap();
+        let [owner, a, b, c] = <[String; 4]>::try_from(accounts).unwrap();
+
+        // Deploy contract and connect all users
+        let survey = deploy(3, &owner, RcCell::new(sim.for_user(&owner)), &project).unwrap();
+        let address = survey.api().borrow().contract_handle();
+        let voters = vec![a, b, c];
+        let users = voters
+            .iter()
+            .map(|user| {
+                connect::<ProvingSchemeGroth16, _, _, _, _>(
+                    address.as_ref(),
+                    user,
+                    RcCell::new(sim.for_user(user)),
+                    compile_zkay_file,
+                    verification_contract_names,
+                    &project,
+                )
+                .unwrap()
+            })
+            .collect();
+        (survey, users, voters)
+    }
+
+    // Survey scenario (survey.zkay) on the in-process evm: the organizer deploys, three users vote and
+    // the organizer publishes the homomorphically counted results
+    #[test]
+    pub fn test_offchain_simulation_survey() {
+        if std::env::var("ZKAY_SKIP_REAL_ENC_TESTS").is_ok_and(|v| v == "1") {
+            return;
+        }
+        let _cfg = MockConfig::new("ecdh-chaskey", "elgamal", "w3-evm");
+        let d = output_dir("Survey");
+        let (survey, users, _) = deploy_survey(&d);
+
+        // Execute all transactions and check assertions
+        assert!(!survey.min_votes_reached().unwrap());
+        users[0].vote(Choice::a as u8).unwrap();
+        users[1].vote(Choice::b as u8).unwrap();
+        users[2].vote(Choice::b as u8).unwrap();
+        // No double-voting
+        assert!(matches!(
+            users[0].vote(Choice::b as u8),
+            Err(ZkayRuntimeError::RequireFailed(_))
+        ));
+        assert!(survey.min_votes_reached().unwrap());
+
+        // Only the organizer can publish the results
+        assert!(matches!(
+            users[0].publish_results(),
+            Err(ZkayRuntimeError::RequireFailed(_))
+        ));
+        survey.publish_results().unwrap();
+        assert!(users[0].is_result_published().unwrap());
+        assert_eq!(
+            users[0].get_result_for(&choice(Choice::a)).unwrap(),
+            DataType::from(1u128)
+        );
+        assert_eq!(
+            users[0].get_result_for(&choice(Choice::b)).unwrap(),
+            DataType::from(2u128)
+        );
+        assert_eq!(users[0].get_winning_choice().unwrap(), choice(Choice::b));
+        // No voting after the results were published
+        assert!(matches!(
+            users[2].vote(Choice::c as u8),
+            Err(ZkayRuntimeError::RequireFailed(_))
+        ));
+
+        std::fs::remove_dir_all(&d).unwrap();
+    }
+
+    // A dry run of vote simulates the transaction and reports its effects without sending it
+    #[test]
+    pub fn test_offchain_simulation_survey_dry_run() {
+        if std::env::var("ZKAY_SKIP_REAL_ENC_TESTS").is_ok_and(|v| v == "1") {
+            return;
+        }
+        let _cfg = MockConfig::new("ecdh-chaskey", "elgamal", "w3-evm");
+        let d = output_dir("SurveyDryRun");
+        let (survey, users, voters) = deploy_survey(&d);
+
+        for user in &users {
+            user.api().borrow().set_dry_run(Some(DryRun {
+                generate_proof: false,
+            }));
+        }
+        let report = users[0].vote(Choice::a as u8).unwrap().unwrap();
+        assert_eq!(report.function, "vote");
+        assert_eq!(report.estimated_gas, None);
+        assert_eq!(report.plaintext_values["votum"], choice(Choice::a));
+        assert_eq!(
+            report.plaintext_values["secret0_plain_votum"],
+            choice(Choice::a)
+        );
+        assert_eq!(
+            report.plaintext_values["secret1_plain"],
+            choice(Choice::none)
+        );
+        assert_eq!(
+            report.encrypted_state_writes,
+            [
+                format!("current_votes[{}]", voters[0]),
+                "a_count".to_owned(),
+                "b_count".to_owned(),
+                "c_count".to_owned(),
+            ]
+        );
+        assert_eq!(
+            report.declassified_outputs,
+            BTreeMap::from([("zk__out0_plain".to_owned(), DataType::Bool(true))])
+        );
+        assert_eq!(users[0].api().borrow().dry_run_report(), Some(report));
+
+        // No transaction was sent: the votes were not counted and the same users can still vote
+        users[1].vote(Choice::b as u8).unwrap().unwrap();
+        users[2].vote(Choice::b as u8).unwrap().unwrap();
+        assert!(!survey.min_votes_reached().unwrap());
+        for user in &users {
+            user.api().borrow().set_dry_run(None);
+            assert_eq!(user.vote(Choice::c as u8).unwrap(), None);
+        }
+        assert!(survey.min_votes_reached().unwrap());
+
+        std::fs::remove_dir_all(&d).unwrap();
+    }
+
+    // A registry left over from a reset chain, whose pki and library contracts have no code, is replaced by the
+    // contracts deployed instead
+    #[test]
+    pub fn test_offchain_simulation_survey_stale_registry() {
+        if std::env::var("ZKAY_SKIP_REAL_ENC_TESTS").is_ok_and(|v| v == "1") {
+            return;
+        }
+        let _cfg = MockConfig::new("ecdh-chaskey", "elgamal", "w3-evm");
+        let d = output_dir("SurveyStaleRegistry");
+        let mut stale = Deployments::new(EVM_CHAIN_ID);
+        let (crypto_backends, lib_names) = {
+            let cfg = CFG.lock().unwrap();
+            (cfg.all_crypto_params(), cfg.external_crypto_lib_names())
+        };
+        let mut addresses = (1..).map(Address::with_last_byte);
+        stale.pki = crypto_backends.into_iter().zip(&mut addresses).collect();
+        stale.libraries = lib_names.into_iter().zip(&mut addresses).collect();
+        stale.save(&d).unwrap();
+
+        let (survey, users, _) = deploy_survey(&d);
+        users[0].vote(Choice::a as u8).unwrap();
+        assert!(!survey.min_votes_reached().unwrap());
+
+        let deployments = Deployments::load(&d, EVM_CHAIN_ID).unwrap();
+        assert!(deployments.pki.keys().eq(stale.pki.keys()));
+        assert!(deployments.libraries.keys().eq(stale.libraries.keys()));
+        let stale_addresses: Vec<_> = stale.pki.values().chain(stale.libraries.values()).collect();
+        assert!(deployments
+            .pki
+            .values()
+            .chain(deployments.libraries.values())
+            .all(|address| !stale_addresses.contains(&address)));
+        assert_eq!(
+            deployments.contract_address("Survey").unwrap(),
+            survey.api().borrow().contract_handle().unwrap()
+        );
+
+        std::fs::remove_dir_all(&d).unwrap();
+    }
+}
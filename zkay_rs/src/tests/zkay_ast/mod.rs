@@ -1,5 +1,8 @@
 pub mod test_ast;
 pub mod test_build_ast;
+pub mod test_contract_graph;
+pub mod test_information_leakage;
+pub mod test_lints;
 pub mod test_parent_setter;
 pub mod test_process_ast;
 pub mod test_symbol_table;
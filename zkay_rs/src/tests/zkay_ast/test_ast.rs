@@ -3,11 +3,12 @@
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloy_primitives::U256;
     use rccell::RcCell;
     use zkay_ast::ast::{
         ASTBaseProperty, ASTChildren, AssignmentStatementBase, BooleanLiteralExpr, BuiltinFunction,
         FunctionCallExprBase, IdentifierExpr, IdentifierExprUnion, IntoAST, NumberLiteralExpr,
-        RequireStatement,
+        NumberLiteralType, NumberLiteralTypeUnion, RequireStatement,
     };
     // class TestASTSimpleStorageDetailed(ZkayTestCase):
     #[test]
@@ -54,4 +55,32 @@ mod tests {
         );
         assert_eq!(c.code(), "0 + 0");
     }
+    #[test]
+    fn test_number_literal_type() {
+        let literal = |v: &str| {
+            let t = NumberLiteralType::new(NumberLiteralTypeUnion::String(v.to_owned()));
+            (
+                t.number_type_name_base.signed,
+                t.number_type_name_base.bitwidth.unwrap(),
+                t.value(),
+            )
+        };
+        assert_eq!(literal("0"), (false, 8, String::from("0")));
+        assert_eq!(literal("255"), (false, 8, String::from("255")));
+        assert_eq!(literal("256"), (false, 16, String::from("256")));
+        assert_eq!(literal("0xff"), (false, 8, String::from("255")));
+        assert_eq!(literal("-1"), (true, 8, String::from("-1")));
+        assert_eq!(literal("-128"), (true, 8, String::from("-128")));
+        assert_eq!(literal("-129"), (true, 16, String::from("-129")));
+
+        let int256_min = format!("-{}", U256::ONE << 255);
+        assert_eq!(literal(&int256_min), (true, 256, int256_min.clone()));
+        // # Above the range of int256
+        let uint256_max = U256::MAX.to_string();
+        assert_eq!(literal(&uint256_max), (false, 256, uint256_max.clone()));
+
+        let t = NumberLiteralType::new(NumberLiteralTypeUnion::I32(-129));
+        assert_eq!(t.number_type_name_base.bitwidth, Some(16));
+        assert!(t.number_type_name_base.signed);
+    }
 }
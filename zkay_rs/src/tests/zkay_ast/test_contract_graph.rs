@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use ast_builder::process_ast::get_processed_ast;
+    use rccell::RcCell;
+    use zkay_ast::analysis::contract_graph::{
+        contract_graph, CallEdge, ContractGraph, FunctionNode, PrivacyFlow,
+    };
+    use zkay_ast::global_defs::{global_defs, global_vars};
+
+    fn node(function: &str, hybrid: bool, requires_verification: bool) -> FunctionNode {
+        FunctionNode {
+            id: format!("C.{function}"),
+            contract: String::from("C"),
+            function: function.to_owned(),
+            is_constructor: false,
+            hybrid,
+            requires_verification,
+            has_static_body: true,
+            is_recursive: false,
+        }
+    }
+
+    #[test]
+    pub fn test_call_graph_dot() {
+        let graph = ContractGraph {
+            functions: vec![node("f", false, true), node("g", true, true)],
+            calls: vec![CallEdge {
+                caller: String::from("C.f"),
+                callee: String::from("C.g"),
+            }],
+            flows: vec![],
+        };
+        let dot = graph.call_graph_dot();
+        assert!(dot.starts_with("digraph call_graph {"));
+        assert!(dot.contains(r#""C.f" [label="f", style="filled"];"#));
+        assert!(dot.contains(r#""C.g" [label="g", style="bold,filled"];"#));
+        assert!(dot.contains(r#""C.f" -> "C.g";"#));
+    }
+
+    #[test]
+    pub fn test_privacy_flow_dot() {
+        let graph = ContractGraph {
+            functions: vec![node("f", true, true)],
+            calls: vec![],
+            flows: vec![PrivacyFlow {
+                function: String::from("C.f"),
+                from: String::from("me"),
+                to: String::from("all"),
+                kind: String::from("reveal"),
+                count: 2,
+            }],
+        };
+        assert!(graph
+            .privacy_flow_dot()
+            .contains(r#""C.f:me" -> "C.f:all" [label="reveal x2"];"#));
+    }
+
+    #[test]
+    pub fn test_contract_graph_of_contract() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract Graph {
+    final address owner;
+    uint@owner secret;
+    uint total;
+
+    constructor() public {
+        owner = me;
+    }
+
+    function store(uint@me v) public {
+        require(owner == me);
+        secret = v;
+    }
+
+    function publish() public {
+        require(owner == me);
+        total = reveal(secret, all);
+    }
+
+    function add(uint v) public {
+        total = helper(v);
+    }
+
+    function helper(uint v) internal returns (uint) {
+        return total + v;
+    }
+}
+"#;
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let ast = get_processed_ast(code, None, global_vars).unwrap();
+        let graph = contract_graph(&ast);
+
+        let function = |name: &str| {
+            graph
+                .functions
+                .iter()
+                .find(|f| f.function == name)
+                .unwrap_or_else(|| panic!("no node for {name}"))
+        };
+        assert_eq!(graph.functions.len(), 5);
+        assert_eq!(
+            graph.functions.iter().filter(|f| f.is_constructor).count(),
+            1
+        );
+        for name in ["store", "publish"] {
+            let f = function(name);
+            assert_eq!(f.id, format!("Graph.{name}"));
+            assert!(f.hybrid && f.requires_verification, "{f:?}");
+        }
+        for name in ["add", "helper"] {
+            let f = function(name);
+            assert!(!f.hybrid && !f.requires_verification, "{f:?}");
+        }
+
+        assert_eq!(
+            graph.calls,
+            vec![CallEdge {
+                caller: String::from("Graph.add"),
+                callee: String::from("Graph.helper"),
+            }]
+        );
+        let flow = |function: &str, from: &str, to: &str, kind: &str| PrivacyFlow {
+            function: function.to_owned(),
+            from: from.to_owned(),
+            to: to.to_owned(),
+            kind: kind.to_owned(),
+            count: 1,
+        };
+        assert_eq!(
+            graph.flows,
+            vec![
+                flow("Graph.publish", "owner", "all", "reveal"),
+                flow("Graph.store", "me", "owner", "assignment"),
+            ]
+        );
+    }
+}
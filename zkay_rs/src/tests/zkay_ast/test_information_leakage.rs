@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use ast_builder::process_ast::get_processed_ast;
+    use rccell::RcCell;
+    use zkay_ast::analysis::information_leakage::{information_leakage_analysis, LeakKind};
+    use zkay_ast::global_defs::{global_defs, global_vars};
+    use zkay_examples::examples::{Example, CODE_DIR};
+
+    fn leaks_of(code: &str) -> Vec<zkay_ast::analysis::information_leakage::LeakRecord> {
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let ast = get_processed_ast(code, None, global_vars).unwrap();
+        information_leakage_analysis(&ast)
+    }
+
+    #[test]
+    pub fn test_exposure_warning() {
+        let example = Example::new(
+            CODE_DIR
+                .join("ExposureWarning.zkay")
+                .to_str()
+                .unwrap()
+                .to_owned(),
+        );
+        let leaks = leaks_of(&example.code());
+        assert_eq!(leaks.len(), 1, "{leaks:?}");
+        let leak = &leaks[0];
+        assert_eq!(leak.kind, LeakKind::ImplicitExposure);
+        assert_eq!(leak.contract, "ExposureWarning");
+        assert_eq!(leak.function, "notifyAboutExposure");
+        assert!(leak.expr.contains("exposures[target]"), "{}", leak.expr);
+        assert_eq!(leak.guard, vec![String::from("is_infected[me]")]);
+        assert!(leak.guard_aliases.is_empty());
+    }
+
+    #[test]
+    pub fn test_guard_aliases_of_reveal() {
+        let code = "pragma zkay ^0.3.0;
+
+contract Guarded {
+    final address owner;
+    uint@owner secret;
+    uint total;
+
+    constructor() public {
+        owner = me;
+    }
+
+    function publish(uint x) public {
+        if (x > 0) {
+            require(owner == me);
+            total = reveal(secret, all);
+        } else {
+            total = 0;
+        }
+    }
+}
+";
+        let leaks = leaks_of(code);
+        assert_eq!(leaks.len(), 1, "{leaks:?}");
+        let leak = &leaks[0];
+        assert_eq!(leak.kind, LeakKind::Reveal);
+        assert_eq!(leak.function, "publish");
+        assert_eq!(leak.old_label, "owner");
+        assert_eq!(leak.new_label, "all");
+        assert_eq!(
+            leak.guard,
+            vec![String::from("x > 0"), String::from("owner == me")]
+        );
+        assert_eq!(leak.guard_aliases.len(), 1, "{:?}", leak.guard_aliases);
+        assert!(leak.guard_aliases[0].contains("owner") && leak.guard_aliases[0].contains("me"));
+    }
+}
@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod tests {
+    use ast_builder::process_ast::{get_processed_ast, ASTFlags};
+    use rccell::RcCell;
+    use std::collections::BTreeMap;
+    use zkay_ast::global_defs::{global_defs, global_vars};
+    use zkay_ast::lints::{
+        allowed_lints, find_lint, is_soundness_check, run_lints, validate_lint_levels,
+        LintDiagnostic, LintLevel,
+    };
+
+    // Diagnostics of all lints on the processed (but not yet linted) AST of `code`
+    fn lint(code: &str) -> Vec<LintDiagnostic> {
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let flags = ASTFlags::FLAG_ALL.difference(ASTFlags::LINTS).bits();
+        let ast = get_processed_ast(code, Some(flags), global_vars).unwrap();
+        run_lints(code, &ast, true).unwrap()
+    }
+
+    fn fired<'a>(diagnostics: &'a [LintDiagnostic], name: &str) -> Vec<&'a LintDiagnostic> {
+        diagnostics.iter().filter(|d| d.lint == name).collect()
+    }
+
+    #[test]
+    pub fn test_allow_comment_covers_own_and_next_line() {
+        let code = "contract C {\n    // zkay-allow(rehom_constant, unused_private_state)\n    uint@me x;\n}\n";
+        let allowed = allowed_lints(code);
+        for line in [2, 3] {
+            assert!(allowed[&line].contains("rehom_constant"));
+            assert!(allowed[&line].contains("unused_private_state"));
+        }
+        assert!(!allowed.contains_key(&4));
+    }
+
+    #[test]
+    pub fn test_lint_levels() {
+        assert_eq!("deny".parse::<LintLevel>().unwrap(), LintLevel::Deny);
+        assert!("error".parse::<LintLevel>().is_err());
+        assert!(is_soundness_check("eval_order"));
+        assert!(is_soundness_check("private_loop"));
+        assert!(find_lint("eval_order").is_none());
+        assert_eq!(
+            find_lint("implicit_exposure").unwrap().default_level,
+            LintLevel::Warn
+        );
+    }
+
+    #[test]
+    pub fn test_validate_lint_levels() {
+        let levels =
+            |name: &str, level: &str| BTreeMap::from([(name.to_owned(), level.to_owned())]);
+        assert_eq!(
+            validate_lint_levels(&levels("rehom_constant", "deny")).unwrap()["rehom_constant"],
+            LintLevel::Deny
+        );
+        assert!(validate_lint_levels(&levels("rehom_constant", "error")).is_err());
+        assert!(validate_lint_levels(&levels("no_such_lint", "warn")).is_err());
+        for soundness_check in ["eval_order", "private_loop"] {
+            let err = validate_lint_levels(&levels(soundness_check, "allow")).unwrap_err();
+            assert!(err.to_string().contains("soundness check"), "{err}");
+        }
+    }
+
+    #[test]
+    pub fn test_eval_order() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract EvalOrder {
+    uint x;
+
+    function inc() public returns (uint) {
+        x = x + 1;
+        return x;
+    }
+
+    function f() public returns (uint) {
+        // zkay-allow(eval_order)
+        return inc() + x;
+    }
+}
+"#;
+        let diagnostics = lint(code);
+        let found = fired(&diagnostics, "eval_order");
+        assert!(!found.is_empty(), "{diagnostics:?}");
+        // soundness checks cannot be suppressed
+        assert!(found
+            .iter()
+            .all(|d| d.level == LintLevel::Deny && d.line == 13));
+    }
+
+    #[test]
+    pub fn test_private_loop() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract PrivateLoop {
+    function sum(uint@me n) public returns (uint@me) {
+        uint@me total = 0;
+        for (uint i = 0; i < 3; i++) {
+            total = total + n;
+        }
+        return total;
+    }
+}
+"#;
+        let diagnostics = lint(code);
+        let found = fired(&diagnostics, "private_loop");
+        assert_eq!(found.len(), 1, "{diagnostics:?}");
+        assert_eq!((found[0].level, found[0].line), (LintLevel::Deny, 6));
+        assert!(found[0].message.contains("Loop body"));
+
+        // with lints enabled, processing fails with the lint error instead of panicking
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let err = get_processed_ast(code, None, global_vars).unwrap_err();
+        assert!(err.to_string().contains("private_loop"), "{err}");
+    }
+
+    #[test]
+    pub fn test_implicit_exposure() {
+        let code = zkay_examples::examples::Example::new(
+            zkay_examples::examples::CODE_DIR.join("ExposureWarning.zkay"),
+        )
+        .code();
+        let diagnostics = lint(&code);
+        let found = fired(&diagnostics, "implicit_exposure");
+        assert_eq!(found.len(), 1, "{diagnostics:?}");
+        assert_eq!((found[0].level, found[0].line), (LintLevel::Warn, 26));
+        assert!(diagnostics.iter().all(|d| d.level != LintLevel::Deny));
+    }
+
+    #[test]
+    pub fn test_private_require() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract PrivateRequire {
+    uint@me balance;
+
+    function withdraw(uint amount) public {
+        require(reveal(balance, all) >= amount);
+        balance = balance - amount;
+    }
+}
+"#;
+        let diagnostics = lint(code);
+        let found = fired(&diagnostics, "private_require");
+        assert_eq!(found.len(), 1, "{diagnostics:?}");
+        assert_eq!((found[0].level, found[0].line), (LintLevel::Warn, 7));
+    }
+
+    #[test]
+    pub fn test_unused_private_state() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract Unused {
+    uint@me used;
+    uint@me unused;
+    // zkay-allow(unused_private_state)
+    uint@me allowed;
+
+    function set(uint@me v) public {
+        used = v;
+    }
+}
+"#;
+        let diagnostics = lint(code);
+        let found = fired(&diagnostics, "unused_private_state");
+        assert_eq!(found.len(), 1, "{diagnostics:?}");
+        assert_eq!(found[0].line, 5);
+        assert!(found[0].message.contains("unused"));
+    }
+
+    #[test]
+    pub fn test_rehom_constant() {
+        let code = r#"pragma zkay ^0.3.0;
+
+contract RehomConstant {
+    uint32@me<+> total;
+
+    function reset() public {
+        total = addhom(0);
+    }
+}
+"#;
+        let diagnostics = lint(code);
+        let found = fired(&diagnostics, "rehom_constant");
+        assert_eq!(found.len(), 1, "{diagnostics:?}");
+        assert_eq!((found[0].level, found[0].line), (LintLevel::Warn, 7));
+        assert!(found[0].message.contains("addhom(0)"));
+    }
+}
@@ -17,7 +17,8 @@ mod tests {
         let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
         for (name, example) in ALL_EXAMPLES.iter() {
             println!("{:?}", name);
-            let _ast = get_processed_ast(&example.code(), Some(0b0011_0111), global_vars.clone());
+            let _ast =
+                get_processed_ast(&example.code(), Some(0b0111_0111), global_vars.clone()).unwrap();
             assert!(true);
         }
     }
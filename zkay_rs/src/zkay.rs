@@ -6,8 +6,11 @@
 #![allow(unused_mut)]
 #![allow(unused_braces)]
 use crate::cmd::{
-    call::CallArgs, compile::CompileArgs, create::CreateArgs, estimate::EstimateArgs, rpc::RpcArgs,
-    send::SendTxArgs,
+    call::CallArgs, check::CheckArgs, compile::CompileArgs, config::ConfigArgs, create::CreateArgs,
+    deploy_libs::DeployCryptoLibsArgs, deploy_libs::DeployPkiArgs, doctor::DoctorArgs,
+    estimate::EstimateArgs, estimate_cost::EstimateCostArgs, export::ExportArgs, graph::GraphArgs,
+    import::ImportArgs, init::InitArgs, leaks::LeaksArgs, rpc::RpcArgs, send::SendTxArgs,
+    solify::SolifyArgs, survey::SurveyArgs, update_solc::UpdateSolcArgs,
 };
 // use crate::cmd::{
 //     access_list::AccessListArgs, artifact::ArtifactArgs, bind::BindArgs, call::CallArgs,
@@ -43,14 +46,69 @@ pub struct Zkay {
 #[derive(Subcommand)]
 #[allow(clippy::large_enum_variant)]
 pub enum ZkaySubcommand {
+    /// Create a new zkay project.
+    #[command(visible_alias = "i")]
+    Init(InitArgs),
+
     /// Compile the project's smart contracts.
     #[command(visible_aliases = ["z", "compilez"])]
     Compile(CompileArgs),
 
+    /// Type check a zkay contract without generating code.
+    #[command(visible_alias = "ch")]
+    Check(CheckArgs),
+
+    /// List all declassifications and implicit exposures of private data.
+    #[command(visible_alias = "l")]
+    Leaks(LeaksArgs),
+
+    /// Estimate constraint counts and verification gas of the proof circuits.
+    #[command(visible_alias = "ec")]
+    EstimateCost(EstimateCostArgs),
+
+    /// Export the call graph and privacy flows as Graphviz DOT or JSON.
+    #[command(visible_alias = "gr")]
+    Graph(GraphArgs),
+
+    /// Emit the public Solidity skeleton of a zkay contract.
+    #[command(visible_alias = "so")]
+    Solify(SolifyArgs),
+
+    /// Package a compiled contract and its keys for distribution.
+    #[command(visible_alias = "ex")]
+    Export(ExportArgs),
+
+    /// Verify and compile a packaged contract with the packaged keys.
+    #[command(visible_alias = "im")]
+    Import(ImportArgs),
+
+    /// Install solc binaries into the local solc cache.
+    #[command(visible_alias = "us")]
+    UpdateSolc(UpdateSolcArgs),
+
+    /// Check that java, jsnark, libsnark, solc and the zkay directories are usable.
+    #[command(visible_alias = "dr")]
+    Doctor(DoctorArgs),
+
+    /// Run the Survey example contract.
+    Survey(SurveyArgs),
+
+    /// Show the effective zkay configuration.
+    #[command(visible_alias = "cfg")]
+    Config(ConfigArgs),
+
     /// Deploy a smart contract.
     #[command(visible_alias = "d")]
     Create(CreateArgs),
 
+    /// Deploy the global pki contract for a crypto backend.
+    #[command(visible_alias = "dp")]
+    DeployPki(DeployPkiArgs),
+
+    /// Deploy the crypto libraries required by the proving scheme.
+    #[command(visible_alias = "dl")]
+    DeployCryptoLibs(DeployCryptoLibsArgs),
+
     /// Sign and publish a transaction.
     #[command(name = "send", visible_alias = "s")]
     SendTx(SendTxArgs),
@@ -32,7 +32,8 @@ use rccell::RcCell;
 use proving_scheme::backends::gm17::ProvingSchemeGm17;
 use proving_scheme::backends::groth16::ProvingSchemeGroth16;
 use proving_scheme::proving_scheme::{ProvingScheme, VerifyingKeyMeta};
-use solidity::compiler::check_compilation;
+use solidity::compiler::{check_compilation, check_compilation_with_source_map};
+use solidity::source_map::transformed_source_map;
 use transformation::zkay_contract_transformer::transform_ast;
 use zkay_config::{
     config::{library_compilation_environment, CFG},
@@ -44,17 +45,21 @@ use zkay_utils::helpers::{lines_of_code, read_file}; //, without_extension};
 use zkay_utils::progress_printer::{fail_print, print_step, success_print};
 use zkay_utils::timer::time_measure;
 // use zkay_utils::timer::time_measure
+use alloy_primitives::keccak256;
 use ast_builder::process_ast::{get_processed_ast, get_verification_contract_names};
 use lazy_static::lazy_static;
 use serde_json::json;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use zkay_ast::ast::{DeepClone, IntoAST};
 use zkay_ast::global_defs::{
     array_length_member, global_defs, global_vars, GlobalDefs, GlobalVars,
 };
 use zkay_ast::homomorphism::Homomorphism;
-use zkay_ast::visitors::solidity_visitor::to_solidity;
+use zkay_ast::visitors::solidity_visitor::{to_solidity, to_solidity_with_provenance};
 
 // fn proving_scheme_classes<T,VK>(proving_scheme: &str) -> T
 // where
@@ -108,10 +113,11 @@ pub fn compile_zkay_file(
         .to_str();
 
     // compile
+    let result;
     with_context_block!(var _tm= time_measure("compileFull",false,false)=>{
     // let (cg, _) =
-    compile_zkay(code.as_str(), output_dir, import_keys);});
-    Ok(())
+    result = compile_zkay(code.as_str(), output_dir, import_keys);});
+    result
 }
 
 // """
@@ -129,7 +135,7 @@ pub fn compile_zkay_file(
 // :raise ZkayCompilerError: if any compilation stage fails
 // :raise RuntimeError: if import_keys is true and zkay file, manifest file or any of the key files is missing
 // """
-fn compile_zkay(code: &str, output_dir: &str, import_keys: bool) {
+fn compile_zkay(code: &str, output_dir: &str, import_keys: bool) -> anyhow::Result<()> {
     // -> (CircuitGenerator, String)
     // Copy zkay code to output
     let zkay_filename = "contract.zkay";
@@ -149,7 +155,8 @@ fn compile_zkay(code: &str, output_dir: &str, import_keys: bool) {
     }
     let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
     // Type checking
-    let zkay_ast = get_processed_ast(code, None, global_vars.clone());
+    let zkay_ast = get_processed_ast(code, None, global_vars.clone())
+        .map_err(|e| anyhow::anyhow!("\n\nLINT ERROR: {e}"))?;
     let (ast, circuits);
     // Contract transformation
     with_context_block!(var _ps=print_step("Transforming zkay -> public contract")=>{
@@ -191,10 +198,11 @@ fn compile_zkay(code: &str, output_dir: &str, import_keys: bool) {
         );});
     });
     let output_filename = "contract.sol";
+    let (solidity_code, provenance) = to_solidity_with_provenance(&ast);
     // Write public contract file
     with_context_block!(var _ps= print_step("Write public solidity code")=>{
         let _solidity_code_output =
-            _dump_to_output(&to_solidity(&ast), output_dir, output_filename, false);
+            _dump_to_output(&solidity_code, output_dir, output_filename, false);
     });
     // Get all circuit helpers for the transformed contract
     let circuits: Vec<_> = circuits.values().cloned().collect();
@@ -220,7 +228,8 @@ fn compile_zkay(code: &str, output_dir: &str, import_keys: bool) {
     if let Some(_v) = kwargs.get("verifier_names") {
         // assert!(isinstance(v, list));
         let mut verifier_names =
-            get_verification_contract_names((None, Some(zkay_ast.clone())), global_vars.clone());
+            get_verification_contract_names((None, Some(zkay_ast.clone())), global_vars.clone())
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
         verifier_names.sort_unstable();
         let mut verifier_contract_type_codes: Vec<_> = cg
             .circuit_generator_base
@@ -274,21 +283,30 @@ fn compile_zkay(code: &str, output_dir: &str, import_keys: bool) {
     }
 
     // Generate circuits and corresponding verification contracts
-    cg.generate_circuits(import_keys);
+    cg.generate_circuits(import_keys)
+        .map_err(|e| anyhow::anyhow!("\n\nCOMPILER ERROR: {e}"))?;
 
     // Check that all verification contracts and the main contract compile
     let fns = cg
         .circuit_generator_base
         .get_verification_contract_filenames();
-    let main_solidity_files = fns
-        .iter()
-        .map(|v| PathBuf::from(v))
-        .chain([PathBuf::from(output_dir).join(output_filename)]);
-    for f in main_solidity_files {
-        check_compilation(f.to_str().unwrap(), false, "");
+    for f in &fns {
+        check_compilation(f, false, "");
     }
+    // errors in the public contract are reported at the zkay function they were generated from
+    let source_map = transformed_source_map(code, solidity_code.len(), &provenance);
+    check_compilation_with_source_map(
+        PathBuf::from(output_dir)
+            .join(output_filename)
+            .to_str()
+            .unwrap(),
+        false,
+        code,
+        Some(&source_map),
+    );
 
     // (cg, solidity_code_output)
+    Ok(())
 }
 
 // def use_configuration_from_manifest(contract_dir: str) -> Any:
@@ -384,95 +402,221 @@ fn load_contract_transaction_interface_from_module(
 //     c = load_contract_transaction_interface_from_directory(contract_dir, contract_name)
 //     return c.connect(address=contract_address, user=account, project_dir=contract_dir)
 
-// def _collect_package_contents(contract_dir: str, check_all_files: bool) -> List[str]:
-//     """
-//     Return list of relative paths of all files which should be part of the package for the contract in contract_dir.
+// Name of the archive member which maps every packaged file to its keccak256 hash
+pub const PACKAGE_INDEX_FILENAME: &str = "package-index.json";
 
-//     Raises an exception if contract.zkay, manifest.json or any of the files required by contract.zkay is missing.
+// """
+// Return list of relative paths of all files which should be part of the package for the contract in contract_dir.
 
-//     :param contract_dir: path to directory containing manifest and zkay file
-//     :param check_all_files: if true, checks whether all expected files are present
-//     :raise FileNotFoundError: if any of the expected files is not present
-//     :return: list of relative paths (relative to contract_dir)
-//     """
+// Raises an exception if contract.zkay, manifest.json or any of the files required by contract.zkay is missing.
 
-//     zkay_filename = os.path.join(contract_dir, 'contract.zkay')
-//     if not os.path.exists(zkay_filename):
-//         raise FileNotFoundError('contract.zkay not found in package')
+// :param contract_dir: path to directory containing manifest and zkay file
+// :param check_all_files: if true, checks whether all expected files are present and also includes
+//                         the generated solidity files (public contract, verification contracts and libraries)
+// :raise FileNotFoundError: if any of the expected files is not present
+// :return: list of relative paths (relative to contract_dir)
+// """
+fn _collect_package_contents(
+    contract_dir: &str,
+    check_all_files: bool,
+) -> anyhow::Result<Vec<String>> {
+    let dir = Path::new(contract_dir);
+    let zkay_filename = dir.join("contract.zkay");
+    anyhow::ensure!(
+        zkay_filename.is_file(),
+        "contract.zkay not found in package"
+    );
+    anyhow::ensure!(
+        dir.join("manifest.json").is_file(),
+        "manifest.json not found in package"
+    );
+    let manifest = Manifest::load(contract_dir);
+
+    let mut files = vec![String::from("contract.zkay"), String::from("manifest.json")];
+    let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+    let verifier_names = get_verification_contract_names(
+        (Some(read_file(zkay_filename.to_str().unwrap())), None),
+        global_vars,
+    )
+    .map_err(|e| anyhow::anyhow!("\n\nLINT ERROR: {e}"))?;
+    with_context_block!(var _mc=Manifest::with_manifest_config(manifest)=>{
+        let snark_backend = CFG.lock().unwrap().snark_backend();
+        let proving_scheme = CFG.lock().unwrap().proving_scheme();
+        let cg = generator_classes(&snark_backend)(vec![], proving_scheme, contract_dir.to_owned());
+        for v in verifier_names {
+            let circuit_dir = CFG.lock().unwrap().get_circuit_output_dir_name(v);
+            files.extend(cg.get_vk_and_pk_filenames().into_iter().map(|k| format!("{circuit_dir}/{k}")));
+        }
+    });
 
-//     manifest_filename = os.path.join(contract_dir, 'manifest.json')
-//     if not os.path.exists(manifest_filename):
-//         raise FileNotFoundError('manifest.json not found in package')
-//     manifest = Manifest.load(contract_dir)
+    if check_all_files {
+        let mut sol_files: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|f| _is_generated_solidity_file(f))
+            .collect();
+        sol_files.sort_unstable();
+        files.extend(sol_files);
+        for f in &files {
+            anyhow::ensure!(dir.join(f).is_file(), "{f} not found");
+        }
+    }
+    Ok(files)
+}
 
-//     files = ['contract.zkay', 'manifest.json']
-//     with open(zkay_filename) as f:
-//         verifier_names = get_verification_contract_names(f.read())
-//     with Manifest.with_manifest_config(manifest):
-//         gen_cls = generator_classes[cfg.snark_backend]
-//         files += [os.path.join(cfg.get_circuit_output_dir_name(v), k)
-//                   for k in gen_cls.get_vk_and_pk_filenames() for v in verifier_names]
-
-//     if check_all_files:
-//         for f in files:
-//             path = os.path.join(contract_dir, f)
-//             if not os.path.exists(path) or not os.path.isfile(path):
-//                 raise FileNotFoundError(f)
-//     return files
-
-// def package_zkay_contract(zkay_output_dir: str, output_filename: str):
-//     """Package zkay contract for distribution."""
-//     if not output_filename.endswith('.zkp'):
-//         output_filename += '.zkp'
-
-//     with print_step('Packaging for distribution'):
-//         files = _collect_package_contents(zkay_output_dir, true)
-
-//         with tempfile.TemporaryDirectory() as tmpdir:
-//             for file in files:
-//                 src = os.path.join(zkay_output_dir, file)
-//                 dest = os.path.join(tmpdir, file)
-//                 os.makedirs(os.path.dirname(dest), exist_ok=true)
-//                 shutil.copyfile(src, dest)
-//             shutil.make_archive(without_extension(output_filename), 'zip', tmpdir)
-//         os.rename(f'{without_extension(output_filename)}.zip', output_filename)
-
-// def extract_zkay_package(zkp_filename: str, output_dir: str):
-//     """
-//     Unpack and compile a zkay contract.
+// Solidity files are written to the top level of the output directory
+fn _is_generated_solidity_file(filename: &str) -> bool {
+    !filename.contains('/') && filename.ends_with(".sol")
+}
 
-//     :param zkp_filename: path to the packaged contract
-//     :param output_dir: directory where to unpack and compile the contract
-//     :raise Exception: if::fails
-//     """
-//     os.makedirs(output_dir)
-//     try:
-//         with zipfile.ZipFile(zkp_filename) as zkp:
-//             with print_step('Checking zip file integrity'):
-//                 if zkp.testzip() is not None:
-//                     raise ValueError('Corrupt archive')
-
-//             with print_step('Checking for correct file structure'):
-//                 zkp.extract('contract.zkay', output_dir)
-//                 zkp.extract('manifest.json', output_dir)
-//                 expected_files = sorted(_collect_package_contents(output_dir, false))
-//                 contained_files = sorted([d.filename for d in zkp.infolist() if not d.is_dir()])
-//                 if expected_files != contained_files:
-//                     raise ValueError(f'Package is invalid, does not match expected contents')
-
-//             with print_step('Extracting archive'):
-//                 zkp.extractall(output_dir)
-
-//         // Compile extracted contract
-//         zkay_filename = os.path.join(output_dir, 'contract.zkay')
-//         manifest = Manifest.load(output_dir)
-//         with Manifest.with_manifest_config(manifest):
-//             compile_zkay_file(zkay_filename, output_dir, import_keys=true)
-//     except Exception as e:
-//         // If there was an exception, the archive is not safe -> remove extracted contents
-//         print(f'Package {zkp_filename} is either corrupt or incompatible with this zkay version.')
-//         shutil.rmtree(output_dir)
-//         raise e
+// """
+// Package zkay contract for distribution.
+
+// The archive contains all files returned by _collect_package_contents and an index with the keccak256 hash of each of them.
+// :return: the filename of the package
+// """
+pub fn package_zkay_contract(
+    zkay_output_dir: &str,
+    output_filename: &str,
+) -> anyhow::Result<String> {
+    let output_filename = if output_filename.ends_with(".zkp") {
+        output_filename.to_owned()
+    } else {
+        format!("{output_filename}.zkp")
+    };
+
+    with_context_block!(var _ps=print_step("Packaging for distribution")=>{
+        let files = _collect_package_contents(zkay_output_dir, true)?;
+        let mut index = serde_json::Map::new();
+        let mut zkp = zip::ZipWriter::new(File::create(&output_filename)?);
+        let options = zip::write::SimpleFileOptions::default();
+        for file in files {
+            let content = std::fs::read(Path::new(zkay_output_dir).join(&file))?;
+            zkp.start_file(file.as_str(), options)?;
+            zkp.write_all(&content)?;
+            index.insert(file, Value::String(keccak256(&content).to_string()));
+        }
+        zkp.start_file(PACKAGE_INDEX_FILENAME, options)?;
+        zkp.write_all(serde_json::to_string_pretty(&index)?.as_bytes())?;
+        zkp.finish()?;
+    });
+    Ok(output_filename)
+}
+
+// """
+// Unpack and compile a zkay contract.
+
+// :param zkp_filename: path to the packaged contract
+// :param output_dir: directory where to unpack and compile the contract
+// :raise Exception: if import fails
+// """
+pub fn extract_zkay_package(zkp_filename: &str, output_dir: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !Path::new(output_dir).exists(),
+        "output directory {output_dir} already exists"
+    );
+    std::fs::create_dir_all(output_dir)?;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        _extract_and_compile(zkp_filename, output_dir)
+    }));
+    if !matches!(result, Ok(Ok(()))) {
+        // If there was an exception, the archive is not safe -> remove extracted contents
+        println!(
+            "Package {zkp_filename} is either corrupt or incompatible with this zkay version."
+        );
+        let _ = std::fs::remove_dir_all(output_dir);
+    }
+    match result {
+        Ok(r) => r,
+        Err(e) => std::panic::resume_unwind(e),
+    }
+}
+
+fn _extract_and_compile(zkp_filename: &str, output_dir: &str) -> anyhow::Result<()> {
+    let mut zkp = zip::ZipArchive::new(File::open(zkp_filename)?)?;
+    let mut contents = BTreeMap::new();
+
+    with_context_block!(var _ps=print_step("Checking archive integrity")=>{
+        for i in 0..zkp.len() {
+            let mut file = zkp.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            anyhow::ensure!(
+                file.enclosed_name().is_some(),
+                "Package contains invalid path {}",
+                file.name()
+            );
+            // reading the whole entry also verifies its crc
+            let mut content = vec![];
+            file.read_to_end(&mut content)?;
+            contents.insert(file.name().to_owned(), content);
+        }
+        let index = contents
+            .remove(PACKAGE_INDEX_FILENAME)
+            .ok_or_else(|| anyhow::anyhow!("{PACKAGE_INDEX_FILENAME} not found in package"))?;
+        let index: BTreeMap<String, String> = serde_json::from_slice(&index)?;
+        anyhow::ensure!(
+            index.keys().eq(contents.keys()),
+            "Package is invalid, hash index does not match contents"
+        );
+        for (name, content) in &contents {
+            anyhow::ensure!(
+                index[name] == keccak256(content).to_string(),
+                "Package is invalid, hash mismatch for {name}"
+            );
+        }
+    });
+
+    with_context_block!(var _ps=print_step("Checking for correct file structure")=>{
+        for name in ["contract.zkay", "manifest.json"] {
+            let content = contents
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("{name} not found in package"))?;
+            std::fs::write(Path::new(output_dir).join(name), content)?;
+        }
+        let mut expected_files = _collect_package_contents(output_dir, false)?;
+        expected_files.sort_unstable();
+        let contained_files: Vec<_> = contents
+            .keys()
+            .filter(|f| !_is_generated_solidity_file(f))
+            .cloned()
+            .collect();
+        anyhow::ensure!(
+            expected_files == contained_files,
+            "Package is invalid, does not match expected contents"
+        );
+    });
+
+    with_context_block!(var _ps=print_step("Extracting archive")=>{
+        for (name, content) in &contents {
+            let path = Path::new(output_dir).join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+    });
+
+    // Compile extracted contract
+    let zkay_filename = Path::new(output_dir).join("contract.zkay");
+    let manifest = Manifest::load(output_dir);
+    with_context_block!(var _mc=Manifest::with_manifest_config(manifest)=>{
+        compile_zkay_file(zkay_filename.to_str().unwrap(), output_dir, true)?;
+    });
+
+    // The contracts generated from the imported keys have to be the audited ones from the package
+    with_context_block!(var _ps=print_step("Comparing generated contracts with package")=>{
+        for (name, content) in contents.iter().filter(|(f, _)| _is_generated_solidity_file(f)) {
+            let generated = std::fs::read(Path::new(output_dir).join(name))?;
+            anyhow::ensure!(
+                keccak256(&generated) == keccak256(content),
+                "{name} generated by this zkay version differs from the packaged one"
+            );
+        }
+    });
+    Ok(())
+}
 
 // """
 // Dump 'content' into file 'output_dir/filename' and optionally check if it compiles error-free with solc.
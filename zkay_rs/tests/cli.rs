@@ -0,0 +1,43 @@
+// Runs the zkay binary, the subcommands are dispatched by main
+use std::process::{Command, Output};
+
+fn zkay(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_zkay"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("run zkay")
+}
+
+#[test]
+fn check_accepts_valid_contract() {
+    let out = zkay(&["check", "examples/code/Addition.zkay"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(String::from_utf8_lossy(&out.stdout).contains("ok"));
+}
+
+#[test]
+fn errors_exit_with_code_1() {
+    let out = zkay(&["check", "examples/code/DoesNotExist.zkay"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("does not exist"));
+
+    let out = zkay(&["check", "examples/type_errors/Addition.zkay"]);
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn config_show_lists_options() {
+    let out = zkay(&["config", "show", "--format", "json"]);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let options: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert!(options.get("proving_scheme").is_some());
+}
@@ -0,0 +1,407 @@
+// """
+// Conversion between runtime values (DataType) and abi values (DynSolValue), driven by the contract abi.
+
+// Arguments are converted according to the resolved types of the function or constructor inputs:
+// ciphertexts, keys and randomness become uint256[N] arrays whose length must match the crypto params,
+// lists become arrays or tuples (structs), and enums are passed as their uint8 value.
+// A value which does not fit its abi type is reported with the parameter path, e.g. "zk__in[3]".
+// Return values are decoded the other way round, ciphertexts and keys from their uint256[N] arrays.
+// """
+use alloy_dyn_abi::{DynSolType, DynSolValue, FunctionExt, Specifier};
+use alloy_json_abi::{Function, Param};
+use alloy_primitives::{hex, Address, I256, U256};
+use zkay_transaction_crypto_params::params::CryptoParams;
+
+use crate::interface::ZkayRuntimeError;
+use crate::types::{CipherValue, DataType, PublicKeyValue, Value};
+
+// Abi values of args for params (the inputs of a function or constructor)
+pub fn encode_args(
+    params: &[Param],
+    args: &[DataType],
+) -> Result<Vec<DynSolValue>, ZkayRuntimeError> {
+    if params.len() != args.len() {
+        return Err(ZkayRuntimeError::BlockchainError(format!(
+            "Expected {} arguments, found {}",
+            params.len(),
+            args.len()
+        )));
+    }
+    params
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| encode_param(param, arg))
+        .collect()
+}
+
+pub fn encode_param(param: &Param, value: &DataType) -> Result<DynSolValue, ZkayRuntimeError> {
+    let ty = param.resolve().map_err(|e| ZkayRuntimeError::AbiMismatch {
+        param: param.name.clone(),
+        expected: param.ty.clone(),
+        found: e.to_string(),
+    })?;
+    // # report enums and structs by their solidity name instead of uint8 / tuple
+    to_dyn_sol_value(value, &ty, &param.name).map_err(|e| match (e, &param.internal_type) {
+        (
+            ZkayRuntimeError::AbiMismatch {
+                param: path, found, ..
+            },
+            Some(internal_type),
+        ) if path == param.name => ZkayRuntimeError::AbiMismatch {
+            param: path,
+            expected: internal_type.to_string(),
+            found,
+        },
+        (e, _) => e,
+    })
+}
+
+// Abi value of value as type ty, path names the value in errors
+pub fn to_dyn_sol_value(
+    value: &DataType,
+    ty: &DynSolType,
+    path: &str,
+) -> Result<DynSolValue, ZkayRuntimeError> {
+    let mismatch = |found: String| ZkayRuntimeError::AbiMismatch {
+        param: path.to_owned(),
+        expected: ty.sol_type_name().into_owned(),
+        found,
+    };
+    match (value, ty) {
+        (DataType::CipherValue(v), _) => {
+            let len = v.params.as_ref().map(CryptoParams::cipher_len);
+            encode_words(&v.contents, len, ty, path)
+        }
+        (DataType::PublicKeyValue(v), _) => {
+            let len = v.params.as_ref().map(CryptoParams::key_len);
+            encode_words(&v.contents, len, ty, path)
+        }
+        (DataType::RandomnessValue(v), _) => {
+            let len = v.params.as_ref().and_then(CryptoParams::randomness_len);
+            encode_words(&v.contents, len, ty, path)
+        }
+        (DataType::PrivateKeyValue(v), _) => encode_words(&v.contents, None, ty, path),
+        (DataType::Bool(b), DynSolType::Bool) => Ok(DynSolValue::Bool(*b)),
+        (DataType::Int(i), DynSolType::Uint(bits)) if fits_uint(*i, *bits) => {
+            Ok(DynSolValue::Uint(*i, *bits))
+        }
+        (DataType::Int(i), DynSolType::Int(bits)) => I256::try_from(*i)
+            .ok()
+            .filter(|i| fits_int(*i, *bits))
+            .map(|i| DynSolValue::Int(i, *bits))
+            .ok_or_else(|| mismatch(describe(value))),
+        (DataType::SignedInt(i), DynSolType::Int(bits)) if fits_int(*i, *bits) => {
+            Ok(DynSolValue::Int(*i, *bits))
+        }
+        (DataType::SignedInt(i), DynSolType::Uint(bits))
+            if !i.is_negative() && fits_uint(i.into_raw(), *bits) =>
+        {
+            Ok(DynSolValue::Uint(i.into_raw(), *bits))
+        }
+        // # addresses, numbers and byte strings in their textual representation
+        (DataType::String(s), _) => ty
+            .coerce_str(s)
+            .map_err(|e| mismatch(format!("\"{s}\" ({e})"))),
+        (DataType::List(l), DynSolType::Array(elem)) => {
+            encode_list(l, |_| elem.as_ref(), path).map(DynSolValue::Array)
+        }
+        (DataType::List(l), DynSolType::FixedArray(elem, len)) if l.len() == *len => {
+            encode_list(l, |_| elem.as_ref(), path).map(DynSolValue::FixedArray)
+        }
+        (DataType::List(l), DynSolType::Tuple(types)) if l.len() == types.len() => {
+            encode_list(l, |i| &types[i], path).map(DynSolValue::Tuple)
+        }
+        (DataType::List(l), _) => Err(mismatch(format!("list of {} values", l.len()))),
+        _ => Err(mismatch(describe(value))),
+    }
+}
+
+// Runtime value of an abi value, addresses are checksummed and bytes 0x prefixed hex
+pub fn from_dyn_sol_value(value: &DynSolValue) -> DataType {
+    match value {
+        DynSolValue::Bool(b) => DataType::Bool(*b),
+        DynSolValue::Uint(i, _) => DataType::Int(*i),
+        DynSolValue::Int(i, _) => DataType::SignedInt(*i),
+        DynSolValue::Address(a) => DataType::String(a.to_checksum(None)),
+        DynSolValue::FixedBytes(word, size) => {
+            DataType::String(hex::encode_prefixed(&word[..*size]))
+        }
+        DynSolValue::Bytes(b) => DataType::String(hex::encode_prefixed(b)),
+        DynSolValue::String(s) => DataType::String(s.clone()),
+        DynSolValue::Array(l) | DynSolValue::FixedArray(l) | DynSolValue::Tuple(l) => {
+            DataType::List(l.iter().map(from_dyn_sol_value).collect())
+        }
+        _ => DataType::String(format!("{value:?}")),
+    }
+}
+
+// Textual representation of a plain abi value, as taken by the return value and state variable constructors
+pub fn to_text(value: &DynSolValue) -> String {
+    match from_dyn_sol_value(value) {
+        DataType::String(s) => s,
+        v => describe(&v),
+    }
+}
+
+// Address returned by a contract
+pub fn address_from_dyn_sol_value(value: &DynSolValue) -> Result<Address, ZkayRuntimeError> {
+    value
+        .as_address()
+        .ok_or_else(|| ZkayRuntimeError::AbiMismatch {
+            param: "return value".to_owned(),
+            expected: "address".to_owned(),
+            found: format!("{value:?}"),
+        })
+}
+
+// Ciphertext of crypto backend params returned as uint256[N] by a contract
+pub fn cipher_from_dyn_sol_value(
+    value: &DynSolValue,
+    params: &CryptoParams,
+) -> Result<Value<String, CipherValue>, ZkayRuntimeError> {
+    let contents = decode_words(value, params.cipher_len(), &params.crypto_name)?;
+    Ok(Value::new(contents, Some(params.clone()), None))
+}
+
+// Public key of crypto backend params returned as uint256[N] by a contract (e.g. by the pki contract)
+pub fn key_from_dyn_sol_value(
+    value: &DynSolValue,
+    params: &CryptoParams,
+) -> Result<Value<String, PublicKeyValue>, ZkayRuntimeError> {
+    let contents = decode_words(value, params.key_len(), &params.crypto_name)?;
+    Ok(Value::new(contents, Some(params.clone()), None))
+}
+
+// Decoded return values of function, one per output
+pub fn decode_output(
+    function: &Function,
+    output: &[u8],
+) -> Result<Vec<DynSolValue>, ZkayRuntimeError> {
+    function.abi_decode_output(output, false).map_err(|e| {
+        ZkayRuntimeError::BlockchainError(format!(
+            "Could not decode return value of {}: {e}",
+            function.name
+        ))
+    })
+}
+
+// The return value of a function with a single output, e.g. a state variable getter
+pub fn single_output(
+    name: &str,
+    mut values: Vec<DynSolValue>,
+) -> Result<DynSolValue, ZkayRuntimeError> {
+    if values.len() != 1 {
+        return Err(ZkayRuntimeError::BlockchainError(format!(
+            "Expected a single return value of {name}, found {}",
+            values.len()
+        )));
+    }
+    Ok(values.remove(0))
+}
+
+// Words of a uint256[len] array of crypto backend crypto_name
+fn decode_words(
+    value: &DynSolValue,
+    len: i32,
+    crypto_name: &str,
+) -> Result<Vec<String>, ZkayRuntimeError> {
+    let mismatch = |found: String| ZkayRuntimeError::AbiMismatch {
+        param: "return value".to_owned(),
+        expected: format!("uint256[{len}] ({crypto_name})"),
+        found,
+    };
+    let words = match value {
+        DynSolValue::Array(l) | DynSolValue::FixedArray(l) => l,
+        _ => return Err(mismatch(format!("{value:?}"))),
+    };
+    if words.len() != len as usize {
+        return Err(mismatch(format!("{} words", words.len())));
+    }
+    words
+        .iter()
+        .map(|word| match word {
+            DynSolValue::Uint(i, _) => Ok(i.to_string()),
+            _ => Err(mismatch(format!("{word:?}"))),
+        })
+        .collect()
+}
+
+fn encode_list<'a>(
+    values: &[DataType],
+    elem_ty: impl Fn(usize) -> &'a DynSolType,
+    path: &str,
+) -> Result<Vec<DynSolValue>, ZkayRuntimeError> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| to_dyn_sol_value(v, elem_ty(i), &format!("{path}[{i}]")))
+        .collect()
+}
+
+// uint256[N] (or uint256[]) array of the words of a ciphertext, key or randomness
+fn encode_words(
+    words: &[String],
+    crypto_len: Option<i32>,
+    ty: &DynSolType,
+    path: &str,
+) -> Result<DynSolValue, ZkayRuntimeError> {
+    let mismatch = |found: String| ZkayRuntimeError::AbiMismatch {
+        param: path.to_owned(),
+        expected: ty.sol_type_name().into_owned(),
+        found,
+    };
+    let (elem, len) = match ty {
+        DynSolType::FixedArray(elem, len) => (elem.as_ref(), Some(*len)),
+        DynSolType::Array(elem) => (elem.as_ref(), None),
+        _ => return Err(mismatch(format!("array of {} words", words.len()))),
+    };
+    if let Some(crypto_len) = crypto_len.filter(|&n| n as usize != words.len()) {
+        return Err(mismatch(format!(
+            "{} words, but its crypto params require {crypto_len}",
+            words.len()
+        )));
+    }
+    if len.is_some_and(|len| len != words.len()) {
+        return Err(mismatch(format!("{} words", words.len())));
+    }
+    let words = words
+        .iter()
+        .enumerate()
+        .map(|(i, w)| to_dyn_sol_value(&DataType::String(w.clone()), elem, &format!("{path}[{i}]")))
+        .collect::<Result<_, _>>()?;
+    Ok(if len.is_some() {
+        DynSolValue::FixedArray(words)
+    } else {
+        DynSolValue::Array(words)
+    })
+}
+
+fn describe(value: &DataType) -> String {
+    match value {
+        DataType::Bool(b) => b.to_string(),
+        DataType::Int(i) => i.to_string(),
+        DataType::SignedInt(i) => i.to_string(),
+        _ => format!("{value:?}"),
+    }
+}
+
+fn fits_uint(i: U256, bits: usize) -> bool {
+    bits >= 256 || i < U256::from(1) << bits
+}
+
+fn fits_int(i: I256, bits: usize) -> bool {
+    bits >= 256 || {
+        let bound = U256::from(1) << (bits - 1);
+        if i.is_negative() {
+            i.unsigned_abs() <= bound
+        } else {
+            i.into_raw() < bound
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(name: &str, ty: &str) -> Param {
+        Param {
+            ty: ty.to_owned(),
+            name: name.to_owned(),
+            components: vec![],
+            internal_type: None,
+        }
+    }
+
+    #[test]
+    fn cipher_length_is_checked_against_crypto_params() {
+        let params = CryptoParams::new("ecdh-chaskey".to_owned());
+        let cipher = |len: usize| {
+            DataType::CipherValue(Value::new(
+                vec!["1".to_owned(); len],
+                Some(params.clone()),
+                None,
+            ))
+        };
+        let len = params.cipher_len() as usize;
+        let value = encode_param(&param("votum", &format!("uint256[{len}]")), &cipher(len));
+        assert_eq!(
+            value.unwrap(),
+            DynSolValue::FixedArray(vec![DynSolValue::Uint(U256::from(1), 256); len])
+        );
+        let err = encode_param(&param("votum", "uint256[]"), &cipher(len + 1)).unwrap_err();
+        assert!(matches!(err, ZkayRuntimeError::AbiMismatch { param, .. } if param == "votum"));
+    }
+
+    #[test]
+    fn tuples_and_enums() {
+        let values = encode_args(
+            &[param("option", "uint8"), param("entry", "(bool,int32[])")],
+            &[
+                DataType::Int(U256::from(2)),
+                DataType::List(vec![
+                    DataType::Bool(true),
+                    DataType::List(vec![DataType::SignedInt(I256::MINUS_ONE)]),
+                ]),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            from_dyn_sol_value(&values[1]),
+            DataType::List(vec![
+                DataType::Bool(true),
+                DataType::List(vec![DataType::SignedInt(I256::MINUS_ONE)]),
+            ])
+        );
+        let err = encode_args(
+            &[param("option", "uint8")],
+            &[DataType::Int(U256::from(256))],
+        );
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "Argument option does not match abi type uint8: 256"
+        );
+    }
+
+    #[test]
+    fn return_values_are_decoded_per_output() {
+        let params = CryptoParams::new("ecdh-chaskey".to_owned());
+        let len = params.cipher_len() as usize;
+        let function = Function {
+            name: "get".to_owned(),
+            inputs: vec![],
+            outputs: vec![
+                param("", &format!("uint256[{len}]")),
+                param("", "bool"),
+                param("", "address"),
+            ],
+            state_mutability: alloy_json_abi::StateMutability::View,
+        };
+        let cipher = DynSolValue::FixedArray(vec![DynSolValue::Uint(U256::from(7), 256); len]);
+        let output = DynSolValue::Tuple(vec![
+            cipher,
+            DynSolValue::Bool(true),
+            DynSolValue::Address(Address::ZERO),
+        ])
+        .abi_encode_params();
+
+        let values = decode_output(&function, &output).unwrap();
+        assert_eq!(values.len(), 3);
+        let cipher = cipher_from_dyn_sol_value(&values[0], &params).unwrap();
+        assert_eq!(cipher.contents, vec!["7".to_owned(); len]);
+        assert_eq!(cipher.params, Some(params.clone()));
+        assert!(matches!(
+            cipher_from_dyn_sol_value(&values[1], &params),
+            Err(ZkayRuntimeError::AbiMismatch { .. })
+        ));
+        assert_eq!(to_text(&values[1]), "true");
+        assert_eq!(
+            address_from_dyn_sol_value(&values[2]).unwrap(),
+            Address::ZERO
+        );
+        assert!(address_from_dyn_sol_value(&values[1]).is_err());
+
+        assert!(single_output("get", values).is_err());
+        assert!(decode_output(&function, &output[..32]).is_err());
+    }
+}
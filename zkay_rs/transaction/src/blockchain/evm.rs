@@ -0,0 +1,876 @@
+// """
+// In-process blockchain backend.
+
+// Runs an embedded EVM (revm via foundry-evm) instead of connecting to an ethereum node, so that
+// scenario tests and offchain simulations work without a network and without an external process.
+// Test accounts are pre-funded, every transaction is mined into its own block immediately and the
+// block timestamp can be controlled by the test (see set_block_timestamp and increase_time).
+// """
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::{Function, JsonAbi};
+use alloy_primitives::{hex, keccak256, Address, Bytes, U256};
+use alloy_sol_types::{decode_revert_reason, Revert, SolError};
+use foundry_cli::{opts::RpcOpts, utils::remove_contract};
+use foundry_common::compile;
+use foundry_compilers::{
+    artifacts::{BytecodeObject, CompactBytecode},
+    ArtifactId, Project,
+};
+use foundry_config::Config;
+use foundry_evm::{executors::TracingExecutor, revm::primitives::Env};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::blockchain::abi;
+use crate::blockchain::web3rs::{encode_function_call, Web3Blockchain};
+use crate::deployments::Deployments;
+use crate::interface::{ZkayBlockchainInterface, ZkayProverInterface, ZkayRuntimeError};
+use crate::types::{
+    AddressValue, BlockStruct, DataType, MsgStruct, PublicKeyValue, TxStruct, Value,
+};
+use ast_builder::process_ast::get_verification_contract_names;
+use my_logging::{log_context::log_context, logger::data};
+use privacy::library_contracts;
+use rccell::RcCell;
+use zkay_ast::global_defs::{global_defs, global_vars};
+use zkay_config::{
+    config::{zk_print_banner, CFG},
+    config_user::UserConfig,
+    with_context_block, zk_print,
+};
+use zkay_transaction_crypto_params::params::CryptoParams;
+use zkay_utils::helpers::{get_contract_names, save_to_file};
+
+// Number of pre-funded accounts, account 0 is the default sender (same as eth-tester)
+pub const EVM_TEST_ACCOUNT_COUNT: usize = 20;
+// Chain id reported to contracts (ganache default)
+pub const EVM_CHAIN_ID: u64 = 1337;
+// Timestamp of the first block
+pub const EVM_GENESIS_TIMESTAMP: u64 = 1_600_000_000;
+const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+// 1'000'000 ether per test account
+fn test_account_balance() -> U256 {
+    U256::from(10).pow(U256::from(24))
+}
+
+// Deterministic address of the i-th test account
+fn test_account(i: usize) -> Address {
+    Address::from_word(keccak256(format!("zkay test account {i}")))
+}
+
+// The string passed to require/revert, other revert data (e.g. custom errors) as decoded by alloy or hex
+fn revert_reason(output: &[u8]) -> String {
+    Revert::abi_decode(output, false)
+        .map(|revert| revert.reason)
+        .unwrap_or_else(|_| decode_revert_reason(output).unwrap_or_else(|| hex::encode(output)))
+}
+
+fn parse_address(address: &str) -> eyre::Result<Address> {
+    Address::from_str(address).map_err(|_| eyre::eyre!("Invalid address {address}"))
+}
+
+#[derive(Clone)]
+pub struct EvmBlockchain<P: ZkayProverInterface> {
+    _lib_addresses: RcCell<Option<BTreeMap<String, Address>>>,
+    _pki_contract: RcCell<Option<BTreeMap<String, Address>>>,
+    prover: RcCell<P>,
+    next_acc_idx: RcCell<i32>,
+    executor: RcCell<TracingExecutor>,
+    accounts: Vec<Address>,
+    // abi and creation code (including constructor arguments) of every contract deployed on or connected to this chain
+    contracts: RcCell<BTreeMap<Address, (JsonAbi, Bytes)>>,
+    project: Option<Project>,
+}
+
+impl<P: ZkayProverInterface> EvmBlockchain<P> {
+    pub fn new(prover: RcCell<P>, rpc: Option<RpcOpts>) -> Self {
+        // the rpc options are only used to locate the foundry project, there is no node to connect to
+        let project = match rpc.as_ref() {
+            Some(rpc) => Config::from(rpc).project().ok(),
+            None => Config::load_with_root(std::env::temp_dir())
+                .sanitized()
+                .project()
+                .ok(),
+        };
+
+        let mut env = Env::default();
+        env.cfg.chain_id = EVM_CHAIN_ID;
+        // verification contracts can exceed the EIP-170 code size limit
+        env.cfg.limit_contract_code_size = Some(usize::MAX);
+        env.block.number = U256::from(1);
+        env.block.timestamp = U256::from(EVM_GENESIS_TIMESTAMP);
+        env.block.gas_limit = U256::from(BLOCK_GAS_LIMIT);
+        let mut executor = TracingExecutor::new(env, None, None, false, false, false);
+        let accounts: Vec<_> = (0..EVM_TEST_ACCOUNT_COUNT).map(test_account).collect();
+        for account in &accounts {
+            executor
+                .set_balance(*account, test_account_balance())
+                .expect("fund test account");
+        }
+        Self {
+            prover,
+            _lib_addresses: RcCell::new(None),
+            _pki_contract: RcCell::new(None),
+            next_acc_idx: RcCell::new(1),
+            executor: RcCell::new(executor),
+            accounts,
+            contracts: RcCell::new(BTreeMap::new()),
+            project,
+        }
+    }
+
+    // """All pre-funded accounts, including the default account."""
+    pub fn accounts(&self) -> Vec<Address> {
+        self.accounts.clone()
+    }
+
+    // """Timestamp of the block in which the next transaction is mined."""
+    pub fn block_timestamp(&self) -> u64 {
+        self.executor.borrow().env().block.timestamp.saturating_to()
+    }
+
+    // """Set the timestamp of the block in which the next transaction is mined."""
+    pub fn set_block_timestamp(&self, timestamp: u64) {
+        self.executor.borrow_mut().env_mut().block.timestamp = U256::from(timestamp);
+    }
+
+    // """Balance of the given account in wei."""
+    pub fn balance(&self, address: Address) -> U256 {
+        self.executor
+            .borrow()
+            .get_balance(address)
+            .expect("get balance")
+    }
+
+    // """Advance the block time by the given number of seconds."""
+    pub fn increase_time(&self, seconds: u64) {
+        self.set_block_timestamp(self.block_timestamp() + seconds);
+    }
+
+    // Instant mining: every transaction gets its own block, timestamps strictly increase
+    fn mine(&self) {
+        let mut executor = self.executor.borrow_mut();
+        let block = &mut executor.env_mut().block;
+        block.number += U256::from(1);
+        block.timestamp += U256::from(1);
+    }
+
+    // """Deploy raw creation code (including encoded constructor arguments) and return the contract address."""
+    pub fn deploy_bytecode(
+        &self,
+        sender: Address,
+        code: Bytes,
+        value: U256,
+    ) -> eyre::Result<Address> {
+        let result = self
+            .executor
+            .borrow_mut()
+            .deploy(sender, code, value, None)?;
+        self.mine();
+        zk_print!("Consumed gas: {}", result.raw.gas_used);
+        data("gas", &result.raw.gas_used.to_string());
+        Ok(result.address)
+    }
+
+    pub fn compile_contract(
+        &self,
+        sol_filename: &PathBuf,
+        contract_name: &str,
+        project: &Project,
+    ) -> eyre::Result<(JsonAbi, CompactBytecode, ArtifactId)> {
+        let output = compile::compile_target(sol_filename, project, false)?;
+        remove_contract(output, sol_filename, contract_name)
+    }
+
+    fn _deploy_contract(
+        &self,
+        sender: &str,
+        abi: JsonAbi,
+        bin: CompactBytecode,
+        args: &[String],
+        wei_amount: Option<i32>,
+    ) -> eyre::Result<Address> {
+        let BytecodeObject::Bytecode(bin) = bin.object else {
+            eyre::bail!("Dynamic linking is not supported, library addresses must be hardcoded before compilation")
+        };
+        let mut code = bin.to_vec();
+        if let Some(constructor) = &abi.constructor {
+            let args: Vec<_> = args.iter().cloned().map(DataType::String).collect();
+            let tokens = abi::encode_args(&constructor.inputs, &args)?;
+            code.extend(constructor.abi_encode_input(&tokens)?);
+        }
+        let code = Bytes::from(code);
+        let address = self.deploy_bytecode(
+            parse_address(sender)?,
+            code.clone(),
+            U256::from(wei_amount.unwrap_or(0)),
+        )?;
+        self.contracts.borrow_mut().insert(address, (abi, code));
+        Ok(address)
+    }
+
+    // Function of the contract at address called name, together with its abi encoded call data
+    fn encode_call(
+        &self,
+        contract: &Address,
+        name: &str,
+        args: &[DataType],
+    ) -> eyre::Result<(Function, Bytes)> {
+        let contracts = self.contracts.borrow();
+        let (abi, _) = contracts
+            .get(contract)
+            .ok_or_else(|| eyre::eyre!("No contract deployed at address {contract}"))?;
+        encode_function_call(abi, contract, name, args)
+    }
+
+    // Execute a view function without committing the state changes, a revert is reported as RequireFailed
+    fn call_function(
+        &self,
+        contract: &Address,
+        sender: Address,
+        name: &str,
+        args: &[DataType],
+    ) -> eyre::Result<Vec<DynSolValue>> {
+        let (function, calldata) = self.encode_call(contract, name, args)?;
+        let result = self
+            .executor
+            .borrow_mut()
+            .call_raw(sender, *contract, calldata, U256::ZERO)
+            .map_err(|e| eyre::eyre!("Call to {name} failed: {e}"))?;
+        if result.reverted {
+            return Err(ZkayRuntimeError::RequireFailed(format!(
+                "Call to {name} reverted with {}",
+                revert_reason(&result.result)
+            ))
+            .into());
+        }
+        Ok(abi::decode_output(&function, &result.result)?)
+    }
+
+    fn __hardcoded_external_contracts_ctx(
+        &self,
+        contract_dir: &PathBuf,
+        pki_verifier_addresses: &BTreeMap<String, Address>,
+    ) -> PathBuf {
+        // # Hardcode contract addresses
+        let mut c = std::fs::read_to_string(contract_dir.join("contract.sol")).unwrap();
+        for (key, val) in pki_verifier_addresses {
+            c = c.replace(
+                &format!("{key}(0)"),
+                &format!("{key}({})", val.to_checksum(None)),
+            );
+        }
+        let output_filename = contract_dir.join("contract.inst.sol");
+        let _ = std::fs::write(output_filename.clone(), c);
+        output_filename
+    }
+}
+
+impl<P: ZkayProverInterface> Web3Blockchain for EvmBlockchain<P> {
+    fn _create_w3_instance(&self) {
+        // nothing to connect to, the EVM is created in new
+    }
+}
+
+impl<P: ZkayProverInterface> ZkayBlockchainInterface<P> for EvmBlockchain<P> {
+    fn prover(&self) -> RcCell<P> {
+        self.prover.clone()
+    }
+    fn _pki_contract(&self) -> RcCell<Option<BTreeMap<String, Address>>> {
+        self._pki_contract.clone()
+    }
+    fn _lib_addresses(&self) -> RcCell<Option<BTreeMap<String, Address>>> {
+        self._lib_addresses.clone()
+    }
+    fn is_debug_backend(&self) -> bool {
+        true
+    }
+    fn default_address(&self) -> Option<AddressValue> {
+        None
+    }
+    fn create_test_accounts(&self, count: i32) -> Vec<String> {
+        let next_acc_idx = *self.next_acc_idx.borrow() as usize;
+        assert!(
+            self.accounts.len() >= next_acc_idx + count as usize,
+            "Can have at most {} dummy accounts in total",
+            self.accounts.len() - 1
+        );
+        *self.next_acc_idx.borrow_mut() += count;
+        self.accounts[next_acc_idx..next_acc_idx + count as usize]
+            .iter()
+            .map(|a| a.to_checksum(None))
+            .collect()
+    }
+    fn deploy_solidity_contract<T: Clone + Default, V: Clone + Default>(
+        &self,
+        sol_filename: &str,
+        contract_name: Option<String>,
+        sender: &str,
+        project: &Project,
+    ) -> eyre::Result<Address> {
+        let contract_name =
+            contract_name.unwrap_or_else(|| get_contract_names(sol_filename)[0].clone());
+        let (abi, bin, _id) =
+            self.compile_contract(&PathBuf::from(sol_filename), &contract_name, project)?;
+        self._deploy_contract(sender, abi, bin, &[], None)
+    }
+    fn get_special_variables(
+        &self,
+        sender: &String,
+        wei_amount: i32,
+    ) -> (MsgStruct, BlockStruct, TxStruct) {
+        let executor = self.executor.borrow();
+        let env = executor.env();
+        zk_print!("Current block timestamp: {}", env.block.timestamp);
+        (
+            MsgStruct::new(sender.clone(), wei_amount),
+            BlockStruct::new(
+                env.block.coinbase.to_checksum(None),
+                env.block.difficulty.saturating_to(),
+                env.block.gas_limit.saturating_to(),
+                env.block.number.saturating_to(),
+                env.block.timestamp.saturating_to(),
+            ),
+            TxStruct::new(env.tx.gas_price.saturating_to(), sender.clone()),
+        )
+    }
+    fn _default_address(&self) -> Option<String> {
+        // an integer refers to one of the test accounts
+        let account = CFG.lock().unwrap().blockchain_default_account();
+        match account.as_deref().map(usize::from_str) {
+            Some(Ok(idx)) => self.accounts.get(idx).map(|a| a.to_checksum(None)),
+            _ => account,
+        }
+    }
+    fn block_number(&self) -> eyre::Result<u64> {
+        Ok(self.executor.borrow().env().block.number.saturating_to())
+    }
+    fn chain_id(&self) -> eyre::Result<u64> {
+        Ok(EVM_CHAIN_ID)
+    }
+    fn _get_balance(&self, address: &str) -> eyre::Result<i32> {
+        Ok(self.balance(parse_address(address)?).saturating_to())
+    }
+    fn _req_public_key(
+        &self,
+        address: &String,
+        crypto_params: &CryptoParams,
+    ) -> eyre::Result<Value<String, PublicKeyValue>> {
+        let pki_contract = self.pki_contract(&crypto_params.crypto_name)?;
+        let pk = self._req_state_var(&pki_contract, "getPk", address)?;
+        Ok(abi::key_from_dyn_sol_value(&pk, crypto_params)?)
+    }
+    fn _announce_public_key(
+        &self,
+        address: &str,
+        pk: &Value<String, PublicKeyValue>,
+        crypto_params: &CryptoParams,
+    ) -> eyre::Result<()> {
+        let result;
+        with_context_block!(var _a= log_context("announcePk")=>{
+            let pki_contract = self.pki_contract(&crypto_params.crypto_name)?;
+            result = self._transact(
+                &pki_contract,
+                address,
+                "announcePk",
+                &vec![DataType::PublicKeyValue(pk.clone())],
+                None,
+            )
+        });
+        result.map_err(|e| e.wrap_err("Could not announce public key"))
+    }
+    fn _req_state_var(
+        &self,
+        contract_handle: &Address,
+        name: &str,
+        indices: &String,
+    ) -> eyre::Result<DynSolValue> {
+        let args: Vec<DataType> = if indices.is_empty() {
+            vec![]
+        } else {
+            indices
+                .split(',')
+                .map(|i| DataType::String(i.trim().to_owned()))
+                .collect()
+        };
+        let values = self.call_function(contract_handle, self.accounts[0], name, &args)?;
+        Ok(abi::single_output(name, values)?)
+    }
+    fn _call(
+        &self,
+        contract_handle: Address,
+        sender: &String,
+        name: &str,
+        args: &Vec<DataType>,
+    ) -> eyre::Result<Vec<DynSolValue>> {
+        let sender = parse_address(sender)?;
+        self.call_function(&contract_handle, sender, name, args)
+    }
+    fn _transact(
+        &self,
+        contract_handle: &Address,
+        sender: &str,
+        function: &str,
+        actual_args: &Vec<DataType>,
+        wei_amount: Option<i32>,
+    ) -> eyre::Result<()> {
+        let (_, calldata) = self.encode_call(contract_handle, function, actual_args)?;
+        let result = self
+            .executor
+            .borrow_mut()
+            .transact_raw(
+                parse_address(sender)?,
+                *contract_handle,
+                calldata,
+                U256::from(wei_amount.unwrap_or(0)),
+            )
+            .map_err(|e| eyre::eyre!("Transaction {function} failed: {e}"))?;
+        self.mine();
+        if result.reverted {
+            return Err(ZkayRuntimeError::TransactionReverted {
+                function: function.to_owned(),
+                reason: revert_reason(&result.result),
+            }
+            .into());
+        }
+        zk_print!("Consumed gas: {}", result.gas_used);
+        data("gas", &result.gas_used.to_string());
+        Ok(())
+    }
+    fn _estimate_gas(
+        &self,
+        contract_handle: &Address,
+        sender: &str,
+        function: &str,
+        actual_args: &Vec<DataType>,
+        wei_amount: Option<i32>,
+    ) -> eyre::Result<u64> {
+        // # execute without committing, like eth_estimateGas
+        let (_, calldata) = self.encode_call(contract_handle, function, actual_args)?;
+        let result = self
+            .executor
+            .borrow_mut()
+            .call_raw(
+                parse_address(sender)?,
+                *contract_handle,
+                calldata,
+                U256::from(wei_amount.unwrap_or(0)),
+            )
+            .map_err(|e| eyre::eyre!("Gas estimation of {function} failed: {e}"))?;
+        eyre::ensure!(
+            !result.reverted,
+            "Transaction {function} would revert with {}",
+            decode_revert_reason(&result.result).unwrap_or_else(|| result.result.to_string())
+        );
+        Ok(result.gas_used)
+    }
+    fn _deploy(
+        &self,
+        project_dir: &PathBuf,
+        sender: &str,
+        contract: &str,
+        actual_args: Vec<String>,
+        wei_amount: Option<i32>,
+        project: &Project,
+    ) -> eyre::Result<(Address, BTreeMap<String, Address>)> {
+        let mut project_dir = project_dir.clone();
+        project_dir.pop();
+        let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
+        let verifier_names = get_verification_contract_names(
+            (
+                std::fs::read_to_string(project_dir.join("contract.zkay")).ok(),
+                None,
+            ),
+            global_vars,
+        )?;
+
+        // Deploy verification contracts if not already done
+        let external_contract_addresses =
+            self._deploy_dependencies(sender, &project_dir, verifier_names, project)?;
+        let filename =
+            self.__hardcoded_external_contracts_ctx(&project_dir, &external_contract_addresses);
+        let (abi, bin, _id) = self.compile_contract(&filename, contract, project)?;
+        let handle;
+        with_context_block!(var _a= log_context("constructor")=>{
+            with_context_block!(var _b= log_context(contract)=>{
+                handle = self._deploy_contract(sender, abi, bin, &actual_args, wei_amount);
+            });
+        });
+        zk_print!(r#"Deployed contract "{contract}" at address "{handle:?}""#);
+        Ok((handle?, external_contract_addresses))
+    }
+    fn _deploy_dependencies(
+        &self,
+        sender: &str,
+        project_dir: &PathBuf,
+        verifier_names: Vec<String>,
+        project: &Project,
+    ) -> eyre::Result<BTreeMap<String, Address>> {
+        let mut vf = BTreeMap::new();
+        for verifier_name in verifier_names {
+            with_context_block!(var _a= log_context("constructor")=>{
+                with_context_block!(var _b= log_context(&verifier_name)=>{
+                    let filename = project_dir.join(format!("{verifier_name}.sol"));
+                    let (abi, bin, _id) = self.compile_contract(&filename, &verifier_name, project)?;
+                    vf.insert(verifier_name.clone(), self._deploy_contract(sender, abi, bin, &[], None)?);
+                });
+            });
+        }
+        for crypto_params in CFG.lock().unwrap().all_crypto_params() {
+            let pki_contract_name = CFG
+                .lock()
+                .unwrap()
+                .get_pki_contract_name(&CryptoParams::new(crypto_params.clone()).identifier_name());
+            vf.insert(pki_contract_name, self.pki_contract(&crypto_params)?);
+        }
+        Ok(vf)
+    }
+    fn _connect_libraries(&self) -> eyre::Result<BTreeMap<String, Address>> {
+        zk_print_banner("Deploying Libraries".to_owned());
+        let project = self
+            .project
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("No foundry project to compile the library contracts"))?;
+        let sender = self.accounts[0].to_checksum(None);
+        // # The embedded chain is not persistent -> always automatically deploy libraries
+        let tmpdir = std::env::temp_dir();
+        let mut pki_contracts = BTreeMap::new();
+        for crypto_params in CFG.lock().unwrap().all_crypto_params() {
+            let crypto_param = CryptoParams::new(crypto_params.clone());
+            let pki_contract_name = CFG
+                .lock()
+                .unwrap()
+                .get_pki_contract_name(&crypto_param.identifier_name());
+            let pki_sol = save_to_file(
+                Some(tmpdir.clone()),
+                &format!("{pki_contract_name}.sol"),
+                &library_contracts::get_pki_contract(&crypto_param),
+            );
+            let (abi, bin, _id) =
+                self.compile_contract(&PathBuf::from(pki_sol), &pki_contract_name, project)?;
+            let contract = self._deploy_contract(&sender, abi, bin, &[], None)?;
+            zk_print!(
+                r#"Deployed pki contract for crypto back-end {crypto_params} at address "{contract:?}""#
+            );
+            pki_contracts.insert(crypto_params, contract);
+        }
+        *self._pki_contract.borrow_mut() = Some(pki_contracts);
+
+        let verify_sol = save_to_file(
+            Some(tmpdir),
+            "verify_libs.sol",
+            &library_contracts::get_verify_libs_code(),
+        );
+        let mut lib_addresses = BTreeMap::new();
+        for lib in CFG.lock().unwrap().external_crypto_lib_names() {
+            let (abi, bin, _id) =
+                self.compile_contract(&PathBuf::from(verify_sol.clone()), &lib, project)?;
+            let out = self._deploy_contract(&sender, abi, bin, &[], None)?;
+            zk_print!(r#"Deployed crypto lib {lib} at address "{out:?}""#);
+            lib_addresses.insert(lib, out);
+        }
+        *self._lib_addresses.borrow_mut() = Some(lib_addresses.clone());
+        Ok(lib_addresses)
+    }
+    fn _reuse_libraries(&self, deployments: &Deployments) -> eyre::Result<bool> {
+        let crypto_backends = CFG.lock().unwrap().all_crypto_params();
+        let lib_names = CFG.lock().unwrap().external_crypto_lib_names();
+        let Some((pki_contracts, lib_addresses)) =
+            deployments.libraries_for(&crypto_backends, &lib_names)
+        else {
+            return Ok(false);
+        };
+        // # The embedded chain is not persistent -> only contracts deployed by this process still exist
+        let undeployed = |address: &&Address| {
+            self.executor
+                .borrow()
+                .is_empty_code(**address)
+                .unwrap_or(true)
+        };
+        if let Some(address) = pki_contracts
+            .values()
+            .chain(lib_addresses.values())
+            .find(undeployed)
+        {
+            return Err(ZkayRuntimeError::IntegrityError(format!(
+                "No contract deployed at recorded address {address}"
+            ))
+            .into());
+        }
+        *self._pki_contract.borrow_mut() = Some(pki_contracts);
+        *self._lib_addresses.borrow_mut() = Some(lib_addresses);
+        Ok(true)
+    }
+    fn _connect(
+        &self,
+        project_dir: &str,
+        contract: &str,
+        address: Address,
+        project: &Project,
+    ) -> eyre::Result<(JsonAbi, CompactBytecode, ArtifactId)> {
+        let filename = PathBuf::from(project_dir).join("contract.sol");
+        let (abi, bin, id) = self.compile_contract(&filename, contract, project)?;
+        // remember the abi, so that transactions can be issued to the connected contract
+        if let Some((known_abi, _)) = self.contracts.borrow_mut().get_mut(&address) {
+            *known_abi = abi.clone();
+        }
+        Ok((abi, bin, id))
+    }
+    fn _verify_contract_integrity(
+        &self,
+        address: &Address,
+        sol_filename: &PathBuf,
+        _libraries: Option<&BTreeMap<String, Address>>,
+        contract_name: Option<String>,
+        _is_library: bool,
+        _cwd: Option<PathBuf>,
+        project: &Project,
+    ) -> eyre::Result<Address> {
+        let contract_name = contract_name
+            .unwrap_or_else(|| get_contract_names(&sol_filename.to_string_lossy())[0].clone());
+        let deployed = self
+            .contracts
+            .borrow()
+            .get(address)
+            .map(|(_, code)| code.clone());
+        let Some(deployed) = deployed.filter(|_| {
+            !self
+                .executor
+                .borrow()
+                .is_empty_code(*address)
+                .unwrap_or(true)
+        }) else {
+            eyre::bail!("Expected contract {contract_name} is not deployed at address {address}")
+        };
+        let (_abi, bin, _id) = self.compile_contract(sol_filename, &contract_name, project)?;
+        let expected = bin
+            .object
+            .into_bytes()
+            .ok_or_else(|| eyre::eyre!("Contract {contract_name} is not linked"))?;
+        // the deployed creation code additionally contains the constructor arguments
+        eyre::ensure!(
+            deployed.starts_with(&expected),
+            "Deployed contract at address {address} does not match local contract {sol_filename:?}"
+        );
+        zk_print!(
+            "Contract@{address} matches {}:{contract_name}",
+            sol_filename.file_name().unwrap().to_str().unwrap()
+        );
+        Ok(*address)
+    }
+    fn _verify_library_integrity(
+        &self,
+        libraries: BTreeMap<String, PathBuf>,
+        _contract_with_libs_addr: &String,
+        _sol_with_libs_filename: &PathBuf,
+        project: &Project,
+    ) -> eyre::Result<BTreeMap<String, Address>> {
+        // libraries are deployed by this backend itself, their addresses are known
+        let lib_addresses = self.lib_addresses()?.borrow().clone().unwrap_or_default();
+        let mut addresses = BTreeMap::new();
+        for (lib_name, lib_sol) in libraries {
+            let Some(lib_address) = lib_addresses.get(&lib_name) else {
+                continue;
+            };
+            self._verify_contract_integrity(
+                lib_address,
+                &lib_sol,
+                None,
+                Some(lib_name.clone()),
+                true,
+                None,
+                project,
+            )?;
+            addresses.insert(lib_name, *lib_address);
+        }
+        Ok(addresses)
+    }
+    fn _verify_zkay_contract_integrity(
+        &self,
+        address: &Address,
+        project_dir: &PathBuf,
+        pki_verifier_addresses: &BTreeMap<String, Address>,
+        project: &Project,
+    ) -> eyre::Result<()> {
+        let sol_file = self.__hardcoded_external_contracts_ctx(project_dir, pki_verifier_addresses);
+        self._verify_contract_integrity(
+            address,
+            &sol_file,
+            None,
+            None,
+            false,
+            Some(project_dir.to_owned()),
+            project,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::prover::jsnark::JsnarkProver;
+
+    // creation code of a contract whose runtime code returns 42 for any call
+    const RETURN_42_CODE: &str = "600a600c600039600a6000f3602a60005260206000f3";
+
+    // Stores ciphertexts like a zkay contract: the getter balance(address owner, uint256 i) returns storage slot
+    // owner + i (element i of balance[owner]), set(uint256 slot, uint256 value) writes a slot
+    const CIPHER_STORE_ABI: &str = r#"[
+        {"type":"function","name":"balance","stateMutability":"view",
+         "inputs":[{"name":"","type":"address"},{"name":"","type":"uint256"}],
+         "outputs":[{"name":"","type":"uint256"}]},
+        {"type":"function","name":"set","stateMutability":"nonpayable",
+         "inputs":[{"name":"slot","type":"uint256"},{"name":"value","type":"uint256"}],
+         "outputs":[]}
+    ]"#;
+
+    fn cipher_store_code() -> Bytes {
+        let set_selector = hex::encode(&keccak256("set(uint256,uint256)")[..4]);
+        let runtime = format!(
+            "600035 60e01c 63{set_selector} 14 601f 57 \
+             600435 602435 01 54 600052 60206000f3 \
+             5b 602435 600435 55 00"
+        );
+        let code = format!("6028600c6000396028 6000f3 {runtime}");
+        hex::decode(code.replace(' ', "")).unwrap().into()
+    }
+
+    // Reverts with Error("no") for any call
+    const REVERT_ABI: &str = r#"[
+        {"type":"function","name":"get","stateMutability":"view",
+         "inputs":[],"outputs":[{"name":"","type":"uint256"}]}
+    ]"#;
+    const REVERT_CODE: &str = "6023600c60003960236000f3\
+                               6308c379a060e01b600052 6020600452 6002602452 616e6f60f01b604452 60646000fd";
+
+    // Deploy code with the given abi from the default account
+    fn deploy(chain: &EvmBlockchain<JsnarkProver>, abi: &str, code: Bytes) -> Address {
+        let sender = chain.accounts()[0].to_checksum(None);
+        let code = CompactBytecode {
+            object: BytecodeObject::Bytecode(code),
+            source_map: None,
+            link_references: BTreeMap::new(),
+        };
+        let abi: JsonAbi = serde_json::from_str(abi).unwrap();
+        chain
+            ._deploy_contract(&sender, abi, code, &[], None)
+            .unwrap()
+    }
+
+    // Deploy the ciphertext store from the default account
+    pub(crate) fn deploy_cipher_store(chain: &EvmBlockchain<JsnarkProver>) -> Address {
+        deploy(chain, CIPHER_STORE_ABI, cipher_store_code())
+    }
+
+    // Slot of the i-th ciphertext element of balance[owner]
+    pub(crate) fn cipher_slot(owner: &str, i: u64) -> String {
+        (U256::from_be_slice(parse_address(owner).unwrap().as_slice()) + U256::from(i)).to_string()
+    }
+
+    fn blockchain() -> EvmBlockchain<JsnarkProver> {
+        EvmBlockchain::new(RcCell::new(JsnarkProver), None)
+    }
+
+    #[test]
+    fn test_accounts_are_funded() {
+        let chain = blockchain();
+        let accounts = chain.create_test_accounts(3);
+        assert_eq!(accounts.len(), 3);
+        assert_eq!(accounts[0], chain.accounts()[1].to_checksum(None));
+        assert_eq!(
+            chain.create_test_accounts(1)[0],
+            chain.accounts()[4].to_checksum(None)
+        );
+        for account in &accounts {
+            assert_eq!(
+                chain.balance(parse_address(account).unwrap()),
+                test_account_balance()
+            );
+        }
+    }
+
+    #[test]
+    fn transactions_are_mined_instantly() {
+        let chain = blockchain();
+        let sender = chain.accounts()[0];
+        chain.set_block_timestamp(2_000_000_000);
+        let address = chain
+            .deploy_bytecode(
+                sender,
+                alloy_primitives::hex::decode(RETURN_42_CODE)
+                    .unwrap()
+                    .into(),
+                U256::ZERO,
+            )
+            .unwrap();
+        let (_, block, _) = chain.get_special_variables(&sender.to_checksum(None), 0);
+        assert_eq!(block.number, 2);
+        assert_eq!(block.timestamp, 2_000_000_001);
+        chain.increase_time(3600);
+        assert_eq!(chain.block_timestamp(), 2_000_003_601);
+
+        let result = chain
+            .executor
+            .borrow_mut()
+            .call_raw(sender, address, Bytes::new(), U256::ZERO)
+            .unwrap();
+        assert_eq!(U256::from_be_slice(&result.result), U256::from(42));
+    }
+
+    #[test]
+    fn failed_calls_are_errors() {
+        let chain = blockchain();
+        let sender = chain.accounts()[0].to_checksum(None);
+        let reverting = deploy(
+            &chain,
+            REVERT_ABI,
+            hex::decode(REVERT_CODE.replace(' ', "")).unwrap().into(),
+        );
+        let err = chain._call(reverting, &sender, "get", &vec![]).unwrap_err();
+        assert_eq!(
+            ZkayRuntimeError::from(err),
+            ZkayRuntimeError::RequireFailed("Call to get reverted with no".to_owned())
+        );
+
+        let store = deploy_cipher_store(&chain);
+        let owner = DataType::String(sender.clone());
+        let zero = DataType::String("0".to_owned());
+        assert_eq!(
+            chain
+                ._call(
+                    store,
+                    &sender,
+                    "balance",
+                    &vec![owner.clone(), zero.clone()]
+                )
+                .unwrap(),
+            vec![DynSolValue::Uint(U256::ZERO, 256)]
+        );
+        for (contract, name, args) in [
+            (store, "missing", vec![]),
+            (store, "balance", vec![owner.clone()]),
+            (store, "balance", vec![zero.clone(), zero]),
+            (Address::ZERO, "balance", vec![owner]),
+        ] {
+            let err = chain._call(contract, &sender, name, &args).unwrap_err();
+            assert!(matches!(
+                ZkayRuntimeError::from(err),
+                ZkayRuntimeError::BlockchainError(_) | ZkayRuntimeError::AbiMismatch { .. }
+            ));
+        }
+        assert!(chain
+            ._call(store, &"0x1".to_owned(), "missing", &vec![])
+            .is_err());
+        // # A word is not an address
+        let balance = chain
+            ._req_state_var(&store, "balance", &format!("{sender},0"))
+            .unwrap();
+        assert!(matches!(
+            abi::address_from_dyn_sol_value(&balance),
+            Err(ZkayRuntimeError::AbiMismatch { .. })
+        ));
+    }
+}
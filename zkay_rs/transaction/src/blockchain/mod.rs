@@ -4,12 +4,17 @@
 // ==========
 // Submodules
 // ==========
+// * :py:mod:`.abi`: Conversion between runtime values and abi values.
 // * :py:mod:`.web3py`: Contains several web3-based backends.
+// * :py:mod:`.evm`: In-process EVM backend for tests.
 // """
 
 // from .web3py import Web3TesterBlockchain, Web3HttpGanacheBlockchain
 // from .web3py import Web3IpcBlockchain, Web3WebsocketBlockchain, Web3HttpBlockchain, Web3CustomBlockchain
+// from .evm import EvmBlockchain
+pub mod abi;
 pub mod estimate;
+pub mod evm;
 pub mod rpc;
 pub mod tx;
 pub mod web3;
@@ -20,12 +20,12 @@
 // use foundry_cli::{handler, utils};
 use std::str::FromStr;
 use alloy_chains::Chain;
-use alloy_dyn_abi::{DynSolValue, JsonAbiExt, Specifier};
-use alloy_json_abi::{Constructor, JsonAbi};
+use alloy_dyn_abi::{DynSolValue, FunctionExt, JsonAbiExt, Specifier};
+use alloy_json_abi::{Constructor, Function, JsonAbi};
 use alloy_network::{AnyNetwork, EthereumWallet, TransactionBuilder};
 use alloy_primitives::{hex, Address, Bytes};
 use alloy_provider::{PendingTransactionError, Provider, ProviderBuilder};
-use alloy_rpc_types::{AnyTransactionReceipt, TransactionRequest};
+use alloy_rpc_types::{AnyTransactionReceipt, TransactionInput, TransactionRequest};
 use alloy_serde::WithOtherFields;
 use alloy_signer::Signer;
 use alloy_transport::{Transport, TransportError};
@@ -59,15 +59,23 @@ use foundry_config::{
         value::{Dict, Map},
         Metadata, Profile,
     },
-    merge_impl_figment_convert, Config,
+    merge_impl_figment_convert, Config, SolcReq,
+};
+use std::{
+    borrow::Borrow,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
-use std::{borrow::Borrow, marker::PhantomData, path::PathBuf, sync::Arc};
 
-use crate::interface::{ZkayBlockchainInterface, ZkayProverInterface};
+use crate::blockchain::abi;
+use crate::deployments::Deployments;
+use crate::interface::{ZkayBlockchainInterface, ZkayProverInterface, ZkayRuntimeError};
 use my_logging::{log_context::log_context, logger::data};
 use privacy::library_contracts;
 use serde_json::{json, Map as JsonMap,  Value as JsonValue};
 use solidity::compiler::compile_solidity_json;
+use solidity::solc_manager::solc_binary;
 use std::borrow::BorrowMut;
 use std::collections::BTreeMap;
 use zkay_config::{
@@ -350,6 +358,8 @@ pub struct Web3BlockchainBase<P: ZkayProverInterface, W> {
     eth: Option<EthereumOpts>,
     rpc: Option<RpcOpts>,
     project:Option<Project>,
+    // abi of every contract whose integrity was verified (or which was connected to), to read its state
+    abis: RcCell<BTreeMap<Address, JsonAbi>>,
     _web3: PhantomData<W>,
 }
 impl<P: ZkayProverInterface, W> Web3BlockchainBase<P, W> {
@@ -367,6 +377,7 @@ impl<P: ZkayProverInterface, W> Web3BlockchainBase<P, W> {
             eth,
             rpc,
             project,
+            abis: RcCell::new(BTreeMap::new()),
             _web3: PhantomData,
         }
     }
@@ -454,9 +465,17 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         // elif isinstance(cfg.blockchain_default_account, int):
         //     return self.w3.eth.accounts[cfg.blockchain_default_account]
     }
-    fn _get_balance(&self, _address: &str) -> i32 {
+    fn block_number(&self) -> eyre::Result<u64> {
+        let provider = utils::get_provider(&self.config()?)?;
+        Ok(utils::block_on(async { provider.get_block_number().await })?)
+    }
+    fn chain_id(&self) -> eyre::Result<u64> {
+        let provider = utils::get_provider(&self.config()?)?;
+        Ok(utils::block_on(async { provider.get_chain_id().await })?)
+    }
+    fn _get_balance(&self, _address: &str) -> eyre::Result<i32> {
         // self.w3.eth.get_balance(address)
-        0
+        Ok(0)
     }
 
     fn _req_public_key(
@@ -464,12 +483,9 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         address: &String,
         crypto_params: &CryptoParams,
     ) -> eyre::Result<Value<String, PublicKeyValue>> {
-        let pki_contract = self.pki_contract(&crypto_params.crypto_name);
-        Ok(Value::<String, PublicKeyValue>::new(
-            vec![self._req_state_var::<String>(&pki_contract, "getPk", address)],
-            Some(crypto_params.clone()),
-            None,
-        ))
+        let pki_contract = self.pki_contract(&crypto_params.crypto_name)?;
+        let pk = self._req_state_var(&pki_contract, "getPk", address)?;
+        Ok(abi::key_from_dyn_sol_value(&pk, crypto_params)?)
     }
 
     fn _announce_public_key(
@@ -477,9 +493,9 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         address: &str,
         pk: &Value<String, PublicKeyValue>,
         crypto_params: &CryptoParams,
-    ) {
+    ) -> eyre::Result<()> {
         //         with log_context(f"announcePk"):
-        let pki_contract = self.pki_contract(&crypto_params.crypto_name);
+        let pki_contract = self.pki_contract(&crypto_params.crypto_name)?;
         self._transact(
             &pki_contract,
             address,
@@ -487,27 +503,35 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
             &vec![DataType::PublicKeyValue(pk.clone())],
             None,
         )
+        .map_err(|e| e.wrap_err("Could not announce public key"))
     }
 
-    fn _req_state_var<R: Clone + Default>(
+    fn _req_state_var(
         &self,
-        _contract_handle: &Address,
-        _name: &str,
-        _indices: &String,
-    ) -> R {
-        //         try:
-        // contract_handle.functions[name](indices).call()
-        //         except Exception as e:
-        //             raise BlockChainError(e.args)
-        R::default()
+        contract_handle: &Address,
+        name: &str,
+        indices: &String,
+    ) -> eyre::Result<DynSolValue> {
+        let args: Vec<DataType> = if indices.is_empty() {
+            vec![]
+        } else {
+            indices
+                .split(',')
+                .map(|i| DataType::String(i.trim().to_owned()))
+                .collect()
+        };
+        let values = self
+            .eth_call(contract_handle, None, name, &args)
+            .map_err(|e| e.wrap_err(format!("Could not read state variable {name}")))?;
+        Ok(abi::single_output(name, values)?)
     }
     fn _call(
         &self,
-        _contract_handle: Address,
-        _sender: &String,
-        _name: &str,
-        _args: &Vec<DataType>,
-    ) -> String {
+        contract_handle: Address,
+        sender: &String,
+        name: &str,
+        args: &Vec<DataType>,
+    ) -> eyre::Result<Vec<DynSolValue>> {
         //         try:
         // let fct = contract_handle.functions[name];
         // let gas_amount = self._gas_heuristic(sender, fct(args));
@@ -515,7 +539,9 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         // fct(args).call(tx)
         //         except Exception as e:
         //             raise BlockChainError(e.args)
-        String::new()
+        let sender = Address::from_str(sender)
+            .map_err(|_| eyre::eyre!("Invalid sender address {sender}"))?;
+        self.eth_call(&contract_handle, Some(sender), name, args)
     }
     fn _transact(
         &self,
@@ -524,7 +550,7 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         _function: &str,
         _actual_args: &Vec<DataType>,
         _wei_amount: Option<i32>,
-    ) {
+    ) -> eyre::Result<()> {
         // let fct = if function == "constructor" {
         //     contract_handle.constructor
         // } else {
@@ -547,6 +573,30 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         // zk_print!("Consumed gas: {gas}");
         // data("gas", gas);
         // tx_receipt
+        Ok(())
+    }
+    fn _estimate_gas(
+        &self,
+        contract_handle: &Address,
+        sender: &str,
+        function: &str,
+        actual_args: &Vec<DataType>,
+        wei_amount: Option<i32>,
+    ) -> eyre::Result<u64> {
+        let abi = self.abis.borrow().get(contract_handle).cloned().ok_or_else(|| {
+            eyre::eyre!("Unknown contract at address {contract_handle}, its integrity must be verified first")
+        })?;
+        let (_, calldata) = encode_function_call(&abi, contract_handle, function, actual_args)?;
+        let provider = utils::get_provider(&self.config()?)?;
+        let tx = WithOtherFields::new(
+            TransactionRequest::default()
+                .from(Address::from_str(sender)?)
+                .to(*contract_handle)
+                .value(alloy_primitives::U256::from(wei_amount.unwrap_or(0)))
+                .input(TransactionInput::new(calldata)),
+        );
+        let gas = utils::block_on(async { provider.estimate_gas(&tx).await })?;
+        Ok(u64::try_from(gas)?)
     }
 
     fn _deploy(
@@ -557,7 +607,7 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         actual_args: Vec<String>,
         wei_amount: Option<i32>,
         project: &Project,
-    ) -> eyre::Result<Address> {
+    ) -> eyre::Result<(Address, BTreeMap<String, Address>)> {
         let mut project_dir = project_dir.clone();
         project_dir.pop();
         let global_vars = RcCell::new(global_vars(RcCell::new(global_defs())));
@@ -568,7 +618,7 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
                 None,
             ),
             global_vars,
-        );
+        )?;
 
         // Deploy verification contracts if not already done
         let external_contract_addresses = self
@@ -620,7 +670,7 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
             });
         });
         zk_print!(r#"Deployed contract "{contract}" at address "{handle:?}""#);
-        handle
+        Ok((handle?, external_contract_addresses))
     }
 
     fn _deploy_dependencies(
@@ -632,14 +682,14 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
     ) -> eyre::Result<BTreeMap<String, Address>> {
         // # Deploy verification contracts if not already done
         let mut vf = BTreeMap::new();
-        let lib_addresses = self.lib_addresses();
+        let lib_addresses = self.lib_addresses()?;
         for verifier_name in verifier_names {
             with_context_block!(var _a= log_context("constructor")=>{
                 with_context_block!(var _b=log_context(&format!("{verifier_name}"))=>{
                     let filename = project_dir.join( &format!("{verifier_name}.sol"));
                     let (abi, bin, id) = self.compile_contract(&filename, &verifier_name, lib_addresses.borrow().as_ref(),&PathBuf::from("."),project)?;
                     with_context_block!(var _tm= time_measure("transaction_full",false,false)=>{
-                        vf.insert(verifier_name.clone(),self._deploy_contract(sender, abi.clone(),&vec![],None,abi, bin, id).unwrap());
+                        vf.insert(verifier_name.clone(),self._deploy_contract(sender, abi.clone(),&vec![],None,abi, bin, id)?);
                     });
             });
             });
@@ -649,18 +699,16 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
                 .lock()
                 .unwrap()
                 .get_pki_contract_name(&CryptoParams::new(crypto_params.clone()).identifier_name());
-            let pki_contract_address = self.pki_contract(&crypto_params);
+            let pki_contract_address = self.pki_contract(&crypto_params)?;
             vf.insert(pki_contract_name, pki_contract_address.into());
         }
         Ok(vf)
     }
 
-    fn _connect_libraries(
-        &self,
-    ) -> eyre::Result<BTreeMap<String, Address>> {
-        assert!(
+    fn _connect_libraries(&self) -> eyre::Result<BTreeMap<String, Address>> {
+        eyre::ensure!(
             !CFG.lock().unwrap().blockchain_pki_address().is_empty(),
-            "Must specify pki address in config."
+            ZkayRuntimeError::BlockchainError("Must specify pki address in config.".to_owned())
         );
 
         let mut lib_addresses = vec![];
@@ -680,87 +728,58 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
                     .collect();
             }
 
-            assert!(
-                lib_addresses.len() == CFG.lock().unwrap().external_crypto_lib_names().len(),
-                "Must specify all crypto library addresses in config\nExpected {} was {}",
-                CFG.lock().unwrap().external_crypto_lib_names().len(),
-                lib_addresses.len()
-            );
-        }
-        // with_context_block!(var _lce=library_compilation_environment()=>{
-        let tmpdir = std::env::temp_dir();
-        // let mut _pki_contract=BTreeMap::new();
-        let all_crypto_params=CFG.lock().unwrap().all_crypto_params();
-        for crypto_params in all_crypto_params {
-            let crypto_param=CryptoParams::new(crypto_params.clone());
-            let pki_contract_code =
-                library_contracts::get_pki_contract(&crypto_param);
-            let pki_contract_name = CFG
-                .lock()
-                .unwrap()
-                .get_pki_contract_name(&crypto_param.identifier_name());
-            let pki_sol = save_to_file(
-                Some(tmpdir.clone()),
-                &format!("{pki_contract_name}.sol"),
-                &pki_contract_code,
-            );
-            let _=self._verify_contract_integrity(
-                    &Address::from_str(&CFG.lock().unwrap().blockchain_pki_address()).unwrap(),
-                    &PathBuf::from(pki_sol),
-                    None,
-                    Some(pki_contract_name),
-                    false,
-                    None,
-                    self.project.as_ref().unwrap(),
-                )?;
-            self._pki_contract.borrow_mut().as_mut().unwrap().insert(
-                crypto_params,
-                Address::default(),
+            let expected = CFG.lock().unwrap().external_crypto_lib_names().len();
+            eyre::ensure!(
+                lib_addresses.len() == expected,
+                ZkayRuntimeError::BlockchainError(format!(
+                    "Must specify all crypto library addresses in config\nExpected {expected} was {}",
+                    lib_addresses.len()
+                ))
             );
         }
-        //  *self._pki_contract.borrow_mut()=_pki_contract;
-        let verify_sol = save_to_file(
-            Some(tmpdir),
-            "verify_libs.sol",
-            &library_contracts::get_verify_libs_code(),
-        );
-        // let mut _lib_addresses = BTreeMap::new();
-        for (lib, addr) in CFG
+        let pki_address = Address::from_str(&CFG.lock().unwrap().blockchain_pki_address())?;
+        let pki_addresses = CFG
+            .lock()
+            .unwrap()
+            .all_crypto_params()
+            .into_iter()
+            .map(|crypto_params| (crypto_params, pki_address))
+            .collect();
+        let lib_addresses = CFG
             .lock()
             .unwrap()
             .external_crypto_lib_names()
-            .iter()
+            .into_iter()
             .zip(&lib_addresses)
-        {
-            let _out = self._verify_contract_integrity(
-                &Address::from_str(addr).unwrap(),
-                &PathBuf::from(verify_sol.clone()),
-                None,
-                Some(lib.clone()),
-                true,
-                None,
-                self.project.as_ref().unwrap(),
-            )?;
-            self._lib_addresses
-                .borrow_mut()
-                .as_mut()
-                .unwrap()
-                .insert(lib.clone(), Address::from_str(addr).unwrap());
-        }
-        // self._lib_addresses = _lib_addresses;
-        // });
-        self._lib_addresses.borrow().clone().ok_or(eyre::eyre!(""))
+            .map(|(lib, addr)| Ok::<_, eyre::Report>((lib, Address::from_str(addr)?)))
+            .collect::<eyre::Result<_>>()?;
+        self._verify_libraries(pki_addresses, lib_addresses)
+    }
+
+    fn _reuse_libraries(&self, deployments: &Deployments) -> eyre::Result<bool> {
+        let crypto_backends = CFG.lock().unwrap().all_crypto_params();
+        let lib_names = CFG.lock().unwrap().external_crypto_lib_names();
+        let Some((pki_addresses, lib_addresses)) =
+            deployments.libraries_for(&crypto_backends, &lib_names)
+        else {
+            return Ok(false);
+        };
+        // # Recorded contracts are verified just like configured ones
+        self._verify_libraries(pki_addresses, lib_addresses)?;
+        Ok(true)
     }
 
     fn _connect(
         &self,
         project_dir: &str,
         contract: &str,
-        _address: Address,
-         project: &Project,
+        address: Address,
+        project: &Project,
     ) -> eyre::Result<(JsonAbi, CompactBytecode, ArtifactId)> {
         let filename = PathBuf::from(project_dir).join("contract.sol");
-        let (abi, bin, id) = self.compile_contract(&filename, contract, None, &PathBuf::from("."),project)?;
+        let (abi, bin, id) =
+            self.compile_contract(&filename, contract, None, &PathBuf::from("."), project)?;
+        self.abis.borrow_mut().insert(address, abi.clone());
         //  self.w3.eth.contract(
         //     address=address, abi=cout["abi"]
         // )
@@ -769,107 +788,87 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
 
     fn _verify_contract_integrity(
         &self,
-        mut address: &Address,
+        address: &Address,
         sol_filename: &PathBuf,
         libraries: Option<&BTreeMap<String, Address>>,
-        mut contract_name: Option<String>,
+        contract_name: Option<String>,
         is_library: bool,
         cwd: Option<PathBuf>,
- project: &Project,
-    ) ->  eyre::Result<Address> {
-        // if is_instance(address, bytes)
-        //     {address = self.w3.toChecksumAddress(address);}
-
+        _project: &Project,
+    ) -> eyre::Result<Address> {
         let contract_name = if let Some(contract_name) = contract_name {
             contract_name
         } else {
             get_contract_names(&sol_filename.to_string_lossy().to_string())[0].clone()
         };
-        let actual_byte_code = self.__normalized_hex("get_code(address)".to_owned()); //self.w3.eth.get_code(address) MYTODO
-        assert!(
+        let actual_byte_code = self.get_code(address)?;
+        eyre::ensure!(
             !actual_byte_code.is_empty(),
-            "Expected contract {} is not deployed at address {address}",
-            contract_name
+            "Expected contract {contract_name} is not deployed at address {address}"
         );
 
-        let (_abi, bin, _id) = self.compile_contract(
-            sol_filename,
-            &contract_name,
-            libraries,
-            cwd.as_ref().unwrap_or(&PathBuf::from(".")),
-            project,
+        let (abi, bin) =
+            self.compile_deployed_contract(sol_filename, &contract_name, cwd.as_ref())?;
+        let mut expected_byte_code = deployed_code_hex(&bin);
+        link_libraries(
+            &mut expected_byte_code,
+            &bin,
+            libraries.unwrap_or(&BTreeMap::new()),
         )?;
-        let mut expected_byte_code =
-            self.__normalized_hex(String::from_utf8(bin.object.into_bytes().unwrap().into()).unwrap());
-
-        if is_library {
+        copy_immutables(&mut expected_byte_code, &actual_byte_code, &bin);
+        if is_library && expected_byte_code.len() >= 42 {
             // # https://github.com/ethereum/solidity/issues/7101
             expected_byte_code = expected_byte_code[..2].to_owned()
-                + &self.__normalized_hex("address.clone()".to_owned())
+                + &self.__normalized_hex(address.to_string())
                 + &expected_byte_code[42..]
         };
 
-        assert!(
-            actual_byte_code == expected_byte_code,
+        eyre::ensure!(
+            strip_metadata(&actual_byte_code) == strip_metadata(&expected_byte_code),
             "Deployed contract at address {address} does not match local contract {sol_filename:?}"
         );
         zk_print!(
             "Contract@{address} matches {}:{contract_name}",
             sol_filename.file_name().unwrap().to_str().unwrap()
         );
-
-        // self.w3.eth.contract(
-        //     address=address, abi=cout["abi"]
-        // )
-        Ok(address.clone())
+        self.abis.borrow_mut().insert(*address, abi);
+        Ok(*address)
     }
     fn _verify_library_integrity(
         &self,
         libraries: BTreeMap<String, PathBuf>,
         contract_with_libs_addr: &String,
         sol_with_libs_filename: &PathBuf,
-        project:&Project,
+        project: &Project,
     ) -> eyre::Result<BTreeMap<String, Address>> {
         let cname =
             get_contract_names(&sol_with_libs_filename.to_string_lossy().to_string())[0].clone();
-        let actual_code =
-            self.__normalized_hex("self.w3.eth.getCode(contract_with_libs_addr)".to_owned());
-        assert!(
+        let actual_code = self.get_code(&Address::from_str(contract_with_libs_addr)?)?;
+        eyre::ensure!(
             !actual_code.is_empty(),
             "Expected contract {cname} is not deployed at address {contract_with_libs_addr}"
         );
-    let (_abi, bin, _id)=self.compile_contract(sol_with_libs_filename, &cname, None, &PathBuf::from("."),project)?;
-        let code_with_placeholders = self.__normalized_hex(
-            String::from_utf8(bin.object.into_bytes().unwrap().into()).unwrap()
-        );
-
-        assert!(
+        let (_abi, bin) = self.compile_deployed_contract(sol_with_libs_filename, &cname, None)?;
+        let code_with_placeholders = deployed_code_hex(&bin);
+        eyre::ensure!(
             actual_code.len() == code_with_placeholders.len(),
             "Local code of contract {cname} has different length than remote contract"
         );
 
         let mut addresses = BTreeMap::new();
         for (lib_name, lib_sol) in libraries {
-            // # Compute placeholder according to
-            // # https://solidity.readthedocs.io/en/v0.5.13/using-the-compiler.html#using-the-commandline-compiler
-            let hash = String::new(); //self.w3.solidityKeccak(
-                                      //     vec!["string"],
-                                      //     vec![format!(
-                                      //         "{}:{lib_name}",
-                                      //         lib_sol.file_name()
-                                      //     )],
-                                      // );
-
-            let placeholder = format!("__${}$__", &self.__normalized_hex(hash)[..34]);
-
-            // # Retrieve concrete address in deployed code at placeholder offset in local code and verify library contract integrity
-            let lib_address_offset = code_with_placeholders.find(&placeholder);
-            if let Some(_lib_address_offset) = lib_address_offset {
-                let lib_address: Address = Address::default(); // self
-                                                        // .w3
-                                                        // .toChecksumAddress(actual_code[lib_address_offset..lib_address_offset + 40]);
-                                                        // with cfg.library_compilation_environment():
-                let _=self._verify_contract_integrity(
+            // # Retrieve concrete address in deployed code at the link reference offsets in local code and verify library contract integrity
+            let mut lib_addresses: Vec<&str> = link_offsets(&bin, &lib_name)
+                .map(|start| &actual_code[start..start + 40])
+                .collect();
+            lib_addresses.dedup();
+            let lib_address = match lib_addresses[..] {
+                [] => continue,
+                [lib_address] => Address::from_str(lib_address)?,
+                _ => eyre::bail!("Contract {cname} links different addresses for library {lib_name}"),
+            };
+            with_context_block!(var _lce= library_compilation_environment()=>{
+                self._verify_contract_integrity(
                     &lib_address,
                     &lib_sol,
                     None,
@@ -877,9 +876,9 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
                     true,
                     None,
                     project,
-                );
-                addresses.insert(lib_name.clone(), lib_address.clone());
-            }
+                )?
+            });
+            addresses.insert(lib_name.clone(), lib_address);
         }
         Ok(addresses)
     }
@@ -888,31 +887,181 @@ impl<P: ZkayProverInterface, W> ZkayBlockchainInterface<P> for Web3BlockchainBas
         address: &Address,
         project_dir: &PathBuf,
         pki_verifier_addresses: &BTreeMap<String, Address>,
-        project:&Project,
-    ) {
+        project: &Project,
+    ) -> eyre::Result<()> {
         let sol_file =
-            self.__hardcoded_external_contracts_ctx(project_dir,project_dir, &pki_verifier_addresses);
-        let _=self._verify_contract_integrity(
+            self.__hardcoded_external_contracts_ctx(project_dir, project_dir, pki_verifier_addresses);
+        self._verify_contract_integrity(
             address,
-            &PathBuf::from(sol_file),
-            None,
+            &sol_file,
+            self.lib_addresses()?.borrow().as_ref(),
             None,
             false,
             Some(project_dir.to_owned()),
             project,
-        );
+        )?;
+        Ok(())
     }
 
     //     @contextmanager
-    fn lib_addresses(&self) -> RcCell<Option<BTreeMap<String, Address>>> {
-        if self._lib_addresses.borrow().is_none() {
-            let _=self._connect_libraries();
-        }
+    fn _lib_addresses(&self) -> RcCell<Option<BTreeMap<String, Address>>> {
         self._lib_addresses.clone()
     }
 }
 
 impl<P: ZkayProverInterface, W> Web3BlockchainBase<P, W> {
+    fn config(&self) -> eyre::Result<Config> {
+        let rpc = self
+            .rpc
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("No rpc endpoint configured for blockchain backend"))?;
+        Ok(Config::from(rpc))
+    }
+
+    // Check that the pki contracts (per crypto backend) and crypto libraries at the given addresses
+    // match the local library code, and use them from now on
+    fn _verify_libraries(
+        &self,
+        pki_addresses: BTreeMap<String, Address>,
+        lib_addresses: BTreeMap<String, Address>,
+    ) -> eyre::Result<BTreeMap<String, Address>> {
+        let _lce = library_compilation_environment();
+        let tmpdir = std::env::temp_dir();
+        let project = self
+            .project
+            .as_ref()
+            .ok_or_else(|| eyre::eyre!("No foundry project to compile the library contracts"))?;
+        // # Mismatches (or contracts which cannot be verified at all) are reported as IntegrityError
+        let integrity_error =
+            |e: eyre::Report| eyre::Report::new(ZkayRuntimeError::IntegrityError(format!("{e:#}")));
+        let mut _pki_contract = BTreeMap::new();
+        for (crypto_params, pki_address) in pki_addresses {
+            let crypto_param = CryptoParams::new(crypto_params.clone());
+            let pki_contract_code = library_contracts::get_pki_contract(&crypto_param);
+            let pki_contract_name = CFG
+                .lock()
+                .unwrap()
+                .get_pki_contract_name(&crypto_param.identifier_name());
+            let pki_sol = save_to_file(
+                Some(tmpdir.clone()),
+                &format!("{pki_contract_name}.sol"),
+                &pki_contract_code,
+            );
+            let contract = self
+                ._verify_contract_integrity(
+                    &pki_address,
+                    &PathBuf::from(pki_sol),
+                    None,
+                    Some(pki_contract_name),
+                    false,
+                    None,
+                    project,
+                )
+                .map_err(integrity_error)?;
+            _pki_contract.insert(crypto_params, contract);
+        }
+        *self._pki_contract.borrow_mut() = Some(_pki_contract);
+        let verify_sol = save_to_file(
+            Some(tmpdir),
+            "verify_libs.sol",
+            &library_contracts::get_verify_libs_code(),
+        );
+        let mut _lib_addresses = BTreeMap::new();
+        for (lib, addr) in lib_addresses {
+            let out = self
+                ._verify_contract_integrity(
+                    &addr,
+                    &PathBuf::from(verify_sol.clone()),
+                    None,
+                    Some(lib.clone()),
+                    true,
+                    None,
+                    project,
+                )
+                .map_err(integrity_error)?;
+            _lib_addresses.insert(lib, out);
+        }
+        *self._lib_addresses.borrow_mut() = Some(_lib_addresses.clone());
+        Ok(_lib_addresses)
+    }
+
+    // Deployed runtime code at address as normalized hex (empty if there is no contract)
+    fn get_code(&self, address: &Address) -> eyre::Result<String> {
+        let provider = utils::get_provider(&self.config()?)?;
+        let code = utils::block_on(async { provider.get_code_at(*address).await })?;
+        Ok(hex::encode(code))
+    }
+
+    // Call the view function name of a contract whose abi is known, returns the decoded return values.
+    // A revert is reported as RequireFailed.
+    fn eth_call(
+        &self,
+        contract: &Address,
+        sender: Option<Address>,
+        name: &str,
+        args: &[DataType],
+    ) -> eyre::Result<Vec<DynSolValue>> {
+        let abi = self.abis.borrow().get(contract).cloned().ok_or_else(|| {
+            eyre::eyre!(
+                "Unknown contract at address {contract}, its integrity must be verified first"
+            )
+        })?;
+        let (function, calldata) = encode_function_call(&abi, contract, name, args)?;
+        let provider = utils::get_provider(&self.config()?)?;
+        let mut tx = TransactionRequest::default()
+            .to(*contract)
+            .input(TransactionInput::new(calldata));
+        if let Some(sender) = sender {
+            tx = tx.from(sender);
+        }
+        let tx = WithOtherFields::new(tx);
+        let output = utils::block_on(async { provider.call(&tx).await }).map_err(|e| {
+            match e
+                .as_error_resp()
+                .filter(|resp| resp.message.contains("revert"))
+            {
+                Some(resp) => {
+                    let reason = format!("Call to {name} reverted: {}", resp.message);
+                    ZkayRuntimeError::RequireFailed(reason).into()
+                }
+                None => eyre::Report::new(e),
+            }
+        })?;
+        Ok(abi::decode_output(&function, &output)?)
+    }
+
+    // """
+    // Compile sol_filename with the solc version and optimizer settings of the current configuration
+    // (e.g. from the manifest or the library compilation environment).
+
+    // :return: abi and deployed (runtime) bytecode of contract_name
+    // """
+    pub fn compile_deployed_contract(
+        &self,
+        sol_filename: &PathBuf,
+        contract_name: &str,
+        cwd: Option<&PathBuf>,
+    ) -> eyre::Result<(JsonAbi, CompactDeployedBytecode)> {
+        let root = cwd
+            .cloned()
+            .or_else(|| sol_filename.parent().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from("."));
+        let project = solc_project(&root)?;
+        let mut output = compile::compile_target(sol_filename, &project, false)?;
+        let artifact = output.remove(sol_filename, contract_name).ok_or_else(|| {
+            eyre::eyre!("Could not find contract {contract_name} in {sol_filename:?}")
+        })?;
+        let abi = artifact
+            .get_abi()
+            .ok_or_else(|| eyre::eyre!("No abi for contract {contract_name}"))?
+            .into_owned();
+        let bin = artifact
+            .get_deployed_bytecode()
+            .ok_or_else(|| eyre::eyre!("No deployed bytecode for contract {contract_name}"))?
+            .into_owned();
+        Ok((abi, bin))
+    }
+
     pub fn compile_contract(
         &self,
         sol_filename: &PathBuf,
@@ -947,7 +1096,7 @@ impl<P: ZkayProverInterface, W> Web3BlockchainBase<P, W> {
         &self,
         _sender: &str,
         _contract_interface: JsonAbi,
-        _args: &Vec<String>,
+        args: &Vec<String>,
         _wei_amount: Option<i32>,
         abi: JsonAbi,
         bin: CompactBytecode,
@@ -989,7 +1138,13 @@ impl<P: ZkayProverInterface, W> Web3BlockchainBase<P, W> {
         };
 
         // Add arguments to constructor
-        let params = vec![];
+        let params = match &abi.constructor {
+            Some(constructor) => {
+                let args: Vec<_> = args.iter().cloned().map(DataType::String).collect();
+                self.parse_constructor_args(constructor, &args)?
+            }
+            None => vec![],
+        };
         //  if let Some(constructor) = &abi.constructor {
         //     let constructor_args = self
         //         .constructor_args_path
@@ -1265,18 +1420,122 @@ impl<P: ZkayProverInterface, W> Web3BlockchainBase<P, W> {
     fn parse_constructor_args(
         &self,
         constructor: &Constructor,
-        constructor_args: &[String],
+        constructor_args: &[DataType],
     ) -> eyre::Result<Vec<DynSolValue>> {
-        let mut params = Vec::with_capacity(constructor.inputs.len());
-        for (input, arg) in constructor.inputs.iter().zip(constructor_args) {
-            // resolve the input type directly
-            let ty = input
-                .resolve()
-                .wrap_err_with(|| format!("Could not resolve constructor arg: input={input}"))?;
-            params.push((ty, arg));
+        Ok(abi::encode_args(&constructor.inputs, constructor_args)?)
+    }
+}
+
+// """Foundry project rooted at root which compiles with the configured solc version and optimizer runs."""
+pub fn solc_project(root: &Path) -> eyre::Result<Project> {
+    let mut config = Config::load_with_root(root).sanitized();
+    let runs = CFG.lock().unwrap().opt_solc_optimizer_runs();
+    config.solc = Some(SolcReq::Local(solc_binary()));
+    config.optimizer = runs >= 0;
+    config.optimizer_runs = runs.max(0) as usize;
+    Ok(config.project()?)
+}
+
+// Function name of the contract at address (with as many parameters as args), together with the abi encoded call data
+pub(crate) fn encode_function_call(
+    abi: &JsonAbi,
+    contract: &Address,
+    name: &str,
+    args: &[DataType],
+) -> eyre::Result<(Function, Bytes)> {
+    let function = abi
+        .function(name)
+        .and_then(|overloads| overloads.iter().find(|f| f.inputs.len() == args.len()))
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "Contract at address {contract} has no function {name} with {} arguments",
+                args.len()
+            )
+        })?
+        .clone();
+    let tokens = abi::encode_args(&function.inputs, args)?;
+    let calldata = function.abi_encode_input(&tokens)?;
+    Ok((function, calldata.into()))
+}
+
+// Deployed code as normalized hex, unlinked library addresses are left as placeholders
+fn deployed_code_hex(bin: &CompactDeployedBytecode) -> String {
+    match bin.bytecode.as_ref().map(|b| &b.object) {
+        Some(BytecodeObject::Bytecode(code)) => hex::encode(code),
+        Some(BytecodeObject::Unlinked(code)) => code.trim_start_matches("0x").to_lowercase(),
+        None => String::new(),
+    }
+}
+
+// Hex offsets of all references to library lib_name in bin
+fn link_offsets<'a>(
+    bin: &'a CompactDeployedBytecode,
+    lib_name: &'a str,
+) -> impl Iterator<Item = usize> + 'a {
+    bin.bytecode
+        .iter()
+        .flat_map(|b| b.link_references.values())
+        .filter_map(move |libs| libs.get(lib_name))
+        .flatten()
+        .map(|offsets| offsets.start as usize * 2)
+}
+
+// Write the library addresses to their link reference offsets in the hex code
+fn link_libraries(
+    code: &mut String,
+    bin: &CompactDeployedBytecode,
+    libraries: &BTreeMap<String, Address>,
+) -> eyre::Result<()> {
+    let names = bin
+        .bytecode
+        .iter()
+        .flat_map(|b| b.link_references.values())
+        .flat_map(|libs| libs.keys())
+        .collect::<std::collections::BTreeSet<_>>();
+    for name in names {
+        let address = libraries
+            .get(name)
+            .ok_or_else(|| eyre::eyre!("No address for library {name}"))?;
+        let address = hex::encode(address);
+        for start in link_offsets(bin, name) {
+            code.replace_range(start..start + 40, &address);
         }
-        let params = params.iter().map(|(ty, arg)| (ty, arg.as_str()));
-        parse_tokens(params).map_err(Into::into)
+    }
+    Ok(())
+}
+
+// Take the values of immutable variables from the actual code (they are only known after construction)
+fn copy_immutables(expected: &mut String, actual: &str, bin: &CompactDeployedBytecode) {
+    for offsets in bin.immutable_references.values().flatten() {
+        let range = offsets.start as usize * 2..(offsets.start + offsets.length) as usize * 2;
+        if range.end <= expected.len() && range.end <= actual.len() {
+            expected.replace_range(range.clone(), &actual[range]);
+        }
+    }
+}
+
+// """
+// Strip the CBOR encoded metadata which solc appends to the runtime code.
+
+// The metadata contains a hash of the source files (including comments and paths), which must not cause
+// a mismatch between otherwise identical code. Its length is stored in the last two bytes.
+// """
+pub fn strip_metadata(code: &str) -> &str {
+    let Some(length) = code
+        .len()
+        .checked_sub(4)
+        .and_then(|i| usize::from_str_radix(&code[i..], 16).ok())
+    else {
+        return code;
+    };
+    let Some(start) = code.len().checked_sub((length + 2) * 2) else {
+        return code;
+    };
+    // a CBOR map with one to seven entries (e.g. ipfs/bzzr1 and solc)
+    if matches!(&code[start..start + 2], "a1" | "a2" | "a3" | "a4" | "a5" | "a6" | "a7") {
+        &code[..start]
+    } else {
+        code
     }
 }
 
@@ -1448,3 +1707,165 @@ pub struct Web3CustomBlockchain;
 //     fn _create_w3_instance(self) -> Web3:
 //         assert isinstance(cfg.blockchain_node_uri, Web3)
 //         return cfg.blockchain_node_uri
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use foundry_compilers::artifacts::Offsets;
+
+    // placeholder which solc emits for an unlinked library reference (20 bytes)
+    const LIB_PLACEHOLDER: &str = "__$f1f3eb40f5bc1ad1344716ced8b8a0431d$__";
+
+    // CBOR metadata as appended by solc 0.8.24, the ipfs hash depends on the source files
+    fn metadata(hash_byte: &str) -> String {
+        format!(
+            "a264697066735822 1220{}64736f6c6343000818 0033",
+            hash_byte.repeat(32)
+        )
+        .replace(' ', "")
+    }
+
+    fn deployed(
+        code: &str,
+        link_references: BTreeMap<String, BTreeMap<String, Vec<Offsets>>>,
+        immutable_references: BTreeMap<String, Vec<Offsets>>,
+    ) -> CompactDeployedBytecode {
+        CompactDeployedBytecode {
+            bytecode: Some(CompactBytecode {
+                object: BytecodeObject::Unlinked(code.to_owned()),
+                source_map: None,
+                link_references,
+            }),
+            immutable_references,
+        }
+    }
+
+    // PUSH20 <Lib> DELEGATECALL PUSH20 <Lib> PUSH32 <immutable> POP
+    fn contract_with_library_and_immutable(hash_byte: &str) -> (String, CompactDeployedBytecode) {
+        let code = format!(
+            "73{LIB_PLACEHOLDER}5af473{LIB_PLACEHOLDER}7f{}50{}",
+            "00".repeat(32),
+            metadata(hash_byte)
+        );
+        let links = BTreeMap::from([(
+            "Lib.sol".to_owned(),
+            BTreeMap::from([(
+                "Lib".to_owned(),
+                vec![
+                    Offsets {
+                        start: 1,
+                        length: 20,
+                    },
+                    Offsets {
+                        start: 24,
+                        length: 20,
+                    },
+                ],
+            )]),
+        )]);
+        let immutables = BTreeMap::from([(
+            "7".to_owned(),
+            vec![Offsets {
+                start: 45,
+                length: 32,
+            }],
+        )]);
+        let bin = deployed(&code, links, immutables);
+        (code, bin)
+    }
+
+    #[test]
+    fn strip_metadata_removes_cbor_trailer() {
+        let code = "6080604052600080fd";
+        assert_eq!(strip_metadata(&format!("{code}{}", metadata("ab"))), code);
+        assert_eq!(
+            strip_metadata(&format!("{code}{}", metadata("ab"))),
+            strip_metadata(&format!("{code}{}", metadata("cd")))
+        );
+    }
+
+    #[test]
+    fn strip_metadata_keeps_code_without_metadata() {
+        assert_eq!(strip_metadata("6080604052600080fd"), "6080604052600080fd");
+        // trailing length which does not point at a CBOR map
+        assert_eq!(
+            strip_metadata("6080604052600080fd0004"),
+            "6080604052600080fd0004"
+        );
+        assert_eq!(strip_metadata("00"), "00");
+        assert_eq!(strip_metadata(""), "");
+    }
+
+    #[test]
+    fn link_offsets_of_library_references() {
+        let (_, bin) = contract_with_library_and_immutable("ab");
+        assert_eq!(link_offsets(&bin, "Lib").collect::<Vec<_>>(), vec![2, 48]);
+        assert_eq!(link_offsets(&bin, "Other").count(), 0);
+    }
+
+    #[test]
+    fn link_libraries_replaces_placeholders() {
+        let (code, bin) = contract_with_library_and_immutable("ab");
+        let lib = Address::repeat_byte(0x11);
+        let mut linked = deployed_code_hex(&bin);
+        assert_eq!(linked, code);
+        link_libraries(
+            &mut linked,
+            &bin,
+            &BTreeMap::from([("Lib".to_owned(), lib)]),
+        )
+        .unwrap();
+        assert_eq!(linked, code.replace(LIB_PLACEHOLDER, &hex::encode(lib)));
+
+        // a placeholder without an address cannot be linked
+        let mut unlinked = deployed_code_hex(&bin);
+        assert!(link_libraries(&mut unlinked, &bin, &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn copy_immutables_from_deployed_code() {
+        let (code, bin) = contract_with_library_and_immutable("ab");
+        let value = format!("{:064x}", 42);
+        let actual = code.replacen(&"00".repeat(32), &value, 1);
+        let mut expected = code.clone();
+        copy_immutables(&mut expected, &actual, &bin);
+        assert_eq!(expected, actual);
+
+        // code which is shorter than the referenced range is left untouched
+        let mut short = code[..60].to_owned();
+        copy_immutables(&mut short, &actual, &bin);
+        assert_eq!(short, code[..60]);
+    }
+
+    #[test]
+    fn deployed_code_matches_only_without_tampering() {
+        let (_, bin) = contract_with_library_and_immutable("ab");
+        let libraries = BTreeMap::from([("Lib".to_owned(), Address::repeat_byte(0x11))]);
+
+        // code on chain: linked, with a constructed immutable and metadata of a different source path
+        let (mut actual, _) = contract_with_library_and_immutable("cd");
+        link_libraries(&mut actual, &bin, &libraries).unwrap();
+        let actual = actual.replacen(&"00".repeat(32), &format!("{:064x}", 42), 1);
+
+        let mut expected = deployed_code_hex(&bin);
+        link_libraries(&mut expected, &bin, &libraries).unwrap();
+        copy_immutables(&mut expected, &actual, &bin);
+        assert_eq!(strip_metadata(&actual), strip_metadata(&expected));
+
+        // a single modified byte of the code (DELEGATECALL -> CALL) must be detected
+        let tampered = format!("{}f1{}", &actual[..44], &actual[46..]);
+        assert_eq!(&actual[44..46], "f4");
+        assert_ne!(strip_metadata(&tampered), strip_metadata(&expected));
+
+        // as well as a library address which differs from the expected one
+        let mut other_lib = deployed_code_hex(&bin);
+        link_libraries(
+            &mut other_lib,
+            &bin,
+            &BTreeMap::from([("Lib".to_owned(), Address::repeat_byte(0x22))]),
+        )
+        .unwrap();
+        copy_immutables(&mut other_lib, &actual, &bin);
+        assert_ne!(strip_metadata(&actual), strip_metadata(&other_lib));
+    }
+}
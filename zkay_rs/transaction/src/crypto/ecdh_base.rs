@@ -13,6 +13,7 @@ use ark_std::rand;
 use ark_std::rand::Rng;
 use jsnark_interface::jsnark_interface::CIRCUIT_BUILDER_JAR;
 use jsnark_interface::jsnark_interface::JARS_DIR;
+use jsnark_interface::jsnark_interface::{JAVA_MAX_HEAP, JAVA_MIN_HEAP};
 use std::fs;
 use std::path::PathBuf;
 use zkay_config::config_user::UserConfig;
@@ -30,8 +31,8 @@ pub trait EcdhBase<
         let (keys, _) = run_command(
             vec![
                 "java",
-                "-Xms4096m",
-                "-Xmx16384m",
+                JAVA_MIN_HEAP,
+                JAVA_MAX_HEAP,
                 "-cp",
                 &format!("{CIRCUIT_BUILDER_JAR}"),
                 "zkay.ZkayECDHGenerator",
@@ -51,8 +52,8 @@ pub trait EcdhBase<
         let (ret, _) = run_command(
             vec![
                 "java",
-                "-Xms4096m",
-                "-Xmx16384m",
+                JAVA_MIN_HEAP,
+                JAVA_MAX_HEAP,
                 "-cp",
                 &format!("{CIRCUIT_BUILDER_JAR}"),
                 "zkay.ZkayECDHGenerator",
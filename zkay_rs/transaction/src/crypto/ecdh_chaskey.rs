@@ -15,9 +15,11 @@ use crate::interface::{
 use crate::types::{
     AddressValue, CipherValue, DataType, KeyPair, PrivateKeyValue, PublicKeyValue, Value,
 };
+use alloy_primitives::U256;
 use ark_ff::BigInteger256;
 use ark_std::rand;
 use jsnark_interface::jsnark_interface::CIRCUIT_BUILDER_JAR;
+use jsnark_interface::jsnark_interface::{JAVA_MAX_HEAP, JAVA_MIN_HEAP};
 use rand::RngCore;
 use rccell::{RcCell, WeakCell};
 use rustc_serialize::hex::ToHex;
@@ -101,8 +103,8 @@ impl<
         let (iv_cipher, _) = run_command(
             vec![
                 "java",
-                "-Xms4096m",
-                "-Xmx16384m",
+                JAVA_MIN_HEAP,
+                JAVA_MAX_HEAP,
                 "-cp",
                 &format!("{CIRCUIT_BUILDER_JAR}"),
                 "zkay.ChaskeyLtsCbc",
@@ -126,7 +128,7 @@ impl<
             vec![],
         )
     }
-    fn _dec(&self, mut cipher: Vec<String>, sk: &String) -> (u64, Vec<String>) {
+    fn _dec(&self, mut cipher: Vec<String>, sk: &String) -> (U256, Vec<String>) {
         // # Extract sender address from cipher metadata and request corresponding public key
         let sender_pk = cipher.pop().unwrap();
         // assert!( cipher.len() == self.params.cipher_payload_len);
@@ -144,8 +146,8 @@ impl<
         let (plain, _) = run_command(
             vec![
                 "java",
-                "-Xms4096m",
-                "-Xmx16384m",
+                JAVA_MIN_HEAP,
+                JAVA_MAX_HEAP,
                 "-cp",
                 &format!("{CIRCUIT_BUILDER_JAR}"),
                 "zkay.ChaskeyLtsCbc",
@@ -157,7 +159,7 @@ impl<
             None,
             false,
         );
-        let plain = u64::from_str_radix(plain.unwrap().split("\n").last().unwrap(), 16).unwrap();
+        let plain = U256::from_str_radix(plain.unwrap().split("\n").last().unwrap(), 16).unwrap();
 
         (plain, vec![])
     }
@@ -16,6 +16,7 @@ use crate::interface::{
 use crate::types::{
     AddressValue, CipherValue, DataType, KeyPair, PrivateKeyValue, PublicKeyValue, Value,
 };
+use alloy_primitives::U256;
 use ark_ec::twisted_edwards::TECurveConfig;
 use ark_std::UniformRand;
 use babyjubjub_rs::{Point, PrivateKey};
@@ -139,7 +140,7 @@ impl<
             .collect();
         (cipher_chunks, vec![r.into_bigint().to_string()])
     }
-    fn _dec(&self, cipher: Vec<String>, sk: &String) -> (u64, Vec<String>) {
+    fn _dec(&self, cipher: Vec<String>, sk: &String) -> (U256, Vec<String>) {
         // with time_measure("elgamal_decrypt"):
         let c1 = BabyJubJub::new(
             Fq::from_str(&cipher[0]).unwrap(),
@@ -154,7 +155,7 @@ impl<
         let plain = self._de_embed(plain_embedded.into());
 
         // # TODO randomness misused for the secret key, which is an extremely ugly hack...
-        (plain, vec![sk.clone()])
+        (U256::from(plain), vec![sk.clone()])
     }
 }
 impl<
@@ -234,7 +235,7 @@ impl<
     > ZkayHomomorphicCryptoInterface<P, B, K> for ElgamalCrypto<P, B, K>
 {
     fn do_op(&self, op: &str, _public_key: Vec<String>, args: Vec<DataType>) -> Vec<String> {
-        fn deserialize(operand: &DataType) -> (Option<(BabyJubJub, BabyJubJub)>, Option<u128>) {
+        fn deserialize(operand: &DataType) -> (Option<(BabyJubJub, BabyJubJub)>, Option<Fr>) {
             // # if ciphertext is 0, return (Point.ZERO, Point.ZERO) == Enc(0, 0)
             if let DataType::CipherValue(operand) = operand {
                 if &operand.contents == &vec![0.to_string(); 4] {
@@ -251,7 +252,12 @@ impl<
                     (Some((c1, c2)), None)
                 }
             } else if let DataType::Int(operand) = operand {
-                (None, Some(*operand))
+                (None, Some(Fr::from_str(&operand.to_string()).unwrap()))
+            } else if let DataType::SignedInt(operand) = operand {
+                // # negative scalars are taken modulo the curve order
+                let abs = Fr::from_str(&operand.unsigned_abs().to_string()).unwrap();
+                let scalar = if operand.is_negative() { -abs } else { abs };
+                (None, Some(scalar))
             } else {
                 (None, None)
             }
@@ -268,12 +274,12 @@ impl<
                 e2 = arg0.1 + arg1.1.neg();
             }
             ((Some(arg0), None), (None, Some(arg1))) if op == "*" => {
-                e1 = arg0.0 * Fr::from(arg1);
-                e2 = arg0.1 * Fr::from(arg1);
+                e1 = arg0.0 * arg1;
+                e2 = arg0.1 * arg1;
             }
             ((None, Some(arg0)), (Some(arg1), None)) if op == "*" => {
-                e1 = arg1.0 * Fr::from(arg0);
-                e2 = arg1.1 * Fr::from(arg0);
+                e1 = arg1.0 * arg0;
+                e2 = arg1.1 * arg0;
             }
             _ => {
                 panic!("Unsupported operation {op}");
@@ -0,0 +1,141 @@
+// """
+// Registry of the contracts deployed on a network.
+
+// The addresses of the pki contracts, crypto libraries, verification contracts and main contracts deployed from a
+// project directory are persisted as deployments/<chain-id>.json next to its manifest.json, so that later deployments
+// can reuse the pki and library contracts and contracts can be connected to by name.
+// """
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use alloy_primitives::Address;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployments {
+    pub chain_id: u64,
+    // crypto backend -> pki contract
+    pub pki: BTreeMap<String, Address>,
+    // crypto library name -> library contract
+    pub libraries: BTreeMap<String, Address>,
+    pub verifiers: BTreeMap<String, Address>,
+    pub contracts: BTreeMap<String, Address>,
+}
+
+impl Deployments {
+    pub fn new(chain_id: u64) -> Self {
+        Self {
+            chain_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn path(project_dir: &Path, chain_id: u64) -> PathBuf {
+        project_dir
+            .join("deployments")
+            .join(format!("{chain_id}.json"))
+    }
+
+    // Registry of the network with chain_id, empty if nothing was deployed there yet
+    pub fn load(project_dir: &Path, chain_id: u64) -> eyre::Result<Self> {
+        let path = Self::path(project_dir, chain_id);
+        if !path.try_exists()? {
+            return Ok(Self::new(chain_id));
+        }
+        let deployments: Self = serde_json::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|e| eyre::eyre!("Invalid deployment registry {}: {e}", path.display()))?;
+        eyre::ensure!(
+            deployments.chain_id == chain_id,
+            "Deployment registry {} belongs to chain {}",
+            path.display(),
+            deployments.chain_id
+        );
+        Ok(deployments)
+    }
+
+    pub fn save(&self, project_dir: &Path) -> eyre::Result<()> {
+        let path = Self::path(project_dir, self.chain_id);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        // # write to a temporary file first, an interrupted save must not corrupt the registry
+        let tmp = path.with_extension("json.tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn contract_address(&self, contract: &str) -> eyre::Result<Address> {
+        self.contracts.get(contract).copied().ok_or_else(|| {
+            eyre::eyre!(
+                "No deployment of contract {contract} recorded for chain {}",
+                self.chain_id
+            )
+        })
+    }
+
+    // The recorded pki and library contracts, if there is one for every crypto backend and library
+    pub fn libraries_for(
+        &self,
+        crypto_backends: &[String],
+        lib_names: &[String],
+    ) -> Option<(BTreeMap<String, Address>, BTreeMap<String, Address>)> {
+        let pick = |recorded: &BTreeMap<String, Address>, names: &[String]| {
+            names
+                .iter()
+                .map(|name| Some((name.clone(), *recorded.get(name)?)))
+                .collect::<Option<BTreeMap<_, _>>>()
+        };
+        Some((
+            pick(&self.pki, crypto_backends)?,
+            pick(&self.libraries, lib_names)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_round_trip() {
+        let project_dir =
+            std::env::temp_dir().join(format!("zkay_deployments_{}", std::process::id()));
+        assert_eq!(
+            Deployments::load(&project_dir, 1337).unwrap(),
+            Deployments::new(1337)
+        );
+
+        let mut deployments = Deployments::new(1337);
+        deployments
+            .pki
+            .insert("elgamal".to_owned(), Address::with_last_byte(1));
+        deployments
+            .contracts
+            .insert("Survey".to_owned(), Address::with_last_byte(2));
+        deployments.save(&project_dir).unwrap();
+        assert!(!Deployments::path(&project_dir, 1337)
+            .with_extension("json.tmp")
+            .exists());
+
+        let loaded = Deployments::load(&project_dir, 1337).unwrap();
+        assert_eq!(loaded, deployments);
+        assert_eq!(
+            loaded.contract_address("Survey").unwrap(),
+            Address::with_last_byte(2)
+        );
+        assert!(loaded.contract_address("Other").is_err());
+        assert!(Deployments::load(&project_dir, 1)
+            .unwrap()
+            .contracts
+            .is_empty());
+
+        let elgamal = ["elgamal".to_owned()];
+        assert!(loaded.libraries_for(&elgamal, &[]).is_some());
+        assert!(loaded
+            .libraries_for(&elgamal, &["BabyJubJub".to_owned()])
+            .is_none());
+        std::fs::remove_dir_all(project_dir).unwrap();
+    }
+}
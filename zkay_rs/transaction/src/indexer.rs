@@ -0,0 +1,526 @@
+// Local decrypted view of the encrypted state of a deployed zkay contract.
+//
+// Reading an encrypted state variable through StateDict costs a chain request and a decryption
+// (an ElGamal decryption includes a discrete log) per access. The indexer instead follows the chain,
+// re-reads the ciphertexts of the watched state locations once per new block and only decrypts the
+// ciphertexts which actually changed. The plaintexts are kept in a json store next to the compiled
+// contract, so a restarted wallet starts from the last indexed block.
+//
+// The store is NOT encrypted: anyone who can read it learns the decrypted values of the user. It is
+// created readable and writable by its owner only (mode 0600 on unix), the same protection the key
+// files of the crypto backends in the data directory rely on.
+//
+// The generated contracts do not emit events for state changes, so ciphertexts are read with
+// req_state_var. A user can only decrypt the locations owned by them, which locations these are
+// depends on the contract, hence the locations have to be registered with watch/watch_mine.
+use crate::blockchain::abi;
+use crate::blockchain::web3rs::Web3Blockchain;
+use crate::interface::{
+    ZkayBlockchainInterface, ZkayCryptoInterface, ZkayKeystoreInterface, ZkayProverInterface,
+};
+use crate::runtime::Runtime;
+use crate::types::{CipherValue, Value};
+use alloy_primitives::{Address, U256};
+use rccell::RcCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use zkay_config::{config::CFG, zk_print};
+use zkay_transaction_crypto_params::params::CryptoParams;
+
+// Directory (relative to the contract output directory) which holds the index stores
+pub const STATE_INDEX_DIR: &str = "state_index";
+
+// Encrypted state location name[indices[0]][indices[1]]...
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StateLocation {
+    pub name: String,
+    pub indices: Vec<String>,
+}
+impl StateLocation {
+    pub fn new(name: &str, indices: &[&str]) -> Self {
+        Self {
+            name: name.to_owned(),
+            indices: indices.iter().map(|i| i.to_string()).collect(),
+        }
+    }
+
+    // Key of the location in the store, same notation as the StateDict keys
+    pub fn key(&self) -> String {
+        self.name.clone()
+            + &self
+                .indices
+                .iter()
+                .map(|k| format!("[{k}]"))
+                .collect::<Vec<_>>()
+                .concat()
+    }
+}
+
+// Decrypted value of a watched location together with the ciphertext it was decrypted from
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedEntry {
+    pub location: StateLocation,
+    pub crypto_backend: String,
+    pub cipher: Vec<String>,
+    // value as encoded in the circuit, i.e. two's complement for int types (see DataType::to_uint)
+    pub plain: U256,
+    // block in which this ciphertext was first seen by the indexer
+    pub block: u64,
+}
+
+// Contents of an index store
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedState {
+    pub contract: String,
+    pub user: String,
+    pub last_block: Option<u64>,
+    pub entries: BTreeMap<String, IndexedEntry>,
+}
+impl IndexedState {
+    pub fn load(path: &Path) -> eyre::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let s = std::fs::read_to_string(path)?;
+        serde_json::from_str(&s)
+            .map(Some)
+            .map_err(|e| eyre::eyre!("Corrupt state index {}: {e}", path.display()))
+    }
+
+    // Write to a temporary file first, an interrupted save must not destroy the store
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // # plaintexts of the user, only the owner may read them
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp)?;
+        // # mode only applies to new files, the temporary file may be left over from an interrupted save
+        #[cfg(unix)]
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+pub struct StateIndexer<
+    P: ZkayProverInterface + Clone,
+    B: ZkayBlockchainInterface<P> + Web3Blockchain + Clone,
+    K: ZkayKeystoreInterface<P, B> + Clone,
+> {
+    runtime: RcCell<Runtime<P, B, K>>,
+    contract: Address,
+    user_addr: String,
+    store_path: PathBuf,
+    watched: BTreeMap<String, (StateLocation, CryptoParams)>,
+    state: IndexedState,
+}
+impl<
+        P: ZkayProverInterface + Clone,
+        B: ZkayBlockchainInterface<P> + Web3Blockchain + Clone,
+        K: ZkayKeystoreInterface<P, B> + Clone,
+    > StateIndexer<P, B, K>
+{
+    // """
+    // Open (or create) the index of contract for user_addr.
+
+    // :param project_dir: output directory of the compiled contract, the store is kept in project_dir/state_index
+    // :param contract: address of the deployed contract
+    // :param user_addr: account whose keys are used for decryption
+    // """
+    pub fn open(
+        runtime: RcCell<Runtime<P, B, K>>,
+        project_dir: &Path,
+        contract: Address,
+        user_addr: &str,
+    ) -> eyre::Result<Self> {
+        let store_path = Self::store_path(project_dir, &contract, user_addr);
+        let state = IndexedState::load(&store_path)?.unwrap_or_else(|| IndexedState {
+            contract: contract.to_checksum(None),
+            user: user_addr.to_owned(),
+            ..Default::default()
+        });
+        eyre::ensure!(
+            state.contract == contract.to_checksum(None) && state.user == user_addr,
+            "State index {} belongs to {}@{}",
+            store_path.display(),
+            state.user,
+            state.contract
+        );
+        Ok(Self {
+            runtime,
+            contract,
+            user_addr: user_addr.to_owned(),
+            store_path,
+            watched: BTreeMap::new(),
+            state,
+        })
+    }
+
+    pub fn store_path(project_dir: &Path, contract: &Address, user_addr: &str) -> PathBuf {
+        project_dir
+            .join(STATE_INDEX_DIR)
+            .join(format!("{}_{user_addr}.json", contract.to_checksum(None)))
+    }
+
+    // Watch location name[indices...], which must be encrypted for the user (empty crypto_backend = main crypto backend)
+    pub fn watch(&mut self, name: &str, indices: &[&str], crypto_backend: &str) {
+        let crypto_backend = if crypto_backend.is_empty() {
+            CFG.lock().unwrap().main_crypto_backend()
+        } else {
+            crypto_backend.to_owned()
+        };
+        let location = StateLocation::new(name, indices);
+        self.watched.insert(
+            location.key(),
+            (location, CryptoParams::new(crypto_backend)),
+        );
+    }
+
+    // Watch name[user], the usual layout of values owned by an address (e.g. mapping(address!x => uint@x))
+    pub fn watch_mine(&mut self, name: &str, crypto_backend: &str) {
+        let user_addr = self.user_addr.clone();
+        self.watch(name, &[&user_addr], crypto_backend);
+    }
+
+    // """
+    // Bring the index up to date with the latest block.
+
+    // :return: number of watched locations whose value changed
+    // """
+    pub fn sync(&mut self) -> eyre::Result<usize> {
+        let head = self.runtime.borrow().blockchain().borrow().block_number()?;
+        let all_indexed = self
+            .watched
+            .keys()
+            .all(|key| self.state.entries.contains_key(key));
+        if self.state.last_block == Some(head) && all_indexed {
+            return Ok(0);
+        }
+
+        let mut changed = 0;
+        for (key, (location, crypto_params)) in &self.watched {
+            match self.index_location(key, location, crypto_params, head) {
+                Ok(Some(entry)) => {
+                    self.state.entries.insert(key.clone(), entry);
+                    changed += 1;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // # keep the locations decrypted so far, last_block stays behind so the next sync retries the rest
+                    self.state.save(&self.store_path)?;
+                    return Err(e);
+                }
+            }
+        }
+        self.state.last_block = Some(head);
+        self.state.save(&self.store_path)?;
+        Ok(changed)
+    }
+
+    // """
+    // Follow the chain, syncing every poll_interval.
+
+    // :param on_sync: called with the number of changed locations after every sync, following stops when it returns false
+    // """
+    pub fn follow(
+        &mut self,
+        poll_interval: Duration,
+        mut on_sync: impl FnMut(&Self, usize) -> bool,
+    ) -> eyre::Result<()> {
+        loop {
+            let changed = self.sync()?;
+            if !on_sync(self, changed) {
+                return Ok(());
+            }
+            std::thread::sleep(poll_interval);
+        }
+    }
+
+    // Decrypted value of name[indices...] as of the last sync
+    pub fn get(&self, name: &str, indices: &[&str]) -> Option<U256> {
+        self.entry(name, indices).map(|entry| entry.plain)
+    }
+
+    pub fn entry(&self, name: &str, indices: &[&str]) -> Option<&IndexedEntry> {
+        self.state
+            .entries
+            .get(&StateLocation::new(name, indices).key())
+    }
+
+    // All indexed locations of state variable name
+    pub fn entries<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a IndexedEntry> + 'a {
+        self.state
+            .entries
+            .values()
+            .filter(move |entry| entry.location.name == name)
+    }
+
+    pub fn last_block(&self) -> Option<u64> {
+        self.state.last_block
+    }
+
+    // New entry of location at block head, None if its ciphertext did not change since the last sync
+    fn index_location(
+        &self,
+        key: &str,
+        location: &StateLocation,
+        crypto_params: &CryptoParams,
+        head: u64,
+    ) -> eyre::Result<Option<IndexedEntry>> {
+        let cipher = self.read_cipher(location, crypto_params)?;
+        if self
+            .state
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.cipher == cipher)
+        {
+            return Ok(None);
+        }
+        let plain = self.decrypt(&cipher, crypto_params)?;
+        zk_print!("Indexed {key} = {plain} at block {head}");
+        Ok(Some(IndexedEntry {
+            location: location.clone(),
+            crypto_backend: crypto_params.crypto_name.clone(),
+            cipher,
+            plain,
+            block: head,
+        }))
+    }
+
+    // A ciphertext is stored as uint[cipher_len], its public getter takes the element index as last argument
+    fn read_cipher(
+        &self,
+        location: &StateLocation,
+        crypto_params: &CryptoParams,
+    ) -> eyre::Result<Vec<String>> {
+        let blockchain = self.runtime.borrow().blockchain();
+        let blockchain = blockchain.borrow();
+        (0..crypto_params.cipher_len())
+            .map(|i| {
+                let indices = location
+                    .indices
+                    .iter()
+                    .cloned()
+                    .chain([i.to_string()])
+                    .collect::<Vec<_>>()
+                    .join(",");
+                blockchain
+                    .req_state_var(&self.contract, &location.name, &indices)
+                    .map(|value| abi::to_text(&value))
+            })
+            .collect()
+    }
+
+    fn decrypt(&self, cipher: &[String], crypto_params: &CryptoParams) -> eyre::Result<U256> {
+        // # Ciphertext is all zeros, i.e. uninitialized -> zero
+        if cipher.iter().all(|c| {
+            c.trim_start_matches("0x")
+                .trim_start_matches('0')
+                .is_empty()
+        }) {
+            return Ok(U256::ZERO);
+        }
+        eyre::ensure!(
+            self.runtime
+                .borrow()
+                .keystore(crypto_params)?
+                .borrow()
+                .has_initialized_keys_for(&self.user_addr),
+            "No {} keys for {} in the keystore",
+            crypto_params.crypto_name,
+            self.user_addr
+        );
+        let crypto = self.runtime.borrow().crypto(crypto_params)?;
+        let (plain, _) = crypto.borrow().dec(
+            &Value::<String, CipherValue>::new(cipher.to_vec(), Some(crypto_params.clone()), None),
+            &self.user_addr,
+        );
+        Ok(plain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::evm::tests::{cipher_slot as slot, deploy_cipher_store};
+    use crate::blockchain::evm::EvmBlockchain;
+    use crate::keystore::simple::SimpleKeystore;
+    use crate::prover::jsnark::JsnarkProver;
+    use crate::runtime::_crypto_classes;
+    use crate::types::DataType;
+
+    type Chain = EvmBlockchain<JsnarkProver>;
+
+    fn set(
+        chain: &RcCell<Chain>,
+        contract: &Address,
+        sender: &str,
+        slot: String,
+        value: impl ToString,
+    ) {
+        chain
+            .borrow()
+            .transact(
+                contract,
+                sender,
+                "set",
+                vec![DataType::String(slot), DataType::String(value.to_string())],
+                vec![false, false],
+                None,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn location_key_matches_state_dict_notation() {
+        assert_eq!(StateLocation::new("total", &[]).key(), "total");
+        assert_eq!(
+            StateLocation::new("balance", &["0xab", "3"]).key(),
+            "balance[0xab][3]"
+        );
+    }
+
+    #[test]
+    fn indexed_state_roundtrip() {
+        let path = std::env::temp_dir()
+            .join(format!("zkay_state_index_{}", std::process::id()))
+            .join("index.json");
+        assert_eq!(IndexedState::load(&path).unwrap(), None);
+
+        let location = StateLocation::new("balance", &["0xab"]);
+        let state = IndexedState {
+            contract: "0x01".to_owned(),
+            user: "0xab".to_owned(),
+            last_block: Some(7),
+            entries: BTreeMap::from([(
+                location.key(),
+                IndexedEntry {
+                    location,
+                    crypto_backend: "elgamal".to_owned(),
+                    cipher: vec!["1".to_owned(), "2".to_owned()],
+                    plain: U256::from(42),
+                    block: 5,
+                },
+            )]),
+        };
+        state.save(&path).unwrap();
+        assert_eq!(IndexedState::load(&path).unwrap(), Some(state));
+        #[cfg(unix)]
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn sync_follows_evm_state() {
+        let chain = RcCell::new(Chain::new(RcCell::new(JsnarkProver), None));
+        let [sender, user] = [0, 1].map(|i| chain.borrow().accounts()[i].to_checksum(None));
+        let contract = deploy_cipher_store(&chain.borrow());
+
+        let crypto_params = CryptoParams::new("elgamal".to_owned());
+        let keystore = RcCell::new(SimpleKeystore::<JsnarkProver, Chain>::new(
+            chain.clone(),
+            crypto_params.clone(),
+        ));
+        let crypto = RcCell::new(_crypto_classes("elgamal", keystore.clone()).unwrap());
+        let runtime = RcCell::new(Runtime::new(
+            chain.clone(),
+            RcCell::new(BTreeMap::from([("elgamal".to_owned(), crypto.clone())])),
+            RcCell::new(BTreeMap::from([("elgamal".to_owned(), keystore.clone())])),
+            RcCell::new(JsnarkProver),
+        ));
+        let project_dir =
+            std::env::temp_dir().join(format!("zkay_state_index_sync_{}", std::process::id()));
+        let open = || StateIndexer::open(runtime.clone(), &project_dir, contract, &user).unwrap();
+        let mut indexer = open();
+        indexer.watch_mine("balance", "elgamal");
+        indexer.watch("balance", &[sender.as_str()], "elgamal");
+
+        // # uninitialized ciphertexts are zero, decrypting them needs no keys
+        assert_eq!(indexer.sync().unwrap(), 2);
+        assert_eq!(indexer.get("balance", &[user.as_str()]), Some(U256::ZERO));
+        assert_eq!(indexer.entries("balance").count(), 2);
+        assert_eq!(indexer.sync().unwrap(), 0);
+        let synced_block = indexer.last_block().unwrap();
+
+        // # new block, but the watched ciphertexts are unchanged
+        set(&chain, &contract, &sender, slot(&user, 1000), 1);
+        assert_eq!(indexer.sync().unwrap(), 0);
+        assert!(indexer.last_block().unwrap() > synced_block);
+        let synced_block = indexer.last_block().unwrap();
+
+        // # a changed ciphertext has to be decrypted with the keys of the user, which are not in the keystore
+        set(&chain, &contract, &sender, slot(&user, 0), 7);
+        let err = indexer.sync().unwrap_err();
+        assert!(err.to_string().contains("No elgamal keys"), "{err}");
+        assert_eq!(indexer.get("balance", &[user.as_str()]), Some(U256::ZERO));
+
+        // # a restarted indexer continues from the stored state
+        let mut indexer = open();
+        assert_eq!(indexer.last_block(), Some(synced_block));
+        assert_eq!(indexer.get("balance", &[sender.as_str()]), Some(U256::ZERO));
+
+        // # keys of the user, added locally without announcing them in the pki contract
+        let key_pair = crypto.borrow()._generate_or_load_key_pair(&user);
+        keystore
+            .borrow()
+            .local_pk_store()
+            .borrow_mut()
+            .insert(user.clone(), key_pair.pk.clone());
+        keystore
+            .borrow()
+            .local_key_pairs()
+            .borrow_mut()
+            .insert(user.clone(), key_pair);
+        let (cipher, _) = crypto.borrow().enc("42".to_owned(), &user, &user);
+        assert_eq!(cipher.contents.len(), crypto_params.cipher_len() as usize);
+        for (i, c) in cipher.contents.iter().enumerate() {
+            set(&chain, &contract, &sender, slot(&user, i as u64), c);
+        }
+        indexer.watch_mine("balance", "elgamal");
+        indexer.watch("balance", &[sender.as_str()], "elgamal");
+        // # locations are synced in key order, the missing getter fails after the user's balance was decrypted
+        indexer.watch("missing", &[], "elgamal");
+        assert!(indexer.sync().is_err());
+        assert_eq!(
+            indexer.get("balance", &[user.as_str()]),
+            Some(U256::from(42))
+        );
+        let indexed_block = indexer.entry("balance", &[user.as_str()]).unwrap().block;
+
+        // # the decrypted value was stored although the sync failed
+        let mut indexer = open();
+        assert_eq!(indexer.last_block(), Some(synced_block));
+        assert_eq!(
+            indexer.get("balance", &[user.as_str()]),
+            Some(U256::from(42))
+        );
+        indexer.watch_mine("balance", "elgamal");
+        indexer.watch("balance", &[sender.as_str()], "elgamal");
+        assert_eq!(indexer.sync().unwrap(), 0);
+        assert!(indexer.last_block().unwrap() > synced_block);
+
+        // # unchanged ciphertexts are not decrypted again in later blocks
+        set(&chain, &contract, &sender, slot(&user, 1000), 2);
+        assert_eq!(indexer.sync().unwrap(), 0);
+        let entry = indexer.entry("balance", &[user.as_str()]).unwrap();
+        assert_eq!(entry.plain, U256::from(42));
+        assert_eq!(entry.block, indexed_block);
+
+        std::fs::remove_dir_all(&project_dir).unwrap();
+    }
+}
@@ -68,12 +68,14 @@ use std::{borrow::Borrow, marker::PhantomData, path::PathBuf, sync::Arc};
 
 use crate::crypto::ecdh_chaskey::EcdhChaskeyCrypto;
 use crate::crypto::elgamal::ElgamalCrypto;
+use crate::deployments::Deployments;
 use crate::runtime::CryptoClass;
 use ark_ff::BigInteger256;
 use enum_dispatch::enum_dispatch;
 use path_absolutize::Absolutize;
 use privacy::library_contracts::BN128_SCALAR_FIELD;
 use privacy::library_contracts::BN128_SCALAR_FIELDS;
+use privacy::manifest::Manifest;
 use proving_scheme::proving_scheme::ProvingScheme;
 use rccell::RcCell;
 use std::collections::BTreeMap;
@@ -83,6 +85,7 @@ use zkay_transaction_crypto_params::params::CryptoParams;
 use zkp_u256::{Zero, U256};
 // use zkay_frontend::compile_zkay_file;
 // use crate::runtime::Runtime;
+use crate::blockchain::abi;
 use crate::blockchain::web3rs::Web3BlockchainBase;
 use crate::blockchain::web3rs::Web3HttpGanacheBlockchain;
 use crate::blockchain::web3rs::Web3TesterBlockchain;
@@ -93,9 +96,9 @@ use crate::types::{
 };
 use serde_json::{json, Map as JsonMap,  Value as JsonValue};
 use zkay_config::{
-    config::{zk_print_banner, CFG},
+    config::{library_compilation_environment, zk_print_banner, CFG},
     config_user::UserConfig,
-    zk_print,
+    with_context_block, zk_print,
 };
 use zkay_utils::progress_printer::success_print;
 use zkay_utils::timer::time_measure;
@@ -113,6 +116,84 @@ use zkay_utils::timer::time_measure;
 //     """Exception which is raised when a transaction fails."""
 //     pass
 
+// """
+// Errors returned by the runtime API (and the generated offchain simulators).
+
+// A require which fails during simulation is returned as RequireFailed (RequireException in the python runtime)
+// before any proof is generated or transaction is issued, so callers can recover from it.
+// StaleState is returned when a transaction is (or would be) rejected because the on-chain private state its proof
+// was generated for has been modified by another transaction in the meantime.
+// The transaction can be re-simulated and re-proven against the current state.
+// """
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ZkayRuntimeError {
+    #[error("{0}")]
+    RequireFailed(String),
+    #[error("Proof generation failed: {0}")]
+    ProofGenerationFailed(String),
+    #[error("Decryption failed: {0}")]
+    DecryptionFailed(String),
+    #[error("Unknown {kind} backend \"{name}\"")]
+    UnknownBackend { kind: String, name: String },
+    #[error("{0}")]
+    BlockchainError(String),
+    #[error("Integrity check failed: {0}")]
+    IntegrityError(String),
+    // a value does not fit the declared (u)int type or the comparison range of the circuit
+    #[error("Value out of range: {0}")]
+    ValueOutOfRange(String),
+    // a value does not match the abi type of the contract function parameter it is passed for
+    #[error("Argument {param} does not match abi type {expected}: {found}")]
+    AbiMismatch {
+        param: String,
+        expected: String,
+        found: String,
+    },
+    // a transaction was mined but reverted, reason is the decoded revert string (or the hex encoded return data)
+    #[error("Transaction failed: {function} reverted with {reason}")]
+    TransactionReverted { function: String, reason: String },
+    // the worker of a runtime session (see service.rs) has stopped
+    #[error("Session of {0} is closed")]
+    SessionClosed(String),
+    #[error(
+        "Proof for {function} was generated for outdated state ({} modified by another transaction)",
+        locations.join(", ")
+    )]
+    StaleState {
+        function: String,
+        // state locations (name[idx]...) whose value changed since they were read
+        locations: Vec<String>,
+    },
+}
+impl ZkayRuntimeError {
+    pub fn unknown_backend(kind: &str, name: &str) -> Self {
+        Self::UnknownBackend {
+            kind: kind.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+}
+// Backends report errors as eyre reports, runtime errors raised inside a backend are passed through unchanged
+impl From<eyre::Report> for ZkayRuntimeError {
+    fn from(err: eyre::Report) -> Self {
+        err.downcast::<ZkayRuntimeError>()
+            .unwrap_or_else(|err| Self::BlockchainError(format!("{err:#}")))
+    }
+}
+
+// Revert reasons of verification contracts which reject a proof (gm17 checks the proof in two steps)
+pub const INVALID_PROOF_REVERT_REASONS: [&str; 3] =
+    ["invalid proof", "invalid proof 1/2", "invalid proof 2/2"];
+
+// Whether err is a transaction which was reverted by the verification contract because it rejected the proof
+pub fn is_invalid_proof_revert(err: &eyre::Report) -> bool {
+    matches!(
+        err.downcast_ref::<ZkayRuntimeError>(),
+        Some(ZkayRuntimeError::TransactionReverted { reason, .. })
+            if INVALID_PROOF_REVERT_REASONS.contains(&reason.as_str())
+    )
+}
+
 // class ProofGenerationError(Exception){
 //     """Exception which is raised when proof generation fails."""
 //     pass
@@ -141,21 +222,25 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
     //         self._pki_contract = None
     //         self._lib_addresses = None
     fn _pki_contract(&self) -> RcCell<Option<BTreeMap<String, Address>>>;
-    fn pki_contract(&self, crypto_backend: &str) -> Address {
+    fn pki_contract(&self, crypto_backend: &str) -> eyre::Result<Address> {
         if self._pki_contract().borrow().is_none() {
-            let _=self._connect_libraries();
+            self._connect_libraries()?;
         }
         self._pki_contract()
             .borrow()
             .as_ref()
-            .unwrap()
-            .get(crypto_backend)
-            .unwrap()
-            .clone()
+            .and_then(|pki_contract| pki_contract.get(crypto_backend).copied())
+            .ok_or_else(|| ZkayRuntimeError::unknown_backend("crypto", crypto_backend).into())
     }
 
+    fn _lib_addresses(&self) -> RcCell<Option<BTreeMap<String, Address>>>;
     //     @property
-    fn lib_addresses(&self) -> RcCell<Option<BTreeMap<String, Address>>>;
+    fn lib_addresses(&self) -> eyre::Result<RcCell<Option<BTreeMap<String, Address>>>> {
+        if self._lib_addresses().borrow().is_none() {
+            self._connect_libraries()?;
+        }
+        Ok(self._lib_addresses())
+    }
     // if self._lib_addresses is None:
     //     self._connect_libraries()
     // return self._lib_addresses
@@ -166,6 +251,10 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
     ) -> eyre::Result<BTreeMap<String, Address>>;
     //         pass
 
+    // Use the pki and library contracts recorded in deployments instead of connecting to (or deploying) new ones,
+    // returns false if the registry does not contain usable ones
+    fn _reuse_libraries(&self, deployments: &Deployments) -> eyre::Result<bool>;
+
     //     # PUBLIC API
 
     //     @property
@@ -177,7 +266,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
     //     None
     // }
     //         """
-    //         Return addresses of pre-funded accounts (only implemented for w3-eth-tester and w3-ganache, for debugging).
+    //         Return addresses of pre-funded accounts (only implemented for w3-eth-tester, w3-ganache and w3-evm, for debugging).
 
     //         :param count: how many accounts
     //         :raise NotImplementedError: if the backend does not support dummy accounts
@@ -214,6 +303,12 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         0
     }
 
+    // """Return the number of the most recent block."""
+    fn block_number(&self) -> eyre::Result<u64>;
+
+    // """Return the chain id of the network, which identifies its deployment registry."""
+    fn chain_id(&self) -> eyre::Result<u64>;
+
     //         """
     //         Request the public key for the designated address from the PKI contract.
 
@@ -247,7 +342,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         sender: &str,
         pk: &Value<String, PublicKeyValue>,
         crypto_params: &CryptoParams,
-    ) {
+    ) -> eyre::Result<()> {
         //         assert isinstance(sender, AddressValue)
         //         assert isinstance(pk, PublicKeyValue)
         zk_print!(r#"Announcing public key "{pk:?}" for address "{sender}""#);
@@ -259,17 +354,22 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
 
     //         :param contract_handle: contract from which to read state
     //         :param name: name of the state variable
-    //         :param indices: if the request is for an (nested) array/map index value, the values of all index keys.
+    //         :param indices: if the request is for an (nested) array/map index value, the values of all index keys (comma separated).
     //         :raise BlockChainError: if request fails
     //         :return: The value
     //         """
-    fn req_state_var(&self, contract_handle: &Address, name: &str, indices: &String) -> String {
+    fn req_state_var(
+        &self,
+        contract_handle: &Address,
+        name: &str,
+        indices: &String,
+    ) -> eyre::Result<DynSolValue> {
         //bool, int, str, bytes
         //         assert contract_handle is not None
         zk_print!(r#"Requesting state variable "{name}""#);
-        let val = self._req_state_var(contract_handle, name, indices);
-        zk_print!(r#"Got value {val} for state variable "{name}""#);
-        val
+        let val = self._req_state_var(contract_handle, name, indices)?;
+        zk_print!(r#"Got value {val:?} for state variable "{name}""#);
+        Ok(val)
     }
     //         """
     //         Call the specified pure/view function in the given contract with the provided arguments.
@@ -279,7 +379,8 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
     //         :param name: name of the function to call
     //         :param args: argument values
     //         :raise BlockChainError: if request fails
-    //         :return: function return value (single value if one return value, list if multiple return values)
+    //         :raise RequireFailed: if the function reverts
+    //         :return: function return values, one per output
     //         """
     fn call(
         &self,
@@ -287,13 +388,13 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         sender: &String,
         name: &str,
         args: Vec<DataType>,
-    ) -> String {
+    ) -> eyre::Result<Vec<DynSolValue>> {
         //-> Union[bool, int, str, bytes, List]:
         //         assert contract_handle is not None
         zk_print!("Calling contract function {name}{:?}", args);
-        let val = self._call(contract_handle, sender, name, &args);
-        zk_print!("Got return value {val}");
-        val
+        let val = self._call(contract_handle, sender, name, &args)?;
+        zk_print!("Got return value {val:?}");
+        Ok(val)
     }
 
     //         """
@@ -320,7 +421,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         actual_args: Vec<DataType>,
         should_encrypt: Vec<bool>,
         wei_amount: Option<i32>,
-    ) {
+    ) -> eyre::Result<()> {
         //         assert contract_handle is not None
         self.__check_args(actual_args.clone(), should_encrypt);
         zk_print!(r#"Issuing transaction for function "{function}" from account "{sender}""#);
@@ -329,6 +430,20 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         zk_print!("");
         ret
     }
+    // Gas a transaction for function with actual_args would consume, nothing is sent
+    // :raise BlockChainError: if the transaction would fail
+    fn estimate_gas(
+        &self,
+        contract_handle: &Address,
+        sender: &str,
+        function: &str,
+        actual_args: Vec<DataType>,
+        should_encrypt: Vec<bool>,
+        wei_amount: Option<i32>,
+    ) -> eyre::Result<u64> {
+        self.__check_args(actual_args.clone(), should_encrypt);
+        self._estimate_gas(contract_handle, sender, function, &actual_args, wei_amount)
+    }
     // Issue a deployment transaction which constructs the specified contract with the provided constructor arguments on the chain.
 
     // **WARNING: THIS ISSUES A CRYPTO CURRENCY TRANSACTION (GAS COST)**
@@ -354,7 +469,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         project:&Project,
     ) -> eyre::Result<Address> {
         if !self.is_debug_backend() && CFG.lock().unwrap().crypto_backend() == "dummy" {
-            eyre::bail!("SECURITY ERROR: Dummy encryption can only be used with debug blockchain backends (w3-eth-tester or w3-ganache).")
+            eyre::bail!("SECURITY ERROR: Dummy encryption can only be used with debug blockchain backends (w3-eth-tester, w3-ganache or w3-evm).")
         }
         zk_print_banner(format!("Deploy {contract}"));
 
@@ -365,11 +480,63 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
                 .collect(),
             should_encrypt,
         );
+        // # Reuse the pki and library contracts of earlier deployments on this network,
+        // # unless the ones to use are configured explicitly
+        let mut deployments = Deployments::load(project_dir, self.chain_id()?)?;
+        let libraries_connected = self._pki_contract().borrow().is_some();
+        let libraries_configured = {
+            let cfg = CFG.lock().unwrap();
+            !cfg.blockchain_pki_address().is_empty()
+                || !cfg.blockchain_crypto_lib_addresses().is_empty()
+        };
+        if !libraries_connected && !libraries_configured {
+            let registry = Deployments::path(project_dir, deployments.chain_id);
+            match self._reuse_libraries(&deployments) {
+                Ok(true) => {
+                    zk_print!(
+                        "Using pki and library contracts from {}",
+                        registry.display()
+                    );
+                }
+                Ok(false) => {}
+                // # e.g. the local chain was reset, new contracts are deployed and replace the recorded ones
+                Err(e) => {
+                    zk_print!(
+                        "Recorded pki and library contracts in {} are not usable, deploying new ones: {e:#}",
+                        registry.display()
+                    );
+                    *self._pki_contract().borrow_mut() = None;
+                    *self._lib_addresses().borrow_mut() = None;
+                }
+            }
+        }
         zk_print!("Deploying contract {contract}{:?}", actual_args); //
-        let _ret = self._deploy(project_dir, sender, contract, actual_args, wei_amount,project);
+        let (address, external_contract_addresses) =
+            self._deploy(project_dir, sender, contract, actual_args, wei_amount, project)?;
         zk_print!("");
-        // Ok(json!({}))
-        _ret
+
+        // # Record the deployment, the external contracts other than the pki contracts are verifiers
+        let pki_contract = self._pki_contract().borrow().clone().unwrap_or_default();
+        let pki_names: Vec<_> = pki_contract
+            .keys()
+            .map(|crypto_backend| {
+                CFG.lock()
+                    .unwrap()
+                    .get_pki_contract_name(&CryptoParams::new(crypto_backend.clone()).identifier_name())
+            })
+            .collect();
+        deployments.verifiers.extend(
+            external_contract_addresses
+                .into_iter()
+                .filter(|(name, _)| !pki_names.contains(name)),
+        );
+        deployments.pki.extend(pki_contract);
+        deployments
+            .libraries
+            .extend(self._lib_addresses().borrow().clone().unwrap_or_default());
+        deployments.contracts.insert(contract.to_owned(), address);
+        deployments.save(project_dir)?;
+        Ok(address)
     }
 
     //         """
@@ -405,7 +572,8 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
 
     //         :param project_dir: directory where the zkay file, manifest and snark keys reside
     //         :param contract: name of the contract to connect to
-    //         :param contract_address: address of the deployed contract
+    //         :param contract_address: address of the deployed contract, \
+    //                                  if None the address recorded in the deployment registry of the network for contract
     //         :param user_address: account which connects to the contract
     //         :raise IntegrityError: if the integrity check fails (mismatch between local code and remote contract)
     //         :return: contract handle for the specified contract
@@ -414,42 +582,57 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         &self,
         project_dir: &PathBuf,
         contract: &str,
-        contract_address: &Address,
+        contract_address: Option<&Address>,
         user_address: String,
         compile_zkay_file: fn(
             input_file_path: &str,
             output_dir: &str,
             import_keys: bool,
         ) -> anyhow::Result<()>,
-        get_verification_contract_names: fn(code_or_ast: String) -> Vec<String>,
+        get_verification_contract_names: fn(code_or_ast: String) -> eyre::Result<Vec<String>>,
         project:&Project,
     ) -> eyre::Result<Address> {
-        assert!( self.is_debug_backend() || CFG.lock().unwrap().crypto_backend() != "dummy","SECURITY ERROR: Dummy encryption can only be used with debug blockchain backends (w3-eth-tester or w3-ganache).{},{}",self.is_debug_backend(),CFG.lock().unwrap().crypto_backend());
+        if !self.is_debug_backend() && CFG.lock().unwrap().crypto_backend() == "dummy" {
+            eyre::bail!("SECURITY ERROR: Dummy encryption can only be used with debug blockchain backends (w3-eth-tester, w3-ganache or w3-evm).")
+        }
 
+        let contract_address = &match contract_address {
+            Some(address) => *address,
+            None => Deployments::load(project_dir, self.chain_id()?)?.contract_address(contract)?,
+        };
         zk_print_banner(format!("Connect to {contract}@{contract_address}"));
 
+        // # Compile with the solc version and settings the contract was originally compiled with
+        let _manifest_config = Manifest::with_manifest_config(Manifest::load(
+            &project_dir.to_string_lossy(),
+        ));
+
         // If not already done, compile zkay file to generate main and verification contracts (but don"t generate new prover/verification keys and manifest)
         let zk_file = PathBuf::from(project_dir).join("contract.zkay");
-        assert!(
-            zk_file.try_exists().is_ok(),
+        eyre::ensure!(
+            zk_file.is_file(),
             "No zkay contract found in specified directory"
         );
-        println!("zk_file===={zk_file:?}");
-        let mut verifier_names = vec![];
-        if PathBuf::from(project_dir)
-            .join("contract.sol")
-            .try_exists()
-            .is_ok()
-        {
-            verifier_names =
-                get_verification_contract_names(std::fs::read_to_string(zk_file).unwrap());
-        } else {
-            let _ = compile_zkay_file(&zk_file.to_string_lossy().to_string(), &project_dir.to_string_lossy().to_string(), true);
+        if !PathBuf::from(project_dir).join("contract.sol").is_file() {
+            compile_zkay_file(
+                &zk_file.to_string_lossy(),
+                &project_dir.to_string_lossy(),
+                true,
+            )
+            .map_err(|e| eyre::eyre!("Could not compile {}: {e:#}", zk_file.display()))?;
         }
+        let verifier_names = get_verification_contract_names(std::fs::read_to_string(&zk_file)?)?;
 
         zk_print!("Connecting to contract {contract}@{contract_address}");
-        let _contract_on_chain = self._connect(&project_dir.to_string_lossy().to_string(), contract, contract_address.clone(),project);
-        println!("==========");
+        self._connect(
+            &project_dir.to_string_lossy().to_string(),
+            contract,
+            contract_address.clone(),
+            project,
+        )?;
+        // # Mismatches (or contracts which cannot be verified at all) are reported as IntegrityError
+        let integrity_error =
+            |e: eyre::Report| eyre::Report::new(ZkayRuntimeError::IntegrityError(format!("{e:#}")));
         let mut pki_verifier_addresses = BTreeMap::new();
 
         // # Check integrity of all pki contracts
@@ -460,22 +643,24 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
                 .lock()
                 .unwrap()
                 .get_pki_contract_name(&CryptoParams::new(crypto_params.clone()).identifier_name());
-            let mut pki_address = self._req_state_var::<Address>(
-                &Address::from_str("contract_on_chain.as_ref().unwrap()").unwrap(),
+            let pki_address = abi::address_from_dyn_sol_value(&self._req_state_var(
+                contract_address,
                 &format!("{contract_name}_inst"),
                 &String::default(),
-            );
+            )?)?;
             pki_verifier_addresses.insert(contract_name.clone(), pki_address.clone());
-            // with cfg.library_compilation_environment(){
-            let contract = self._verify_contract_integrity(
-                &pki_address,
-                &PathBuf::from(project_dir).join(format!("{contract_name}.sol")),
-                None,
-                None,
-                false,
-                None,
-                project,
-            )?;
+            let contract;
+            with_context_block!(var _lce= library_compilation_environment()=>{
+                contract = self._verify_contract_integrity(
+                    &pki_address,
+                    &PathBuf::from(project_dir).join(format!("{contract_name}.sol")),
+                    None,
+                    None,
+                    false,
+                    None,
+                    project,
+                ).map_err(integrity_error)?
+            });
             _pki_contract.insert(
                 CryptoParams::new(crypto_params.clone()).crypto_name,
                 contract,
@@ -499,42 +684,42 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
                     )
                 })
                 .collect();
-            let some_vcontract = self._req_state_var(
-                &Address::from_str("contract_on_chain.as_ref().unwrap()").unwrap(),
+            let some_vcontract = abi::to_text(&self._req_state_var(
+                contract_address,
                 &format!("{some_vname}_inst"),
                 &String::default(),
-            );
+            )?);
             let mut libs = self._verify_library_integrity(
                 libraries,
                 &some_vcontract,
                 &PathBuf::from(project_dir).join(format!("{some_vname}.sol")),
                 project,
-            )?;
-            *self.lib_addresses().borrow_mut() = Some(libs.clone());
+            ).map_err(integrity_error)?;
+            *self._lib_addresses().borrow_mut() = Some(libs.clone());
 
             for verifier in verifier_names {
-                let v_address = self._req_state_var::<Address>(
-                    &Address::from_str("contract_on_chain.as_ref().unwrap()").unwrap(),
+                let v_address = abi::address_from_dyn_sol_value(&self._req_state_var(
+                    contract_address,
                     &format!("{verifier}_inst"),
                     &String::default(),
-                );
+                )?)?;
                 pki_verifier_addresses.insert(verifier.clone(), v_address.clone());
                 let vcontract = self._verify_contract_integrity(
                     &v_address,
                     &PathBuf::from(project_dir).join(format!("{verifier}.sol")),
-                    self.lib_addresses().borrow().as_ref(),
+                    self._lib_addresses().borrow().as_ref(),
                     None,
                     false,
                     None,
                     project,
-                )?;
+                ).map_err(integrity_error)?;
 
                 // # Verify prover key
-                let expected_hash = self._req_state_var::<String>(
+                let expected_hash = abi::to_text(&self._req_state_var(
                     &vcontract,
                     &CFG.lock().unwrap().prover_key_hash_name(),
                     &String::default(),
-                );
+                )?);
                 // from zkay.transaction.runtime import Runtime
                 let actual_hash = self.prover().borrow().get_prover_key_hash(
                     &PathBuf::from(project_dir)
@@ -546,9 +731,11 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
                         .to_string_lossy()
                         .to_string(),
                 );
-                assert!(
-                    expected_hash.into_bytes() == actual_hash,
-                    r#"Prover key hash in deployed verification contract does not match local prover key file for "{verifier}""#
+                eyre::ensure!(
+                    expected_hash.trim_start_matches("0x").to_lowercase() == hex::encode(actual_hash),
+                    ZkayRuntimeError::IntegrityError(format!(
+                        r#"Prover key hash in deployed verification contract does not match local prover key file for "{verifier}""#
+                    ))
                 );
             }
         }
@@ -558,7 +745,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
             project_dir,
             &pki_verifier_addresses,
             project,
-        );
+        ).map_err(integrity_error)?;
 
         // with success_print(){
         zk_print!("OK: Bytecode on blockchain matches local zkay contract");
@@ -648,7 +835,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         project_dir: &PathBuf,
         pki_verifier_addresses: &BTreeMap<String, Address>,
         project:&Project,
-    );
+    ) -> eyre::Result<()>;
     //         pass
 
     //     @abstractmethod
@@ -656,7 +843,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
     //         pass
 
     //     @abstractmethod
-    fn _get_balance(&self, address: &str) -> i32;
+    fn _get_balance(&self, address: &str) -> eyre::Result<i32>;
     //         pass
 
     //     @abstractmethod
@@ -684,7 +871,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         address: &str,
         pk: &Value<String, PublicKeyValue>,
         crypto_params: &CryptoParams,
-    );
+    ) -> eyre::Result<()>;
     //         pass
 
     //     @abstractmethod
@@ -694,16 +881,16 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         sender: &String,
         name: &str,
         args: &Vec<DataType>,
-    ) -> String;
+    ) -> eyre::Result<Vec<DynSolValue>>;
     //         pass
 
     //     @abstractmethod
-    fn _req_state_var<R: Clone + Default>(
+    fn _req_state_var(
         &self,
         contract_handle: &Address,
         name: &str,
         indices: &String,
-    ) -> R;
+    ) -> eyre::Result<DynSolValue>;
     //         pass
 
     //     @abstractmethod
@@ -714,10 +901,20 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         function: &str,
         actual_args: &Vec<DataType>,
         wei_amount: Option<i32>,
-    );
+    ) -> eyre::Result<()>;
     // pass
 
+    fn _estimate_gas(
+        &self,
+        contract_handle: &Address,
+        sender: &str,
+        function: &str,
+        actual_args: &Vec<DataType>,
+        wei_amount: Option<i32>,
+    ) -> eyre::Result<u64>;
+
     //     @abstractmethod
+    // Returns the address of the contract and of the external contracts it was linked against
     fn _deploy(
         &self,
         project_dir: &PathBuf,
@@ -726,7 +923,7 @@ pub trait ZkayBlockchainInterface<P: ZkayProverInterface> {
         actual_args: Vec<String>,
         wei_amount: Option<i32>,
         project: &Project,
-    ) -> eyre::Result<Address>;
+    ) -> eyre::Result<(Address, BTreeMap<String, Address>)>;
     //         pass
 
     //     @abstractmethod
@@ -769,7 +966,7 @@ pub trait ZkayKeystoreInterface<P: ZkayProverInterface, B: ZkayBlockchainInterfa
     //         :param key_pair: cryptographic keys
     //         :raise TransactionFailedException: if announcement transaction fails
     //         """
-    fn add_keypair(&mut self, address: &str, key_pair: KeyPair) {
+    fn add_keypair(&mut self, address: &str, key_pair: KeyPair) -> eyre::Result<()> {
         self.local_key_pairs()
             .borrow_mut()
             .insert(address.to_owned(), key_pair.clone());
@@ -785,8 +982,9 @@ pub trait ZkayKeystoreInterface<P: ZkayProverInterface, B: ZkayBlockchainInterfa
             //         except BlockChainError:
             self.conn()
                 .borrow()
-                .announce_public_key(address, &key_pair.pk, &crypto_params);
+                .announce_public_key(address, &key_pair.pk, &crypto_params)?;
         }
+        Ok(())
     }
     //         """Return true if keys for address are already in the store."""
     fn has_initialized_keys_for(&self, address: &String) -> bool {
@@ -884,9 +1082,9 @@ pub trait ZkayCryptoInterface<
 
     //         :param address: the address for which to generate keys
     //         """
-    fn generate_or_load_key_pair(&self, address: &str) {
+    fn generate_or_load_key_pair(&self, address: &str) -> eyre::Result<()> {
         let v = self._generate_or_load_key_pair(&address.to_owned());
-        self.keystore().borrow_mut().add_keypair(address, v) ;
+        self.keystore().borrow_mut().add_keypair(address, v)
     }
 
     //         """
@@ -962,7 +1160,7 @@ pub trait ZkayCryptoInterface<
         &self,
         cipher: &Value<String, CipherValue>,
         my_addr: &String,
-    ) -> (u64, Option<Value<String, RandomnessValue>>) {
+    ) -> (alloy_primitives::U256, Option<Value<String, RandomnessValue>>) {
         // assert isinstance(cipher, CipherValue), f"Tried to decrypt value of type {type(cipher).__name__}"
         // assert isinstance(my_addr, AddressValue)
         zk_print!("Decrypting value {:?} for {my_addr}", cipher.contents); //, verbosity_level=2
@@ -975,7 +1173,7 @@ pub trait ZkayCryptoInterface<
         if cipher == &default_cipher {
             // # Ciphertext is all zeros, i.e. uninitialized -> zero
             return (
-                0,
+                alloy_primitives::U256::ZERO,
                 if self.params().is_symmetric_cipher() {
                     None
                 } else {
@@ -1067,7 +1265,8 @@ pub trait ZkayCryptoInterface<
     //         pass
 
     //     @abstractmethod
-    fn _dec(&self, cipher: Vec<String>, sk: &String) -> (u64, Vec<String>);
+    // # The plaintext is the unsigned circuit representation, i.e. negative values in two's complement
+    fn _dec(&self, cipher: Vec<String>, sk: &String) -> (alloy_primitives::U256, Vec<String>);
     //         pass
 }
 // class ZkayHomomorphicCryptoInterface(ZkayCryptoInterface){
@@ -1122,7 +1321,7 @@ pub trait ZkayProverInterface {
         mut priv_values: Vec<String>,
         in_vals: Vec<String>,
         out_vals: Vec<String>,
-    ) -> Vec<String> {
+    ) -> Result<Vec<String>, ZkayRuntimeError> {
         for i in 0..priv_values.len() {
             let arg = priv_values[i].clone();
             // assert not isinstance(arg, JsonValue) or isinstance(arg, (RandomnessValue, AddressValue))
@@ -1141,14 +1340,16 @@ pub trait ZkayProverInterface {
         let (priv_values, in_vals, out_vals) = (priv_values, in_vals, out_vals);
 
         // # Check for overflows
-        assert!(
-            priv_values
-                .iter()
-                .chain(&in_vals)
-                .chain(&out_vals)
-                .all(|arg| U256::from_decimal_str(arg).unwrap() < *BN128_SCALAR_FIELDS),
-            "argument overflow"
-        );
+        if !priv_values
+            .iter()
+            .chain(&in_vals)
+            .chain(&out_vals)
+            .all(|arg| U256::from_decimal_str(arg).map_or(false, |arg| arg < *BN128_SCALAR_FIELDS))
+        {
+            return Err(ZkayRuntimeError::ProofGenerationFailed(
+                "argument overflow".to_owned(),
+            ));
+        }
 
         // with time_measure(f"generate_proof", True){
         let verify_dir = CFG.lock().unwrap().get_circuit_output_dir_name(
@@ -1156,17 +1357,12 @@ pub trait ZkayProverInterface {
                 .unwrap()
                 .get_verification_contract_name(contract, function),
         );
-        let proof = self._generate_proof(
-            &PathBuf::from(project_dir)
-                .join(verify_dir)
-                .absolutize()
-                .unwrap()
-                .to_path_buf(),
-            priv_values,
-            in_vals,
-            out_vals,
-        );
-        proof
+        let verify_dir = PathBuf::from(project_dir)
+            .join(verify_dir)
+            .absolutize()
+            .map_err(|e| ZkayRuntimeError::ProofGenerationFailed(e.to_string()))?
+            .to_path_buf();
+        self._generate_proof(&verify_dir, priv_values, in_vals, out_vals)
     }
     //     @abstractmethod
     fn _generate_proof(
@@ -1175,7 +1371,7 @@ pub trait ZkayProverInterface {
         priv_values: Vec<String>,
         in_vals: Vec<String>,
         out_vals: Vec<String>,
-    ) -> Vec<String>;
+    ) -> Result<Vec<String>, ZkayRuntimeError>;
     //         pass
 
     //     @abstractmethod
@@ -1183,3 +1379,68 @@ pub trait ZkayProverInterface {
     fn get_prover_key_hash(&self, verifier_directory: &str) -> Vec<u8>;
     //         pass
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reverted(reason: &str) -> eyre::Report {
+        ZkayRuntimeError::TransactionReverted {
+            function: "vote".to_owned(),
+            reason: reason.to_owned(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn invalid_proof_revert_matches_reason_exactly() {
+        for reason in INVALID_PROOF_REVERT_REASONS {
+            assert!(is_invalid_proof_revert(&reverted(reason)), "{reason}");
+        }
+        assert!(!is_invalid_proof_revert(&reverted("not an invalid proof")));
+        assert!(!is_invalid_proof_revert(&reverted("Invalid proof")));
+        assert!(!is_invalid_proof_revert(&eyre::eyre!("invalid proof")));
+        assert!(!is_invalid_proof_revert(
+            &ZkayRuntimeError::RequireFailed("invalid proof".to_owned()).into()
+        ));
+    }
+
+    #[test]
+    fn runtime_errors_pass_through_reports() {
+        let require = ZkayRuntimeError::RequireFailed("require(x > 0) failed".to_owned());
+        assert_eq!(
+            ZkayRuntimeError::from(eyre::Report::new(require.clone())),
+            require
+        );
+        assert_eq!(
+            ZkayRuntimeError::from(
+                eyre::Report::new(require.clone()).wrap_err("Could not read state variable x")
+            ),
+            require
+        );
+        assert_eq!(
+            ZkayRuntimeError::from(eyre::eyre!("connection refused")),
+            ZkayRuntimeError::BlockchainError("connection refused".to_owned())
+        );
+    }
+
+    #[test]
+    fn runtime_error_messages() {
+        assert_eq!(
+            ZkayRuntimeError::unknown_backend("crypto", "rsa").to_string(),
+            r#"Unknown crypto backend "rsa""#
+        );
+        assert_eq!(
+            ZkayRuntimeError::StaleState {
+                function: "vote".to_owned(),
+                locations: vec!["a_count".to_owned(), "votes[0xab]".to_owned()],
+            }
+            .to_string(),
+            "Proof for vote was generated for outdated state (a_count, votes[0xab] modified by another transaction)"
+        );
+        assert_eq!(
+            reverted("invalid proof").to_string(),
+            "Transaction failed: vote reverted with invalid proof"
+        );
+    }
+}
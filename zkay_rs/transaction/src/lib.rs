@@ -4,9 +4,12 @@
 // ==========
 // Submodules
 // ==========
+// * :py:mod:`.deployments`: Registry of the contracts deployed on each network
+// * :py:mod:`.indexer`: Local decrypted view of a contract's encrypted state
 // * :py:mod:`.interface`: Runtime API interface
 // * :py:mod:`.offchain`: Offchain simulator base class with common functionality
 // * :py:mod:`.runtime`: Static class which provides access to the individual API backend singletons.
+// * :py:mod:`.service`: Thread-safe, async sessions for services which serve many users.
 // * :py:mod:`.types`: Type wrapper classes (for safer API interactions) used by the Runtime API.
 
 // ===========
@@ -20,11 +23,14 @@
 
 pub mod blockchain;
 pub mod crypto;
+pub mod deployments;
+pub mod indexer;
 pub mod int_casts;
 pub mod interface;
 pub mod keystore;
 pub mod offchain;
 pub mod prover;
 pub mod runtime;
+pub mod service;
 pub mod solidity_math;
 pub mod types;
@@ -12,7 +12,8 @@
 // from enum import IntEnum
 // from typing import Dict, Union, CallableType, Any, Optional, List, Tuple, ContextManager
  use alloy_json_abi::JsonAbi;
-use alloy_primitives::Address;
+use alloy_dyn_abi::DynSolValue;
+use alloy_primitives::{uint, Address, U256};
 use std::path::PathBuf;
 use foundry_compilers::Project;
 use foundry_cli::opts::{RpcOpts,EthereumOpts};
@@ -34,14 +35,16 @@ use std::collections::BTreeMap;
 use zkay_config::config_user::UserConfig;
 use zkay_config::{
     config::{zk_print_banner, CFG},
-    with_context_block,
+    with_context_block, zk_print,
 };
 use zkay_transaction_crypto_params::params::CryptoParams;
 // use zkay::transaction::int_casts :: __convert as int_cast;
+use crate::blockchain::abi::{self, cipher_from_dyn_sol_value};
 use crate::blockchain::web3rs::Web3Blockchain;
+use crate::indexer::StateIndexer;
 use crate::interface::{
-    ZkayBlockchainInterface, ZkayCryptoInterface, ZkayHomomorphicCryptoInterface,
-    ZkayKeystoreInterface, ZkayProverInterface,
+    is_invalid_proof_revert, ZkayBlockchainInterface, ZkayCryptoInterface,
+    ZkayHomomorphicCryptoInterface, ZkayKeystoreInterface, ZkayProverInterface, ZkayRuntimeError,
 };
 use crate::runtime::Runtime;
 use crate::types::{
@@ -52,7 +55,11 @@ use ark_ff::{BigInteger, BigInteger256, Field, MontFp, PrimeField};
 use zkay_ast::homomorphism::Homomorphism;
 use zkay_utils::progress_printer::fail_print;
 // bn128_scalar_field = bn128_scalar_field
-const _BN128_COMP_SCALAR_FIELD: BigInteger256 = BigInteger256::one();
+const _BN128_SCALAR_FIELD: U256 = uint!(
+    21888242871839275222246405745257275088548364400416034343698204186575808495617_U256
+);
+// 1 << 252, magnitude bound for values which are compared in the circuit
+const _BN128_COMP_SCALAR_FIELD: U256 = U256::from_limbs([0, 0, 0, 1 << 60]);
 type CallableType = fn(String) -> DataType; //: Clone + Default + std::iter::FromIterator<T>
 
 use std::ops::{Index, IndexMut};
@@ -60,6 +67,15 @@ use std::ops::{Index, IndexMut};
 // class RequireException(Exception):
 //     pass
 
+// """Simulated require statement, a failing require aborts the simulated function with RequireFailed."""
+pub fn require(condition: bool, msg: &str) -> std::result::Result<(), ZkayRuntimeError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(ZkayRuntimeError::RequireFailed(msg.to_owned()))
+    }
+}
+
 // class StateDict:
 //     """Dictionary which wraps access to state variables"""
 use std::marker::PhantomData;
@@ -70,7 +86,7 @@ pub struct StateDict<
     C: ZkayCryptoInterface<P, B, K> + ZkayHomomorphicCryptoInterface<P, B, K> + Clone,
 > {
     api: RcCell<ApiWrapper<P, B, K>>,
-    __state: BTreeMap<String, DataType>,
+    __state: RcCell<BTreeMap<String, DataType>>,
     __constructors: RcCell<BTreeMap<String, (bool, CryptoParams, CallableType)>>,
     _prover: PhantomData<P>,
     _bc: PhantomData<B>,
@@ -86,7 +102,7 @@ impl<
     pub fn new(api: RcCell<ApiWrapper<P, B, K>>) -> Self {
         Self {
             api,
-            __state: BTreeMap::new(),
+            __state: RcCell::new(BTreeMap::new()),
             __constructors: RcCell::new(BTreeMap::new()),
             _prover: PhantomData,
             _bc: PhantomData,
@@ -99,7 +115,7 @@ impl<
     //         self.__constructors: Dict[str, (bool, CryptoParams, CallableType)] = {}
 
     pub fn clear(&mut self) {
-        self.__state.clear();
+        self.__state.borrow_mut().clear();
     }
     // """Define the wrapper constructor for a state variable."""= CFG.lock().unwrap().main_crypto_backend
     pub fn decl(&self, name: &str, constructor: CallableType, cipher: bool, crypto_backend: &str) {
@@ -120,138 +136,122 @@ impl<
         self.__constructors.borrow().keys().cloned().collect()
     }
 
-    pub fn get_plain(&self, name: &str, _indices: Vec<String>) -> Option<DataType> {
-        let (_is_cipher, _crypto_params, _constr) = self.__constructors.borrow()[name].clone();
-        // let val = self.__get(vec![name.to_owned()].into_iter().chain(indices.into_iter()).collect(), false);
-        // if is_cipher {
-        //     let (ret, _) = self.api.dec(val, constr, &crypto_params.crypto_name);
-        //     return ret
-        // }
-        //   val
-        None
+    pub fn get_plain(
+        &self,
+        name: &str,
+        indices: Vec<String>,
+    ) -> std::result::Result<DataType, ZkayRuntimeError> {
+        let (is_cipher, crypto_params, constr) = self.__constructor(name)?;
+        let val = self.get_raw(name, indices)?;
+        if is_cipher {
+            let (ret, _) = self
+                .api
+                .borrow()
+                .dec(val, constr, &crypto_params.crypto_name)?;
+            return Ok(ret);
+        }
+        Ok(val)
     }
 
-    pub fn get_raw(&self, name: &str, indices: Vec<String>) -> Option<&DataType> {
-        self.__get(
-            vec![name.to_owned()]
-                .into_iter()
-                .chain(indices.into_iter())
-                .collect(),
-            false,
-        )
+    pub fn get_raw(
+        &self,
+        name: &str,
+        indices: Vec<String>,
+    ) -> std::result::Result<DataType, ZkayRuntimeError> {
+        let key: Vec<&str> = [name]
+            .into_iter()
+            .chain(indices.iter().map(String::as_str))
+            .collect();
+        self.__get(&key, false)
     }
 
     // """
     // Return value of the state variable (or index of state variable) key
 
-    // :param key: Either a string with the state variable name (primitive variables) or a Tuple with the name and all index key values
-    // :raise KeyError: if location does not exist on the chain
+    // :param key: The state variable name followed by all index key values
+    // :raise BlockchainError: if location does not exist on the chain
     // :return: The requested value
     // """
-    pub fn __getitem__(&self, key: &str) -> &DataType {
-        self.__get(vec![key.to_owned()], true).unwrap()
+    pub fn __getitem__(&self, key: &[&str]) -> std::result::Result<DataType, ZkayRuntimeError> {
+        self.__get(key, true)
     }
 
     // """
     // Assign value to state variable (or to index of state variable)
 
-    // :param key: Either a string with the state variable name (primitive variables) or a Tuple with the name and all index key values
+    // :param key: The state variable name followed by all index key values
     // :param value: Correctly wrapped value which should be assigned to the specified state location
     // """
-    pub fn __setitem__(&mut self, mut key: Vec<String>, value: DataType) {
-        // if not isinstance(key, Tuple)
-        //     key = (key, )
-        let var = key[0].clone();
-        let loc = var
-            + &key[1..]
-                .iter()
-                .map(|k| format!("[{k}]"))
-                .collect::<Vec<_>>()
-                .concat();
+    pub fn __setitem__(&self, key: &[&str], value: DataType) {
+        let loc = Self::location(key);
 
         // # Write to state
-        self.__state.insert(loc, value);
+        self.__record_write(key[0], &loc);
+        self.__state.borrow_mut().insert(loc, value);
+    }
+    fn __constructor(
+        &self,
+        var: &str,
+    ) -> std::result::Result<(bool, CryptoParams, CallableType), ZkayRuntimeError> {
+        self.__constructors
+            .borrow()
+            .get(var)
+            .cloned()
+            .ok_or_else(|| {
+                ZkayRuntimeError::BlockchainError(format!("Unknown state variable {var}"))
+            })
     }
-    pub fn __get(&self, key: Vec<String>, cache: bool) -> Option<&DataType> {
-        // if not isinstance(key, Tuple):
-        //     key = (key, )
-        let (var, _indices) = (&key[0], &key[1..]);
-        let loc = var.to_owned()
+    // name[idx]... of the state location key
+    fn location(key: &[&str]) -> String {
+        key[0].to_owned()
             + &key[1..]
                 .iter()
                 .map(|k| format!("[{k}]"))
-                .collect::<Vec<_>>()
-                .concat();
+                .collect::<String>()
+    }
+    // Report assignments to encrypted state variables to the api (for dry run reports)
+    fn __record_write(&self, var: &str, loc: &str) {
+        if self
+            .__constructors
+            .borrow()
+            .get(var)
+            .map_or(false, |(is_cipher, _, _)| *is_cipher)
+        {
+            self.api.borrow().record_encrypted_write(loc);
+        }
+    }
+    pub fn __get(
+        &self,
+        key: &[&str],
+        cache: bool,
+    ) -> std::result::Result<DataType, ZkayRuntimeError> {
+        let (var, indices) = (key[0], &key[1..]);
+        let loc = Self::location(key);
 
         // # Retrieve from state scope
-
         if cache {
-            if let v @ Some(_) = self.__state.get(&loc) {
-                return v;
+            if let Some(v) = self.__state.borrow().get(&loc) {
+                return Ok(v.clone());
             }
         }
 
-        // let (is_cipher, crypto_params, constr) = self.__constructors[var.as_str()];
-        // try:
-        // let val = if is_cipher {
-        //     let cipher_len = crypto_params.cipher_len();
-        //     CipherValue(
-        //         &self.api._req_state_var(&var, indices, cipher_len),
-        //         crypto_params,
-        //     )
-        // } else {
-        //     constr(&self.api._req_state_var(var, indices))
-        // };
-        // // except BlockChainError:
-        // //     raise KeyError(key)
-        // if cache {
-        //     self.__state.insert(loc, val.clone());
-        // }
-        // val
-        None
-    }
-}
-
-impl<
-        P: ZkayProverInterface + Clone,
-        B: ZkayBlockchainInterface<P> + Clone,
-        K: ZkayKeystoreInterface<P, B> + Clone,
-        C: ZkayCryptoInterface<P, B, K> + ZkayHomomorphicCryptoInterface<P, B, K> + Clone,
-    > Index<&[&str]> for StateDict<P, B, K, C>
-{
-    type Output = DataType;
-
-    fn index(&self, index: &[&str]) -> &Self::Output {
-        let var = index[0].to_owned();
-        let loc = var
-            + &index[1..]
-                .iter()
-                .map(|k| format!("[{k}]"))
-                .collect::<Vec<_>>()
-                .concat();
-        self.__getitem__(&loc)
-    }
-}
-
-impl<
-        P: ZkayProverInterface + Clone,
-        B: ZkayBlockchainInterface<P> + Clone,
-        K: ZkayKeystoreInterface<P, B> + Clone,
-        C: ZkayCryptoInterface<P, B, K> + ZkayHomomorphicCryptoInterface<P, B, K> + Clone,
-    > IndexMut<&[&str]> for StateDict<P, B, K, C>
-{
-    fn index_mut(&mut self, index: &[&str]) -> &mut Self::Output {
-        let var = index[0].to_owned();
-        let loc = var
-            + &index[1..]
-                .iter()
-                .map(|k| format!("[{k}]"))
-                .collect::<Vec<_>>()
-                .concat();
-
-        // # Write to state
-        self.__state.get_mut(&loc).unwrap()
-        // panic!("Variable not found");
+        let (is_cipher, crypto_params, constr) = self.__constructor(var)?;
+        let indices = indices.join(",");
+        let val = if is_cipher {
+            let cipher_len = crypto_params.cipher_len();
+            let contents = self.api.borrow()._req_state_var(var, indices, cipher_len)?;
+            DataType::CipherValue(Value::<String, CipherValue>::new(
+                contents.split(',').map(str::to_owned).collect(),
+                Some(crypto_params),
+                None,
+            ))
+        } else {
+            constr(self.api.borrow()._req_state_var(var, indices, 0)?)
+        };
+        if cache {
+            self.__state.borrow_mut().insert(loc, val.clone());
+        }
+        Ok(val)
     }
 }
 
@@ -380,13 +380,16 @@ impl<
         trans_sec_size: i32,
         wei_amount: i32,
         name: &str,
-    ) -> (
-        bool,
-        WithFunctionCtx<C, P, B, K, WithApiFunctionCtx, WithLogContext>,
-    ) {
+    ) -> std::result::Result<
+        (
+            bool,
+            WithFunctionCtx<C, P, B, K, WithApiFunctionCtx, WithLogContext>,
+        ),
+        ZkayRuntimeError,
+    > {
         let fc;
         let is_external;
-        with_context_block!(var afc= self.contract_simulator_ref().borrow().api.borrow().api_function_ctx(trans_sec_size as usize, Some(wei_amount))=>{
+        with_context_block!(var afc= self.contract_simulator_ref().borrow().api.borrow().api_function_ctx(trans_sec_size as usize, Some(wei_amount))?=>{
          is_external=afc.is_external.borrow().map_or(false,|x|x);
         let mut t_idx=0;
 
@@ -418,7 +421,25 @@ impl<
         //         self.state.clear()
         });
         });
-        (is_external, fc)
+        Ok((is_external, fc))
+    }
+
+    fn _with_transaction_retries<R>(
+        &self,
+        name: &str,
+        mut call: impl FnMut() -> std::result::Result<R, ZkayRuntimeError>,
+    ) -> std::result::Result<R, ZkayRuntimeError> {
+        let max_retries = CFG.lock().unwrap().blockchain_max_transaction_retries();
+        let mut retries = 0;
+        loop {
+            match call() {
+                Err(e @ ZkayRuntimeError::StaleState { .. }) if retries < max_retries => {
+                    retries += 1;
+                    zk_print!("{e}, re-running {name} ({retries}/{max_retries})");
+                }
+                result => return result,
+            }
+        }
     }
 }
 
@@ -442,10 +463,24 @@ pub trait ContractSimulatorConfig<
         trans_sec_size: i32,
         wei_amount: i32,
         name: &str,
-    ) -> (
-        bool,
-        WithFunctionCtx<C, P, B, K, WithApiFunctionCtx, WithLogContext>,
-    );
+    ) -> std::result::Result<
+        (
+            bool,
+            WithFunctionCtx<C, P, B, K, WithApiFunctionCtx, WithLogContext>,
+        ),
+        ZkayRuntimeError,
+    >;
+
+    // """
+    // Run an external function (simulation, proof generation and transaction) and run it again against the current
+    // state if its transaction conflicted with another transaction which modified the private state it read,
+    // at most cfg.blockchain_max_transaction_retries times.
+    // """
+    fn _with_transaction_retries<R>(
+        &self,
+        name: &str,
+        call: impl FnMut() -> std::result::Result<R, ZkayRuntimeError>,
+    ) -> std::result::Result<R, ZkayRuntimeError>;
 }
 
 #[derive(Clone)]
@@ -548,13 +583,17 @@ impl<
 
     // @staticmethod
     // """Generate/Load keys for the given address."""
-    pub fn initialize_keys_for(&self, address: &str) {
-        let _account = address.to_owned();
-        for _crypto_params in CFG.lock().unwrap().all_crypto_params() {
-            // if !self.runtime.keystore(&CryptoParams::new(crypto_params.clone())).has_initialized_keys_for(&address.to_owned()) {
-            //     self.runtime.crypto(&CryptoParams::new(crypto_params.clone())).generate_or_load_key_pair(&account)
-            // };
+    pub fn initialize_keys_for(&self, address: &str) -> std::result::Result<(), ZkayRuntimeError> {
+        let account = address.to_owned();
+        let all_crypto_params = CFG.lock().unwrap().all_crypto_params();
+        for crypto_params in all_crypto_params {
+            let crypto_params = CryptoParams::new(crypto_params);
+            let runtime = self.runtime.borrow();
+            if !runtime.keystore(&crypto_params)?.borrow().has_initialized_keys_for(&account) {
+                runtime.crypto(&crypto_params)?.borrow().generate_or_load_key_pair(&account)?;
+            }
         }
+        Ok(())
     }
 
     // @staticmethod
@@ -565,24 +604,87 @@ impl<
         // self.runtime.borrow_mut().reset();
     }
 
+    // """
+    // Open the local index of the encrypted state of this contract, decrypted with the keys of the user.
+    // Locations to index have to be registered with watch/watch_mine before syncing.
+    // """
+    pub fn state_indexer(&self) -> eyre::Result<StateIndexer<P, B, K>> {
+        let api = self.api.borrow();
+        let contract = api
+            .contract_handle()
+            .ok_or_else(|| eyre::eyre!("Contract must be deployed or connected before indexing its state"))?;
+        StateIndexer::open(
+            self.runtime.clone(),
+            &PathBuf::from(api.project_dir()),
+            contract,
+            &api.user_address(),
+        )
+    }
+
     // @staticmethod
     // """
     // Create count pre-funded dummy accounts (if supported by backend)
 
     // :param count: # of accounts to create
-    // :return: if count == 1 -> returns a address, otherwise returns a tuple of count addresses
+    // :return: addresses of the new accounts
     // """
-    pub fn create_dummy_accounts(&self, _count: i32) -> Vec<String> {
-        let accounts: Vec<String> = vec![]; //self.runtime.blockchain().create_test_accounts(count);
+    pub fn create_dummy_accounts(&self, count: i32) -> std::result::Result<Vec<String>, ZkayRuntimeError> {
+        let accounts = self.runtime.borrow().blockchain().borrow().create_test_accounts(count);
         for account in &accounts {
-            self.initialize_keys_for(account);
-        }
-        if accounts.len() == 1 {
-            accounts[..1].to_vec()
-        } else {
-            accounts
+            self.initialize_keys_for(account)?;
         }
+        Ok(accounts)
     }
+
+    // """
+    // Create a contract simulator for the same contract which issues its transactions from user_addr.
+    // The runtime (blockchain connection, keystores, crypto backends and prover) is shared with this simulator.
+    // """
+    pub fn for_user(&self, user_addr: &str) -> Self {
+        let api = self.api.borrow();
+        let user_api = ApiWrapper::new(
+            &api.project_dir(),
+            &api.__contract_name.borrow(),
+            user_addr,
+            api.__conn.clone(),
+            api.__keystore.clone(),
+            api.__crypto.clone(),
+            api.__prover.clone(),
+        );
+        Self::new(self.runtime.clone(), RcCell::new(user_api))
+    }
+}
+
+// Options of a dry run, see ApiWrapper::set_dry_run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DryRun {
+    // # without a proof the gas of the transaction cannot be estimated
+    pub generate_proof: bool,
+}
+
+// Effects of a transaction which was simulated but not sent
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DryRunReport {
+    pub function: String,
+    // plaintext secret circuit inputs computed by the user, by circuit variable name
+    pub plaintext_values: BTreeMap<String, DataType>,
+    // encrypted state locations (name[idx]...) which the transaction assigns
+    pub encrypted_state_writes: Vec<String>,
+    // circuit outputs which are revealed in plaintext
+    pub declassified_outputs: BTreeMap<String, DataType>,
+    // None if the proof was not generated
+    pub estimated_gas: Option<u64>,
+}
+
+// Public circuit input zk__in[in_idx] which is (an element of) the value of a state variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StateInput {
+    in_idx: usize,
+    // name[idx]..., as reported in StaleState
+    location: String,
+    name: String,
+    // getter arguments, including the element index for ciphertexts
+    indices: String,
 }
 
 #[derive(Clone)]
@@ -606,6 +708,10 @@ pub struct ApiWrapper<
     all_priv_values: RcCell<Option<Vec<String>>>,
     current_all_index: RcCell<Option<i32>>,
     is_external: RcCell<Option<bool>>,
+    state_inputs: RcCell<Vec<StateInput>>,
+    proof_inputs: RcCell<Option<Vec<String>>>,
+    dry_run: RcCell<Option<DryRun>>,
+    dry_run_report: RcCell<Option<DryRunReport>>,
 }
 impl<
         P: ZkayProverInterface + Clone,
@@ -680,6 +786,19 @@ impl<
         // function behavior depending on whether a call is external or not (e.g. encrypting parameters or not)
         // """
         let is_external: RcCell<Option<bool>> = RcCell::new(None);
+
+        // """
+        // Public circuit inputs of the current transaction which are state values, and the public circuit inputs of its proof.
+        // Used to detect whether the proof became stale because another transaction modified the state.
+        // """
+        let state_inputs: RcCell<Vec<StateInput>> = RcCell::new(vec![]);
+        let proof_inputs: RcCell<Option<Vec<String>>> = RcCell::new(None);
+
+        // """
+        // If set, transactions are simulated but not sent, the effects of the current one are collected in dry_run_report
+        // """
+        let dry_run: RcCell<Option<DryRun>> = RcCell::new(None);
+        let dry_run_report: RcCell<Option<DryRunReport>> = RcCell::new(None);
         Self {
             __conn,
             __keystore,
@@ -696,6 +815,10 @@ impl<
             all_priv_values,
             current_all_index,
             is_external,
+            state_inputs,
+            proof_inputs,
+            dry_run,
+            dry_run_report,
         }
     }
     // @property
@@ -709,6 +832,16 @@ impl<
         self.__user_addr.borrow().clone()
     }
 
+    // @property
+    pub fn project_dir(&self) -> String {
+        self.__project_dir.borrow().clone()
+    }
+
+    // @property
+    pub fn contract_handle(&self) -> Option<Address> {
+        *self.__contract_handle.borrow()
+    }
+
     // @property
     pub fn keystore(&self) -> RcCell<K> {
         // # Method only exists for compatibility, new code generators only generate calls to get_keystore
@@ -731,25 +864,65 @@ impl<
             .pk(&self.user_address())
     }
 
-    pub fn call_fct<F: Fn()>(&self, sec_offset: i32, fct: F) {
+    pub fn call_fct<R, F: Fn() -> R>(&self, sec_offset: i32, fct: F) -> R {
         with_context_block!(var _cc=self.__call_ctx(sec_offset)=>{
-             fct();
+             return fct();
         });
     }
 
     // @staticmethod
     // """
-    // Check whether a comparison with value 'val' can be evaluated correctly in the circuit.
+    // Check whether 'val' is a value of the declared type (u)int<bitwidth>, before it is encrypted or serialized.
 
     // :param val: the value to check
-    // :raises ValueError:
+    // :param bitwidth: bit width of the declared type
+    // :param signed: whether the declared type is signed
+    // :raises ValueOutOfRange:
     // """
-    pub fn range_checked(val: BigInteger256) -> BigInteger256 {
-        assert!(
-            val < _BN128_COMP_SCALAR_FIELD,
-            "Value {val} is too large for comparison, circuit would produce wrong results."
-        );
-        val
+    pub fn range_checked(
+        val: DataType,
+        bitwidth: i32,
+        signed: bool,
+    ) -> std::result::Result<DataType, ZkayRuntimeError> {
+        let value_bits = bitwidth - signed as i32;
+        let fits = |v: U256| value_bits >= 256 || v < U256::from(1) << value_bits as usize;
+        let in_range = match &val {
+            DataType::Bool(_) => true,
+            DataType::Int(i) => fits(*i),
+            // # -2^(bitwidth-1) is the smallest value of int<bitwidth>
+            DataType::SignedInt(i) if i.is_negative() => {
+                signed && i.unsigned_abs() <= U256::from(1) << value_bits.min(255) as usize
+            }
+            DataType::SignedInt(i) => fits(i.into_raw()),
+            _ => false,
+        };
+        if !in_range {
+            return Err(ZkayRuntimeError::ValueOutOfRange(format!(
+                "{val:?} is not a value of type {}int{bitwidth}",
+                if signed { "" } else { "u" }
+            )));
+        }
+        Ok(val)
+    }
+
+    // @staticmethod
+    // """
+    // Check whether a comparison (<, <=, >, >=) with 'val' can be evaluated correctly in the circuit.
+
+    // :raises ValueOutOfRange:
+    // """
+    pub fn comparison_checked(val: DataType) -> std::result::Result<DataType, ZkayRuntimeError> {
+        let magnitude = match &val {
+            DataType::Int(i) => *i,
+            DataType::SignedInt(i) => i.unsigned_abs(),
+            _ => U256::ZERO,
+        };
+        if magnitude >= _BN128_COMP_SCALAR_FIELD {
+            return Err(ZkayRuntimeError::ValueOutOfRange(format!(
+                "Value {magnitude} is too large for comparison, circuit would produce wrong results."
+            )));
+        }
+        Ok(val)
     }
 
     pub fn deploy(
@@ -758,25 +931,23 @@ impl<
         should_encrypt: Vec<bool>,
         wei_amount: Option<i32>,
         project:&Project,
-    ) -> Option<Address> {
-        *self.__contract_handle.borrow_mut() = self
-            .__conn
-            .borrow()
-            .deploy(
-                &PathBuf::from(self.__project_dir.borrow().clone()),
-                &self.__user_addr.borrow(),
-                &self.__contract_name.borrow(),
-                actual_args,
-                should_encrypt,
-                wei_amount,
-                project,
-            )
-            .ok();
-        self.__contract_handle.borrow().clone()
+    ) -> std::result::Result<Address, ZkayRuntimeError> {
+        let contract_handle = self.__conn.borrow().deploy(
+            &PathBuf::from(self.__project_dir.borrow().clone()),
+            &self.__user_addr.borrow(),
+            &self.__contract_name.borrow(),
+            actual_args,
+            should_encrypt,
+            wei_amount,
+            project,
+        )?;
+        *self.__contract_handle.borrow_mut() = Some(contract_handle);
+        Ok(contract_handle)
     }
+    // Connect to the contract at address, or to the one recorded in the deployment registry if None
     pub fn connect<PS: ProvingScheme>(
         &self,
-        address: &Address,
+        address: Option<&Address>,
         compile_zkay_file: fn(
             input_file_path: &str,
             output_dir: &str,
@@ -784,37 +955,183 @@ impl<
         ) -> anyhow::Result<()>,
         get_verification_contract_names: fn(code_or_ast: String) -> Vec<String>,
         project:&Project,
-    ) {
-        *self.__contract_handle.borrow_mut() = self
-            .__conn
-            .borrow()
-            .connect::<PS>(
-                &PathBuf::from(self.__project_dir.borrow().clone()),
-                &self.__contract_name.borrow(),
-                address,
-                self.user_address(),
-                compile_zkay_file,
-                get_verification_contract_names,
-                project,
-            )
-            .ok();
+    ) -> std::result::Result<(), ZkayRuntimeError> {
+        let contract_handle = self.__conn.borrow().connect::<PS>(
+            &PathBuf::from(self.__project_dir.borrow().clone()),
+            &self.__contract_name.borrow(),
+            address,
+            self.user_address(),
+            compile_zkay_file,
+            get_verification_contract_names,
+            project,
+        )?;
+        *self.__contract_handle.borrow_mut() = Some(contract_handle);
+        Ok(())
     }
 
+    // """
+    // Issue the transaction for fname.
+
+    // :raise StaleState: if the state read for the proof was modified by another transaction,
+    //                    either before submission or while the transaction was pending
+    // :raise BlockchainError: if the transaction could not be issued or failed
+    // """
     pub fn transact(
         &self,
         fname: &str,
         args: Vec<DataType>,
         should_encrypt: Vec<bool>,
         wei_amount: Option<i32>,
-    ) {
-        self.__conn.borrow().transact(
-            self.__contract_handle.borrow().as_ref().unwrap(),
+    ) -> std::result::Result<Option<DryRunReport>, ZkayRuntimeError> {
+        let stale_error = |locations| ZkayRuntimeError::StaleState {
+            function: fname.to_owned(),
+            locations,
+        };
+        // # The verification contract would reject the proof anyway
+        let stale = self.stale_state_locations()?;
+        if !stale.is_empty() {
+            return Err(stale_error(stale));
+        }
+        if let Some(dry_run) = *self.dry_run.borrow() {
+            return self.__dry_run_report(fname, args, should_encrypt, wei_amount, dry_run);
+        }
+        let result = self.__conn.borrow().transact(
+            &self.contract_handle_or_err()?,
             &self.__user_addr.borrow(),
             fname,
             args,
             should_encrypt,
             wei_amount,
-        )
+        );
+        match result {
+            Err(e) if is_invalid_proof_revert(&e) => {
+                let stale = self.stale_state_locations()?;
+                if stale.is_empty() {
+                    Err(e.into())
+                } else {
+                    Err(stale_error(stale))
+                }
+            }
+            result => result.map(|_| None).map_err(Into::into),
+        }
+    }
+
+    // """
+    // Simulate transactions instead of sending them.
+
+    // The offchain simulation and the serialization of the proof inputs still run, transact then returns
+    // a report of what the transaction would do. The proof is only generated if dry_run.generate_proof.
+    // """
+    pub fn set_dry_run(&self, dry_run: Option<DryRun>) {
+        *self.dry_run.borrow_mut() = dry_run;
+    }
+
+    // Report of the last transaction simulated in dry run mode
+    pub fn dry_run_report(&self) -> Option<DryRunReport> {
+        self.dry_run_report.borrow().clone()
+    }
+
+    pub fn record_encrypted_write(&self, loc: &str) {
+        if let Some(report) = self.dry_run_report.borrow_mut().as_mut() {
+            if !report.encrypted_state_writes.iter().any(|l| l == loc) {
+                report.encrypted_state_writes.push(loc.to_owned());
+            }
+        }
+    }
+
+    fn __dry_run_report(
+        &self,
+        fname: &str,
+        args: Vec<DataType>,
+        should_encrypt: Vec<bool>,
+        wei_amount: Option<i32>,
+        dry_run: DryRun,
+    ) -> std::result::Result<Option<DryRunReport>, ZkayRuntimeError> {
+        let estimated_gas = if dry_run.generate_proof {
+            Some(self.__conn.borrow().estimate_gas(
+                &self.contract_handle_or_err()?,
+                &self.__user_addr.borrow(),
+                fname,
+                args,
+                should_encrypt,
+                wei_amount,
+            )?)
+        } else {
+            None
+        };
+        zk_print!(r#"Dry run of "{fname}", the transaction is not sent"#);
+        let mut report = self.dry_run_report.borrow_mut();
+        let report = report.get_or_insert_with(DryRunReport::default);
+        report.function = fname.to_owned();
+        report.estimated_gas = estimated_gas;
+        Ok(Some(report.clone()))
+    }
+
+    // """
+    // Record that the public circuit inputs zk__in[in_idx..] of the current transaction are the value of state variable
+    // name[indices...] (the count elements of its ciphertext, or the plain value if count is 0).
+
+    // The indices refer to the unhashed zk__in array, which is also what the circuit hashes if cfg.should_use_hash.
+    // """
+    pub fn state_proof_input(&self, in_idx: usize, name: &str, indices: &[&str], count: i32) {
+        let location = name.to_owned() + &indices.iter().map(|i| format!("[{i}]")).collect::<String>();
+        let indices = indices.join(",");
+        let mut state_inputs = self.state_inputs.borrow_mut();
+        if count == 0 {
+            state_inputs.push(StateInput {
+                in_idx,
+                location,
+                name: name.to_owned(),
+                indices,
+            });
+            return;
+        }
+        // # Ciphertexts are arrays, the element index is the last getter argument
+        state_inputs.extend((0..count as usize).map(|i| StateInput {
+            in_idx: in_idx + i,
+            location: location.clone(),
+            name: name.to_owned(),
+            indices: if indices.is_empty() {
+                i.to_string()
+            } else {
+                format!("{indices},{i}")
+            },
+        }));
+    }
+
+    // State locations which are public inputs of the proof of the current transaction and have changed on chain since
+    pub fn stale_state_locations(&self) -> std::result::Result<Vec<String>, ZkayRuntimeError> {
+        let proof_inputs = self.proof_inputs.borrow();
+        let Some(proof_inputs) = proof_inputs.as_ref() else {
+            return Ok(vec![]);
+        };
+        let contract = self.contract_handle_or_err()?;
+        let conn = self.__conn.borrow();
+        let mut locations = vec![];
+        for input in self.state_inputs.borrow().iter() {
+            let Some(proven) = proof_inputs.get(input.in_idx) else {
+                continue;
+            };
+            let on_chain = conn.req_state_var(&contract, &input.name, &input.indices)?;
+            let on_chain = abi::to_text(&on_chain);
+            if DataType::parse_uint(proven.clone()) != DataType::parse_uint(on_chain) {
+                locations.push(input.location.clone());
+            }
+        }
+        // # a ciphertext is stale if any of its elements changed
+        locations.sort();
+        locations.dedup();
+        Ok(locations)
+    }
+
+    // Handle of the contract, which only exists once it is deployed or connected
+    fn contract_handle_or_err(&self) -> std::result::Result<Address, ZkayRuntimeError> {
+        self.__contract_handle.borrow().ok_or_else(|| {
+            ZkayRuntimeError::BlockchainError(format!(
+                "Contract {} is not deployed or connected",
+                self.__contract_name.borrow()
+            ))
+        })
     }
 
     pub fn call(
@@ -822,58 +1139,52 @@ impl<
         fname: &str,
         args: Vec<DataType>,
         ret_val_constructors: Vec<(bool, String, CallableType)>,
-    ) -> DataType {
+    ) -> std::result::Result<DataType, ZkayRuntimeError> {
         let retvals = self.__conn.borrow().call(
-            self.__contract_handle.borrow().as_ref().unwrap().clone(),
+            self.contract_handle_or_err()?,
             &self.__user_addr.borrow(),
             fname,
             args,
-        );
-        if ret_val_constructors.len() == 1 {
-            let (is_cipher, crypto_params_name, callable) = ret_val_constructors[0].clone();
-            self.__get_decrypted_retval(
-                BigInteger256::from_str(&retvals).unwrap(),
-                is_cipher,
-                crypto_params_name,
-                callable,
-            )
-        } else {
-            DataType::List(
-                [retvals]
-                    .iter()
-                    .zip(ret_val_constructors)
-                    .map(|(retval, (is_cipher, homomorphism, constr))| {
-                        self.__get_decrypted_retval(
-                            BigInteger256::from_str(retval).unwrap(),
-                            is_cipher,
-                            homomorphism,
-                            constr,
-                        )
-                    })
-                    .collect(),
-            )
+        )?;
+        if retvals.len() != ret_val_constructors.len() {
+            return Err(ZkayRuntimeError::BlockchainError(format!(
+                "{fname} returned {} values, expected {}",
+                retvals.len(),
+                ret_val_constructors.len()
+            )));
         }
+        let mut retvals = retvals
+            .iter()
+            .zip(ret_val_constructors)
+            .map(|(retval, (is_cipher, crypto_params_name, constr))| {
+                self.__get_decrypted_retval(retval, is_cipher, crypto_params_name, constr)
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        // # single value if one return value, list if multiple return values
+        Ok(if retvals.len() == 1 {
+            retvals.remove(0)
+        } else {
+            DataType::List(retvals)
+        })
     }
     pub fn __get_decrypted_retval(
         &self,
-        raw_value: BigInteger256,
+        raw_value: &DynSolValue,
         is_cipher: bool,
         crypto_params_name: String,
         constructor: CallableType,
-    ) -> DataType {
+    ) -> std::result::Result<DataType, ZkayRuntimeError> {
         if is_cipher {
-            self.dec(
-                DataType::CipherValue(Value::<String, CipherValue>::new(
-                    vec![raw_value.to_string()],
-                    Some(CryptoParams::new(crypto_params_name.clone())),
-                    None,
-                )),
+            let params = CryptoParams::new(crypto_params_name.clone());
+            let cipher = cipher_from_dyn_sol_value(raw_value, &params)?;
+            let (plain, _) = self.dec(
+                DataType::CipherValue(cipher),
                 constructor,
                 &crypto_params_name,
-            )
-            .0
+            )?;
+            Ok(plain)
         } else {
-            constructor(raw_value.to_string())
+            Ok(constructor(abi::to_text(raw_value)))
         }
     }
 
@@ -918,20 +1229,27 @@ impl<
     //= CFG.lock().unwrap().main_crypto_backend
     pub fn enc(
         &self,
-        plain: i32,
+        plain: U256,
         target_addr: Option<String>,
         crypto_backend: &str,
-    ) -> (
-        Value<String, CipherValue>,
-        Option<Value<String, RandomnessValue>>,
-    ) {
+    ) -> std::result::Result<
+        (
+            Value<String, CipherValue>,
+            Option<Value<String, RandomnessValue>>,
+        ),
+        ZkayRuntimeError,
+    > {
         let target_addr = target_addr.map_or(self.__user_addr.borrow().clone(), |ta| ta);
-        self.__crypto
+        let crypto = self
+            .__crypto
             .borrow()
             .get(crypto_backend)
-            .unwrap()
+            .cloned()
+            .ok_or_else(|| ZkayRuntimeError::unknown_backend("crypto", crypto_backend))?;
+        let res = crypto
             .borrow()
-            .enc(plain.to_string(), &self.__user_addr.borrow(), &target_addr)
+            .enc(plain.to_string(), &self.__user_addr.borrow(), &target_addr);
+        Ok(res)
     }
     //= CFG.lock().unwrap().main_crypto_backend
     pub fn dec(
@@ -939,12 +1257,40 @@ impl<
         cipher: DataType,
         constr: CallableType,
         crypto_backend: &str,
-    ) -> (DataType, Option<Value<String, RandomnessValue>>) {
-        let res = self.__crypto.borrow()[crypto_backend].borrow().dec(
-            cipher.try_as_cipher_value_ref().unwrap(),
-            &self.__user_addr.borrow(),
-        );
-        (constr(res.0.to_string()), res.1)
+    ) -> std::result::Result<(DataType, Option<Value<String, RandomnessValue>>), ZkayRuntimeError>
+    {
+        let crypto = self
+            .__crypto
+            .borrow()
+            .get(crypto_backend)
+            .cloned()
+            .ok_or_else(|| ZkayRuntimeError::unknown_backend("crypto", crypto_backend))?;
+        let cipher = cipher.try_as_cipher_value_ref().ok_or_else(|| {
+            ZkayRuntimeError::DecryptionFailed(format!("{cipher:?} is not a ciphertext"))
+        })?;
+        let res = crypto.borrow().dec(cipher, &self.__user_addr.borrow());
+        Ok((constr(res.0.to_string()), res.1))
+    }
+
+    // Keystore and crypto backend of a homomorphic operation
+    fn __homomorphic_backend(
+        &self,
+        params: &CryptoParams,
+    ) -> std::result::Result<(RcCell<K>, RcCell<CryptoClass<P, B, K>>), ZkayRuntimeError> {
+        let unknown = || ZkayRuntimeError::unknown_backend("crypto", &params.crypto_name);
+        let keystore = self
+            .__keystore
+            .borrow()
+            .get(&params.crypto_name)
+            .cloned()
+            .ok_or_else(unknown)?;
+        let crypto_inst = self
+            .__crypto
+            .borrow()
+            .get(&params.crypto_name)
+            .cloned()
+            .ok_or_else(unknown)?;
+        Ok((keystore, crypto_inst))
     }
 
     pub   fn do_homomorphic_op(
@@ -953,25 +1299,23 @@ impl<
         crypto_backend: &str,
         target_addr: String,
         args: Vec<DataType>,
-    ) -> Value<String, CipherValue> {
+    ) -> std::result::Result<Value<String, CipherValue>, ZkayRuntimeError> {
         let params = CryptoParams::new(crypto_backend.to_owned());
-        let pk = self
-            .__keystore
-            .borrow()
-            .get(&params.crypto_name)
-            .unwrap()
-            .borrow()
-            .getPk(&target_addr);
+        let (keystore, crypto_inst) = self.__homomorphic_backend(&params)?;
+        let pk = keystore.borrow().getPk(&target_addr);
         // assert!(
         //     args.iter().all(|arg| !(isinstance(arg, CipherValue)
         //         && params.crypto_name != arg.params.crypto_name)),
         //     "CipherValues from different crypto backends used in homomorphic operation"
         // );
 
-        let mut crypto_inst = self.__crypto.borrow()[&params.crypto_name].clone();
         // assert isinstance(crypto_inst, ZkayHomomorphicCryptoInterface);
         let result = crypto_inst.borrow().do_op(op, pk[..].to_vec(), args);
-        Value::<String, CipherValue>::new(result, Some(params), None)
+        Ok(Value::<String, CipherValue>::new(
+            result,
+            Some(params),
+            None,
+        ))
     }
 
     // """
@@ -984,63 +1328,83 @@ impl<
         target_addr: String,
         data: &mut BTreeMap<String, String>,
         rnd_key: &str,
-    ) {
+    ) -> std::result::Result<(), ZkayRuntimeError> {
         let params = CryptoParams::new(crypto_backend.to_owned());
-        let pk = self
-            .__keystore
-            .borrow()
-            .get(&params.crypto_name)
-            .unwrap()
-            .borrow()
-            .getPk(&target_addr);
-        let mut crypto_inst = self.__crypto.borrow()[&params.crypto_name].clone();
+        let (keystore, crypto_inst) = self.__homomorphic_backend(&params)?;
+        let pk = keystore.borrow().getPk(&target_addr);
         // assert isinstance(crypto_inst, ZkayHomomorphicCryptoInterface);
         let (_result, _rand) = crypto_inst.borrow().do_rerand(arg, pk[..].to_vec());
         data.insert(rnd_key.to_owned(), params.crypto_name.clone()); //# store randomness
                                                                      // CipherValue(result, params)
+        Ok(())
     }
 
-    pub fn _req_state_var(&self, name: &str, indices: String, count: i32) -> String {
+    pub fn _req_state_var(
+        &self,
+        name: &str,
+        indices: String,
+        count: i32,
+    ) -> std::result::Result<String, ZkayRuntimeError> {
         // if self.__contract_handle is None:
         // # TODO check this statically in the type checker
-        assert!(
-            self.__contract_handle.borrow().is_some(),
-            "Cannot read state variable {name} within constructor before it is assigned a value."
-        );
-
+        let contract = self.__contract_handle.borrow().ok_or_else(|| {
+            ZkayRuntimeError::BlockchainError(format!(
+                "Cannot read state variable {name} within constructor before it is assigned a value."
+            ))
+        })?;
+
+        let req = |indices: String| {
+            self.__conn
+                .borrow()
+                .req_state_var(&contract, name, &indices)
+                .map(|value| abi::to_text(&value))
+                .map_err(ZkayRuntimeError::from)
+        };
         if count == 0 {
-            self.__conn.borrow().req_state_var(
-                self.__contract_handle.borrow().as_ref().unwrap(),
-                name,
-                &indices,
-            )
+            req(indices)
         } else {
-            (0..count)
-                .map(|_i| {
-                    self.__conn.borrow().req_state_var(
-                        self.__contract_handle.borrow().as_ref().unwrap(),
-                        &name,
-                        &indices,
-                    )
+            // # Ciphertexts are arrays, the element index is the last getter argument
+            Ok((0..count)
+                .map(|i| {
+                    req(if indices.is_empty() {
+                        i.to_string()
+                    } else {
+                        format!("{indices},{i}")
+                    })
                 })
-                .collect()
+                .collect::<std::result::Result<Vec<_>, _>>()?
+                .join(","))
         }
     }
     // @staticmethod
-    pub fn __serialize_val<T>(_val: T, _bitwidth: i32) -> String {
-        // if isinstance(val, AddressValue):
-        //     val = i32.from_be_bytes(val.val)
-        // else if isinstance(val, IntEnum):
-        //     val = val.value
-        // elif isinstance(val, bool):
-        //     val = i32(val)
-        // elif isinstance(val, i32):
-        //     if val < 0:
-        //         val = int_cast(val, bitwidth, signed=False)
-        //     elif bitwidth == 256:
-        //         val %= bn128_scalar_field
-        // val
-        String::new()
+    pub fn __serialize_val(val: &DataType, bitwidth: i32) -> String {
+        // # addresses are passed as integers, negative values in two's complement
+        match val.to_uint(bitwidth) {
+            Some(v) if matches!(val, DataType::SignedInt(i) if i.is_negative()) => v.to_string(),
+            Some(v) if bitwidth == 256 => (v % _BN128_SCALAR_FIELD).to_string(),
+            Some(v) => v.to_string(),
+            None => val.try_as_string_ref().cloned().unwrap_or_default(),
+        }
+    }
+    fn __serialize_elems(
+        val: &DataType,
+        bitwidth: i32,
+    ) -> std::result::Result<Vec<String>, ZkayRuntimeError> {
+        // # Integers are checked against their declared type, a bit width of 0 denotes a non-integer element
+        let checked = |v: &DataType| -> std::result::Result<String, ZkayRuntimeError> {
+            if bitwidth > 0 && (v.is_int() || v.is_signed_int()) {
+                Self::range_checked(v.clone(), bitwidth, v.is_signed_int())?;
+            }
+            Ok(ApiWrapper::<P, B, K>::__serialize_val(v, bitwidth))
+        };
+        match val {
+            DataType::CipherValue(v) => Ok(v.contents.clone()),
+            DataType::PrivateKeyValue(v) => Ok(v.contents.clone()),
+            DataType::PublicKeyValue(v) => Ok(v.contents.clone()),
+            DataType::RandomnessValue(v) => Ok(v.contents.clone()),
+            DataType::List(l) => l.iter().map(checked).collect(),
+            _ => Ok(vec![checked(val)?]),
+        }
     }
     // @staticmethod
     pub fn __serialize_circuit_array(
@@ -1048,30 +1412,23 @@ impl<
         target_array: &mut Vec<String>,
         target_out_start_idx: i32,
         elem_bitwidths: Vec<i32>,
-    ) {
-        let mut idx = target_out_start_idx;
+    ) -> std::result::Result<(), ZkayRuntimeError> {
+        let mut idx = target_out_start_idx as usize;
         for ((_name, val), &bitwidth) in data.iter().zip(&elem_bitwidths) {
-            // if isinstance(val, (list, Value)) && !isinstance(val, AddressValue) {
-            //     target_array[idx..idx + vallen()] =
-            //     // if isinstance(val, CipherValue) {
-            //     //     val[..val.len()]
-            //     // } else {
-            //     //     val[..]
-            //     // }
-            //     val
-            //     ;
-            //     idx += len(val);
-            // } else {
-            target_array[idx as usize] = ApiWrapper::<P, B, K>::__serialize_val(val, bitwidth);
-            idx += 1;
-            // }
+            let vals = Self::__serialize_elems(val, bitwidth)?;
+            if target_array.len() < idx + vals.len() {
+                target_array.resize(idx + vals.len(), String::new());
+            }
+            target_array[idx..idx + vals.len()].clone_from_slice(&vals);
+            idx += vals.len();
         }
+        Ok(())
     }
     pub fn serialize_circuit_outputs(
         &self,
         zk_data: BTreeMap<String, DataType>,
         out_elem_bitwidths: Vec<i32>,
-    ) -> Vec<String> {
+    ) -> std::result::Result<Vec<String>, ZkayRuntimeError> {
         // # TODO don't depend on out var names for correctness
         let out_vals: BTreeMap<_, _> = zk_data
             .clone()
@@ -1081,31 +1438,66 @@ impl<
 
         let count = out_vals
             .values()
-            .map(|_val| {
-                // if isinstance(val, (Tuple, list)) {
-                // val.len()
-                // } else {
-                1
-                // }
+            .map(|val| match val {
+                DataType::CipherValue(v) => v.contents.len(),
+                DataType::PrivateKeyValue(v) => v.contents.len(),
+                DataType::PublicKeyValue(v) => v.contents.len(),
+                DataType::RandomnessValue(v) => v.contents.len(),
+                DataType::List(l) => l.len(),
+                _ => 1,
             })
-            .sum::<i32>();
-        let mut zk_out = vec![count.to_string()];
-        Self::__serialize_circuit_array(out_vals, &mut zk_out, 0, out_elem_bitwidths);
-        zk_out
+            .sum::<usize>();
+        if let Some(report) = self.dry_run_report.borrow_mut().as_mut() {
+            report.declassified_outputs.extend(
+                out_vals
+                    .iter()
+                    .filter(|(_, val)| is_plaintext(val))
+                    .map(|(name, val)| (name.clone(), val.clone())),
+            );
+        }
+        let mut zk_out = vec![String::new(); count];
+        Self::__serialize_circuit_array(out_vals, &mut zk_out, 0, out_elem_bitwidths)?;
+        Ok(zk_out)
     }
     pub fn serialize_private_inputs(
         &self,
         zk_priv: BTreeMap<String, DataType>,
         priv_elem_bitwidths: Vec<i32>,
-    ) {
-        let mut all_priv_values = self.all_priv_values.borrow().as_ref().unwrap().clone();
+    ) -> std::result::Result<(), ZkayRuntimeError> {
+        if let Some(report) = self.dry_run_report.borrow_mut().as_mut() {
+            report.plaintext_values.extend(
+                zk_priv
+                    .iter()
+                    .filter(|(_, val)| is_plaintext(val))
+                    .map(|(name, val)| (name.clone(), val.clone())),
+            );
+        }
+        let mut all_priv_values = self.__all_priv_values()?;
+        let current_all_index = self
+            .current_all_index
+            .borrow()
+            .ok_or_else(Self::__no_transaction)?;
         Self::__serialize_circuit_array(
             zk_priv,
             &mut all_priv_values,
-            self.current_all_index.borrow().as_ref().unwrap().clone(),
+            current_all_index,
             priv_elem_bitwidths,
-        );
+        )?;
         *self.all_priv_values.borrow_mut() = Some(all_priv_values);
+        Ok(())
+    }
+
+    fn __no_transaction() -> ZkayRuntimeError {
+        ZkayRuntimeError::BlockchainError(
+            "Private circuit inputs are only available during a transaction".to_owned(),
+        )
+    }
+
+    fn __all_priv_values(&self) -> std::result::Result<Vec<String>, ZkayRuntimeError> {
+        self.all_priv_values
+            .borrow()
+            .clone()
+            .ok_or_else(Self::__no_transaction)
     }
 
     pub fn gen_proof(
@@ -1113,12 +1505,17 @@ impl<
         fname: &str,
         in_vals: Vec<String>,
         out_vals: Vec<String>,
-    ) -> Vec<String> {
+    ) -> std::result::Result<Vec<String>, ZkayRuntimeError> {
+        *self.proof_inputs.borrow_mut() = Some(in_vals.clone());
+        if self.dry_run.borrow().is_some_and(|dry_run| !dry_run.generate_proof) {
+            // # the transaction is not sent, so the proof is not needed
+            return Ok(vec![]);
+        }
         self.__prover.borrow().generate_proof(
             &self.__project_dir.borrow(),
             self.__contract_name.borrow().clone(),
             fname.to_owned(),
-            self.all_priv_values.borrow().clone().unwrap(),
+            self.__all_priv_values()?,
             in_vals,
             out_vals,
         )
@@ -1138,19 +1535,31 @@ impl<
         &self,
         trans_sec_size: usize,
         wei_amount: Option<i32>,
-    ) -> WithApiFunctionCtx {
+    ) -> std::result::Result<WithApiFunctionCtx, ZkayRuntimeError> {
         let was_external = *self.is_external.borrow();
         if was_external.is_none() {
-            assert!(self.all_priv_values.borrow().is_none());
+            if self.all_priv_values.borrow().is_some() {
+                return Err(ZkayRuntimeError::BlockchainError(
+                    "Private circuit inputs of a previous transaction were not cleared".to_owned(),
+                ));
+            }
+            let wei_amount = wei_amount.ok_or_else(|| {
+                ZkayRuntimeError::BlockchainError(
+                    "No wei amount given for an external function call".to_owned(),
+                )
+            })?;
             *self.is_external.borrow_mut() = Some(true);
             *self.all_priv_values.borrow_mut() = Some(vec![0.to_string(); trans_sec_size]);
             *self.current_all_index.borrow_mut() = Some(0);
             self.current_priv_values.borrow_mut().clear();
-            self.update_special_variables(wei_amount.unwrap());
+            self.state_inputs.borrow_mut().clear();
+            *self.proof_inputs.borrow_mut() = None;
+            *self.dry_run_report.borrow_mut() = self.dry_run.borrow().map(|_| DryRunReport::default());
+            self.update_special_variables(wei_amount);
         } else {
             *self.is_external.borrow_mut() = Some(false);
         }
-        WithApiFunctionCtx::new(
+        Ok(WithApiFunctionCtx::new(
             self.__current_msg.clone(),
             self.__current_block.clone(),
             self.__current_tx.clone(),
@@ -1159,7 +1568,7 @@ impl<
             self.current_all_index.clone(),
             self.is_external.clone(),
             was_external,
-        )
+        ))
         // try:
         //     yield self.is_external
         // finally:
@@ -1342,6 +1751,17 @@ impl Drop for WithCalCtx {
         ) = (self.old_priv_values.clone(), self.old_all_index.clone());
     }
 }
+// Plaintext (not encrypted) runtime value, keys, randomness and ciphertexts are excluded
+fn is_plaintext(val: &DataType) -> bool {
+    !matches!(
+        val,
+        DataType::CipherValue(_)
+            | DataType::PrivateKeyValue(_)
+            | DataType::PublicKeyValue(_)
+            | DataType::RandomnessValue(_)
+    )
+}
+
 pub type BlockchainClassType = BlockchainClass<JsnarkProver>;
 pub type KeystoreType = SimpleKeystore<JsnarkProver, BlockchainClassType>;
 pub type CryptoClassType = CryptoClass<JsnarkProver, BlockchainClassType, KeystoreType>;
@@ -1352,7 +1772,10 @@ pub fn new_contract_simulator(
     user_addr: &str,
     eth: Option<EthereumOpts>,
     rpc: Option<RpcOpts>,
-) -> ContractSimulator<CryptoClassType, JsnarkProver, BlockchainClassType, KeystoreType> {
+) -> std::result::Result<
+    ContractSimulator<CryptoClassType, JsnarkProver, BlockchainClassType, KeystoreType>,
+    ZkayRuntimeError,
+> {
     // -> ContractSimulator<
     //     CryptoClass<
     //         JsnarkProver,
@@ -1371,11 +1794,11 @@ pub fn new_contract_simulator(
     // }
     // import code
     // code.interact(local=globals())
-    let __prover = RcCell::new(_prover_classes(&CFG.lock().unwrap().snark_backend()));
+    let __prover = RcCell::new(_prover_classes(&CFG.lock().unwrap().snark_backend())?);
     let __blockchain = RcCell::new(_blockchain_classes(
         &CFG.lock().unwrap().blockchain_backend(),
         __prover.clone(),eth,rpc,
-    ));
+    )?);
     // let __keystore=BTreeMap::from([SimpleKeystore::<P,BlockchainClass<P>>::new(blockchain.clone(), crypto_params.clone())]);
     let mut __keystore = BTreeMap::new();
     let mut __crypto = BTreeMap::new();
@@ -1393,7 +1816,7 @@ pub fn new_contract_simulator(
             JsnarkProver,
             BlockchainClass<JsnarkProver>,
             SimpleKeystore<JsnarkProver, BlockchainClass<JsnarkProver>>,
-        >(&crypto_backend, keystore));
+        >(&crypto_backend, keystore)?);
         __crypto.insert(crypto_params.clone(), crypto);
     }
     let __crypto = RcCell::new(__crypto);
@@ -1424,8 +1847,158 @@ pub fn new_contract_simulator(
         __crypto.clone(),
         __prover,
     ));
-    ContractSimulator::<CryptoClassType, JsnarkProver, BlockchainClassType, KeystoreType>::new(
+    Ok(ContractSimulator::<CryptoClassType, JsnarkProver, BlockchainClassType, KeystoreType>::new(
         runtime.clone(),
         api,
-    )
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::evm::tests::{cipher_slot, deploy_cipher_store};
+    use crate::blockchain::evm::EvmBlockchain;
+    use alloy_primitives::I256;
+
+    type Chain = EvmBlockchain<JsnarkProver>;
+    type Api = ApiWrapper<JsnarkProver, Chain, SimpleKeystore<JsnarkProver, Chain>>;
+
+    fn set(api: &Api, slot: String, value: u64) {
+        api.transact(
+            "set",
+            vec![DataType::String(slot), DataType::String(value.to_string())],
+            vec![false, false],
+            None,
+        )
+        .unwrap();
+    }
+
+    fn int(v: i64) -> DataType {
+        DataType::SignedInt(I256::try_from(v).unwrap())
+    }
+
+    #[test]
+    fn range_checked_accepts_values_of_the_declared_type() {
+        let in_range = |val: DataType, bitwidth, signed| {
+            let checked = Api::range_checked(val.clone(), bitwidth, signed);
+            if let Err(e) = &checked {
+                assert!(matches!(e, ZkayRuntimeError::ValueOutOfRange(_)));
+            }
+            checked.map(|checked| assert_eq!(checked, val)).is_ok()
+        };
+        assert!(in_range(DataType::Int(U256::from(255)), 8, false));
+        assert!(!in_range(DataType::Int(U256::from(256)), 8, false));
+        assert!(!in_range(int(-1), 8, false));
+        assert!(in_range(DataType::Bool(true), 1, false));
+        assert!(!in_range(DataType::String("1".to_owned()), 8, false));
+
+        assert!(in_range(int(-128), 8, true));
+        assert!(in_range(int(127), 8, true));
+        assert!(!in_range(int(-129), 8, true));
+        assert!(!in_range(int(128), 8, true));
+        assert!(!in_range(DataType::Int(U256::from(128)), 8, true));
+
+        assert!(in_range(DataType::Int(U256::MAX), 256, false));
+        assert!(in_range(DataType::SignedInt(I256::MIN), 256, true));
+        assert!(in_range(DataType::SignedInt(I256::MAX), 256, true));
+    }
+
+    #[test]
+    fn comparison_checked_rejects_values_beyond_the_comparison_range() {
+        let limit = U256::from(1) << 252;
+        assert!(Api::comparison_checked(DataType::Int(limit - U256::from(1))).is_ok());
+        assert!(Api::comparison_checked(DataType::Int(limit)).is_err());
+        assert!(Api::comparison_checked(DataType::SignedInt(-I256::from_raw(limit))).is_err());
+        assert!(Api::comparison_checked(int(-1)).is_ok());
+    }
+
+    #[test]
+    fn serialized_values_are_circuit_integers() {
+        assert_eq!(Api::__serialize_val(&DataType::Int(U256::from(5)), 8), "5");
+        assert_eq!(Api::__serialize_val(&DataType::Bool(true), 1), "1");
+        assert_eq!(Api::__serialize_val(&int(-1), 8), "255");
+        assert_eq!(Api::__serialize_val(&int(-128), 8), "128");
+        assert_eq!(Api::__serialize_val(&int(-1), 256), U256::MAX.to_string());
+        assert_eq!(
+            Api::__serialize_val(&DataType::Int(U256::MAX), 256),
+            (U256::MAX % _BN128_SCALAR_FIELD).to_string()
+        );
+        assert_eq!(
+            Api::__serialize_val(&DataType::String("0x10".to_owned()), 160),
+            "16"
+        );
+
+        // # Out of range integers are not serialized, non-integer elements have a bit width of 0
+        let mut target = vec![];
+        let data = BTreeMap::from([
+            ("a".to_owned(), int(-1)),
+            ("b".to_owned(), DataType::Int(U256::from(256))),
+        ]);
+        assert!(matches!(
+            Api::__serialize_circuit_array(data, &mut target, 0, vec![8, 8]),
+            Err(ZkayRuntimeError::ValueOutOfRange(_))
+        ));
+        let data = BTreeMap::from([
+            ("a".to_owned(), int(-1)),
+            ("b".to_owned(), DataType::String("pk".to_owned())),
+        ]);
+        Api::__serialize_circuit_array(data, &mut target, 0, vec![8, 0]).unwrap();
+        assert_eq!(target, vec!["255".to_owned(), "pk".to_owned()]);
+    }
+
+    #[test]
+    fn racing_users_invalidate_proof_inputs() {
+        let chain = RcCell::new(Chain::new(RcCell::new(JsnarkProver), None));
+        let contract = deploy_cipher_store(&chain.borrow());
+        let [alice, bob] = [1, 2].map(|i| chain.borrow().accounts()[i].to_checksum(None));
+        let api = |user: &str| {
+            let api = Api::new(
+                ".",
+                "CipherStore",
+                user,
+                chain.clone(),
+                RcCell::new(BTreeMap::new()),
+                RcCell::new(BTreeMap::new()),
+                RcCell::new(JsnarkProver),
+            );
+            *api.__contract_handle.borrow_mut() = Some(contract);
+            api
+        };
+        let (a, b) = (api(&alice), api(&bob));
+        set(&b, cipher_slot(&alice, 0), 3);
+        set(&b, cipher_slot(&alice, 1), 4);
+
+        // # alice proves a transaction which reads her balance, zk__in = [balance[alice], 5]
+        let _ctx = a.api_function_ctx(0, Some(0)).unwrap();
+        let balance = a._req_state_var("balance", alice.clone(), 2).unwrap();
+        a.state_proof_input(0, "balance", &[alice.as_str()], 2);
+        let mut in_vals: Vec<String> = balance.split(',').map(str::to_owned).collect();
+        in_vals.push("5".to_owned());
+        a.set_dry_run(Some(DryRun {
+            generate_proof: false,
+        }));
+        a.gen_proof("transfer", in_vals, vec![]).unwrap();
+        a.set_dry_run(None);
+        assert!(a.stale_state_locations().unwrap().is_empty());
+
+        // # bob changes state which is not an input of the proof
+        set(&b, cipher_slot(&bob, 0), 5);
+        assert!(a.stale_state_locations().unwrap().is_empty());
+
+        // # bob changes both elements of alice's ciphertext, the first one to a value which is another input of the proof
+        set(&b, cipher_slot(&alice, 0), 5);
+        set(&b, cipher_slot(&alice, 1), 6);
+        let location = format!("balance[{alice}]");
+        assert_eq!(a.stale_state_locations().unwrap(), vec![location.clone()]);
+        match a.transact("transfer", vec![], vec![], None) {
+            Err(ZkayRuntimeError::StaleState {
+                function,
+                locations,
+            }) => {
+                assert_eq!(function, "transfer");
+                assert_eq!(locations, vec![location]);
+            }
+            result => panic!("expected stale state, got {result:?}"),
+        }
+    }
 }
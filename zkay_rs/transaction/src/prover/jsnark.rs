@@ -9,7 +9,7 @@
 // from subprocess::SubprocessError
 // use tempfile::TemporaryDirectory
 // use typing::List
-use crate::interface::ZkayProverInterface;
+use crate::interface::{ZkayProverInterface, ZkayRuntimeError};
 use jsnark_interface::jsnark_interface as jsnark;
 use jsnark_interface::libsnark_interface as libsnark;
 use std::path::PathBuf;
@@ -26,7 +26,7 @@ impl ZkayProverInterface for JsnarkProver {
         priv_values: Vec<String>,
         in_vals: Vec<String>,
         out_vals: Vec<String>,
-    ) -> Vec<String> {
+    ) -> Result<Vec<String>, ZkayRuntimeError> {
         let args: Vec<_> = in_vals
             .iter()
             .chain(&out_vals)
@@ -56,10 +56,12 @@ impl ZkayProverInterface for JsnarkProver {
         //     raise ProofGenerationError(e.args)
 
         // with open(proof_path) as f:
-        let s = std::fs::read_to_string(proof_path).unwrap();
+        let s = std::fs::read_to_string(&proof_path).map_err(|e| {
+            ZkayRuntimeError::ProofGenerationFailed(format!("{}: {e}", proof_path.display()))
+        })?;
         let proof_lines = s.split("\n");
         let proof: Vec<_> = proof_lines.map(|x| x.to_owned()).collect(); //list(map(lambda x: int(x, 0), ));
-        proof
+        Ok(proof)
     }
     fn get_prover_key_hash(&self, verifier_directory: &str) -> Vec<u8> {
         hash_file(
@@ -18,6 +18,7 @@ use foundry_compilers::Project;
  use alloy_primitives::Address;
 
 // use alloy_sol_types::sol_data::Address;
+use crate::blockchain::evm::EvmBlockchain;
 use crate::blockchain::web3rs::{
     Web3Blockchain, Web3BlockchainBase, Web3HttpGanacheBlockchain, Web3TesterBlockchain,
 };
@@ -42,7 +43,7 @@ use zkay_config::config_user::UserConfig;
 // use crate::crypto::rsa_pkcs15::RSAPKCS15Crypto;
 use crate::interface::{
     ZkayBlockchainInterface, ZkayCryptoInterface, ZkayHomomorphicCryptoInterface,
-    ZkayKeystoreInterface, ZkayProverInterface,
+    ZkayKeystoreInterface, ZkayProverInterface, ZkayRuntimeError,
 };
 use crate::keystore::simple::SimpleKeystore;
 use crate::prover::jsnark::*;
@@ -77,8 +78,8 @@ pub fn _crypto_classes<
 >(
     crypto_backend: &str,
     key_store: RcCell<K>,
-) -> CryptoClass<P, B, K> {
-    match crypto_backend {
+) -> std::result::Result<CryptoClass<P, B, K>, ZkayRuntimeError> {
+    Ok(match crypto_backend {
         // "dummy" => DummyCrypto,
         // "dummy-hom" => DummyHomCrypto,
         // "rsa-pkcs1.5" => RSAPKCS15Crypto,
@@ -89,14 +90,14 @@ pub fn _crypto_classes<
         }
         // "paillier" => PaillierCrypto,
         "elgamal" => CryptoClass::ElgamalCrypto(ElgamalCrypto::<P, B, K>::new(key_store)),
-        _ => panic!("unknown {crypto_backend}"),
-    }
+        _ => return Err(ZkayRuntimeError::unknown_backend("crypto", crypto_backend)),
+    })
 }
 
-pub fn _prover_classes(snark_backend: &str) -> JsnarkProver {
+pub fn _prover_classes(snark_backend: &str) -> std::result::Result<JsnarkProver, ZkayRuntimeError> {
     match snark_backend {
-        "jsnark" => JsnarkProver,
-        _ => panic!("unknown {snark_backend}"),
+        "jsnark" => Ok(JsnarkProver),
+        _ => Err(ZkayRuntimeError::unknown_backend("snark", snark_backend)),
     }
 }
 
@@ -105,6 +106,7 @@ pub fn _prover_classes(snark_backend: &str) -> JsnarkProver {
 pub enum BlockchainClass<P: ZkayProverInterface + Clone> {
     Web3TesterBlockchain(Web3BlockchainBase<P, Web3TesterBlockchain>),
     Web3HttpGanacheBlockchain(Web3BlockchainBase<P, Web3HttpGanacheBlockchain>),
+    EvmBlockchain(EvmBlockchain<P>),
 }
 // impl<P:ZkayProverInterface>  ZkayBlockchainInterface<P> for BlockchainClass<P>{
 // // pub fn new(blockchain_class:B)->Self{
@@ -119,8 +121,8 @@ pub fn _blockchain_classes<P: ZkayProverInterface + Clone>(
     prover: RcCell<P>,
     eth: Option<EthereumOpts>,
     rpc: Option<RpcOpts>,
-) -> BlockchainClass<P> {
-    match blockchain_backend {
+) -> std::result::Result<BlockchainClass<P>, ZkayRuntimeError> {
+    Ok(match blockchain_backend {
         "w3-eth-tester" => BlockchainClass::Web3TesterBlockchain(Web3BlockchainBase::<
             P,
             Web3TesterBlockchain,
@@ -129,12 +131,13 @@ pub fn _blockchain_classes<P: ZkayProverInterface + Clone>(
             P,
             Web3HttpGanacheBlockchain,
         >::new(prover,eth,rpc)),
+        "w3-evm" => BlockchainClass::EvmBlockchain(EvmBlockchain::new(prover, rpc)),
         // "w3-ipc" => Web3IpcBlockchain,
         // "w3-websocket" => Web3WebsocketBlockchain,
         // "w3-http" => Web3HttpBlockchain,
         // "w3-custom" => Web3CustomBlockchain,
-        _ => panic!("unknown {blockchain_backend}"),
-    }
+        _ => return Err(ZkayRuntimeError::unknown_backend("blockchain", blockchain_backend)),
+    })
 }
 
 // class Runtime:
@@ -200,7 +203,10 @@ impl<
 
     //     @staticmethod
     //         """Return object which implements ZkayKeystoreInterface for given homomorphism."""
-    pub fn keystore(&self, crypto_params: &CryptoParams) -> RcCell<K> {
+    pub fn keystore(
+        &self,
+        crypto_params: &CryptoParams,
+    ) -> std::result::Result<RcCell<K>, ZkayRuntimeError> {
         let crypto_backend = crypto_params.crypto_name.clone();
         // if !self.__keystore.borrow().contains_key(&crypto_backend) {
         //     // let k=SimpleKeystore::<P,BlockchainClass<P>>::new(blockchain.clone(), crypto_params.clone());
@@ -208,12 +214,19 @@ impl<
         //         .borrow_mut()
         //         .insert(crypto_backend.clone(), f(crypto_params));
         // }
-        self.__keystore.borrow()[&crypto_backend].clone()
+        self.__keystore
+            .borrow()
+            .get(&crypto_backend)
+            .cloned()
+            .ok_or_else(|| ZkayRuntimeError::unknown_backend("keystore", &crypto_backend))
     }
 
     //     @staticmethod
     //         """Return object which implements ZkayCryptoInterface for given homomorphism."""
-    pub fn crypto(&self, crypto_params: &CryptoParams) -> RcCell<CryptoClass<P, B, K>> {
+    pub fn crypto(
+        &self,
+        crypto_params: &CryptoParams,
+    ) -> std::result::Result<RcCell<CryptoClass<P, B, K>>, ZkayRuntimeError> {
         let crypto_backend = crypto_params.crypto_name.clone();
         // if !self.__crypto.borrow().contains_key(&crypto_backend) {
         //     let keystore = self.keystore(crypto_params, f).clone();
@@ -222,7 +235,11 @@ impl<
         //         RcCell::new(_crypto_classes::<P, B, K>(&crypto_backend, keystore)),
         //     );
         // }
-        self.__crypto.borrow()[&crypto_backend].clone()
+        self.__crypto
+            .borrow()
+            .get(&crypto_backend)
+            .cloned()
+            .ok_or_else(|| ZkayRuntimeError::unknown_backend("crypto", &crypto_backend))
     }
     //     @staticmethod
     // """Return singleton object which implements ZkayProverInterface."""
@@ -0,0 +1,410 @@
+// Thread-safe, async access to the runtime API for services which serve many users.
+//
+// The runtime backends (Runtime, ApiWrapper, ContractSimulator) share their state through RcCell and
+// the web3 backends call block_on internally, so they can neither be moved between threads nor be used
+// on a thread of an async executor. ZkayService confines the runtime of every user session to a worker
+// on the blocking thread pool of the executor, which also runs simulation and proof generation of the
+// session. The number of workers is bounded, a new session waits until another one is closed.
+// The handles are Arc based, Send + Sync, and their async functions queue a request on the worker of
+// the session and await its result, so the sessions of different users are served concurrently.
+//
+// All sessions use the configuration the contract was compiled with. It is imported from the manifest
+// once when the service is created, connecting a session then leaves the global configuration untouched.
+use crate::interface::ZkayRuntimeError;
+use crate::offchain::{
+    new_contract_simulator, BlockchainClassType, ContractSimulator, CryptoClassType, DryRunReport,
+    KeystoreType,
+};
+use crate::prover::jsnark::JsnarkProver;
+use crate::types::DataType;
+use alloy_primitives::Address;
+use foundry_cli::opts::{EthereumOpts, RpcOpts};
+use foundry_compilers::Project;
+use privacy::manifest::Manifest;
+use proving_scheme::proving_scheme::ProvingScheme;
+use rccell::RcCell;
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+use zkay_config::zk_print;
+
+pub type SessionSimulator =
+    ContractSimulator<CryptoClassType, JsnarkProver, BlockchainClassType, KeystoreType>;
+
+type Job = Box<dyn FnOnce(&RcCell<SessionSimulator>) + Send>;
+
+// # The handles are shared between the tasks of a service
+const _: () = {
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_handles() {
+        assert_send_sync::<ZkayService>();
+        assert_send_sync::<ZkaySession>();
+    }
+};
+
+struct ServiceInner {
+    project_dir: String,
+    contract_name: String,
+    eth: Option<EthereumOpts>,
+    rpc: Option<RpcOpts>,
+    // one permit per session worker
+    workers: Arc<Semaphore>,
+    sessions: Mutex<BTreeMap<String, ZkaySession>>,
+}
+
+// One runtime for a compiled contract, hands out a session per user
+#[derive(Clone)]
+pub struct ZkayService {
+    inner: Arc<ServiceInner>,
+}
+impl ZkayService {
+    // """
+    // :param project_dir: output directory of the compiled contract
+    // :param contract_name: name of the contract the sessions interact with
+    // :param max_sessions: how many sessions are served at the same time
+    // :raise IntegrityError: if the manifest of the contract cannot be loaded
+    // """
+    pub fn new(
+        project_dir: &str,
+        contract_name: &str,
+        eth: Option<EthereumOpts>,
+        rpc: Option<RpcOpts>,
+        max_sessions: usize,
+    ) -> Result<Self, ZkayRuntimeError> {
+        let manifest = Manifest::try_load(project_dir).map_err(|e| {
+            ZkayRuntimeError::IntegrityError(format!(
+                "Could not load manifest of {project_dir}: {e}"
+            ))
+        })?;
+        Manifest::import_manifest_config(manifest);
+        Ok(Self {
+            inner: Arc::new(ServiceInner {
+                project_dir: project_dir.to_owned(),
+                contract_name: contract_name.to_owned(),
+                eth,
+                rpc,
+                workers: Arc::new(Semaphore::new(max_sessions)),
+                sessions: Mutex::new(BTreeMap::new()),
+            }),
+        })
+    }
+
+    // Session of user_addr, its worker is started on first use (once a worker is free)
+    pub async fn session(&self, user_addr: &str) -> Result<ZkaySession, ZkayRuntimeError> {
+        let running = self.inner.sessions.lock().unwrap().get(user_addr).cloned();
+        if let Some(session) = running.filter(|session| !session.is_closed()) {
+            return Ok(session);
+        }
+        let worker = self
+            .inner
+            .workers
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| ZkayRuntimeError::SessionClosed(user_addr.to_owned()))?;
+        let session = ZkaySession::start(
+            worker,
+            &self.inner.project_dir,
+            &self.inner.contract_name,
+            user_addr,
+            self.inner.eth.clone(),
+            self.inner.rpc.clone(),
+        )
+        .await?;
+        // # Another request may have started a session for the same user in the meantime
+        let mut sessions = self.inner.sessions.lock().unwrap();
+        let session = match sessions.get(user_addr) {
+            Some(running) if !running.is_closed() => running.clone(),
+            _ => {
+                sessions.insert(user_addr.to_owned(), session.clone());
+                session
+            }
+        };
+        Ok(session)
+    }
+
+    // Stop the session of user_addr, requests which are already queued are still served,
+    // its worker is free for another session afterwards
+    pub fn close_session(&self, user_addr: &str) -> bool {
+        let session = self.inner.sessions.lock().unwrap().remove(user_addr);
+        session.map(|session| session.close()).is_some()
+    }
+
+    pub fn sessions(&self) -> Vec<String> {
+        self.inner
+            .sessions
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+}
+
+struct SessionInner {
+    user_addr: String,
+    jobs: Mutex<Option<mpsc::Sender<Job>>>,
+}
+
+// Handle of the runtime session of one user, all clones share the same worker
+#[derive(Clone)]
+pub struct ZkaySession {
+    inner: Arc<SessionInner>,
+}
+impl ZkaySession {
+    async fn start(
+        worker: OwnedSemaphorePermit,
+        project_dir: &str,
+        contract_name: &str,
+        user_addr: &str,
+        eth: Option<EthereumOpts>,
+        rpc: Option<RpcOpts>,
+    ) -> Result<Self, ZkayRuntimeError> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let (started, start_result) = oneshot::channel();
+        let (project_dir, contract_name, worker_user) = (
+            project_dir.to_owned(),
+            contract_name.to_owned(),
+            user_addr.to_owned(),
+        );
+        tokio::task::spawn_blocking(move || {
+            // # The worker is released when the session ends
+            let _worker = worker;
+            let simulator = match new_contract_simulator(
+                &project_dir,
+                &contract_name,
+                &worker_user,
+                eth,
+                rpc,
+            ) {
+                Ok(simulator) => RcCell::new(simulator),
+                Err(e) => {
+                    let _ = started.send(Err(e));
+                    return;
+                }
+            };
+            let _ = started.send(Ok(()));
+            // # Runs until all handles of the session are dropped or the session is closed
+            while let Ok(job) = queue.recv() {
+                job(&simulator);
+            }
+            zk_print!("Session of {worker_user} closed");
+        });
+        start_result
+            .await
+            .map_err(|_| ZkayRuntimeError::SessionClosed(user_addr.to_owned()))??;
+        Ok(Self {
+            inner: Arc::new(SessionInner {
+                user_addr: user_addr.to_owned(),
+                jobs: Mutex::new(Some(jobs)),
+            }),
+        })
+    }
+
+    pub fn user_address(&self) -> &str {
+        &self.inner.user_addr
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.jobs.lock().unwrap().is_none()
+    }
+
+    // Stop accepting requests, the worker exits once the queued requests are served
+    pub fn close(&self) {
+        self.inner.jobs.lock().unwrap().take();
+    }
+
+    // """
+    // Run f with the contract simulator of this session on the session worker.
+
+    // Generated contract functions (simulation, proof generation and transaction) are issued this way,
+    // e.g. session.run(|cs| Survey::connect(..., cs.clone(), ...)?.vote(1)).
+    // """
+    pub async fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&RcCell<SessionSimulator>) -> Result<R, ZkayRuntimeError> + Send + 'static,
+    ) -> Result<R, ZkayRuntimeError> {
+        let closed = || {
+            self.close();
+            ZkayRuntimeError::SessionClosed(self.inner.user_addr.clone())
+        };
+        let (result, receiver) = oneshot::channel();
+        let job: Job = Box::new(move |simulator| {
+            let _ = result.send(f(simulator));
+        });
+        let sent = match self.inner.jobs.lock().unwrap().as_ref() {
+            Some(jobs) => jobs.send(job).is_ok(),
+            None => false,
+        };
+        if !sent {
+            return Err(closed());
+        }
+        // # The sender is dropped without a result if the worker panicked
+        receiver.await.map_err(|_| closed())?
+    }
+
+    pub async fn deploy(
+        &self,
+        actual_args: Vec<String>,
+        should_encrypt: Vec<bool>,
+        wei_amount: Option<i32>,
+        project: Project,
+    ) -> Result<Address, ZkayRuntimeError> {
+        self.run(move |simulator| {
+            let api = simulator.borrow().api.clone();
+            let address = api
+                .borrow()
+                .deploy(actual_args, should_encrypt, wei_amount, &project);
+            address
+        })
+        .await
+    }
+
+    pub async fn connect<PS: ProvingScheme + 'static>(
+        &self,
+        address: Option<Address>,
+        compile_zkay_file: fn(
+            input_file_path: &str,
+            output_dir: &str,
+            import_keys: bool,
+        ) -> anyhow::Result<()>,
+        get_verification_contract_names: fn(code_or_ast: String) -> Vec<String>,
+        project: Project,
+    ) -> Result<(), ZkayRuntimeError> {
+        self.run(move |simulator| {
+            let api = simulator.borrow().api.clone();
+            let connected = api.borrow().connect::<PS>(
+                address.as_ref(),
+                compile_zkay_file,
+                get_verification_contract_names,
+                &project,
+            );
+            connected
+        })
+        .await
+    }
+
+    pub async fn transact(
+        &self,
+        fname: &str,
+        args: Vec<DataType>,
+        should_encrypt: Vec<bool>,
+        wei_amount: Option<i32>,
+    ) -> Result<Option<DryRunReport>, ZkayRuntimeError> {
+        let fname = fname.to_owned();
+        self.run(move |simulator| {
+            let api = simulator.borrow().api.clone();
+            let transacted = api
+                .borrow()
+                .transact(&fname, args, should_encrypt, wei_amount);
+            transacted
+        })
+        .await
+    }
+
+    pub async fn call(
+        &self,
+        fname: &str,
+        args: Vec<DataType>,
+        ret_val_constructors: Vec<(bool, String, fn(String) -> DataType)>,
+    ) -> Result<DataType, ZkayRuntimeError> {
+        let fname = fname.to_owned();
+        self.run(move |simulator| {
+            let api = simulator.borrow().api.clone();
+            let ret = api.borrow().call(&fname, args, ret_val_constructors);
+            ret
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use zkay_config::config::CFG;
+
+    const ALICE: &str = "0x00000000000000000000000000000000000a11ce";
+    const BOB: &str = "0x0000000000000000000000000000000000000b0b";
+
+    // Project directory with a manifest of the current configuration
+    fn project_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("zkay_service_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut manifest = serde_json::Map::new();
+        manifest.insert(Manifest::solc_version.to_owned(), json!("0.8.23"));
+        manifest.insert(
+            Manifest::zkay_options.to_owned(),
+            CFG.lock().unwrap().export_compiler_settings(),
+        );
+        std::fs::write(
+            dir.join("manifest.json"),
+            Value::Object(manifest).to_string(),
+        )
+        .unwrap();
+        dir.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn missing_manifest_is_an_error() {
+        let dir = std::env::temp_dir().join("zkay_service_missing_manifest");
+        let created = ZkayService::new(dir.to_str().unwrap(), "Survey", None, None, 1);
+        assert!(matches!(created, Err(ZkayRuntimeError::IntegrityError(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn sessions_are_served_concurrently() {
+        let service =
+            ZkayService::new(&project_dir("concurrent"), "Survey", None, None, 2).unwrap();
+        // # Each request waits for the request of the other session, so they only
+        // # complete if both sessions are served at the same time
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let requests = [ALICE, BOB].map(|user| {
+            let (service, barrier) = (service.clone(), barrier.clone());
+            tokio::spawn(async move {
+                let session = service.session(user).await?;
+                session
+                    .run(move |simulator| {
+                        barrier.wait();
+                        Ok(simulator.borrow().api.borrow().user_address())
+                    })
+                    .await
+            })
+        });
+        for (request, user) in requests.into_iter().zip([ALICE, BOB]) {
+            let served = tokio::time::timeout(Duration::from_secs(60), request)
+                .await
+                .expect("sessions were not served concurrently")
+                .unwrap()
+                .unwrap();
+            assert_eq!(served, user);
+        }
+        assert_eq!(service.sessions(), vec![BOB.to_owned(), ALICE.to_owned()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn sessions_wait_for_a_free_worker() {
+        let service = ZkayService::new(&project_dir("bounded"), "Survey", None, None, 1).unwrap();
+        service.session(ALICE).await.unwrap();
+        let waiting = tokio::spawn({
+            let service = service.clone();
+            async move {
+                service
+                    .session(BOB)
+                    .await
+                    .map(|s| s.user_address().to_owned())
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!waiting.is_finished());
+
+        assert!(service.close_session(ALICE));
+        let started = tokio::time::timeout(Duration::from_secs(60), waiting)
+            .await
+            .expect("closing a session did not free its worker")
+            .unwrap()
+            .unwrap();
+        assert_eq!(started, BOB);
+    }
+}
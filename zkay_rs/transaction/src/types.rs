@@ -6,6 +6,7 @@
 #![allow(unused_mut)]
 #![allow(unused_braces)]
 // use typing::Optional, Collection, Any, Dict, Tuple, List, Union, Callable
+use alloy_primitives::{I256, U256};
 use std::marker::PhantomData;
 use std::ops::{
     Index, IndexMut, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive,
@@ -26,7 +27,10 @@ pub enum DataType {
     PublicKeyValue(Value<String, PublicKeyValue>),
     RandomnessValue(Value<String, RandomnessValue>),
     Bool(bool),
-    Int(u128),
+    // uint<N> values
+    Int(U256),
+    // int<N> values
+    SignedInt(I256),
     String(String),
     List(Vec<DataType>),
 }
@@ -34,15 +38,75 @@ pub enum DataType {
 impl From<u128> for DataType {
     #[inline]
     fn from(item: u128) -> Self {
+        DataType::Int(U256::from(item))
+    }
+}
+impl From<U256> for DataType {
+    #[inline]
+    fn from(item: U256) -> Self {
         DataType::Int(item)
     }
 }
+impl From<I256> for DataType {
+    #[inline]
+    fn from(item: I256) -> Self {
+        DataType::SignedInt(item)
+    }
+}
 impl From<bool> for DataType {
     #[inline]
     fn from(item: bool) -> Self {
         DataType::Bool(item)
     }
 }
+impl DataType {
+    // Constructor for uint return values and state variables (decimal or 0x prefixed hex), other strings are kept as is
+    pub fn parse_uint(v: String) -> Self {
+        U256::from_str_radix(
+            v.trim_start_matches("0x"),
+            if v.starts_with("0x") { 16 } else { 10 },
+        )
+        .map_or(DataType::String(v), DataType::Int)
+    }
+
+    // Constructor for int<BITS> values, either decimal (as returned by the blockchain backends) or the
+    // unsigned circuit representation (as returned by decryption), which is the inverse of to_uint
+    pub fn parse_int<const BITS: usize>(v: String) -> Self {
+        if v.starts_with('-') {
+            return I256::from_dec_str(&v).map_or(DataType::String(v), DataType::SignedInt);
+        }
+        match U256::from_str_radix(&v, 10) {
+            // # Sign extension of bitwidth bit two's complement integers
+            Ok(u) if BITS < 256 && u.bit(BITS - 1) => {
+                DataType::SignedInt(I256::from_raw(u | (U256::MAX << BITS)))
+            }
+            Ok(u) => DataType::SignedInt(I256::from_raw(u)),
+            Err(_) => DataType::String(v),
+        }
+    }
+
+    // Value as an integer of the circuit, i.e. the unsigned representation of a bitwidth bit two's complement integer
+    pub fn to_uint(&self, bitwidth: i32) -> Option<U256> {
+        match self {
+            DataType::Bool(b) => Some(U256::from(*b as u8)),
+            DataType::Int(i) => Some(*i),
+            DataType::SignedInt(i) if i.is_negative() => {
+                let raw = i.into_raw();
+                Some(if bitwidth < 256 {
+                    raw & ((U256::from(1) << bitwidth as usize) - U256::from(1))
+                } else {
+                    raw
+                })
+            }
+            DataType::SignedInt(i) => Some(i.into_raw()),
+            DataType::String(s) => match DataType::parse_uint(s.clone()) {
+                DataType::Int(i) => Some(i),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Default, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Value<T: Clone + Default, V: Clone + Default> {
@@ -452,3 +516,57 @@ impl TxStruct {
         self.origin.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(v: i64) -> DataType {
+        DataType::SignedInt(I256::try_from(v).unwrap())
+    }
+
+    #[test]
+    fn to_uint_is_twos_complement_of_bitwidth() {
+        assert_eq!(DataType::Bool(true).to_uint(8), Some(U256::from(1)));
+        assert_eq!(
+            DataType::Int(U256::from(255)).to_uint(8),
+            Some(U256::from(255))
+        );
+        assert_eq!(int(-1).to_uint(8), Some(U256::from(255)));
+        assert_eq!(int(-128).to_uint(8), Some(U256::from(128)));
+        assert_eq!(int(-1).to_uint(32), Some(U256::from(u32::MAX)));
+        assert_eq!(int(127).to_uint(8), Some(U256::from(127)));
+        assert_eq!(int(-1).to_uint(256), Some(U256::MAX));
+        assert_eq!(
+            DataType::SignedInt(I256::MIN).to_uint(256),
+            Some(U256::from(1) << 255)
+        );
+        assert_eq!(DataType::Int(U256::MAX).to_uint(256), Some(U256::MAX));
+        assert_eq!(
+            DataType::String("0x10".to_owned()).to_uint(8),
+            Some(U256::from(16))
+        );
+        assert_eq!(DataType::String("abc".to_owned()).to_uint(8), None);
+    }
+
+    #[test]
+    fn parse_int_inverts_to_uint() {
+        for v in [-128, -5, -1, 0, 1, 127] {
+            let raw = int(v).to_uint(8).unwrap().to_string();
+            assert_eq!(DataType::parse_int::<8>(raw), int(v));
+            assert_eq!(DataType::parse_int::<8>(v.to_string()), int(v));
+        }
+        for v in [I256::MIN, I256::MINUS_ONE, I256::ZERO, I256::MAX] {
+            let raw = DataType::SignedInt(v).to_uint(256).unwrap().to_string();
+            assert_eq!(DataType::parse_int::<256>(raw), DataType::SignedInt(v));
+            assert_eq!(
+                DataType::parse_int::<256>(v.to_string()),
+                DataType::SignedInt(v)
+            );
+        }
+        assert_eq!(
+            DataType::parse_int::<8>("abc".to_owned()),
+            DataType::String("abc".to_owned())
+        );
+    }
+}
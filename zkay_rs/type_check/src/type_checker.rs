@@ -11,6 +11,7 @@ use rccell::{RcCell, WeakCell};
 // use crate::type_exceptions::{TypeMismatchException, TypeException};
 use zkay_ast::homomorphism::{Homomorphism, HOMOMORPHISM_STORE, REHOM_EXPRESSIONS};
 
+use std::cell::RefCell;
 use std::ops::DerefMut;
 use zkay_ast::ast::{
     enum_value::EnumValue, get_privacy_expr_from_label, is_instance, is_instances,
@@ -32,11 +33,28 @@ use zkay_ast::ast::{
     UserDefinedTypeNameBaseProperty, VariableDeclarationStatement, WhileStatement, AST,
 };
 use zkay_ast::global_defs::GlobalVars;
+use zkay_ast::lints::source_location;
 use zkay_ast::visitors::deep_copy::replace_expr;
 use zkay_ast::visitors::visitor::{AstVisitor, AstVisitorBase, AstVisitorBaseRef};
 use zkay_derive::ASTVisitorBaseRefImpl;
+thread_local! {
+    // source locations of the nodes currently being type checked, innermost last;
+    // left in place when a check panics so the error can be located afterwards
+    static CHECKING: RefCell<Vec<(i32, i32)>> = RefCell::new(Vec::new());
+}
+
+// Location of the node whose type check failed on this thread, if any
+pub fn take_type_error_location() -> Option<(i32, i32)> {
+    CHECKING
+        .with(|c| std::mem::take(&mut *c.borrow_mut()))
+        .into_iter()
+        .rev()
+        .find(|&(line, _)| line > 0)
+}
+
 pub fn type_check(ast: &ASTFlatten, global_vars: RcCell<GlobalVars>) {
     // println!("==========type_check=====================");
+    CHECKING.with(|c| c.borrow_mut().clear());
     check_final(ast);
     let v = TypeCheckVisitor::new(global_vars);
     let _ = v.visit(&ast);
@@ -86,6 +104,20 @@ impl AstVisitor for TypeCheckVisitor {
             )
     }
     fn get_attr(&self, name: &ASTType, ast: &ASTFlatten) -> eyre::Result<Self::Return> {
+        CHECKING.with(|c| c.borrow_mut().push(source_location(ast)));
+        let res = self.dispatch(name, ast);
+        CHECKING.with(|c| c.borrow_mut().pop());
+        res
+    }
+}
+impl TypeCheckVisitor {
+    pub fn new(global_vars: RcCell<GlobalVars>) -> Self {
+        Self {
+            ast_visitor_base: AstVisitorBase::new("post", false),
+            global_vars,
+        }
+    }
+    fn dispatch(&self, name: &ASTType, ast: &ASTFlatten) -> eyre::Result<()> {
         match name {
             ASTType::VariableDeclarationStatement => self.visitVariableDeclarationStatement(ast),
             ASTType::PrimitiveCastExpr => self.visitPrimitiveCastExpr(ast),
@@ -130,14 +162,6 @@ impl AstVisitor for TypeCheckVisitor {
             _ => Err(eyre::eyre!("unimplemented")),
         }
     }
-}
-impl TypeCheckVisitor {
-    pub fn new(global_vars: RcCell<GlobalVars>) -> Self {
-        Self {
-            ast_visitor_base: AstVisitorBase::new("post", false),
-            global_vars,
-        }
-    }
     pub fn get_rhs(
         &self,
         rhs: &ASTFlatten,
@@ -0,0 +1,397 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(nonstandard_style)]
+#![allow(unused_imports)]
+#![allow(unused_mut)]
+#![allow(unused_braces)]
+// """
+// Exports the results of call_graph_analysis, detect_hybrid_functions and alias_analysis
+// as a graph of functions, calls and privacy flows between owners.
+// """
+use crate::analysis::information_leakage::label_of;
+use crate::ast::{
+    expression::{
+        Expression, FunctionCallExprBaseProperty, ReclassifyExpr, ReclassifyExprBaseProperty,
+    },
+    is_instance,
+    statement::{AssignmentStatementBaseProperty, SimpleStatement, Statement},
+    ASTBaseProperty, ASTFlatten, ASTInstanceOf, ASTType, IntoAST, AST,
+};
+use crate::visitors::visitor::{AstVisitor, AstVisitorBase, AstVisitorBaseRef};
+use rccell::RcCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use zkay_derive::ASTVisitorBaseRefImpl;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionNode {
+    // qualified name "Contract.function"
+    pub id: String,
+    pub contract: String,
+    pub function: String,
+    pub is_constructor: bool,
+    // contains private expressions itself
+    pub hybrid: bool,
+    // needs a proof (directly or because it calls a hybrid function)
+    pub requires_verification: bool,
+    pub has_static_body: bool,
+    pub is_recursive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CallEdge {
+    pub caller: String,
+    pub callee: String,
+}
+
+// Data moving from one owner to another within a function
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PrivacyFlow {
+    pub function: String,
+    pub from: String,
+    pub to: String,
+    // reveal or assignment
+    pub kind: String,
+    // number of locations with this flow
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractGraph {
+    pub functions: Vec<FunctionNode>,
+    pub calls: Vec<CallEdge>,
+    pub flows: Vec<PrivacyFlow>,
+}
+
+// """
+// :param ast: type-checked source unit (after call graph, hybrid function and alias analysis)
+// :return: all functions, direct calls between them and privacy flows between owners
+// """
+pub fn contract_graph(ast: &ASTFlatten) -> ContractGraph {
+    let v = ContractGraphVisitor::new();
+    let _ = v.visit(ast);
+    let mut calls = v.calls.borrow().clone();
+    calls.sort();
+    calls.dedup();
+    let flows = v
+        .flows
+        .borrow()
+        .iter()
+        .map(|((function, from, to, kind), count)| PrivacyFlow {
+            function: function.clone(),
+            from: from.clone(),
+            to: to.clone(),
+            kind: kind.clone(),
+            count: *count,
+        })
+        .collect();
+    let functions = v.functions.borrow().clone();
+    ContractGraph {
+        functions,
+        calls,
+        flows,
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
+
+impl ContractGraph {
+    // Graphviz digraph of all functions and calls, hybrid functions are drawn bold,
+    // functions which require verification are filled
+    pub fn call_graph_dot(&self) -> String {
+        let mut lines = vec![
+            String::from("digraph call_graph {"),
+            String::from("    node [shape=box];"),
+        ];
+        let mut by_contract: BTreeMap<&str, Vec<&FunctionNode>> = BTreeMap::new();
+        for f in &self.functions {
+            by_contract.entry(&f.contract).or_default().push(f);
+        }
+        for (i, (contract, fcts)) in by_contract.into_iter().enumerate() {
+            lines.push(format!("    subgraph cluster_{i} {{"));
+            lines.push(format!("        label={};", quote(contract)));
+            for f in fcts {
+                let mut style = vec![];
+                if f.hybrid {
+                    style.push("bold");
+                }
+                if f.requires_verification {
+                    style.push("filled");
+                }
+                lines.push(format!(
+                    "        {} [label={}{}];",
+                    quote(&f.id),
+                    quote(&f.function),
+                    if style.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", style={}", quote(&style.join(",")))
+                    }
+                ));
+            }
+            lines.push(String::from("    }"));
+        }
+        for c in &self.calls {
+            lines.push(format!("    {} -> {};", quote(&c.caller), quote(&c.callee)));
+        }
+        lines.push(String::from("}"));
+        lines.join("\n")
+    }
+
+    // Graphviz digraph with one cluster per function, whose nodes are owners and edges are privacy flows
+    pub fn privacy_flow_dot(&self) -> String {
+        let mut lines = vec![String::from("digraph privacy_flows {")];
+        let mut by_function: BTreeMap<&str, Vec<&PrivacyFlow>> = BTreeMap::new();
+        for f in &self.flows {
+            by_function.entry(&f.function).or_default().push(f);
+        }
+        for (i, (function, flows)) in by_function.into_iter().enumerate() {
+            lines.push(format!("    subgraph cluster_{i} {{"));
+            lines.push(format!("        label={};", quote(function)));
+            for f in flows {
+                lines.push(format!(
+                    "        {} -> {} [label={}];",
+                    quote(&format!("{function}:{}", f.from)),
+                    quote(&format!("{function}:{}", f.to)),
+                    quote(&format!("{} x{}", f.kind, f.count))
+                ));
+            }
+            lines.push(String::from("    }"));
+        }
+        lines.push(String::from("}"));
+        lines.join("\n")
+    }
+}
+
+#[derive(ASTVisitorBaseRefImpl)]
+struct ContractGraphVisitor {
+    pub ast_visitor_base: AstVisitorBase,
+    current_function: RcCell<String>,
+    functions: RcCell<Vec<FunctionNode>>,
+    calls: RcCell<Vec<CallEdge>>,
+    // (function, from, to, kind) -> count
+    flows: RcCell<BTreeMap<(String, String, String, String), usize>>,
+}
+
+impl AstVisitor for ContractGraphVisitor {
+    type Return = ();
+    fn temper_result(&self) -> Self::Return {}
+    fn has_attr(&self, name: &ASTType, ast: &AST) -> bool {
+        matches!(
+            name,
+            ASTType::SourceUnit | ASTType::VariableDeclarationStatement
+        ) || matches!(ast, AST::Expression(Expression::FunctionCallExpr(_)))
+            || matches!(
+                ast,
+                AST::Expression(Expression::ReclassifyExpr(ReclassifyExpr::ReclassifyExpr(
+                    _
+                )))
+            )
+            || matches!(
+                ast,
+                AST::Statement(Statement::SimpleStatement(
+                    SimpleStatement::AssignmentStatement(_)
+                ))
+            )
+    }
+    fn get_attr(&self, name: &ASTType, ast: &ASTFlatten) -> eyre::Result<Self::Return> {
+        match name {
+            ASTType::SourceUnit => self.visitSourceUnit(ast),
+            ASTType::VariableDeclarationStatement => self.visitVariableDeclarationStatement(ast),
+            _ if matches!(
+                ast.to_ast(),
+                AST::Expression(Expression::FunctionCallExpr(_))
+            ) =>
+            {
+                self.visitFunctionCallExpr(ast)
+            }
+            _ if matches!(
+                ast.to_ast(),
+                AST::Expression(Expression::ReclassifyExpr(ReclassifyExpr::ReclassifyExpr(
+                    _
+                )))
+            ) =>
+            {
+                self.visitReclassifyExpr(ast)
+            }
+            _ if matches!(
+                ast.to_ast(),
+                AST::Statement(Statement::SimpleStatement(
+                    SimpleStatement::AssignmentStatement(_)
+                ))
+            ) =>
+            {
+                self.visitAssignmentStatement(ast)
+            }
+            _ => Err(eyre::eyre!("unreach")),
+        }
+    }
+}
+
+impl ContractGraphVisitor {
+    pub fn new() -> Self {
+        Self {
+            ast_visitor_base: AstVisitorBase::new("node-or-children", false),
+            current_function: RcCell::new(String::new()),
+            functions: RcCell::new(vec![]),
+            calls: RcCell::new(vec![]),
+            flows: RcCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn visitSourceUnit(&self, ast: &ASTFlatten) -> eyre::Result<<Self as AstVisitor>::Return> {
+        for c in &ast.try_as_source_unit_ref().unwrap().borrow().contracts {
+            let contract = c.borrow().idf().as_ref().unwrap().borrow().name();
+            let fcts: Vec<_> = c
+                .borrow()
+                .constructor_definitions
+                .iter()
+                .chain(&c.borrow().function_definitions)
+                .cloned()
+                .collect();
+            for f in fcts {
+                let node = {
+                    let f = f.borrow();
+                    FunctionNode {
+                        id: format!("{contract}.{}", f.name()),
+                        contract: contract.clone(),
+                        function: f.name(),
+                        is_constructor: f.is_constructor(),
+                        hybrid: f.requires_verification_when_external,
+                        requires_verification: f.requires_verification,
+                        has_static_body: f.has_static_body,
+                        is_recursive: f.is_recursive,
+                    }
+                };
+                *self.current_function.borrow_mut() = node.id.clone();
+                self.functions.borrow_mut().push(node);
+                let f: ASTFlatten = f.into();
+                let _ = self.visit_children(&f);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn visitFunctionCallExpr(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let func = ast
+            .to_ast()
+            .try_as_expression_ref()
+            .unwrap()
+            .try_as_function_call_expr_ref()
+            .unwrap()
+            .func()
+            .clone();
+        if is_instance(&func, ASTType::LocationExprBase) {
+            let target = func
+                .ast_base_ref()
+                .and_then(|base| base.borrow().target.clone())
+                .and_then(|t| t.upgrade());
+            if let Some(target) =
+                target.filter(|t| is_instance(t, ASTType::ConstructorOrFunctionDefinition))
+            {
+                let function = target
+                    .try_as_constructor_or_function_definition_ref()
+                    .unwrap()
+                    .borrow()
+                    .name();
+                let contract = target
+                    .ast_base_ref()
+                    .and_then(|base| base.borrow().parent.clone())
+                    .and_then(|p| p.upgrade())
+                    .and_then(|p| {
+                        p.try_as_contract_definition_ref()
+                            .map(|c| c.borrow().idf().as_ref().unwrap().borrow().name())
+                    })
+                    .unwrap_or_default();
+                self.calls.borrow_mut().push(CallEdge {
+                    caller: self.current_function.borrow().clone(),
+                    callee: format!("{contract}.{function}"),
+                });
+            }
+        }
+        self.visit_children(ast)
+    }
+
+    pub fn visitReclassifyExpr(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (expr, privacy) = {
+            let a = ast.to_ast();
+            let re = a
+                .try_as_expression_ref()
+                .unwrap()
+                .try_as_reclassify_expr_ref()
+                .unwrap();
+            (re.expr().clone(), re.privacy().clone())
+        };
+        self.add_flow(label_of(&expr), privacy.code(), "reveal");
+        self.visit_children(ast)
+    }
+
+    pub fn visitAssignmentStatement(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (lhs, rhs) = {
+            let a = ast.to_ast();
+            let stmt = a
+                .try_as_statement_ref()
+                .unwrap()
+                .try_as_simple_statement_ref()
+                .unwrap()
+                .try_as_assignment_statement_ref()
+                .unwrap();
+            (stmt.lhs().clone(), stmt.rhs().clone())
+        };
+        if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+            self.add_flow(label_of(&rhs), label_of(&lhs), "assignment");
+        }
+        self.visit_children(ast)
+    }
+
+    pub fn visitVariableDeclarationStatement(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (decl, expr) = {
+            let a = ast.to_ast();
+            let stmt = a
+                .try_as_statement_ref()
+                .unwrap()
+                .try_as_simple_statement_ref()
+                .unwrap()
+                .try_as_variable_declaration_statement_ref()
+                .unwrap();
+            (stmt.variable_declaration.clone(), stmt.expr.clone())
+        };
+        if let Some(expr) = expr {
+            let decl: ASTFlatten = decl.into();
+            self.add_flow(label_of(&expr), label_of(&decl), "assignment");
+        }
+        self.visit_children(ast)
+    }
+
+    // only flows between different owners are recorded
+    fn add_flow(&self, from: String, to: String, kind: &str) {
+        if from == to {
+            return;
+        }
+        *self
+            .flows
+            .borrow_mut()
+            .entry((
+                self.current_function.borrow().clone(),
+                from,
+                to,
+                kind.to_owned(),
+            ))
+            .or_default() += 1;
+    }
+}
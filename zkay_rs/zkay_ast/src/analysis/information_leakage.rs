@@ -0,0 +1,460 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(nonstandard_style)]
+#![allow(unused_imports)]
+#![allow(unused_mut)]
+#![allow(unused_braces)]
+use crate::analysis::{alias_analysis::GuardConditionAnalyzer, partition_state::PartitionState};
+use crate::ast::{
+    expression::{Expression, ReclassifyExpr, ReclassifyExprBaseProperty},
+    is_instance,
+    statement::{
+        AssignmentStatementBaseProperty, SimpleStatement, Statement, StatementBaseProperty,
+        StatementListBaseProperty,
+    },
+    ASTBaseProperty, ASTFlatten, ASTInstanceOf, ASTType, IntoAST, AST,
+};
+use crate::visitors::visitor::{AstVisitor, AstVisitorBase, AstVisitorBaseRef};
+use rccell::RcCell;
+use serde::{Deserialize, Serialize};
+use zkay_derive::ASTVisitorBaseRefImpl;
+
+// How private data leaves its owner at a given location
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakKind {
+    // explicit declassification via reveal(expr, owner)
+    Reveal,
+    // private state written for an owner which is not (provably) me
+    ImplicitExposure,
+}
+
+impl std::fmt::Display for LeakKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reveal => write!(f, "reveal"),
+            Self::ImplicitExposure => write!(f, "implicit exposure"),
+        }
+    }
+}
+
+// One location where private data becomes public or moves to another owner
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeakRecord {
+    pub kind: LeakKind,
+    pub contract: String,
+    pub function: String,
+    pub line: i32,
+    pub column: i32,
+    pub expr: String,
+    pub old_label: String,
+    pub new_label: String,
+    // conditions guarding the location, outermost first
+    pub guard: Vec<String>,
+    // privacy labels which the GuardConditionAnalyzer proves equal under the guard, e.g. "hospital == me"
+    pub guard_aliases: Vec<String>,
+}
+
+// """
+// :param ast: type-checked source unit (after alias analysis)
+// :return: all declassifications and implicit exposures, in source order
+// """
+pub fn information_leakage_analysis(ast: &ASTFlatten) -> Vec<LeakRecord> {
+    collect_leaks(ast).into_iter().map(|(_, l)| l).collect()
+}
+
+// Implicit exposures as (statement, message) pairs (used by the implicit_exposure lint)
+pub fn implicit_exposures(ast: &ASTFlatten) -> Vec<(ASTFlatten, String)> {
+    collect_leaks(ast)
+        .into_iter()
+        .filter(|(_, l)| l.kind == LeakKind::ImplicitExposure)
+        .map(|(elem, l)| {
+            (
+                elem,
+                format!(
+                    "\"{}\" writes private data labeled {} for {} in {}.{}",
+                    l.expr, l.old_label, l.new_label, l.contract, l.function
+                ),
+            )
+        })
+        .collect()
+}
+
+fn collect_leaks(ast: &ASTFlatten) -> Vec<(ASTFlatten, LeakRecord)> {
+    let v = InformationLeakageVisitor::new();
+    let _ = v.visit(ast);
+    let leaks = v.leaks.borrow().clone();
+    leaks
+}
+
+#[derive(ASTVisitorBaseRefImpl)]
+struct InformationLeakageVisitor {
+    pub ast_visitor_base: AstVisitorBase,
+    current_contract: RcCell<String>,
+    current_function: RcCell<String>,
+    // enclosing branch/loop conditions and preceding requires, with the alias analysis state before them
+    guards: RcCell<Vec<(ASTFlatten, Option<PartitionState<AST>>)>>,
+    leaks: RcCell<Vec<(ASTFlatten, LeakRecord)>>,
+}
+
+impl AstVisitor for InformationLeakageVisitor {
+    type Return = ();
+    fn temper_result(&self) -> Self::Return {}
+    fn has_attr(&self, name: &ASTType, ast: &AST) -> bool {
+        matches!(
+            name,
+            ASTType::SourceUnit
+                | ASTType::IfStatement
+                | ASTType::WhileStatement
+                | ASTType::DoWhileStatement
+                | ASTType::ForStatement
+                | ASTType::Block
+        ) || matches!(
+            ast,
+            AST::Expression(Expression::ReclassifyExpr(ReclassifyExpr::ReclassifyExpr(
+                _
+            )))
+        ) || matches!(
+            ast,
+            AST::Statement(Statement::SimpleStatement(
+                SimpleStatement::AssignmentStatement(_)
+            ))
+        )
+    }
+    fn get_attr(&self, name: &ASTType, ast: &ASTFlatten) -> eyre::Result<Self::Return> {
+        match name {
+            ASTType::SourceUnit => self.visitSourceUnit(ast),
+            ASTType::IfStatement => self.visitIfStatement(ast),
+            ASTType::WhileStatement => self.visitWhileStatement(ast),
+            ASTType::DoWhileStatement => self.visitDoWhileStatement(ast),
+            ASTType::ForStatement => self.visitForStatement(ast),
+            ASTType::Block => self.visitBlock(ast),
+            _ if matches!(
+                ast.to_ast(),
+                AST::Expression(Expression::ReclassifyExpr(ReclassifyExpr::ReclassifyExpr(
+                    _
+                )))
+            ) =>
+            {
+                self.visitReclassifyExpr(ast)
+            }
+            _ if matches!(
+                ast.to_ast(),
+                AST::Statement(Statement::SimpleStatement(
+                    SimpleStatement::AssignmentStatement(_)
+                ))
+            ) =>
+            {
+                self.visitAssignmentStatement(ast)
+            }
+            _ => Err(eyre::eyre!("unreach")),
+        }
+    }
+}
+
+impl InformationLeakageVisitor {
+    pub fn new() -> Self {
+        Self {
+            ast_visitor_base: AstVisitorBase::new("node-or-children", false),
+            current_contract: RcCell::new(String::new()),
+            current_function: RcCell::new(String::new()),
+            guards: RcCell::new(vec![]),
+            leaks: RcCell::new(vec![]),
+        }
+    }
+
+    pub fn visitSourceUnit(&self, ast: &ASTFlatten) -> eyre::Result<<Self as AstVisitor>::Return> {
+        for c in &ast.try_as_source_unit_ref().unwrap().borrow().contracts {
+            *self.current_contract.borrow_mut() =
+                c.borrow().idf().as_ref().unwrap().borrow().name();
+            let fcts: Vec<_> = c
+                .borrow()
+                .constructor_definitions
+                .iter()
+                .chain(&c.borrow().function_definitions)
+                .cloned()
+                .collect();
+            for f in fcts {
+                *self.current_function.borrow_mut() = f.borrow().name();
+                let f: ASTFlatten = f.into();
+                let _ = self.visit_children(&f);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn visitIfStatement(&self, ast: &ASTFlatten) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (condition, negated, then_branch, else_branch) = {
+            let a = ast.to_ast();
+            let stmt = a
+                .try_as_statement_ref()
+                .unwrap()
+                .try_as_if_statement_ref()
+                .unwrap();
+            let negated: ASTFlatten = RcCell::new(
+                stmt.condition
+                    .try_as_expression_ref()
+                    .unwrap()
+                    .borrow()
+                    .unop(String::from("!")),
+            )
+            .into();
+            (
+                stmt.condition.clone(),
+                negated,
+                stmt.then_branch.clone().into(),
+                stmt.else_branch.clone().map(ASTFlatten::from),
+            )
+        };
+        let _ = self.visit(&condition);
+        self.guarded(ast, &condition, || self.visit(&then_branch));
+        if let Some(else_branch) = else_branch {
+            self.guarded(ast, &negated, || self.visit(&else_branch));
+        }
+        Ok(())
+    }
+
+    pub fn visitWhileStatement(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (condition, body): (_, ASTFlatten) = {
+            let a = ast.to_ast();
+            let stmt = a
+                .try_as_statement_ref()
+                .unwrap()
+                .try_as_while_statement_ref()
+                .unwrap();
+            (stmt.condition.clone(), stmt.body.clone().into())
+        };
+        let _ = self.visit(&condition);
+        self.guarded(ast, &condition, || self.visit(&body));
+        Ok(())
+    }
+
+    pub fn visitDoWhileStatement(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        // the first iteration is not guarded by the condition
+        self.visit_children(ast)
+    }
+
+    pub fn visitForStatement(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (init, condition, update, body) = {
+            let a = ast.to_ast();
+            let stmt = a
+                .try_as_statement_ref()
+                .unwrap()
+                .try_as_for_statement_ref()
+                .unwrap();
+            (
+                stmt.init.clone().map(ASTFlatten::from),
+                stmt.condition.clone(),
+                stmt.update.clone().map(ASTFlatten::from),
+                ASTFlatten::from(stmt.body.clone()),
+            )
+        };
+        if let Some(init) = init {
+            let _ = self.visit(&init);
+        }
+        let _ = self.visit(&condition);
+        self.guarded(ast, &condition, || {
+            let _ = self.visit(&body);
+            if let Some(update) = update {
+                let _ = self.visit(&update);
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    pub fn visitBlock(&self, ast: &ASTFlatten) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let statements = ast
+            .to_ast()
+            .try_as_statement_ref()
+            .unwrap()
+            .try_as_statement_list_ref()
+            .unwrap()
+            .statements()
+            .clone();
+        // requires guard everything after them in the same block
+        let outer_guards = self.guards.borrow().len();
+        for s in &statements {
+            let _ = self.visit(s);
+            if is_instance(s, ASTType::RequireStatement) {
+                let condition = s
+                    .to_ast()
+                    .try_as_statement_ref()
+                    .unwrap()
+                    .try_as_simple_statement_ref()
+                    .unwrap()
+                    .try_as_require_statement_ref()
+                    .unwrap()
+                    .condition
+                    .clone();
+                self.guards
+                    .borrow_mut()
+                    .push((condition, before_analysis(s)));
+            }
+        }
+        self.guards.borrow_mut().truncate(outer_guards);
+        Ok(())
+    }
+
+    // Visit the statements guarded by condition of stmt with the condition on the guard stack
+    fn guarded(
+        &self,
+        stmt: &ASTFlatten,
+        condition: &ASTFlatten,
+        f: impl FnOnce() -> eyre::Result<<Self as AstVisitor>::Return>,
+    ) {
+        self.guards
+            .borrow_mut()
+            .push((condition.clone(), before_analysis(stmt)));
+        let _ = f();
+        self.guards.borrow_mut().pop();
+    }
+
+    pub fn visitReclassifyExpr(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (expr, privacy) = {
+            let a = ast.to_ast();
+            let re = a
+                .try_as_expression_ref()
+                .unwrap()
+                .try_as_reclassify_expr_ref()
+                .unwrap();
+            (re.expr().clone(), re.privacy().clone())
+        };
+        self.record(
+            LeakKind::Reveal,
+            ast,
+            ast.code(),
+            label_of(&expr),
+            privacy.code(),
+        );
+        // nested reveals are reported separately
+        self.visit_children(ast)
+    }
+
+    pub fn visitAssignmentStatement(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let (lhs, rhs) = {
+            let a = ast.to_ast();
+            let stmt = a
+                .try_as_statement_ref()
+                .unwrap()
+                .try_as_simple_statement_ref()
+                .unwrap()
+                .try_as_assignment_statement_ref()
+                .unwrap();
+            (stmt.lhs().clone(), stmt.rhs().clone())
+        };
+        if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+            let lhs_type = lhs.ast_base_ref().unwrap().borrow().annotated_type.clone();
+            let before_analysis = ast
+                .to_ast()
+                .try_as_statement_ref()
+                .unwrap()
+                .statement_base_ref()
+                .unwrap()
+                .before_analysis
+                .clone();
+            // writes to private state which the alias analysis cannot prove to be owned by me
+            if let Some(lhs_type) = lhs_type {
+                if lhs_type.borrow().is_private()
+                    && !lhs_type.borrow().is_private_at_me(&before_analysis)
+                {
+                    self.record(
+                        LeakKind::ImplicitExposure,
+                        ast,
+                        ast.code(),
+                        label_of(&rhs),
+                        label_of(&lhs),
+                    );
+                }
+            }
+        }
+        self.visit_children(ast)
+    }
+
+    fn record(
+        &self,
+        kind: LeakKind,
+        ast: &ASTFlatten,
+        expr: String,
+        old_label: String,
+        new_label: String,
+    ) {
+        let (line, column) = {
+            let base = ast.ast_base_ref().unwrap();
+            let base = base.borrow();
+            (base.line, base.column)
+        };
+        let guards = self.guards.borrow().clone();
+        self.leaks.borrow_mut().push((
+            ast.clone(),
+            LeakRecord {
+                kind,
+                contract: self.current_contract.borrow().clone(),
+                function: self.current_function.borrow().clone(),
+                line,
+                column,
+                expr,
+                old_label,
+                new_label,
+                guard: guards.iter().map(|(cond, _)| cond.code()).collect(),
+                guard_aliases: guard_aliases(&guards),
+            },
+        ));
+    }
+}
+
+// Privacy label of the annotated type of `ast`, "all" if it has none
+pub(crate) fn label_of(ast: &ASTFlatten) -> String {
+    ast.ast_base_ref()
+        .and_then(|base| base.borrow().annotated_type.clone())
+        .and_then(|at| at.borrow().privacy_annotation.as_ref().map(|p| p.code()))
+        .unwrap_or_else(|| String::from("all"))
+}
+
+fn before_analysis(stmt: &ASTFlatten) -> Option<PartitionState<AST>> {
+    stmt.to_ast()
+        .try_as_statement_ref()
+        .and_then(|s| s.statement_base_ref())
+        .and_then(|base| base.before_analysis.clone())
+}
+
+// Labels the GuardConditionAnalyzer merges into one partition for some guard,
+// which were not already known to be equal before that guard
+fn guard_aliases(guards: &[(ASTFlatten, Option<PartitionState<AST>>)]) -> Vec<String> {
+    let analyzer = GuardConditionAnalyzer::new(false);
+    let mut aliases = vec![];
+    for (condition, before) in guards {
+        let Some(before) = before else {
+            continue;
+        };
+        let after = analyzer.analyze(condition, before);
+        for partition in after._partitions.values() {
+            let labels: Vec<_> = partition.iter().collect();
+            for (i, x) in labels.iter().enumerate() {
+                for y in &labels[i + 1..] {
+                    let alias = format!("{x} == {y}");
+                    if !before.same_partition(x, y) && !aliases.contains(&alias) {
+                        aliases.push(alias);
+                    }
+                }
+            }
+        }
+    }
+    aliases
+}
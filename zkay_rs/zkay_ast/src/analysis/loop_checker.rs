@@ -18,13 +18,17 @@ use crate::visitors::{
     function_visitor::FunctionVisitor,
     visitor::{AstVisitor, AstVisitorBase, AstVisitorBaseRef},
 };
+use rccell::RcCell;
 use zkay_derive::ASTVisitorBaseRefImpl;
-pub fn check_loops(ast: &ASTFlatten) {
+// Loops containing private expressions, as (loop statement, message) pairs (used by the private_loop lint)
+pub fn private_loop_violations(ast: &ASTFlatten) -> Vec<(ASTFlatten, String)> {
     // """
     // Checks if loops don't contain private expressions
     // """
-    let mut v = LoopChecker::new();
+    let v = LoopChecker::new();
     let _ = v.visit(ast);
+    let violations = v.violations.borrow().clone();
+    violations
 }
 
 // class LoopChecker(FunctionVisitor)
@@ -32,6 +36,7 @@ pub fn check_loops(ast: &ASTFlatten) {
 #[derive(ASTVisitorBaseRefImpl)]
 struct LoopChecker {
     pub ast_visitor_base: AstVisitorBase,
+    violations: RcCell<Vec<(ASTFlatten, String)>>,
 }
 impl FunctionVisitor for LoopChecker {}
 impl AstVisitor for LoopChecker {
@@ -63,28 +68,40 @@ impl LoopChecker {
     pub fn new() -> Self {
         Self {
             ast_visitor_base: AstVisitorBase::new("node-or-children", false),
+            violations: RcCell::new(vec![]),
+        }
+    }
+    fn ensure(&self, ast: &ASTFlatten, ok: bool, msg: String) {
+        if !ok {
+            self.violations.borrow_mut().push((ast.clone(), msg));
         }
     }
     pub fn visitWhileStatement(
         &self,
         ast: &ASTFlatten,
     ) -> eyre::Result<<Self as AstVisitor>::Return> {
-        assert!(
+        self.ensure(
+            ast,
             !contains_private_expr(&ast.try_as_while_statement_ref().unwrap().borrow().condition),
-            "Loop condition cannot contain private expressions {:?}",
-            ast.try_as_while_statement_ref().unwrap().borrow().condition
+            format!(
+                "Loop condition cannot contain private expressions {:?}",
+                ast.try_as_while_statement_ref().unwrap().borrow().condition
+            ),
         );
-        assert!(
+        self.ensure(
+            ast,
             !contains_private_expr(
                 &ast.try_as_while_statement_ref()
                     .unwrap()
                     .borrow()
                     .body
                     .clone()
-                    .into()
+                    .into(),
+            ),
+            format!(
+                "Loop body cannot contain private expressions {:?}",
+                ast.try_as_while_statement_ref().unwrap().borrow().body
             ),
-            "Loop body cannot contain private expressions {:?}",
-            ast.try_as_while_statement_ref().unwrap().borrow().body
         );
         self.visit_children(ast)
     }
@@ -93,30 +110,36 @@ impl LoopChecker {
         &self,
         ast: &ASTFlatten,
     ) -> eyre::Result<<Self as AstVisitor>::Return> {
-        assert!(
+        self.ensure(
+            ast,
             !contains_private_expr(
                 &ast.try_as_do_while_statement_ref()
+                    .unwrap()
+                    .borrow()
+                    .condition,
+            ),
+            format!(
+                "Loop condition cannot contain private expressions {:?}",
+                ast.try_as_do_while_statement_ref()
                     .unwrap()
                     .borrow()
                     .condition
             ),
-            "Loop condition cannot contain private expressions {:?}",
-            ast.try_as_do_while_statement_ref()
-                .unwrap()
-                .borrow()
-                .condition
         );
-        assert!(
+        self.ensure(
+            ast,
             !contains_private_expr(
                 &ast.try_as_do_while_statement_ref()
                     .unwrap()
                     .borrow()
                     .body
                     .clone()
-                    .into()
+                    .into(),
+            ),
+            format!(
+                "Loop body cannot contain private expressions {:?}",
+                ast.try_as_do_while_statement_ref().unwrap().borrow().body
             ),
-            "Loop body cannot contain private expressions {:?}",
-            ast.try_as_do_while_statement_ref().unwrap().borrow().body
         );
         self.visit_children(ast)
     }
@@ -125,24 +148,28 @@ impl LoopChecker {
         &self,
         ast: &ASTFlatten,
     ) -> eyre::Result<<Self as AstVisitor>::Return> {
-        assert!(
+        self.ensure(
+            ast,
             !contains_private_expr(
                 &ast.to_ast()
+                    .try_as_statement_ref()
+                    .unwrap()
+                    .try_as_for_statement_ref()
+                    .unwrap()
+                    .condition,
+            ),
+            format!(
+                "Loop condition cannot contain private expressions {:?}",
+                ast.to_ast()
                     .try_as_statement_ref()
                     .unwrap()
                     .try_as_for_statement_ref()
                     .unwrap()
                     .condition
             ),
-            "Loop condition cannot contain private expressions {:?}",
-            ast.to_ast()
-                .try_as_statement_ref()
-                .unwrap()
-                .try_as_for_statement_ref()
-                .unwrap()
-                .condition
         );
-        assert!(
+        self.ensure(
+            ast,
             !contains_private_expr(
                 &ast.to_ast()
                     .try_as_statement_ref()
@@ -151,17 +178,20 @@ impl LoopChecker {
                     .unwrap()
                     .body
                     .clone()
-                    .into()
+                    .into(),
+            ),
+            format!(
+                "Loop body cannot contain private expressions {:?}",
+                ast.to_ast()
+                    .try_as_statement_ref()
+                    .unwrap()
+                    .try_as_for_statement_ref()
+                    .unwrap()
+                    .body
             ),
-            "Loop body cannot contain private expressions {:?}",
-            ast.to_ast()
-                .try_as_statement_ref()
-                .unwrap()
-                .try_as_for_statement_ref()
-                .unwrap()
-                .body
         );
-        assert!(
+        self.ensure(
+            ast,
             !(ast
                 .to_ast()
                 .try_as_statement_ref()
@@ -179,15 +209,17 @@ impl LoopChecker {
                         .update
                         .clone()
                         .unwrap()
-                        .into()
+                        .into(),
                 )),
-            "Loop update statement cannot contain private expressions {:?}",
-            ast.to_ast()
-                .try_as_statement_ref()
-                .unwrap()
-                .try_as_for_statement_ref()
-                .unwrap()
-                .update
+            format!(
+                "Loop update statement cannot contain private expressions {:?}",
+                ast.to_ast()
+                    .try_as_statement_ref()
+                    .unwrap()
+                    .try_as_for_statement_ref()
+                    .unwrap()
+                    .update
+            ),
         );
         self.visit_children(ast)
     }
@@ -7,8 +7,10 @@
 // * :py:mod:`.alias_analysis`: Alias analysis to determine whether the privacy labels at different locations refer to the same address.
 // * :py:mod:`.call_graph`: Compute sets of transitively called functions for each function.
 // * :py:mod:`.circuit_compatibility_checker`: Determine whether the private parts of an AST can be expressed using proof circuits.
+// * :py:mod:`.contract_graph`: Export call graph, hybrid functions and privacy flows between owners.
 // * :py:mod:`.contains_private_checker`: Determine whether element contains any private expressions.
 // * :py:mod:`.hybrid_function_detector`: Determine which functions require verification.
+// * :py:mod:`.information_leakage`: Collect all locations where private data is declassified or exposed to another owner.
 // * :py:mod:`.loop_checker`: Ensure that loops do not contain private expressions.
 // * :py:mod:`.partition_state`: Helper class to store alias analysis state.
 // * :py:mod:`.return_checker`: Ensure that there is at most one return statement per function at the end of the body.
@@ -18,7 +20,9 @@ pub mod alias_analysis;
 pub mod call_graph;
 pub mod circuit_compatibility_checker;
 pub mod contains_private_checker;
+pub mod contract_graph;
 pub mod hybrid_function_detector;
+pub mod information_leakage;
 pub mod loop_checker;
 pub mod partition_state;
 pub mod return_checker;
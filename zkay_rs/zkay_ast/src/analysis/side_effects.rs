@@ -39,8 +39,13 @@ pub fn compute_modified_sets(ast: &ASTFlatten) {
     v.iterate_until_fixed_point(ast);
 }
 
-pub fn check_for_undefined_behavior_due_to_eval_order(ast: &ASTFlatten) {
-    let _ = EvalOrderUBChecker::new().visit(ast);
+// Expressions whose result depends on the undefined evaluation order of their operands, as
+// (element, message) pairs (used by the eval_order lint)
+pub fn eval_order_violations(ast: &ASTFlatten) -> Vec<(ASTFlatten, String)> {
+    let v = EvalOrderUBChecker::new();
+    let _ = v.visit(ast);
+    let violations = v.violations.borrow().clone();
+    violations
 }
 
 // class SideEffectsDetector(AstVisitor)
@@ -519,6 +524,7 @@ impl IndirectModificationDetector {
 #[derive(ASTVisitorBaseRefImpl)]
 struct EvalOrderUBChecker {
     pub ast_visitor_base: AstVisitorBase,
+    violations: RcCell<Vec<(ASTFlatten, String)>>,
 }
 impl AstVisitor for EvalOrderUBChecker {
     type Return = ();
@@ -567,10 +573,11 @@ impl EvalOrderUBChecker {
     pub fn new() -> Self {
         Self {
             ast_visitor_base: AstVisitorBase::new("post", false),
+            violations: RcCell::new(vec![]),
         }
     }
-    // @staticmethod
     pub fn visit_child_expressions(
+        &self,
         parent: &ASTFlatten,
         exprs: &Vec<ASTFlatten>,
     ) -> eyre::Result<<Self as AstVisitor>::Return> {
@@ -585,19 +592,23 @@ impl EvalOrderUBChecker {
                 let modified_values = arg.ast_base_ref().unwrap().borrow().modified_values.clone();
                 let diffset: BTreeSet<_> = modset.intersection(&modified_values).collect();
 
-                assert!(
-                    diffset.is_empty(),
-                    r#"Undefined behavior due to potential side effect on the same value(s) \"{}\" in multiple expression children.\n"
+                if !diffset.is_empty() {
+                    self.violations.borrow_mut().push((
+                        parent.clone(),
+                        format!(
+                            r#"Undefined behavior due to potential side effect on the same value(s) \"{}\" in multiple expression children.\n"
                                         "Solidity does not guarantee an evaluation order for non-shortcircuit expressions.\n"
                                         "Since zkay requires local simulation for transaction transformation, all semantics must be well-defined. {:?}"#,
-                    format!(
-                        "{{{}}}",
-                        (diffset.into_iter().map(|d| (*d).to_string()))
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    ),
-                    parent
-                );
+                            format!(
+                                "{{{}}}",
+                                (diffset.into_iter().map(|d| (*d).to_string()))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                            parent
+                        ),
+                    ));
+                }
                 //????  diffset is_empty
                 // modset = modset.union(diffset).collect();
             }
@@ -624,13 +635,15 @@ impl EvalOrderUBChecker {
                                 .collect::<Vec<_>>()
                                 .join(", ")
                         );
-                        assert!(
-                            false,
-                            r#"Undefined behavior due to read of value(s) \"{setstr}\" which might be modified in this subexpression.\n"
+                        self.violations.borrow_mut().push((
+                            arg.clone(),
+                            format!(
+                                r#"Undefined behavior due to read of value(s) \"{setstr}\" which might be modified in this subexpression.\n"
                             "Solidity does not guarantee an evaluation order for non-shortcircuit expressions.\n"
                             "Since zkay requires local simulation for transaction transformation, all semantics must be well-defined. {:?}"#,
-                            arg
-                        );
+                                arg
+                            ),
+                        ));
                     }
                 }
             }
@@ -665,7 +678,7 @@ impl EvalOrderUBChecker {
         {
             return Ok(());
         }
-        Self::visit_child_expressions(
+        self.visit_child_expressions(
             ast,
             &ast.to_ast()
                 .try_as_expression_ref()
@@ -678,13 +691,13 @@ impl EvalOrderUBChecker {
     }
 
     pub fn visitExpression(&self, ast: &ASTFlatten) -> eyre::Result<<Self as AstVisitor>::Return> {
-        Self::visit_child_expressions(ast, &ast.children())
+        self.visit_child_expressions(ast, &ast.children())
     }
 
     pub fn visitAssignmentStatement(
         &self,
         ast: &ASTFlatten,
     ) -> eyre::Result<<Self as AstVisitor>::Return> {
-        Self::visit_child_expressions(ast, &ast.children())
+        self.visit_child_expressions(ast, &ast.children())
     }
 }
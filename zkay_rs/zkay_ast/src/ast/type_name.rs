@@ -1020,31 +1020,29 @@ impl FullArgsSpecInit for NumberLiteralType {
 impl NumberLiteralType {
     pub fn new(name: NumberLiteralTypeUnion) -> Self {
         // println!("{name:?}");
-        let (iname, uname) = match name {
-            NumberLiteralTypeUnion::String(v) => (
-                I256::from_str_prefixed(&v).ok(),
-                U256::from_str_prefixed(&v).ok(),
-            ), //TODO U256
-            NumberLiteralTypeUnion::I32(v) => (Some(v.as_i256()), None),
-        };
-        let blen = if iname.is_some() {
-            (I256::BITS - iname.unwrap().leading_zeros()) as i32
-        } else {
-            (U256::BITS - uname.unwrap().leading_zeros()) as i32
+        // # Literals above the range of int256 are uint256 values
+        let (negative, magnitude) = match name {
+            NumberLiteralTypeUnion::String(v) => match I256::from_str_prefixed(&v) {
+                Ok(i) => (i.is_negative(), i.unsigned_abs()),
+                Err(_) => (false, U256::from_str_prefixed(&v).unwrap()),
+            },
+            NumberLiteralTypeUnion::I32(v) => (v.is_negative(), U256::from(v.unsigned_abs())),
         };
+        let blen = (U256::BITS - magnitude.leading_zeros()) as i32;
         let (mut signed, mut bitwidth) = (false, blen);
-        if iname.is_some() && iname.unwrap() < 0 {
+        if negative {
             signed = true;
-            if iname.unwrap() != -(1 << (blen - 1)) {
+            // # -2^(n-1) is the smallest value of int<n>
+            if magnitude != U256::ONE << (blen - 1) {
                 bitwidth += 1;
             }
         };
         bitwidth = 8i32.max((bitwidth + 7) / 8 * 8);
         assert!(bitwidth <= 256);
-        let name = if iname.is_some() {
-            iname.unwrap().to_string()
+        let name = if negative {
+            format!("-{magnitude}")
         } else {
-            uname.unwrap().to_string()
+            magnitude.to_string()
         };
         let prefix = name.clone();
         Self {
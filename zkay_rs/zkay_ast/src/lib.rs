@@ -14,6 +14,7 @@
 // Subpackages
 // ===========
 // * :py:mod:`.analysis`: Program analysis functionality
+// * :py:mod:`.lints`: Named, configurable zkay lints built on top of the analyses
 // * :py:mod:`.pointers`: Functionality for resolving references to other AST elements.
 // * :py:mod:`.visitor`: Visitor classes for processing ASTs
 // """
@@ -22,6 +23,7 @@ pub mod ast;
 // pub mod build_ast;
 pub mod global_defs;
 pub mod homomorphism;
+pub mod lints;
 pub mod pointers;
 // pub mod process_ast;
 pub mod circuit_constraints;
@@ -0,0 +1,317 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(nonstandard_style)]
+#![allow(unused_imports)]
+#![allow(unused_mut)]
+#![allow(unused_braces)]
+// """
+// Registry of named zkay lints.
+
+// Every lint has a default level (allow, warn or deny) which can be overridden via the ``lint_levels`` user
+// configuration option. Individual findings can be suppressed with a ``// zkay-allow(lint_name, ...)`` comment
+// on the same line or on the line directly above the offending code.
+
+// The soundness checks (eval_order, private_loop) are reported the same way, but they are always errors:
+// they can neither be configured nor suppressed.
+// """
+pub mod private_require;
+pub mod rehom_constant;
+pub mod unused_private_state;
+
+use crate::analysis::{
+    information_leakage::implicit_exposures, loop_checker::private_loop_violations,
+    side_effects::eval_order_violations,
+};
+use crate::ast::{ASTFlatten, ASTInstanceOf, IntoAST};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use zkay_config::{config::CFG, config_user::UserConfig, zk_print};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl FromStr for LintLevel {
+    type Err = eyre::Report;
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            _ => eyre::bail!("Invalid lint level {s}, must be one of allow, warn, deny"),
+        }
+    }
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Warn => write!(f, "warn"),
+            Self::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+// A lint pass returns the offending AST elements together with a message
+pub type LintPass = fn(&ASTFlatten) -> Vec<(ASTFlatten, String)>;
+
+pub struct Lint {
+    pub name: &'static str,
+    pub default_level: LintLevel,
+    // if true, the lint relies on annotated types and only runs after type checking
+    pub needs_type_check: bool,
+    pub description: &'static str,
+    pub pass: LintPass,
+}
+
+// Programs violating these checks have no sound translation, their level is always deny
+pub const SOUNDNESS_CHECKS: &[Lint] = &[
+    Lint {
+        name: "eval_order",
+        default_level: LintLevel::Deny,
+        needs_type_check: false,
+        description: "side effects whose result depends on the (undefined) evaluation order",
+        pass: eval_order_violations,
+    },
+    Lint {
+        name: "private_loop",
+        default_level: LintLevel::Deny,
+        needs_type_check: true,
+        description: "loop condition, body or update contains private expressions",
+        pass: private_loop_violations,
+    },
+];
+
+pub const LINTS: &[Lint] = &[
+    Lint {
+        name: "implicit_exposure",
+        default_level: LintLevel::Warn,
+        needs_type_check: true,
+        description: "private state is written for an owner which is not provably me",
+        pass: implicit_exposures,
+    },
+    Lint {
+        name: "private_require",
+        default_level: LintLevel::Warn,
+        needs_type_check: true,
+        description: "require condition depends on a revealed private value",
+        pass: private_require::private_require_conditions,
+    },
+    Lint {
+        name: "unused_private_state",
+        default_level: LintLevel::Warn,
+        needs_type_check: true,
+        description: "private state variable is never used",
+        pass: unused_private_state::unused_private_state_variables,
+    },
+    Lint {
+        name: "rehom_constant",
+        default_level: LintLevel::Warn,
+        needs_type_check: true,
+        description: "homomorphism change of a constant expression",
+        pass: rehom_constant::rehom_of_constants,
+    },
+];
+
+pub fn lint_names() -> Vec<String> {
+    LINTS.iter().map(|l| l.name.to_owned()).collect()
+}
+
+// Configurable lint with the given name (soundness checks are not included)
+pub fn find_lint(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|l| l.name == name)
+}
+
+pub fn is_soundness_check(name: &str) -> bool {
+    SOUNDNESS_CHECKS.iter().any(|l| l.name == name)
+}
+
+// """
+// Validate the ``lint_levels`` configuration option.
+
+// :param levels: map from lint name to level, as stored in the user configuration
+// :return: the parsed levels
+// :raise: if a lint does not exist, is a soundness check or has an invalid level
+// """
+pub fn validate_lint_levels(
+    levels: &BTreeMap<String, String>,
+) -> eyre::Result<BTreeMap<&'static str, LintLevel>> {
+    levels
+        .iter()
+        .map(|(name, level)| {
+            if is_soundness_check(name) {
+                eyre::bail!("Lint {name} is a soundness check and is always an error, its level cannot be configured");
+            }
+            let Some(lint) = find_lint(name) else {
+                eyre::bail!(
+                    "Unknown lint {name} in lint_levels, must be one of {}",
+                    lint_names().join(", ")
+                );
+            };
+            Ok((lint.name, level.parse()?))
+        })
+        .collect()
+}
+
+// Effective level of `lint`, taking the user configuration into account
+pub fn lint_level(lint: &Lint) -> eyre::Result<LintLevel> {
+    if is_soundness_check(lint.name) {
+        return Ok(LintLevel::Deny);
+    }
+    let levels = validate_lint_levels(&CFG.lock().unwrap().lint_levels())?;
+    Ok(levels.get(lint.name).copied().unwrap_or(lint.default_level))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintDiagnostic {
+    pub lint: String,
+    pub level: LintLevel,
+    pub line: i32,
+    pub column: i32,
+    pub message: String,
+}
+
+impl fmt::Display for LintDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] at {}:{}: {}",
+            if self.level == LintLevel::Deny {
+                "error"
+            } else {
+                "warning"
+            },
+            self.lint,
+            self.line,
+            self.column,
+            self.message
+        )
+    }
+}
+
+// Line and column of `ast`, falling back to the closest ancestor with location information
+pub fn source_location(ast: &ASTFlatten) -> (i32, i32) {
+    let mut cur = Some(ast.clone());
+    while let Some(a) = cur {
+        let Some(base) = a.ast_base_ref() else {
+            break;
+        };
+        let (line, column, parent) = {
+            let b = base.borrow();
+            (b.line, b.column, b.parent.clone())
+        };
+        if line > 0 {
+            return (line, column);
+        }
+        cur = parent.and_then(|p| p.upgrade());
+    }
+    (0, 0)
+}
+
+// Map from line number to the lints allowed on that line via ``// zkay-allow(...)``.
+// A comment applies to its own line and to the line below it.
+pub fn allowed_lints(code: &str) -> BTreeMap<i32, BTreeSet<String>> {
+    let re = Regex::new(r"//\s*zkay-allow\(([^)]*)\)").unwrap();
+    let mut allowed: BTreeMap<i32, BTreeSet<String>> = BTreeMap::new();
+    for (idx, line) in code.lines().enumerate() {
+        let Some(cap) = re.captures(line) else {
+            continue;
+        };
+        let names: BTreeSet<String> = cap[1]
+            .split(',')
+            .map(|n| n.trim().to_owned())
+            .filter(|n| !n.is_empty())
+            .collect();
+        let line_no = idx as i32 + 1;
+        for l in [line_no, line_no + 1] {
+            allowed.entry(l).or_default().extend(names.iter().cloned());
+        }
+    }
+    allowed
+}
+
+// """
+// Run all enabled lints on `ast`.
+
+// :param code: zkay source code of `ast` (used for suppression comments)
+// :param type_checked: whether `ast` has been type checked (otherwise lints which need types are skipped)
+// :return: the soundness check violations followed by all lint diagnostics which are not allowed, in registry order
+// :raise: if the ``lint_levels`` configuration is invalid
+// """
+pub fn run_lints(
+    code: &str,
+    ast: &ASTFlatten,
+    type_checked: bool,
+) -> eyre::Result<Vec<LintDiagnostic>> {
+    let levels = validate_lint_levels(&CFG.lock().unwrap().lint_levels())?;
+    let allowed = allowed_lints(code);
+    let mut diagnostics = vec![];
+    for lint in SOUNDNESS_CHECKS.iter().chain(LINTS) {
+        let soundness_check = is_soundness_check(lint.name);
+        let level = if soundness_check {
+            LintLevel::Deny
+        } else {
+            levels.get(lint.name).copied().unwrap_or(lint.default_level)
+        };
+        if level == LintLevel::Allow || (lint.needs_type_check && !type_checked) {
+            continue;
+        }
+        for (elem, message) in (lint.pass)(ast) {
+            let (line, column) = source_location(&elem);
+            if !soundness_check && allowed.get(&line).map_or(false, |a| a.contains(lint.name)) {
+                continue;
+            }
+            diagnostics.push(LintDiagnostic {
+                lint: lint.name.to_owned(),
+                level,
+                line,
+                column,
+                message,
+            });
+        }
+    }
+    Ok(diagnostics)
+}
+
+// The lints at level deny which fired, returned by check_lints
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintErrors(pub Vec<LintDiagnostic>);
+
+impl fmt::Display for LintErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<_> = self.0.iter().map(|d| d.to_string()).collect();
+        write!(f, "{}", errors.join("\n"))
+    }
+}
+
+impl std::error::Error for LintErrors {}
+
+// Like run_lints, but prints warnings and fails with LintErrors if any lint at level deny fired
+pub fn check_lints(
+    code: &str,
+    ast: &ASTFlatten,
+    type_checked: bool,
+) -> eyre::Result<Vec<LintDiagnostic>> {
+    let diagnostics = run_lints(code, ast, type_checked)?;
+    for d in diagnostics.iter().filter(|d| d.level == LintLevel::Warn) {
+        zk_print!("{d}");
+    }
+    let errors: Vec<_> = diagnostics
+        .iter()
+        .filter(|d| d.level == LintLevel::Deny)
+        .cloned()
+        .collect();
+    if !errors.is_empty() {
+        return Err(LintErrors(errors).into());
+    }
+    Ok(diagnostics)
+}
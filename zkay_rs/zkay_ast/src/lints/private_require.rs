@@ -0,0 +1,85 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(nonstandard_style)]
+#![allow(unused_imports)]
+#![allow(unused_mut)]
+#![allow(unused_braces)]
+use crate::ast::{
+    expression::{Expression, ReclassifyExpr},
+    statement::{SimpleStatement, Statement},
+    ASTChildren, ASTFlatten, ASTInstanceOf, ASTType, IntoAST, AST,
+};
+use crate::visitors::visitor::{AstVisitor, AstVisitorBase, AstVisitorBaseRef};
+use rccell::RcCell;
+use zkay_derive::ASTVisitorBaseRefImpl;
+
+// A require whose condition is computed from a revealed private value makes the success of the
+// transaction (which is public) depend on private data.
+pub fn private_require_conditions(ast: &ASTFlatten) -> Vec<(ASTFlatten, String)> {
+    let v = PrivateRequireVisitor::new();
+    let _ = v.visit(ast);
+    let found = v.found.borrow().clone();
+    found
+}
+
+fn contains_reveal(ast: &ASTFlatten) -> bool {
+    matches!(
+        ast.to_ast(),
+        AST::Expression(Expression::ReclassifyExpr(ReclassifyExpr::ReclassifyExpr(
+            _
+        )))
+    ) || ast.children().iter().any(contains_reveal)
+}
+
+#[derive(ASTVisitorBaseRefImpl)]
+struct PrivateRequireVisitor {
+    pub ast_visitor_base: AstVisitorBase,
+    found: RcCell<Vec<(ASTFlatten, String)>>,
+}
+impl AstVisitor for PrivateRequireVisitor {
+    type Return = ();
+    fn temper_result(&self) -> Self::Return {}
+    fn has_attr(&self, name: &ASTType, _ast: &AST) -> bool {
+        matches!(name, ASTType::RequireStatement)
+    }
+    fn get_attr(&self, name: &ASTType, ast: &ASTFlatten) -> eyre::Result<Self::Return> {
+        match name {
+            ASTType::RequireStatement => self.visitRequireStatement(ast),
+            _ => Err(eyre::eyre!("unreach")),
+        }
+    }
+}
+impl PrivateRequireVisitor {
+    pub fn new() -> Self {
+        Self {
+            ast_visitor_base: AstVisitorBase::new("node-or-children", false),
+            found: RcCell::new(vec![]),
+        }
+    }
+    pub fn visitRequireStatement(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let condition = ast
+            .to_ast()
+            .try_as_statement_ref()
+            .unwrap()
+            .try_as_simple_statement_ref()
+            .unwrap()
+            .try_as_require_statement_ref()
+            .unwrap()
+            .condition
+            .clone();
+        if contains_reveal(&condition) {
+            self.found.borrow_mut().push((
+                ast.clone(),
+                format!(
+                    "require condition \"{}\" depends on a revealed private value, whether the transaction reverts leaks it",
+                    condition.code()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
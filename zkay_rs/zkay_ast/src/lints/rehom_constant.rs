@@ -0,0 +1,76 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(nonstandard_style)]
+#![allow(unused_imports)]
+#![allow(unused_mut)]
+#![allow(unused_braces)]
+use crate::ast::{
+    expression::{Expression, ReclassifyExpr, ReclassifyExprBaseProperty},
+    is_instance, ASTFlatten, ASTInstanceOf, ASTType, IntoAST, AST,
+};
+use crate::visitors::visitor::{AstVisitor, AstVisitorBase, AstVisitorBaseRef};
+use rccell::RcCell;
+use zkay_derive::ASTVisitorBaseRefImpl;
+
+// Changing the homomorphism of a literal costs a re-encryption inside the circuit,
+// the literal can be used with the target homomorphism directly.
+pub fn rehom_of_constants(ast: &ASTFlatten) -> Vec<(ASTFlatten, String)> {
+    let v = RehomConstantVisitor::new();
+    let _ = v.visit(ast);
+    let found = v.found.borrow().clone();
+    found
+}
+
+#[derive(ASTVisitorBaseRefImpl)]
+struct RehomConstantVisitor {
+    pub ast_visitor_base: AstVisitorBase,
+    found: RcCell<Vec<(ASTFlatten, String)>>,
+}
+impl AstVisitor for RehomConstantVisitor {
+    type Return = ();
+    fn temper_result(&self) -> Self::Return {}
+    fn has_attr(&self, _name: &ASTType, ast: &AST) -> bool {
+        matches!(
+            ast,
+            AST::Expression(Expression::ReclassifyExpr(ReclassifyExpr::RehomExpr(_)))
+        )
+    }
+    fn get_attr(&self, _name: &ASTType, ast: &ASTFlatten) -> eyre::Result<Self::Return> {
+        match ast.to_ast() {
+            AST::Expression(Expression::ReclassifyExpr(ReclassifyExpr::RehomExpr(_))) => {
+                self.visitRehomExpr(ast)
+            }
+            _ => Err(eyre::eyre!("unreach")),
+        }
+    }
+}
+impl RehomConstantVisitor {
+    pub fn new() -> Self {
+        Self {
+            ast_visitor_base: AstVisitorBase::new("node-or-children", false),
+            found: RcCell::new(vec![]),
+        }
+    }
+    pub fn visitRehomExpr(&self, ast: &ASTFlatten) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let expr = ast
+            .to_ast()
+            .try_as_expression_ref()
+            .unwrap()
+            .try_as_reclassify_expr_ref()
+            .unwrap()
+            .expr()
+            .clone();
+        if is_instance(&expr, ASTType::LiteralExprBase) {
+            self.found.borrow_mut().push((
+                ast.clone(),
+                format!(
+                    "\"{}\" changes the homomorphism of the constant \"{}\"",
+                    ast.code(),
+                    expr.code()
+                ),
+            ));
+        }
+        self.visit_children(ast)
+    }
+}
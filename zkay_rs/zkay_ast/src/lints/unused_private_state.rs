@@ -0,0 +1,96 @@
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(nonstandard_style)]
+#![allow(unused_imports)]
+#![allow(unused_mut)]
+#![allow(unused_braces)]
+use crate::ast::{is_instance, ASTFlatten, ASTInstanceOf, ASTType, IntoAST, AST};
+use crate::visitors::visitor::{AstVisitor, AstVisitorBase, AstVisitorBaseRef};
+use rccell::RcCell;
+use zkay_derive::ASTVisitorBaseRefImpl;
+
+// Private state variables which are never referenced still cost a ciphertext slot (and a pki lookup on write).
+pub fn unused_private_state_variables(ast: &ASTFlatten) -> Vec<(ASTFlatten, String)> {
+    let Some(su) = ast.try_as_source_unit_ref() else {
+        return vec![];
+    };
+    let v = StateVariableUseVisitor::new();
+    let _ = v.visit(ast);
+    let used = v.used.borrow().clone();
+
+    let mut unused = vec![];
+    for contract in &su.borrow().contracts {
+        for decl in &contract.borrow().state_variable_declarations {
+            if !is_instance(decl, ASTType::StateVariableDeclaration) {
+                continue;
+            }
+            let (is_private, name) = {
+                let base = decl.ast_base_ref().unwrap();
+                let base = base.borrow();
+                (
+                    base.annotated_type
+                        .as_ref()
+                        .map_or(false, |at| at.borrow().is_private()),
+                    base.idf.as_ref().map(|idf| idf.borrow().name()),
+                )
+            };
+            if is_private && !used.contains(decl) {
+                unused.push((
+                    decl.clone(),
+                    format!(
+                        "private state variable \"{}\" is never used",
+                        name.unwrap_or_default()
+                    ),
+                ));
+            }
+        }
+    }
+    unused
+}
+
+// Collects the targets of all identifier expressions
+#[derive(ASTVisitorBaseRefImpl)]
+struct StateVariableUseVisitor {
+    pub ast_visitor_base: AstVisitorBase,
+    used: RcCell<Vec<ASTFlatten>>,
+}
+impl AstVisitor for StateVariableUseVisitor {
+    type Return = ();
+    fn temper_result(&self) -> Self::Return {}
+    fn has_attr(&self, name: &ASTType, _ast: &AST) -> bool {
+        matches!(name, ASTType::IdentifierExpr)
+    }
+    fn get_attr(&self, name: &ASTType, ast: &ASTFlatten) -> eyre::Result<Self::Return> {
+        match name {
+            ASTType::IdentifierExpr => self.visitIdentifierExpr(ast),
+            _ => Err(eyre::eyre!("unreach")),
+        }
+    }
+}
+impl StateVariableUseVisitor {
+    pub fn new() -> Self {
+        Self {
+            ast_visitor_base: AstVisitorBase::new("node-or-children", false),
+            used: RcCell::new(vec![]),
+        }
+    }
+    pub fn visitIdentifierExpr(
+        &self,
+        ast: &ASTFlatten,
+    ) -> eyre::Result<<Self as AstVisitor>::Return> {
+        let target = ast
+            .ast_base_ref()
+            .unwrap()
+            .borrow()
+            .target
+            .clone()
+            .and_then(|t| t.upgrade());
+        if let Some(target) = target {
+            if is_instance(&target, ASTType::StateVariableDeclaration) {
+                self.used.borrow_mut().push(target);
+            }
+        }
+        Ok(())
+    }
+}
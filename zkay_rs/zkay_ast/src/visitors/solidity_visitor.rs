@@ -35,6 +35,7 @@ use crate::visitors::{
 use eyre::{eyre, Result};
 use rccell::RcCell;
 use std::cmp::Ordering;
+use std::ops::Range;
 use zkay_config::{
     config::{indent, CFG},
     config_version::Versions,
@@ -43,6 +44,52 @@ pub fn to_solidity(ast: &ASTFlatten) -> String {
     SolidityVisitor::new().visit(ast).unwrap()
 }
 
+// Markers delimiting the code generated from a located AST element, they never occur in solidity code
+const ORIGIN_START: char = '\u{1}';
+const ORIGIN_SEP: char = '\u{2}';
+const ORIGIN_END: char = '\u{3}';
+
+// """
+// Solidity code for ast together with its provenance: for every contract and function which was
+// generated from an element of the zkay source (as recorded in the line and column of the AST element
+// by the parser and kept by the transformation), the generated byte range and the zkay line and column.
+// Nested elements come after their enclosing element.
+// """
+pub fn to_solidity_with_provenance(ast: &ASTFlatten) -> (String, Vec<(Range<usize>, i32, i32)>) {
+    let marked = SolidityVisitor {
+        provenance: true,
+        ..SolidityVisitor::new()
+    }
+    .visit(ast)
+    .unwrap();
+    let mut code = String::with_capacity(marked.len());
+    let mut open = vec![];
+    let mut provenance = vec![];
+    let mut chars = marked.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ORIGIN_START => {
+                let location: String = chars.by_ref().take_while(|&c| c != ORIGIN_SEP).collect();
+                let (line, column) = location.split_once(':').unwrap();
+                open.push((
+                    provenance.len(),
+                    code.len(),
+                    line.parse().unwrap(),
+                    column.parse().unwrap(),
+                ));
+                // reserve the slot, so that enclosing elements come first
+                provenance.push((0..0, 0, 0));
+            }
+            ORIGIN_END => {
+                let (slot, start, line, column) = open.pop().unwrap();
+                provenance[slot] = (start..code.len(), line, column);
+            }
+            c => code.push(c),
+        }
+    }
+    (code, provenance)
+}
+
 // class SolidityVisitor(CodeVisitor)
 impl AstVisitorBaseRef for SolidityVisitor {
     fn ast_visitor_base_ref(&self) -> &AstVisitorBase {
@@ -51,6 +98,8 @@ impl AstVisitorBaseRef for SolidityVisitor {
 }
 pub struct SolidityVisitor {
     pub code_visitor_base: CodeVisitorBase,
+    // mark the code of located contracts and functions, see to_solidity_with_provenance
+    provenance: bool,
 }
 impl SolidityVisitor {
     // pub fn __init__(self)
@@ -59,11 +108,22 @@ impl SolidityVisitor {
     pub fn new() -> Self {
         Self {
             code_visitor_base: CodeVisitorBase::new(false),
+            provenance: false,
         }
     }
     pub fn display_final(&self) -> bool {
         self.code_visitor_base.display_final
     }
+    fn mark_origin(&self, ast: &ASTFlatten, code: String) -> String {
+        let (line, column) = ast.ast_base_ref().map_or((0, 0), |base| {
+            let base = base.borrow();
+            (base.line, base.column)
+        });
+        if !self.provenance || line <= 0 {
+            return code;
+        }
+        format!("{ORIGIN_START}{line}:{column}{ORIGIN_SEP}{code}{ORIGIN_END}")
+    }
 }
 
 impl AstVisitor for SolidityVisitor {
@@ -180,14 +240,16 @@ impl AstVisitor for SolidityVisitor {
             ASTType::VariableDeclaration => self.visit_VariableDeclaration(ast),
             ASTType::VariableDeclarationStatement => self.visit_VariableDeclarationStatement(ast),
             ASTType::Parameter => self.visit_Parameter(ast),
-            ASTType::ConstructorOrFunctionDefinition => {
-                self.visit_ConstructorOrFunctionDefinition(ast)
-            }
+            ASTType::ConstructorOrFunctionDefinition => self
+                .visit_ConstructorOrFunctionDefinition(ast)
+                .map(|code| self.mark_origin(ast, code)),
             ASTType::EnumValue => self.visit_EnumValue(ast),
             ASTType::EnumDefinition => self.visit_EnumDefinition(ast),
             ASTType::StructDefinition => self.visit_StructDefinition(ast),
             ASTType::StateVariableDeclaration => self.visit_StateVariableDeclaration(ast),
-            ASTType::ContractDefinition => self.visit_ContractDefinition(ast),
+            ASTType::ContractDefinition => self
+                .visit_ContractDefinition(ast)
+                .map(|code| self.mark_origin(ast, code)),
             ASTType::SourceUnit => self.visit_SourceUnit(ast),
             _ if matches!(ast.to_ast(), AST::TypeName(TypeName::ElementaryTypeName(_))) => {
                 self.visit_ElementaryTypeName(ast)